@@ -132,18 +132,6 @@ struct Notification {
     action_url: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct CreateNotificationPayload {
-    user_id: String,
-    title: String,
-    body: String,
-    notification_type: String,
-    priority: Option<String>,
-    data: Option<serde_json::Value>,
-    image_url: Option<String>,
-    action_url: Option<String>,
-}
-
 #[derive(Deserialize)]
 struct MarkReadPayload {
     notification_ids: Vec<String>,
@@ -2262,48 +2250,6 @@ async fn register_device_token(
     ))
 }
 
-/// POST /api/admin/notifications/send
-/// Send notification to user(s) - Admin only
-async fn send_notification(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    Json(payload): Json<CreateNotificationPayload>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let _claims = require_roles(&headers, &["admin"])?;
-    
-    let user_id = Uuid::parse_str(&payload.user_id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid user ID".to_string()))?;
-    
-    let priority = payload.priority.unwrap_or_else(|| "normal".to_string());
-    
-    let notification_id = Uuid::new_v4();
-    
-    sqlx::query(
-        "INSERT INTO notifications (id, user_id, title, body, type, priority, data, image_url, action_url)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
-    )
-    .bind(notification_id)
-    .bind(user_id)
-    .bind(&payload.title)
-    .bind(&payload.body)
-    .bind(&payload.notification_type)
-    .bind(&priority)
-    .bind(&payload.data)
-    .bind(&payload.image_url)
-    .bind(&payload.action_url)
-    .execute(&state.db)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
-    
-    Ok((
-        StatusCode::CREATED,
-        Json(serde_json::json!({
-            "message": "Notification sent successfully",
-            "notification_id": notification_id.to_string(),
-        })),
-    ))
-}
-
 // ============================================================================
 // ADMIN DASHBOARD HANDLERS
 // ============================================================================
@@ -2761,7 +2707,6 @@ async fn main() {
         .route("/api/notifications", get(get_notifications))
         .route("/api/notifications/mark-read", post(mark_notifications_read))
         .route("/api/notifications/register-device", post(register_device_token))
-        .route("/api/admin/notifications/send", post(send_notification))
         // 📊 Admin Dashboard & Export
         .route("/api/admin/dashboard", get(get_admin_dashboard))
         .route("/api/admin/export", get(export_data))
@@ -1,9 +1,21 @@
-// Recibe datos de los content scripts y los envía al backend local
-const ENDPOINT = 'http://localhost:3000/api/tracking/telemetry';
+// Recibe datos de los content scripts, los encola y los envía al backend local por lotes.
+// Si la red cae, la cola persiste en chrome.storage y se reenvía al volver (idempotente por seq).
+const BATCH_ENDPOINT = 'http://localhost:3000/api/tracking/telemetry/batch';
+const MAX_QUEUE = 20000; // ~28 h a una update cada 5 s
+const FLUSH_ALARM = 'telemetry-flush';
+
+let flushing = false;
+let maxBatchItems = 500;
+let retryAfter = 0;
+// Serializa enqueue para que dos mensajes no tomen el mismo seq
+let enqueueChain = Promise.resolve();
 
 chrome.runtime.onMessage.addListener((message, sender, sendResponse) => {
   if (message?.type === 'TELEMETRY_UPDATE') {
-    forwardTelemetry(message)
+    const queued = enqueueChain.then(() => enqueueTelemetry(message));
+    enqueueChain = queued.catch(() => {});
+    queued
+      .then(() => flushQueue())
       .then(() => sendResponse({ ok: true }))
       .catch((err) => sendResponse({ ok: false, error: String(err) }));
     // Mantener canal abierto para respuesta async
@@ -12,30 +24,141 @@ chrome.runtime.onMessage.addListener((message, sender, sendResponse) => {
   return false;
 });
 
-async function getRoomId() {
+chrome.alarms.create(FLUSH_ALARM, { periodInMinutes: 1 });
+chrome.alarms.onAlarm.addListener((alarm) => {
+  if (alarm.name === FLUSH_ALARM) flushQueue().catch(() => {});
+});
+
+async function getConfig() {
   return new Promise((resolve) => {
-    chrome.storage.local.get({ ROOM_ID: null }, (data) => resolve(data.ROOM_ID));
+    chrome.storage.local.get(
+      { ROOM_ID: null, DEVICE_ID: null, KEY_ID: null, DEVICE_SECRET: null, NEXT_SEQ: null },
+      (data) => resolve(data)
+    );
   });
 }
 
-async function forwardTelemetry(msg) {
-  const roomId = await getRoomId();
-  if (!roomId) throw new Error('ROOM_ID not configured');
+function toHex(buffer) {
+  return Array.from(new Uint8Array(buffer))
+    .map((b) => b.toString(16).padStart(2, '0'))
+    .join('');
+}
+
+// Firma HMAC-SHA256 de: timestamp \n nonce \n METHOD \n path \n sha256(body)
+async function signRequest(secret, method, path, body) {
+  const encoder = new TextEncoder();
+  const timestamp = Math.floor(Date.now() / 1000);
+  const nonce = crypto.randomUUID().replace(/-/g, '');
+  const bodyHash = toHex(await crypto.subtle.digest('SHA-256', encoder.encode(body)));
+  const message = [timestamp, nonce, method, path, bodyHash].join('\n');
+
+  const key = await crypto.subtle.importKey(
+    'raw',
+    encoder.encode(secret),
+    { name: 'HMAC', hash: 'SHA-256' },
+    false,
+    ['sign']
+  );
+  const signature = toHex(await crypto.subtle.sign('HMAC', key, encoder.encode(message)));
+
+  return { timestamp, nonce, signature };
+}
+
+function storageGet(defaults) {
+  return new Promise((resolve) => chrome.storage.local.get(defaults, resolve));
+}
+
+function storageSet(values) {
+  return new Promise((resolve) => chrome.storage.local.set(values, resolve));
+}
+
+function requireConfig(config) {
+  if (!config.ROOM_ID) throw new Error('ROOM_ID not configured');
+  if (!config.DEVICE_ID || !config.KEY_ID || !config.DEVICE_SECRET) {
+    throw new Error('Device credentials not configured');
+  }
+}
 
-  const payload = {
-    room_id: roomId,
+async function signedFetch(config, method, body) {
+  const path = new URL(BATCH_ENDPOINT).pathname;
+  const { timestamp, nonce, signature } = await signRequest(config.DEVICE_SECRET, method, path, body);
+  return fetch(BATCH_ENDPOINT, {
+    method,
+    headers: {
+      'Content-Type': 'application/json',
+      'X-Device-Id': config.DEVICE_ID,
+      'X-Key-Id': config.KEY_ID,
+      'X-Timestamp': String(timestamp),
+      'X-Nonce': nonce,
+      'X-Signature': signature
+    },
+    body: method === 'GET' ? undefined : body
+  });
+}
+
+// La secuencia se sincroniza con el servidor la primera vez (o tras reinstalar),
+// para no reutilizar seqs que el backend ya confirmó.
+async function nextSeq(config) {
+  if (config.NEXT_SEQ) return config.NEXT_SEQ;
+  const res = await signedFetch(config, 'GET', '');
+  if (!res.ok) throw new Error(`HTTP ${res.status}`);
+  const ack = await res.json();
+  return ack.last_acked_seq + 1;
+}
+
+async function enqueueTelemetry(msg) {
+  const config = await getConfig();
+  requireConfig(config);
+
+  const seq = await nextSeq(config);
+  const { TELEMETRY_QUEUE: queue } = await storageGet({ TELEMETRY_QUEUE: [] });
+  queue.push({
+    seq,
+    room_id: config.ROOM_ID,
     platform: msg.platform,
     tokens_count: msg.tokens || 0,
     tips_count: msg.tips || 0,
     viewers_count: msg.viewers || 0,
     timestamp: Math.floor(Date.now() / 1000)
-  };
-
-  const res = await fetch(ENDPOINT, {
-    method: 'POST',
-    headers: { 'Content-Type': 'application/json' },
-    body: JSON.stringify(payload)
   });
+  // Si la cola desborda se descartan las más viejas (el saldo es acumulado, no se pierden tokens)
+  const trimmed = queue.length > MAX_QUEUE ? queue.slice(queue.length - MAX_QUEUE) : queue;
+  await storageSet({ TELEMETRY_QUEUE: trimmed, NEXT_SEQ: seq + 1 });
+}
 
-  if (!res.ok) throw new Error(`HTTP ${res.status}`);
+async function flushQueue() {
+  if (flushing || Date.now() < retryAfter) return;
+  flushing = true;
+  try {
+    const config = await getConfig();
+    requireConfig(config);
+
+    for (;;) {
+      const { TELEMETRY_QUEUE: queue } = await storageGet({ TELEMETRY_QUEUE: [] });
+      if (queue.length === 0) return;
+
+      const batch = queue.slice(0, maxBatchItems);
+      const res = await signedFetch(config, 'POST', JSON.stringify(batch));
+
+      if (res.status === 429) {
+        const seconds = Number(res.headers.get('Retry-After')) || 5;
+        retryAfter = Date.now() + seconds * 1000;
+        return;
+      }
+      if (res.status === 413 && maxBatchItems > 1) {
+        maxBatchItems = Math.max(1, Math.floor(maxBatchItems / 2));
+        continue;
+      }
+      if (!res.ok) throw new Error(`HTTP ${res.status}`);
+
+      const result = await res.json();
+      maxBatchItems = result.limits?.max_items || maxBatchItems;
+      const { TELEMETRY_QUEUE: current } = await storageGet({ TELEMETRY_QUEUE: [] });
+      await storageSet({ TELEMETRY_QUEUE: current.filter((item) => item.seq > result.last_acked_seq) });
+
+      if (!result.complete) return;
+    }
+  } finally {
+    flushing = false;
+  }
 }
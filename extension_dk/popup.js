@@ -1,23 +1,48 @@
 const roomInput = document.getElementById('room');
+const deviceInput = document.getElementById('device');
+const keyInput = document.getElementById('key');
+const secretInput = document.getElementById('secret');
 const stateEl = document.getElementById('state');
 const saveBtn = document.getElementById('save');
 
-chrome.storage.local.get({ ROOM_ID: '' }, (data) => {
+chrome.storage.local.get({ ROOM_ID: '', DEVICE_ID: '', KEY_ID: '', DEVICE_SECRET: '' }, (data) => {
   roomInput.value = data.ROOM_ID || '';
-  updateState();
+  deviceInput.value = data.DEVICE_ID || '';
+  keyInput.value = data.KEY_ID || '';
+  // El secreto no se muestra; solo se indica si ya está guardado
+  secretInput.placeholder = data.DEVICE_SECRET ? '•••••• (guardado)' : secretInput.placeholder;
+  updateState(false, Boolean(data.DEVICE_SECRET));
 });
 
 saveBtn.addEventListener('click', () => {
-  const roomId = roomInput.value.trim();
-  chrome.storage.local.set({ ROOM_ID: roomId }, () => {
-    updateState(true);
+  const values = {
+    ROOM_ID: roomInput.value.trim(),
+    DEVICE_ID: deviceInput.value.trim(),
+    KEY_ID: keyInput.value.trim()
+  };
+  const secret = secretInput.value.trim();
+  if (secret) values.DEVICE_SECRET = secret;
+
+  chrome.storage.local.get({ DEVICE_ID: '' }, (current) => {
+    // Otro dispositivo: la secuencia se vuelve a sincronizar con el servidor
+    if (current.DEVICE_ID !== values.DEVICE_ID) values.NEXT_SEQ = null;
+    saveValues(values);
   });
 });
 
-function updateState(saved = false) {
-  if (roomInput.value.trim()) {
-    stateEl.textContent = saved ? '🟢 Transmitiendo datos...' : '🟡 Configurado. Listo.';
-  } else {
+function saveValues(values) {
+  chrome.storage.local.set(values, () => {
+    secretInput.value = '';
+    chrome.storage.local.get({ DEVICE_SECRET: '' }, (data) => updateState(true, Boolean(data.DEVICE_SECRET)));
+  });
+}
+
+function updateState(saved = false, hasSecret = false) {
+  if (!roomInput.value.trim()) {
     stateEl.textContent = '⚪ Sin Room ID';
+  } else if (!deviceInput.value.trim() || !keyInput.value.trim() || !hasSecret) {
+    stateEl.textContent = '🟠 Faltan credenciales del dispositivo';
+  } else {
+    stateEl.textContent = saved ? '🟢 Transmitiendo datos...' : '🟡 Configurado. Listo.';
   }
 }
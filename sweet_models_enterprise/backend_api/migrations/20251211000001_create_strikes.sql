@@ -0,0 +1,86 @@
+-- Strikes persistidos, política configurable y apelaciones
+
+-- payroll_payouts ya se usa para multas (status PENALTY + notes); alinear el esquema
+ALTER TYPE payout_status ADD VALUE IF NOT EXISTS 'PENALTY';
+ALTER TABLE payroll_payouts ADD COLUMN IF NOT EXISTS notes TEXT;
+
+-- Política de strikes: ventana móvil y escalera de consecuencias por nivel
+CREATE TABLE IF NOT EXISTS strike_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    window_days INT NOT NULL CHECK (window_days > 0),
+    ladder JSONB NOT NULL, -- [{ "level": 1, "xp_burn_reason": "STRIKE_1", "consequences": [...] }]
+    is_active BOOLEAN NOT NULL DEFAULT FALSE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Solo una política activa a la vez
+CREATE UNIQUE INDEX IF NOT EXISTS idx_strike_policies_active
+    ON strike_policies(is_active)
+    WHERE is_active = TRUE;
+
+CREATE TABLE IF NOT EXISTS strikes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    attendance_log_id UUID REFERENCES attendance_logs(id) ON DELETE SET NULL,
+    policy_id UUID REFERENCES strike_policies(id) ON DELETE SET NULL,
+    level INT NOT NULL CHECK (level > 0),
+    reason TEXT NOT NULL DEFAULT 'LATE_ARRIVAL',
+    status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'APPEALED', 'VOIDED')),
+    consequences JSONB NOT NULL DEFAULT '[]'::jsonb,
+    xp_burned BIGINT NOT NULL DEFAULT 0,
+    penalty_payout_id UUID REFERENCES payroll_payouts(id) ON DELETE SET NULL,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    voided_at TIMESTAMPTZ,
+    voided_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    void_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_strikes_user_issued ON strikes(user_id, issued_at DESC);
+CREATE INDEX IF NOT EXISTS idx_strikes_status ON strikes(status);
+
+CREATE TABLE IF NOT EXISTS strike_appeals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    strike_id UUID NOT NULL REFERENCES strikes(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING' CHECK (status IN ('PENDING', 'APPROVED', 'REJECTED')),
+    reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    resolution_note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Una apelación abierta por strike
+CREATE UNIQUE INDEX IF NOT EXISTS idx_strike_appeals_pending
+    ON strike_appeals(strike_id)
+    WHERE status = 'PENDING';
+CREATE INDEX IF NOT EXISTS idx_strike_appeals_status ON strike_appeals(status, created_at);
+
+CREATE OR REPLACE FUNCTION set_timestamp_strikes()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_strikes_updated_at ON strikes;
+CREATE TRIGGER trg_strikes_updated_at
+    BEFORE UPDATE ON strikes
+    FOR EACH ROW
+    EXECUTE FUNCTION set_timestamp_strikes();
+
+-- Política por defecto equivalente a las reglas originales (semana de 7 días)
+INSERT INTO strike_policies (name, window_days, ladder, is_active)
+SELECT 'default', 7,
+       '[
+          {"level": 1, "xp_burn_reason": "STRIKE_1", "consequences": [{"type": "HALF_PAY_TODAY"}]},
+          {"level": 2, "xp_burn_reason": "STRIKE_2", "consequences": [{"type": "DOWNGRADE_WEEK", "factor": 0.5}]},
+          {"level": 3, "xp_burn_reason": "STRIKE_3", "consequences": [{"type": "FINE", "amount_cop": 1000000}]}
+        ]'::jsonb,
+       TRUE
+WHERE NOT EXISTS (SELECT 1 FROM strike_policies WHERE is_active = TRUE);
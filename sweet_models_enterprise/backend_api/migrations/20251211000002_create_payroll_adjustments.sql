@@ -0,0 +1,25 @@
+-- Ajustes de nómina durables (consecuencias de strikes y otras correcciones)
+CREATE TABLE IF NOT EXISTS payroll_adjustments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    strike_id UUID REFERENCES strikes(id) ON DELETE SET NULL,
+    kind VARCHAR(30) NOT NULL CHECK (kind IN ('DAY_PAY_FACTOR', 'WEEK_PAY_FACTOR', 'FIXED_DEDUCTION')),
+    -- Día de producción al que aplica (para WEEK_PAY_FACTOR, cualquier día de la semana ISO)
+    work_date DATE NOT NULL,
+    factor DOUBLE PRECISION CHECK (factor IS NULL OR (factor >= 0 AND factor <= 1)),
+    amount_cop DOUBLE PRECISION CHECK (amount_cop IS NULL OR amount_cop >= 0),
+    reason TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'REVERSED')),
+    reversed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT chk_adjustment_value CHECK (
+        (kind = 'FIXED_DEDUCTION' AND amount_cop IS NOT NULL)
+        OR (kind <> 'FIXED_DEDUCTION' AND factor IS NOT NULL)
+    )
+);
+
+CREATE INDEX IF NOT EXISTS idx_payroll_adjustments_user_date ON payroll_adjustments(user_id, work_date);
+CREATE INDEX IF NOT EXISTS idx_payroll_adjustments_strike ON payroll_adjustments(strike_id);
+
+COMMENT ON TABLE payroll_adjustments IS 'Ajustes aplicados por el cálculo de nómina; reversibles si se anula el origen';
+COMMENT ON COLUMN payroll_adjustments.factor IS 'Multiplicador sobre la producción del día (DAY) o de la semana (WEEK)';
@@ -0,0 +1,52 @@
+-- Cierre de turno por room: checklist con fotos del turno saliente,
+-- confirmación/disputa del turno entrante o moderador, multas solo tras confirmar
+CREATE TABLE IF NOT EXISTS shift_closeouts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    room_id INT NOT NULL,
+    shift_id INT NOT NULL CHECK (shift_id BETWEEN 1 AND 4),
+    work_date DATE NOT NULL,
+    week_id VARCHAR(10) NOT NULL,
+    -- Integrantes del turno y producción registrada al momento del cierre
+    members UUID[] NOT NULL DEFAULT '{}',
+    total_tokens DOUBLE PRECISION NOT NULL DEFAULT 0,
+    checklist JSONB NOT NULL DEFAULT '[]'::jsonb,
+    photo_urls TEXT[] NOT NULL DEFAULT '{}',
+    notes TEXT,
+    submitted_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    submitted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING_REVIEW'
+        CHECK (status IN ('PENDING_REVIEW', 'DISPUTED', 'CONFIRMED')),
+    disputed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    disputed_at TIMESTAMPTZ,
+    dispute_reason TEXT,
+    dispute_photo_urls TEXT[] NOT NULL DEFAULT '{}',
+    verdict VARCHAR(10) CHECK (verdict IN ('CLEAN', 'DIRTY')),
+    reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    review_notes TEXT,
+    penalties_applied JSONB NOT NULL DEFAULT '[]'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT uq_shift_closeout UNIQUE (room_id, shift_id, work_date),
+    CONSTRAINT chk_closeout_verdict CHECK (status <> 'CONFIRMED' OR verdict IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_shift_closeouts_status ON shift_closeouts(status, submitted_at);
+CREATE INDEX IF NOT EXISTS idx_shift_closeouts_room_date ON shift_closeouts(room_id, work_date DESC);
+
+CREATE OR REPLACE FUNCTION set_timestamp_shift_closeouts()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_shift_closeouts_updated_at ON shift_closeouts;
+CREATE TRIGGER trg_shift_closeouts_updated_at
+    BEFORE UPDATE ON shift_closeouts
+    FOR EACH ROW
+    EXECUTE FUNCTION set_timestamp_shift_closeouts();
+
+COMMENT ON COLUMN shift_closeouts.total_tokens IS 'Suma de production_logs de los integrantes ese día (no se acepta del cliente)';
+COMMENT ON COLUMN shift_closeouts.penalties_applied IS 'Multas aplicadas al confirmar: GROUP_SHORTFALL, DIRTY_ROOM';
@@ -0,0 +1,103 @@
+-- Registro de estudios, rooms y activos (cámaras, iluminación, computadores)
+CREATE TABLE IF NOT EXISTS studios (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL UNIQUE,
+    address TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- id entero: es el mismo que usan user_shifts.assigned_room y los cierres de turno
+CREATE TABLE IF NOT EXISTS rooms (
+    id SERIAL PRIMARY KEY,
+    studio_id UUID NOT NULL REFERENCES studios(id) ON DELETE RESTRICT,
+    name TEXT NOT NULL,
+    capacity INT NOT NULL DEFAULT 3 CHECK (capacity > 0),
+    status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'MAINTENANCE', 'RETIRED')),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (studio_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS studio_assets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    studio_id UUID NOT NULL REFERENCES studios(id) ON DELETE RESTRICT,
+    room_id INT REFERENCES rooms(id) ON DELETE SET NULL,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('CAMERA', 'LIGHTING', 'COMPUTER')),
+    name TEXT NOT NULL,
+    brand TEXT,
+    model TEXT,
+    serial_number TEXT UNIQUE,
+    -- Cámaras: vínculo opcional con la configuración de stream existente
+    camera_id INT REFERENCES cameras(id) ON DELETE SET NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'MAINTENANCE', 'RETIRED')),
+    assigned_at TIMESTAMPTZ,
+    purchased_at DATE,
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_studio_assets_room ON studio_assets(room_id);
+CREATE INDEX IF NOT EXISTS idx_studio_assets_kind_status ON studio_assets(kind, status);
+
+CREATE TABLE IF NOT EXISTS maintenance_tickets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    asset_id UUID REFERENCES studio_assets(id) ON DELETE CASCADE,
+    room_id INT REFERENCES rooms(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    description TEXT,
+    priority VARCHAR(10) NOT NULL DEFAULT 'MEDIUM' CHECK (priority IN ('LOW', 'MEDIUM', 'HIGH')),
+    -- Si bloquea el room, este no se puede asignar en el roster hasta resolverse
+    blocks_room BOOLEAN NOT NULL DEFAULT FALSE,
+    status VARCHAR(20) NOT NULL DEFAULT 'OPEN' CHECK (status IN ('OPEN', 'IN_PROGRESS', 'RESOLVED')),
+    opened_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    assigned_to UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolution TEXT,
+    resolved_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT chk_ticket_target CHECK (asset_id IS NOT NULL OR room_id IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_maintenance_tickets_status ON maintenance_tickets(status, priority);
+CREATE INDEX IF NOT EXISTS idx_maintenance_tickets_room ON maintenance_tickets(room_id) WHERE status <> 'RESOLVED';
+
+CREATE OR REPLACE FUNCTION set_timestamp_studio_registry()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_studios_updated_at ON studios;
+CREATE TRIGGER trg_studios_updated_at BEFORE UPDATE ON studios
+    FOR EACH ROW EXECUTE FUNCTION set_timestamp_studio_registry();
+DROP TRIGGER IF EXISTS trg_rooms_updated_at ON rooms;
+CREATE TRIGGER trg_rooms_updated_at BEFORE UPDATE ON rooms
+    FOR EACH ROW EXECUTE FUNCTION set_timestamp_studio_registry();
+DROP TRIGGER IF EXISTS trg_studio_assets_updated_at ON studio_assets;
+CREATE TRIGGER trg_studio_assets_updated_at BEFORE UPDATE ON studio_assets
+    FOR EACH ROW EXECUTE FUNCTION set_timestamp_studio_registry();
+DROP TRIGGER IF EXISTS trg_maintenance_tickets_updated_at ON maintenance_tickets;
+CREATE TRIGGER trg_maintenance_tickets_updated_at BEFORE UPDATE ON maintenance_tickets
+    FOR EACH ROW EXECUTE FUNCTION set_timestamp_studio_registry();
+
+-- Estudio y rooms 1-3 existentes (los ids ya usados en user_shifts)
+INSERT INTO studios (name) VALUES ('Principal') ON CONFLICT (name) DO NOTHING;
+INSERT INTO rooms (id, studio_id, name)
+SELECT v.id, s.id, v.name
+FROM studios s, (VALUES (1, 'Room 1'), (2, 'Room 2'), (3, 'Room 3')) AS v(id, name)
+WHERE s.name = 'Principal'
+ON CONFLICT (id) DO NOTHING;
+SELECT setval(pg_get_serial_sequence('rooms', 'id'), GREATEST((SELECT MAX(id) FROM rooms), 1));
+
+-- El roster deja de estar limitado a 3 rooms fijos: ahora referencia el registro
+ALTER TABLE user_shifts DROP CONSTRAINT IF EXISTS user_shifts_assigned_room_check;
+ALTER TABLE user_shifts DROP CONSTRAINT IF EXISTS fk_user_shifts_room;
+ALTER TABLE user_shifts
+    ADD CONSTRAINT fk_user_shifts_room FOREIGN KEY (assigned_room) REFERENCES rooms(id);
+CREATE INDEX IF NOT EXISTS idx_user_shifts_room_week ON user_shifts(assigned_room, assigned_shift, week_id);
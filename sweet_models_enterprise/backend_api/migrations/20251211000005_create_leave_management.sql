@@ -0,0 +1,47 @@
+-- Ausencias justificadas: vacaciones, incapacidad médica y permisos personales
+CREATE TABLE IF NOT EXISTS leave_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    leave_type VARCHAR(20) NOT NULL CHECK (leave_type IN ('VACATION', 'MEDICAL', 'PERSONAL')),
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    days INT NOT NULL CHECK (days > 0),
+    reason TEXT,
+    -- Soporte (incapacidad médica); URL emitida por /api/upload
+    document_url TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING' CHECK (status IN ('PENDING', 'APPROVED', 'REJECTED', 'CANCELLED')),
+    reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    review_note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT chk_leave_range CHECK (end_date >= start_date),
+    CONSTRAINT chk_medical_document CHECK (leave_type <> 'MEDICAL' OR document_url IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_leave_requests_user_dates ON leave_requests(user_id, start_date, end_date);
+CREATE INDEX IF NOT EXISTS idx_leave_requests_status ON leave_requests(status, created_at);
+
+-- Cupo anual por modelo y tipo; sin fila se usa el cupo por defecto del código
+CREATE TABLE IF NOT EXISTS leave_balances (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    year INT NOT NULL,
+    leave_type VARCHAR(20) NOT NULL CHECK (leave_type IN ('VACATION', 'MEDICAL', 'PERSONAL')),
+    allowance_days INT NOT NULL CHECK (allowance_days >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, year, leave_type)
+);
+
+CREATE OR REPLACE FUNCTION set_timestamp_leave_requests()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_leave_requests_updated_at ON leave_requests;
+CREATE TRIGGER trg_leave_requests_updated_at
+    BEFORE UPDATE ON leave_requests
+    FOR EACH ROW
+    EXECUTE FUNCTION set_timestamp_leave_requests();
@@ -0,0 +1,95 @@
+-- Serie de tiempo de telemetría de la extensión (historial para nómina y analítica)
+-- Muestras crudas particionadas por día; rollups de 1 minuto y 1 hora
+CREATE TABLE IF NOT EXISTS telemetry_samples (
+    room_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    sampled_at TIMESTAMPTZ NOT NULL,
+    tokens_count BIGINT NOT NULL DEFAULT 0,
+    tips_count BIGINT NOT NULL DEFAULT 0,
+    viewers_count INT NOT NULL DEFAULT 0,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+) PARTITION BY RANGE (sampled_at);
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_samples_room_time
+    ON telemetry_samples(room_id, platform, sampled_at);
+
+-- Recoge muestras con timestamps fuera de las particiones creadas (relojes desfasados)
+CREATE TABLE IF NOT EXISTS telemetry_samples_default PARTITION OF telemetry_samples DEFAULT;
+
+-- Crea la partición diaria si no existe (la llama el worker de mantenimiento)
+CREATE OR REPLACE FUNCTION ensure_telemetry_partition(day DATE)
+RETURNS VOID AS $$
+DECLARE
+    part_name TEXT := 'telemetry_samples_' || to_char(day, 'YYYYMMDD');
+BEGIN
+    IF to_regclass(part_name) IS NULL THEN
+        EXECUTE format(
+            'CREATE TABLE %I PARTITION OF telemetry_samples FOR VALUES FROM (%L) TO (%L)',
+            part_name, day::TIMESTAMPTZ, (day + 1)::TIMESTAMPTZ
+        );
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Retención: elimina particiones diarias anteriores a `cutoff`
+CREATE OR REPLACE FUNCTION drop_telemetry_partitions_before(cutoff DATE)
+RETURNS INT AS $$
+DECLARE
+    part RECORD;
+    dropped INT := 0;
+BEGIN
+    FOR part IN
+        SELECT c.relname
+        FROM pg_inherits i
+        JOIN pg_class c ON c.oid = i.inhrelid
+        JOIN pg_class p ON p.oid = i.inhparent
+        WHERE p.relname = 'telemetry_samples'
+          AND c.relname ~ '^telemetry_samples_[0-9]{8}$'
+          AND to_date(substring(c.relname FROM '[0-9]{8}$'), 'YYYYMMDD') < cutoff
+    LOOP
+        EXECUTE format('DROP TABLE IF EXISTS %I', part.relname);
+        dropped := dropped + 1;
+    END LOOP;
+    DELETE FROM telemetry_samples_default WHERE sampled_at < cutoff::TIMESTAMPTZ;
+    RETURN dropped;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT ensure_telemetry_partition(CURRENT_DATE);
+SELECT ensure_telemetry_partition(CURRENT_DATE + 1);
+
+-- tokens/tips son contadores acumulados de la plataforma: se guarda el último valor del bucket
+CREATE TABLE IF NOT EXISTS telemetry_rollups_1m (
+    room_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    bucket TIMESTAMPTZ NOT NULL,
+    samples INT NOT NULL,
+    tokens_min BIGINT NOT NULL,
+    tokens_last BIGINT NOT NULL,
+    tips_last BIGINT NOT NULL,
+    viewers_avg DOUBLE PRECISION NOT NULL,
+    viewers_max INT NOT NULL,
+    PRIMARY KEY (room_id, platform, bucket)
+);
+
+CREATE TABLE IF NOT EXISTS telemetry_rollups_1h (
+    room_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    bucket TIMESTAMPTZ NOT NULL,
+    samples INT NOT NULL,
+    tokens_min BIGINT NOT NULL,
+    tokens_last BIGINT NOT NULL,
+    tips_last BIGINT NOT NULL,
+    viewers_avg DOUBLE PRECISION NOT NULL,
+    viewers_max INT NOT NULL,
+    PRIMARY KEY (room_id, platform, bucket)
+);
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_rollups_1m_bucket ON telemetry_rollups_1m(bucket);
+CREATE INDEX IF NOT EXISTS idx_telemetry_rollups_1h_bucket ON telemetry_rollups_1h(bucket);
+
+-- Marca de agua del worker de rollups
+CREATE TABLE IF NOT EXISTS telemetry_rollup_state (
+    resolution VARCHAR(4) PRIMARY KEY,
+    rolled_until TIMESTAMPTZ NOT NULL
+);
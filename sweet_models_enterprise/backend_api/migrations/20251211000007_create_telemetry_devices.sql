@@ -0,0 +1,32 @@
+-- Credenciales por dispositivo para la ingesta de telemetría (extensión Chrome)
+CREATE TABLE IF NOT EXISTS telemetry_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    -- Room que este dispositivo puede reportar (mismo valor que ROOM_ID en la extensión)
+    room_id TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'REVOKED')),
+    registered_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    last_seen_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_devices_room ON telemetry_devices(room_id);
+
+-- Llaves HMAC; el secreto se entrega una sola vez al registrar/rotar
+CREATE TABLE IF NOT EXISTS telemetry_device_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    device_id UUID NOT NULL REFERENCES telemetry_devices(id) ON DELETE CASCADE,
+    secret TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'ROTATED', 'REVOKED')),
+    -- Llaves rotadas siguen siendo válidas hasta expires_at (periodo de gracia)
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_device_keys_device ON telemetry_device_keys(device_id, status);
+-- Una sola llave activa por dispositivo
+CREATE UNIQUE INDEX IF NOT EXISTS idx_telemetry_device_keys_active
+    ON telemetry_device_keys(device_id)
+    WHERE status = 'ACTIVE';
@@ -0,0 +1,49 @@
+-- Conversión de saldos acumulados de tokens en deltas ganados por sesión
+CREATE TABLE IF NOT EXISTS telemetry_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    room_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    last_sample_at TIMESTAMPTZ NOT NULL,
+    -- FIRST (primer dato), RESET (contador bajó), GAP (silencio prolongado)
+    start_reason VARCHAR(10) NOT NULL CHECK (start_reason IN ('FIRST', 'RESET', 'GAP')),
+    tokens_earned BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_sessions_room ON telemetry_sessions(room_id, platform, started_at DESC);
+
+-- Último saldo visto por room/plataforma (se bloquea por fila al procesar)
+CREATE TABLE IF NOT EXISTS telemetry_counter_state (
+    room_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    session_id UUID NOT NULL REFERENCES telemetry_sessions(id) ON DELETE CASCADE,
+    last_tokens BIGINT NOT NULL,
+    last_sampled_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (room_id, platform)
+);
+
+CREATE TABLE IF NOT EXISTS telemetry_token_deltas (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    room_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    session_id UUID NOT NULL REFERENCES telemetry_sessions(id) ON DELETE CASCADE,
+    sampled_at TIMESTAMPTZ NOT NULL,
+    tokens_delta BIGINT NOT NULL CHECK (tokens_delta >= 0),
+    tokens_balance BIGINT NOT NULL,
+    is_reset BOOLEAN NOT NULL DEFAULT FALSE,
+    after_gap BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Atribución: room del registro, turno y modelo presente en ese momento
+    studio_room_id INT REFERENCES rooms(id) ON DELETE SET NULL,
+    shift_id INT,
+    work_date DATE,
+    model_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- Modelos presentes en el room/turno; model_id solo se fija si hay exactamente una
+    attribution_candidates INT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (room_id, platform, sampled_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_deltas_room_time ON telemetry_token_deltas(room_id, platform, sampled_at);
+CREATE INDEX IF NOT EXISTS idx_telemetry_deltas_model_date ON telemetry_token_deltas(model_id, work_date);
+CREATE INDEX IF NOT EXISTS idx_telemetry_deltas_studio_room ON telemetry_token_deltas(studio_room_id, work_date);
@@ -0,0 +1,48 @@
+-- Borradores de producción generados desde telemetría y conciliación con lo digitado a mano
+CREATE TABLE IF NOT EXISTS production_drafts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    room_id TEXT NOT NULL,
+    studio_room_id INT REFERENCES rooms(id) ON DELETE SET NULL,
+    platform TEXT NOT NULL,
+    work_date DATE NOT NULL,
+    telemetry_tokens BIGINT NOT NULL DEFAULT 0,
+    -- Tokens sin modelo atribuida (room compartido o sin check-in); no pasan a production_logs
+    unattributed_tokens BIGINT NOT NULL DEFAULT 0,
+    flagged BOOLEAN NOT NULL DEFAULT FALSE,
+    status VARCHAR(20) NOT NULL DEFAULT 'DRAFT' CHECK (status IN ('DRAFT', 'CONFIRMED', 'REJECTED')),
+    reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    review_notes TEXT,
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (room_id, platform, work_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_production_drafts_date_status ON production_drafts(work_date DESC, status);
+
+CREATE TABLE IF NOT EXISTS production_draft_lines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    draft_id UUID NOT NULL REFERENCES production_drafts(id) ON DELETE CASCADE,
+    model_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    telemetry_tokens BIGINT NOT NULL,
+    -- Comparación por modelo/día: production_logs manuales no distinguen plataforma
+    model_day_telemetry BIGINT NOT NULL,
+    manual_tokens DOUBLE PRECISION,
+    discrepancy_pct DOUBLE PRECISION,
+    flagged BOOLEAN NOT NULL DEFAULT FALSE,
+    production_log_id UUID REFERENCES production_logs(id) ON DELETE SET NULL,
+    UNIQUE (draft_id, model_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_production_draft_lines_model ON production_draft_lines(model_id);
+
+-- Origen de cada registro de producción; los manuales reemplazados por un borrador confirmado
+-- quedan marcados y dejan de sumar
+ALTER TABLE production_logs
+    ADD COLUMN IF NOT EXISTS source VARCHAR(20) NOT NULL DEFAULT 'MANUAL' CHECK (source IN ('MANUAL', 'TELEMETRY')),
+    ADD COLUMN IF NOT EXISTS platform TEXT,
+    ADD COLUMN IF NOT EXISTS draft_id UUID REFERENCES production_drafts(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS superseded_by_draft UUID REFERENCES production_drafts(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_production_logs_active_model_date
+    ON production_logs(model_id, production_date) WHERE superseded_by_draft IS NULL;
@@ -0,0 +1,5 @@
+-- Ingesta por lotes: secuencia del cliente por dispositivo.
+-- Todo seq <= last_acked_seq ya fue procesado; el cliente reanuda desde last_acked_seq + 1.
+ALTER TABLE telemetry_devices
+    ADD COLUMN IF NOT EXISTS last_acked_seq BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS last_batch_at TIMESTAMPTZ;
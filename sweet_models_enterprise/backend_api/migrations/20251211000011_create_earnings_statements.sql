@@ -0,0 +1,62 @@
+-- Cuentas de las modelos en cada plataforma (username del extracto -> modelo)
+CREATE TABLE IF NOT EXISTS platform_accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    platform TEXT NOT NULL,
+    username TEXT NOT NULL,
+    model_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_platform_accounts_username ON platform_accounts(platform, LOWER(username));
+CREATE INDEX IF NOT EXISTS idx_platform_accounts_model ON platform_accounts(model_id);
+
+CREATE OR REPLACE FUNCTION set_timestamp_platform_accounts()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_platform_accounts_updated_at ON platform_accounts;
+CREATE TRIGGER trg_platform_accounts_updated_at
+BEFORE UPDATE ON platform_accounts
+FOR EACH ROW EXECUTE FUNCTION set_timestamp_platform_accounts();
+
+-- Extractos oficiales de ganancias: fuente autoritativa por periodo
+CREATE TABLE IF NOT EXISTS earnings_statements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    platform TEXT NOT NULL,
+    period_start DATE NOT NULL,
+    period_end DATE NOT NULL,
+    file_name TEXT NOT NULL,
+    file_sha256 TEXT NOT NULL,
+    row_count INT NOT NULL DEFAULT 0,
+    unmatched_count INT NOT NULL DEFAULT 0,
+    total_tokens NUMERIC(15, 2) NOT NULL DEFAULT 0,
+    total_usd NUMERIC(15, 2) NOT NULL DEFAULT 0,
+    -- Reimportar el mismo periodo reemplaza al anterior
+    status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'SUPERSEDED')),
+    imported_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (period_end >= period_start),
+    UNIQUE (platform, file_sha256)
+);
+
+CREATE INDEX IF NOT EXISTS idx_earnings_statements_period ON earnings_statements(platform, period_start, period_end);
+
+CREATE TABLE IF NOT EXISTS earnings_statement_lines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    statement_id UUID NOT NULL REFERENCES earnings_statements(id) ON DELETE CASCADE,
+    row_number INT NOT NULL,
+    username TEXT NOT NULL,
+    model_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    tokens NUMERIC(15, 2) NOT NULL DEFAULT 0,
+    usd NUMERIC(15, 2) NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_earnings_statement_lines_statement ON earnings_statement_lines(statement_id);
+CREATE INDEX IF NOT EXISTS idx_earnings_statement_lines_model ON earnings_statement_lines(model_id);
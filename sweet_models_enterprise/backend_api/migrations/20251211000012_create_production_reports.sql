@@ -0,0 +1,88 @@
+-- Reportes de fin de turno procesados por engine::core, con revisiones y asientos idempotentes
+CREATE TABLE IF NOT EXISTS production_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    room_id INT NOT NULL REFERENCES rooms(id) ON DELETE RESTRICT,
+    shift_id INT NOT NULL CHECK (shift_id BETWEEN 1 AND 4),
+    work_date DATE NOT NULL,
+    source VARCHAR(20) NOT NULL CHECK (source IN ('SUBMITTED', 'TELEMETRY')),
+    revision INT NOT NULL DEFAULT 1,
+    input JSONB NOT NULL,
+    input_sha256 TEXT NOT NULL,
+    report JSONB NOT NULL,
+    binance_rate_cop DOUBLE PRECISION NOT NULL,
+    gross_tokens DOUBLE PRECISION NOT NULL,
+    gross_usd DOUBLE PRECISION NOT NULL,
+    studio_revenue_cop DOUBLE PRECISION NOT NULL,
+    total_penalties_cop DOUBLE PRECISION NOT NULL,
+    -- Con un cierre de turno confirmado las multas ya las cobró operations::room
+    penalties_via_closeout BOOLEAN NOT NULL DEFAULT FALSE,
+    processed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (room_id, shift_id, work_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_production_reports_date ON production_reports(work_date DESC, room_id);
+
+-- Historial de cada procesamiento (la revisión 1 es el original)
+CREATE TABLE IF NOT EXISTS production_report_revisions (
+    report_id UUID NOT NULL REFERENCES production_reports(id) ON DELETE CASCADE,
+    revision INT NOT NULL,
+    input JSONB NOT NULL,
+    report JSONB NOT NULL,
+    diff JSONB,
+    reason TEXT,
+    processed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (report_id, revision)
+);
+
+-- Pago por modelo de la revisión vigente
+CREATE TABLE IF NOT EXISTS production_report_members (
+    report_id UUID NOT NULL REFERENCES production_reports(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    model_id INT NOT NULL,
+    name TEXT NOT NULL,
+    strikes_applied SMALLINT NOT NULL DEFAULT 0,
+    tokens_net DOUBLE PRECISION NOT NULL,
+    usd_net DOUBLE PRECISION NOT NULL,
+    money_cop DOUBLE PRECISION NOT NULL,
+    penalties_cop DOUBLE PRECISION NOT NULL,
+    net_money_cop DOUBLE PRECISION NOT NULL,
+    xp_gained BIGINT NOT NULL,
+    xp_burned BIGINT NOT NULL,
+    xp_after_burn BIGINT NOT NULL,
+    PRIMARY KEY (report_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_production_report_members_user ON production_report_members(user_id);
+
+-- Asientos hacia finanzas (transactions) y gamificación (users.xp). Cada revisión solo
+-- asienta la diferencia contra lo ya asentado; la llave única evita duplicar reintentos.
+CREATE TABLE IF NOT EXISTS production_report_postings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    report_id UUID NOT NULL REFERENCES production_reports(id) ON DELETE CASCADE,
+    revision INT NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('EARNING', 'PENALTY', 'XP_GAIN', 'XP_BURN')),
+    amount DOUBLE PRECISION NOT NULL CHECK (amount <> 0),
+    transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (report_id, revision, user_id, kind)
+);
+
+CREATE INDEX IF NOT EXISTS idx_production_report_postings_user ON production_report_postings(user_id, created_at DESC);
+
+CREATE OR REPLACE FUNCTION set_timestamp_production_reports()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_production_reports_updated_at ON production_reports;
+CREATE TRIGGER trg_production_reports_updated_at
+    BEFORE UPDATE ON production_reports
+    FOR EACH ROW
+    EXECUTE FUNCTION set_timestamp_production_reports();
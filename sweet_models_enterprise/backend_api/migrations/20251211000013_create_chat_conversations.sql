@@ -0,0 +1,52 @@
+-- Chat persistente: conversaciones directas y grupales, mensajes con secuencia por
+-- conversación y acuses de entrega/lectura como marcas de agua por participante
+CREATE TABLE IF NOT EXISTS chat_conversations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('DIRECT', 'GROUP')),
+    title TEXT,
+    -- Par ordenado "a:b" para que dos usuarios tengan una sola conversación directa
+    direct_key TEXT UNIQUE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    last_seq BIGINT NOT NULL DEFAULT 0,
+    last_message_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT chk_direct_key CHECK ((kind = 'DIRECT') = (direct_key IS NOT NULL))
+);
+
+CREATE TABLE IF NOT EXISTS chat_participants (
+    conversation_id UUID NOT NULL REFERENCES chat_conversations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(10) NOT NULL DEFAULT 'MEMBER' CHECK (role IN ('OWNER', 'MEMBER')),
+    delivered_seq BIGINT NOT NULL DEFAULT 0,
+    read_seq BIGINT NOT NULL DEFAULT 0,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (conversation_id, user_id),
+    CONSTRAINT chk_read_after_delivered CHECK (read_seq <= delivered_seq)
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_participants_user ON chat_participants(user_id);
+
+CREATE TABLE IF NOT EXISTS chat_messages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    conversation_id UUID NOT NULL REFERENCES chat_conversations(id) ON DELETE CASCADE,
+    seq BIGINT NOT NULL,
+    sender_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- NULL cuando el mensaje fue borrado (queda la lápida en el historial)
+    content TEXT,
+    -- Id generado por el cliente para que un reintento no duplique el mensaje
+    client_id TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    edited_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    UNIQUE (conversation_id, seq),
+    UNIQUE (sender_id, client_id),
+    CONSTRAINT chk_chat_content_length CHECK (content IS NULL OR char_length(content) <= 4000)
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_messages_history ON chat_messages(conversation_id, seq DESC);
+
+CREATE TRIGGER update_chat_conversations_updated_at
+    BEFORE UPDATE ON chat_conversations
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,20 @@
+-- Llamadas WebRTC: una fila por llamada con su máquina de estados y el registro para el historial
+CREATE TABLE IF NOT EXISTS call_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    caller_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    callee_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    media VARCHAR(10) NOT NULL DEFAULT 'AUDIO' CHECK (media IN ('AUDIO', 'VIDEO')),
+    state VARCHAR(10) NOT NULL DEFAULT 'RINGING'
+        CHECK (state IN ('RINGING', 'ACCEPTED', 'DECLINED', 'MISSED', 'ENDED')),
+    -- TIMEOUT, CANCELLED, DECLINED, HANGUP, DISCONNECT, MAX_DURATION
+    end_reason VARCHAR(20),
+    ended_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    answered_at TIMESTAMPTZ,
+    ended_at TIMESTAMPTZ,
+    CONSTRAINT chk_call_different_users CHECK (caller_id <> callee_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_call_sessions_caller ON call_sessions(caller_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_call_sessions_callee ON call_sessions(callee_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_call_sessions_active ON call_sessions(state) WHERE state IN ('RINGING', 'ACCEPTED');
@@ -0,0 +1,18 @@
+-- Feed: alcance de cada post, hilos de comentarios y borrado lógico
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS visibility VARCHAR(12) NOT NULL DEFAULT 'STUDIO'
+    CHECK (visibility IN ('STUDIO', 'ROOM', 'MODERATORS'));
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS room_id INT REFERENCES rooms(id) ON DELETE RESTRICT;
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE posts ADD CONSTRAINT chk_post_room_scope CHECK ((visibility = 'ROOM') = (room_id IS NOT NULL));
+
+-- Paginación por cursor (created_at, id) sobre los posts vivos
+CREATE INDEX IF NOT EXISTS idx_posts_feed_cursor ON posts(created_at DESC, id DESC) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_posts_room ON posts(room_id, created_at DESC) WHERE room_id IS NOT NULL;
+
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS parent_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS depth SMALLINT NOT NULL DEFAULT 0 CHECK (depth >= 0);
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_comments_thread ON comments(post_id, parent_id, created_at, id);
@@ -0,0 +1,87 @@
+-- Moderación de contenido: estado de cada post/comentario/mensaje, casos en la cola
+-- de moderadores, reportes de usuarios, silenciados y auditoría de cada decisión
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS moderation_status VARCHAR(10) NOT NULL DEFAULT 'VISIBLE'
+    CHECK (moderation_status IN ('VISIBLE', 'PENDING', 'REMOVED'));
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS moderation_status VARCHAR(10) NOT NULL DEFAULT 'VISIBLE'
+    CHECK (moderation_status IN ('VISIBLE', 'PENDING', 'REMOVED'));
+ALTER TABLE chat_messages ADD COLUMN IF NOT EXISTS moderation_status VARCHAR(10) NOT NULL DEFAULT 'VISIBLE'
+    CHECK (moderation_status IN ('VISIBLE', 'PENDING', 'REMOVED'));
+
+CREATE TABLE IF NOT EXISTS moderation_cases (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    target_type VARCHAR(12) NOT NULL CHECK (target_type IN ('POST', 'COMMENT', 'CHAT_MESSAGE')),
+    target_id UUID NOT NULL,
+    author_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Quién abrió el caso: el clasificador automático o un reporte
+    source VARCHAR(10) NOT NULL CHECK (source IN ('CLASSIFIER', 'REPORT')),
+    categories TEXT[] NOT NULL DEFAULT '{}',
+    classifier TEXT,
+    score REAL,
+    report_count INT NOT NULL DEFAULT 0,
+    -- El contenido quedó oculto mientras se revisa
+    hidden BOOLEAN NOT NULL DEFAULT FALSE,
+    status VARCHAR(10) NOT NULL DEFAULT 'OPEN' CHECK (status IN ('OPEN', 'RESOLVED')),
+    resolution VARCHAR(10) CHECK (resolution IN ('APPROVE', 'REMOVE', 'WARN', 'MUTE')),
+    resolution_note TEXT,
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT chk_case_resolution CHECK ((status = 'RESOLVED') = (resolution IS NOT NULL))
+);
+
+-- Un solo caso abierto por contenido: reportes nuevos se suman al existente
+CREATE UNIQUE INDEX IF NOT EXISTS idx_moderation_cases_open
+    ON moderation_cases(target_type, target_id)
+    WHERE status = 'OPEN';
+CREATE INDEX IF NOT EXISTS idx_moderation_cases_queue ON moderation_cases(status, hidden DESC, created_at);
+CREATE INDEX IF NOT EXISTS idx_moderation_cases_author ON moderation_cases(author_id, created_at DESC);
+
+CREATE TRIGGER update_moderation_cases_updated_at
+    BEFORE UPDATE ON moderation_cases
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS moderation_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    case_id UUID NOT NULL REFERENCES moderation_cases(id) ON DELETE CASCADE,
+    target_type VARCHAR(12) NOT NULL CHECK (target_type IN ('POST', 'COMMENT', 'CHAT_MESSAGE')),
+    target_id UUID NOT NULL,
+    reporter_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason VARCHAR(15) NOT NULL
+        CHECK (reason IN ('SPAM', 'HARASSMENT', 'HATE', 'SEXUAL', 'VIOLENCE', 'PERSONAL_DATA', 'OTHER')),
+    details TEXT CHECK (details IS NULL OR char_length(details) <= 1000),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Cada usuario reporta un mismo contenido una sola vez
+    UNIQUE (target_type, target_id, reporter_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_moderation_reports_case ON moderation_reports(case_id);
+
+CREATE TABLE IF NOT EXISTS moderation_mutes (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    muted_until TIMESTAMPTZ NOT NULL,
+    reason TEXT,
+    muted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    case_id UUID REFERENCES moderation_cases(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Auditoría: una fila por decisión, automática (actor_id NULL) o de un moderador
+CREATE TABLE IF NOT EXISTS moderation_decisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    case_id UUID REFERENCES moderation_cases(id) ON DELETE SET NULL,
+    target_type VARCHAR(12) CHECK (target_type IN ('POST', 'COMMENT', 'CHAT_MESSAGE')),
+    target_id UUID,
+    subject_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action VARCHAR(12) NOT NULL
+        CHECK (action IN ('AUTO_HIDE', 'REPORT_HIDE', 'APPROVE', 'REMOVE', 'WARN', 'MUTE', 'UNMUTE')),
+    note TEXT,
+    details JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_moderation_decisions_case ON moderation_decisions(case_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_moderation_decisions_subject ON moderation_decisions(subject_user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_moderation_decisions_created ON moderation_decisions(created_at DESC);
@@ -0,0 +1,31 @@
+-- Archivos subidos por el pipeline de medios: el original (ya sin metadatos si es
+-- imagen) y sus versiones derivadas (miniaturas)
+CREATE TABLE IF NOT EXISTS media_assets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    owner_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('IMAGE', 'VIDEO', 'DOCUMENT')),
+    content_type TEXT NOT NULL,
+    object_key TEXT NOT NULL UNIQUE,
+    url TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+    width INT,
+    height INT,
+    -- TRUE cuando el original se re-codificó sin EXIF/GPS
+    metadata_stripped BOOLEAN NOT NULL DEFAULT FALSE,
+    original_name TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_media_assets_owner ON media_assets(owner_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS media_variants (
+    asset_id UUID NOT NULL REFERENCES media_assets(id) ON DELETE CASCADE,
+    name VARCHAR(20) NOT NULL,
+    object_key TEXT NOT NULL UNIQUE,
+    url TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+    width INT NOT NULL,
+    height INT NOT NULL,
+    PRIMARY KEY (asset_id, name)
+);
@@ -0,0 +1,42 @@
+-- Objetos en buckets privados (contratos, firmas, KYC, evidencias): no tienen URL
+-- permanente, se leen con URLs firmadas que caducan y cada acceso queda registrado
+CREATE TABLE IF NOT EXISTS private_objects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    bucket VARCHAR(10) NOT NULL CHECK (bucket IN ('LEGAL', 'KYC', 'EVIDENCE')),
+    object_key TEXT NOT NULL,
+    owner_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- Para qué se subió (contract_pdf, signature, leave_support...)
+    purpose VARCHAR(40) NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+    original_name TEXT,
+    uploaded_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (bucket, object_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_private_objects_owner ON private_objects(owner_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS storage_access_log (
+    id BIGSERIAL PRIMARY KEY,
+    object_id UUID NOT NULL REFERENCES private_objects(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('UPLOAD', 'PRESIGN')),
+    granted BOOLEAN NOT NULL,
+    ip_address TEXT,
+    user_agent TEXT,
+    -- Caducidad de la URL emitida (solo PRESIGN concedidos)
+    url_expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_storage_access_log_object ON storage_access_log(object_id, created_at DESC);
+
+-- Los contratos nuevos guardan referencias a objetos privados en lugar de URLs públicas;
+-- las URLs de filas antiguas se conservan como histórico
+ALTER TABLE IF EXISTS legal_documents ALTER COLUMN signature_url DROP NOT NULL;
+ALTER TABLE IF EXISTS legal_documents ALTER COLUMN pdf_url DROP NOT NULL;
+ALTER TABLE IF EXISTS legal_documents
+    ADD COLUMN IF NOT EXISTS signature_object_id UUID REFERENCES private_objects(id) ON DELETE SET NULL;
+ALTER TABLE IF EXISTS legal_documents
+    ADD COLUMN IF NOT EXISTS pdf_object_id UUID REFERENCES private_objects(id) ON DELETE SET NULL;
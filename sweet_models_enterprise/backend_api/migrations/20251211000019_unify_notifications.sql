@@ -0,0 +1,20 @@
+-- Notificaciones unificadas: plantillas con idioma, preferencias por categoría,
+-- horas de silencio en la hora local del usuario y estado del push en la bandeja
+ALTER TABLE notification_preferences
+    ADD COLUMN IF NOT EXISTS strike_notifications BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN IF NOT EXISTS reward_notifications BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN IF NOT EXISTS locale VARCHAR(5) NOT NULL DEFAULT 'es' CHECK (locale IN ('es', 'en')),
+    -- Desfase respecto a UTC para evaluar quiet_hours_start/end (Colombia = -300)
+    ADD COLUMN IF NOT EXISTS utc_offset_minutes SMALLINT NOT NULL DEFAULT -300
+        CHECK (utc_offset_minutes BETWEEN -720 AND 840);
+
+ALTER TABLE notifications
+    ADD COLUMN IF NOT EXISTS template VARCHAR(50),
+    -- Clave del evento de dominio que la originó; evita duplicados si NATS reentrega
+    ADD COLUMN IF NOT EXISTS event_id TEXT,
+    ADD COLUMN IF NOT EXISTS push_status VARCHAR(12)
+        CHECK (push_status IN ('SENT', 'FAILED', 'DISABLED', 'QUIET_HOURS', 'NO_DEVICE', 'NO_TRANSPORT'));
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_notifications_event
+    ON notifications(user_id, event_id) WHERE event_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_notifications_inbox ON notifications(user_id, created_at DESC, id DESC);
@@ -0,0 +1,9 @@
+-- Los soportes de ausencias (incapacidades médicas) pasan al bucket privado EVIDENCE;
+-- `document_url` queda solo para filas antiguas hasta migrarlas (POST /api/admin/storage/migrate-legacy)
+ALTER TABLE IF EXISTS leave_requests
+    ADD COLUMN IF NOT EXISTS document_object_id UUID REFERENCES private_objects(id) ON DELETE SET NULL;
+
+-- Una incapacidad médica necesita soporte: URL antigua o documento privado
+ALTER TABLE IF EXISTS leave_requests DROP CONSTRAINT IF EXISTS chk_medical_document;
+ALTER TABLE IF EXISTS leave_requests ADD CONSTRAINT chk_medical_document
+    CHECK (leave_type <> 'MEDICAL' OR document_url IS NOT NULL OR document_object_id IS NOT NULL);
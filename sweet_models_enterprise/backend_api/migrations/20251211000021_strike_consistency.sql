@@ -0,0 +1,14 @@
+-- Auditoría de cambios de XP (quemas y restauraciones); el código ya escribía aquí
+-- pero la tabla solo existía en la documentación
+CREATE TABLE IF NOT EXISTS xp_burn_log (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason VARCHAR(50) NOT NULL,
+    -- Negativo cuando se restaura XP
+    xp_loss BIGINT NOT NULL,
+    previous_xp BIGINT NOT NULL,
+    new_xp BIGINT NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_xp_burn_log_user ON xp_burn_log(user_id, timestamp DESC);
@@ -0,0 +1,8 @@
+-- Eventos de dominio ya notificados, haya o no fila en la bandeja (in-app desactivado):
+-- una reentrega de NATS no vuelve a mandar el push
+CREATE TABLE IF NOT EXISTS notification_events (
+    user_id UUID NOT NULL,
+    event_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, event_id)
+);
@@ -0,0 +1,10 @@
+-- Las llaves de telemetría dejan de guardar el secreto en claro: solo una semilla aleatoria.
+-- El secreto que recibe el dispositivo es HMAC-SHA256(TELEMETRY_KEY_PEPPER, semilla).
+ALTER TABLE telemetry_device_keys ADD COLUMN IF NOT EXISTS secret_seed TEXT;
+
+-- Las llaves existentes no se pueden convertir: se eliminan y cada dispositivo
+-- recibe una nueva al rotar su llave
+DELETE FROM telemetry_device_keys WHERE secret_seed IS NULL;
+
+ALTER TABLE telemetry_device_keys DROP COLUMN IF EXISTS secret;
+ALTER TABLE telemetry_device_keys ALTER COLUMN secret_seed SET NOT NULL;
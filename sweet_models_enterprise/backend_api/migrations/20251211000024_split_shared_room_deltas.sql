@@ -0,0 +1,10 @@
+-- En rooms compartidos el delta se reparte: una fila por modelo presente en la misma muestra.
+-- Las muestras sin nadie presente siguen siendo una sola fila sin dueño.
+ALTER TABLE telemetry_token_deltas DROP CONSTRAINT IF EXISTS telemetry_token_deltas_room_id_platform_sampled_at_key;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_telemetry_deltas_sample_model
+    ON telemetry_token_deltas(room_id, platform, sampled_at, model_id)
+    WHERE model_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_telemetry_deltas_sample_unattributed
+    ON telemetry_token_deltas(room_id, platform, sampled_at)
+    WHERE model_id IS NULL;
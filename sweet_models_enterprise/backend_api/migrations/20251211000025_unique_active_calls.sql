@@ -0,0 +1,26 @@
+-- Una sola llamada activa (sonando o en curso) por usuario en cada lado: el índice único
+-- cierra la carrera entre la comprobación de "ocupado" y el INSERT de la llamada
+
+-- Timbres que quedaron huérfanos (el nodo murió antes de su timeout) pasan a perdidos
+UPDATE call_sessions
+SET state = 'MISSED', end_reason = 'TIMEOUT', ended_at = NOW()
+WHERE state = 'RINGING' AND created_at < NOW() - INTERVAL '45 seconds';
+
+-- Si ya hay duplicados activos se conserva la llamada más reciente
+UPDATE call_sessions c
+SET state = CASE WHEN c.state = 'RINGING' THEN 'MISSED' ELSE 'ENDED' END,
+    end_reason = CASE WHEN c.state = 'RINGING' THEN 'TIMEOUT' ELSE 'HANGUP' END,
+    ended_at = NOW()
+WHERE c.state IN ('RINGING', 'ACCEPTED')
+  AND EXISTS (
+      SELECT 1 FROM call_sessions n
+      WHERE n.state IN ('RINGING', 'ACCEPTED')
+        AND n.id <> c.id
+        AND (n.callee_id = c.callee_id OR n.caller_id = c.caller_id)
+        AND (n.created_at, n.id) > (c.created_at, c.id)
+  );
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_call_sessions_active_callee
+    ON call_sessions(callee_id) WHERE state IN ('RINGING', 'ACCEPTED');
+CREATE UNIQUE INDEX IF NOT EXISTS uq_call_sessions_active_caller
+    ON call_sessions(caller_id) WHERE state IN ('RINGING', 'ACCEPTED');
@@ -0,0 +1,8 @@
+-- Al marcar el pago se guardan los montos con los ajustes de nómina aplicados;
+-- lo aprobado antes de ajustes queda en approved_amount_* para auditoría
+ALTER TABLE payroll_payouts ADD COLUMN IF NOT EXISTS approved_amount_cop NUMERIC(20,2);
+ALTER TABLE payroll_payouts ADD COLUMN IF NOT EXISTS approved_amount_usdt NUMERIC(20,8);
+-- Factor combinado de los ajustes de la semana (1.0 = sin ajuste)
+ALTER TABLE payroll_payouts ADD COLUMN IF NOT EXISTS pay_factor NUMERIC(10,4);
+-- Deducciones fijas por ajustes ya restadas de amount_cop / amount_usdt
+ALTER TABLE payroll_payouts ADD COLUMN IF NOT EXISTS adjustment_deductions_cop NUMERIC(20,2);
@@ -0,0 +1,55 @@
+-- Particiones diarias de telemetría con límites en UTC (no en la zona de la sesión) y que
+-- se pueden crear aunque la partición DEFAULT ya tenga muestras de ese día: se mueven primero
+CREATE OR REPLACE FUNCTION ensure_telemetry_partition(day DATE)
+RETURNS VOID AS $$
+DECLARE
+    part_name TEXT := 'telemetry_samples_' || to_char(day, 'YYYYMMDD');
+    day_from TIMESTAMPTZ := day::TIMESTAMP AT TIME ZONE 'UTC';
+    day_to TIMESTAMPTZ := (day + 1)::TIMESTAMP AT TIME ZONE 'UTC';
+BEGIN
+    IF to_regclass(part_name) IS NOT NULL THEN
+        RETURN;
+    END IF;
+    -- Nadie escribe en DEFAULT mientras se mueven sus filas del día
+    LOCK TABLE telemetry_samples_default IN EXCLUSIVE MODE;
+    EXECUTE format(
+        'CREATE TABLE %I (LIKE telemetry_samples INCLUDING DEFAULTS INCLUDING CONSTRAINTS)',
+        part_name
+    );
+    EXECUTE format(
+        'WITH moved AS (
+             DELETE FROM telemetry_samples_default WHERE sampled_at >= %L AND sampled_at < %L RETURNING *
+         )
+         INSERT INTO %I SELECT * FROM moved',
+        day_from, day_to, part_name
+    );
+    EXECUTE format(
+        'ALTER TABLE telemetry_samples ATTACH PARTITION %I FOR VALUES FROM (%L) TO (%L)',
+        part_name, day_from, day_to
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+-- Retención: elimina particiones diarias anteriores a `cutoff` (día UTC)
+CREATE OR REPLACE FUNCTION drop_telemetry_partitions_before(cutoff DATE)
+RETURNS INT AS $$
+DECLARE
+    part RECORD;
+    dropped INT := 0;
+BEGIN
+    FOR part IN
+        SELECT c.relname
+        FROM pg_inherits i
+        JOIN pg_class c ON c.oid = i.inhrelid
+        JOIN pg_class p ON p.oid = i.inhparent
+        WHERE p.relname = 'telemetry_samples'
+          AND c.relname ~ '^telemetry_samples_[0-9]{8}$'
+          AND to_date(substring(c.relname FROM '[0-9]{8}$'), 'YYYYMMDD') < cutoff
+    LOOP
+        EXECUTE format('DROP TABLE IF EXISTS %I', part.relname);
+        dropped := dropped + 1;
+    END LOOP;
+    DELETE FROM telemetry_samples_default WHERE sampled_at < cutoff::TIMESTAMP AT TIME ZONE 'UTC';
+    RETURN dropped;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,16 @@
+-- Room del estudio de cada registro de producción: el cierre de turno suma solo lo de su room.
+-- Las filas TELEMETRY lo heredan de su borrador; las MANUAL quedan sin room (cuentan para el
+-- room asignado a la modelo esa semana)
+ALTER TABLE production_logs
+    ADD COLUMN IF NOT EXISTS studio_room_id INT REFERENCES rooms(id) ON DELETE SET NULL;
+
+UPDATE production_logs p
+SET studio_room_id = d.studio_room_id
+FROM production_drafts d
+WHERE p.draft_id = d.id AND p.studio_room_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_production_logs_model_room_date
+    ON production_logs(model_id, production_date, studio_room_id);
+
+COMMENT ON COLUMN shift_closeouts.total_tokens IS
+    'Suma de production_logs de los integrantes ese día en el room (no se acepta del cliente)';
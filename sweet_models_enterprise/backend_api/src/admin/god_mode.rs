@@ -178,7 +178,7 @@ async fn fetch_active_rooms(pool: &PgPool, state: &AppState) -> Result<Vec<RoomS
             WHERE DATE(created_at) = CURRENT_DATE
             GROUP BY user_id
         ) t ON t.user_id = a.user_id
-        WHERE r.is_active = true
+        WHERE r.status = 'ACTIVE'
         GROUP BY r.id, r.name, s.id, s.name
         ORDER BY r.id, s.id
         "#,
@@ -7,6 +7,7 @@ const REQUIRED_ENV_VARS: &[(&str, &str)] = &[
     ("REDIS_URL", "Redis connection string (e.g., redis://localhost:6379)"),
     ("NATS_URL", "NATS server URL (e.g., nats://localhost:4222)"),
     ("JWT_SECRET", "Secret key for JWT token signing (use a strong random string)"),
+    ("TELEMETRY_KEY_PEPPER", "Pepper for telemetry device secrets (changing it invalidates every device key)"),
     ("OPENAI_API_KEY", "OpenAI API key for Phoenix auto-repair agent"),
 ];
 
@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::finance::SPREAD_COP;
+use crate::tracking::platforms::{self, PlatformRegistry};
 
 /// Entrada de telemetría recibida desde el Room.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,17 +12,20 @@ pub struct ProductionInput {
     pub room_name: Option<String>,
     /// Indica si el room fue marcado como sucio al cierre del turno
     pub room_dirty: bool,
-    /// Producción bruta por página (tokens)
+    /// Producción bruta por página (tokens); cada página se valora con el token de su plataforma
     pub pages: HashMap<String, f64>,
     /// Modelos activas en el turno
     pub members: Vec<MemberInput>,
-    /// Tasa de conversión COP por token (precio Binance). Si es <= 0, se usa el parámetro por defecto.
+    /// Tasa de conversión COP por USD (precio USDT en Binance). Si es <= 0, se usa el parámetro por defecto.
     pub binance_rate_cop: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemberInput {
     pub model_id: i32,
+    /// Usuario de la modelo; requerido para persistir y contabilizar el reporte
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
     pub name: String,
     /// Número de strikes acumulados en el turno (0-3)
     pub strikes: u8,
@@ -32,6 +39,9 @@ pub struct ProcessedReport {
     pub room_id: i32,
     pub room_name: Option<String>,
     pub gross_tokens: f64,
+    /// Valor USD por página según el token de cada plataforma
+    pub pages_usd: HashMap<String, f64>,
+    pub gross_usd: f64,
     pub studio_tokens: f64,
     pub group_pool_tokens: f64,
     pub studio_revenue_cop: f64,
@@ -45,9 +55,12 @@ pub struct ProcessedReport {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemberPayout {
     pub model_id: i32,
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
     pub name: String,
     pub strikes_applied: u8,
     pub tokens_net: f64,
+    pub usd_net: f64,
     pub money_cop: f64,
     pub xp_gained: i64,
     pub xp_burned: i64,
@@ -69,6 +82,7 @@ pub enum EngineError {
 /// Procesa el reporte de producción aplicando las reglas supremas.
 ///
 /// - Split 40/60 (estudio / bolsa de grupo)
+/// - Dinero: tokens de cada página × valor USD de su plataforma × (tasa - spread)
 /// - XP 1:1 con tokens netos
 /// - Penalización por baja producción (< 1500 tokens): multa $50,000 COP por modelo
 /// - Penalización por room sucio: multa $500,000 COP por modelo
@@ -76,10 +90,27 @@ pub enum EngineError {
 pub fn process_production_report(
     input_json: &str,
     default_binance_rate_cop: f64,
+) -> Result<ProcessedReport, EngineError> {
+    process_production_report_with(input_json, default_binance_rate_cop, platforms::registry())
+}
+
+/// Igual que `process_production_report` pero con un registro de plataformas explícito
+pub fn process_production_report_with(
+    input_json: &str,
+    default_binance_rate_cop: f64,
+    registry: &PlatformRegistry,
 ) -> Result<ProcessedReport, EngineError> {
     let input: ProductionInput = serde_json::from_str(input_json)
         .map_err(|e| EngineError::InvalidJson(e.to_string()))?;
+    process_production_input(input, default_binance_rate_cop, registry)
+}
 
+/// Aplica las reglas a una entrada ya deserializada (pipeline persistido)
+pub fn process_production_input(
+    input: ProductionInput,
+    default_binance_rate_cop: f64,
+    registry: &PlatformRegistry,
+) -> Result<ProcessedReport, EngineError> {
     if input.members.is_empty() {
         return Err(EngineError::NoMembers);
     }
@@ -93,9 +124,18 @@ pub fn process_production_report(
     }
 
     let gross_tokens: f64 = input.pages.values().copied().sum();
+    let pages_usd: HashMap<String, f64> = input
+        .pages
+        .iter()
+        .map(|(page, tokens)| (page.clone(), tokens.max(0.0) * registry.token_usd_value(Some(page))))
+        .collect();
+    let gross_usd: f64 = pages_usd.values().copied().sum();
+    let net_rate = binance_rate - SPREAD_COP;
+
     let studio_tokens = gross_tokens * 0.40;
     let group_pool_tokens = gross_tokens * 0.60;
     let per_member_tokens = group_pool_tokens / input.members.len() as f64;
+    let per_member_usd = gross_usd * 0.60 / input.members.len() as f64;
 
     // Penalizaciones monetarias
     let low_production_penalty = gross_tokens < 1500.0;
@@ -106,7 +146,8 @@ pub fn process_production_report(
 
     for member in input.members.into_iter() {
         let tokens_net = per_member_tokens;
-        let money_cop = tokens_net * (binance_rate - 300.0);
+        let usd_net = per_member_usd;
+        let money_cop = usd_net * net_rate;
         let xp_gained = tokens_net.round() as i64; // relación 1:1
 
         let mut total_xp = member.current_xp + xp_gained;
@@ -146,9 +187,11 @@ pub fn process_production_report(
 
         members.push(MemberPayout {
             model_id: member.model_id,
+            user_id: member.user_id,
             name: member.name,
             strikes_applied: member.strikes,
             tokens_net,
+            usd_net,
             money_cop,
             xp_gained,
             xp_burned,
@@ -158,12 +201,14 @@ pub fn process_production_report(
         });
     }
 
-    let studio_revenue_cop = studio_tokens * (binance_rate - 300.0);
+    let studio_revenue_cop = gross_usd * 0.40 * net_rate;
 
     Ok(ProcessedReport {
         room_id: input.room_id,
         room_name: input.room_name,
         gross_tokens,
+        pages_usd,
+        gross_usd,
         studio_tokens,
         group_pool_tokens,
         studio_revenue_cop,
@@ -173,3 +218,35 @@ pub fn process_production_report(
         total_penalties_cop,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracking::platforms::PlatformConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn pages_are_valued_with_their_platform_token() {
+        let mut registry = PlatformRegistry::builtin();
+        registry.register(Arc::new(
+            serde_json::from_str::<PlatformConfig>(
+                r#"{"id": "bongacams", "display_name": "BongaCams", "token_usd_value": 0.025,
+                    "payout_schedule": "BI_WEEKLY", "fields": ["tokens"]}"#,
+            )
+            .unwrap(),
+        ));
+        let input = r#"{
+            "room_id": 1, "room_name": null, "room_dirty": false,
+            "pages": {"chaturbate": 1000.0, "bongacams": 1000.0},
+            "members": [{"model_id": 1, "name": "A", "strikes": 0, "current_xp": 0}],
+            "binance_rate_cop": 4100.0
+        }"#;
+
+        let report = process_production_report_with(input, 4100.0, &registry).unwrap();
+        assert!((report.gross_usd - 75.0).abs() < 1e-9);
+        assert!((report.members[0].usd_net - 45.0).abs() < 1e-9);
+        assert!((report.members[0].money_cop - 45.0 * 3800.0).abs() < 1e-6);
+        assert!((report.studio_revenue_cop - 30.0 * 3800.0).abs() < 1e-6);
+        assert_eq!(report.members[0].xp_gained, 1200);
+    }
+}
@@ -1,3 +1,7 @@
 pub mod core;
+pub mod reports;
 
-pub use core::{process_production_report, EngineError, MemberPayout, ProcessedReport, ProductionInput};
+pub use core::{process_production_input, process_production_report, process_production_report_with, EngineError, MemberInput, MemberPayout, ProcessedReport, ProductionInput};
+pub use reports::{
+    get_report_handler, list_reports_handler, reprocess_report_handler, submit_report_handler, telemetry_report_handler,
+};
@@ -0,0 +1,1165 @@
+/// Reportes de fin de turno persistidos.
+///
+/// Un `ProductionInput` (enviado por un moderador o armado desde telemetría + roster)
+/// pasa por `process_production_input`, se guarda el `ProcessedReport` y el pago de cada
+/// modelo, y sus efectos se asientan en finanzas (`transactions`, en USD) y gamificación
+/// (`users.xp`, `xp_burn_log` y el rango vía `GamificationEngine`). Los asientos son
+/// diferenciales: cada revisión solo asienta lo que falta contra lo ya asentado, así
+/// reprocesar con la misma entrada no mueve nada y una corrección solo mueve la diferencia.
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{types::Uuid, FromRow, PgPool, Postgres, Transaction};
+
+use super::core::{process_production_input, MemberInput, MemberPayout, ProcessedReport, ProductionInput};
+use crate::{
+    finance::SPREAD_COP,
+    gamification::{self, GamificationEngine},
+    middleware::auth::ModeratorOnly,
+    notifications::{self, DomainEvent},
+    operations::{leave, parse_user_id},
+    state::AppState,
+    tracking::platforms,
+};
+
+pub const SOURCE_SUBMITTED: &str = "SUBMITTED";
+pub const SOURCE_TELEMETRY: &str = "TELEMETRY";
+
+/// Motivo de los asientos de XP en `xp_burn_log`
+const XP_POSTING_REASON: &str = "PRODUCTION_REPORT";
+
+/// Diferencias menores se consideran ya asentadas (redondeo de flotantes)
+const POSTING_EPSILON: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PostingKind {
+    /// Pago neto en USD a la modelo
+    Earning,
+    /// Multas convertidas a USD (monto negativo)
+    Penalty,
+    XpGain,
+    /// XP quemado (monto positivo = XP que se resta)
+    XpBurn,
+}
+
+impl PostingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostingKind::Earning => "EARNING",
+            PostingKind::Penalty => "PENALTY",
+            PostingKind::XpGain => "XP_GAIN",
+            PostingKind::XpBurn => "XP_BURN",
+        }
+    }
+
+    fn is_xp(&self) -> bool {
+        matches!(self, PostingKind::XpGain | PostingKind::XpBurn)
+    }
+}
+
+impl std::str::FromStr for PostingKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "EARNING" => Ok(PostingKind::Earning),
+            "PENALTY" => Ok(PostingKind::Penalty),
+            "XP_GAIN" => Ok(PostingKind::XpGain),
+            "XP_BURN" => Ok(PostingKind::XpBurn),
+            other => Err(format!("Invalid posting kind: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedPosting {
+    pub user_id: Uuid,
+    pub kind: PostingKind,
+    pub amount: f64,
+}
+
+/// Montos que deberían estar asentados para una modelo según el reporte
+pub fn member_targets(payout: &MemberPayout, net_rate: f64, penalties_via_closeout: bool) -> [(PostingKind, f64); 4] {
+    let penalty_usd = if penalties_via_closeout || net_rate <= 0.0 {
+        0.0
+    } else {
+        -payout.penalties_cop / net_rate
+    };
+    [
+        (PostingKind::Earning, payout.usd_net),
+        (PostingKind::Penalty, penalty_usd),
+        (PostingKind::XpGain, payout.xp_gained as f64),
+        (PostingKind::XpBurn, payout.xp_burned as f64),
+    ]
+}
+
+/// Diferencia entre lo que el reporte exige y lo ya asentado. Las modelos que salieron
+/// del reporte en una corrección quedan con objetivo cero (se revierte lo asentado).
+pub fn plan_postings(
+    report: &ProcessedReport,
+    net_rate: f64,
+    penalties_via_closeout: bool,
+    posted: &BTreeMap<(Uuid, PostingKind), f64>,
+) -> Vec<PlannedPosting> {
+    let mut targets: BTreeMap<(Uuid, PostingKind), f64> = posted.keys().map(|key| (*key, 0.0)).collect();
+    for payout in &report.members {
+        let Some(user_id) = payout.user_id else {
+            continue;
+        };
+        for (kind, amount) in member_targets(payout, net_rate, penalties_via_closeout) {
+            *targets.entry((user_id, kind)).or_insert(0.0) += amount;
+        }
+    }
+
+    targets
+        .into_iter()
+        .filter_map(|((user_id, kind), target)| {
+            let delta = target - posted.get(&(user_id, kind)).copied().unwrap_or(0.0);
+            let amount = if kind.is_xp() { delta.round() } else { delta };
+            (amount.abs() > POSTING_EPSILON).then_some(PlannedPosting { user_id, kind, amount })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MemberChange {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemberDiff {
+    pub user_id: Uuid,
+    pub name: String,
+    pub change: MemberChange,
+    pub tokens_net_delta: f64,
+    pub usd_net_delta: f64,
+    pub net_money_cop_delta: f64,
+    pub xp_gained_delta: i64,
+    pub xp_burned_delta: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportDiff {
+    pub gross_tokens_delta: f64,
+    pub gross_usd_delta: f64,
+    pub studio_revenue_cop_delta: f64,
+    pub total_penalties_cop_delta: f64,
+    /// Solo las modelos cuyo pago o XP cambió
+    pub members: Vec<MemberDiff>,
+}
+
+/// Compara dos procesamientos del mismo turno por modelo (user_id)
+pub fn diff_reports(old: &ProcessedReport, new: &ProcessedReport) -> ReportDiff {
+    let by_user = |report: &ProcessedReport| -> BTreeMap<Uuid, MemberPayout> {
+        report
+            .members
+            .iter()
+            .filter_map(|m| m.user_id.map(|id| (id, m.clone())))
+            .collect()
+    };
+    let old_members = by_user(old);
+    let new_members = by_user(new);
+
+    let mut users: Vec<Uuid> = old_members.keys().chain(new_members.keys()).copied().collect();
+    users.sort();
+    users.dedup();
+
+    let members = users
+        .into_iter()
+        .filter_map(|user_id| {
+            let before = old_members.get(&user_id);
+            let after = new_members.get(&user_id);
+            let change = match (before, after) {
+                (None, Some(_)) => MemberChange::Added,
+                (Some(_), None) => MemberChange::Removed,
+                _ => MemberChange::Changed,
+            };
+            let field = |f: fn(&MemberPayout) -> f64| after.map_or(0.0, f) - before.map_or(0.0, f);
+            let xp = |f: fn(&MemberPayout) -> i64| after.map_or(0, f) - before.map_or(0, f);
+            let diff = MemberDiff {
+                user_id,
+                name: after.or(before).map(|m| m.name.clone()).unwrap_or_default(),
+                change,
+                tokens_net_delta: field(|m| m.tokens_net),
+                usd_net_delta: field(|m| m.usd_net),
+                net_money_cop_delta: field(|m| m.net_money_cop),
+                xp_gained_delta: xp(|m| m.xp_gained),
+                xp_burned_delta: xp(|m| m.xp_burned),
+            };
+            let unchanged = diff.change == MemberChange::Changed
+                && diff.tokens_net_delta.abs() <= POSTING_EPSILON
+                && diff.usd_net_delta.abs() <= POSTING_EPSILON
+                && diff.net_money_cop_delta.abs() <= POSTING_EPSILON
+                && diff.xp_gained_delta == 0
+                && diff.xp_burned_delta == 0;
+            (!unchanged).then_some(diff)
+        })
+        .collect();
+
+    ReportDiff {
+        gross_tokens_delta: new.gross_tokens - old.gross_tokens,
+        gross_usd_delta: new.gross_usd - old.gross_usd,
+        studio_revenue_cop_delta: new.studio_revenue_cop - old.studio_revenue_cop,
+        total_penalties_cop_delta: new.total_penalties_cop - old.total_penalties_cop,
+        members,
+    }
+}
+
+/// Cada modelo debe traer user_id y aparecer una sola vez
+pub fn validate_members(members: &[MemberInput]) -> Result<Vec<Uuid>, String> {
+    let mut seen = HashSet::new();
+    members
+        .iter()
+        .map(|m| {
+            let user_id = m.user_id.ok_or(format!("Member '{}' has no user_id", m.name))?;
+            if !seen.insert(user_id) {
+                return Err(format!("Member {} appears more than once", user_id));
+            }
+            Ok(user_id)
+        })
+        .collect()
+}
+
+/// Huella estable de la entrada (serde_json::Value ordena las llaves de `pages`)
+pub fn input_fingerprint(input: &ProductionInput) -> String {
+    let canonical = serde_json::to_value(input).map(|v| v.to_string()).unwrap_or_default();
+    hex::encode(Sha256::digest(canonical.as_bytes()))
+}
+
+// ============================================================================
+// PERSISTENCIA
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ProductionReportRow {
+    pub id: Uuid,
+    pub room_id: i32,
+    pub shift_id: i32,
+    pub work_date: NaiveDate,
+    pub source: String,
+    pub revision: i32,
+    pub input: serde_json::Value,
+    pub input_sha256: String,
+    pub report: serde_json::Value,
+    pub binance_rate_cop: f64,
+    pub gross_tokens: f64,
+    pub gross_usd: f64,
+    pub studio_revenue_cop: f64,
+    pub total_penalties_cop: f64,
+    pub penalties_via_closeout: bool,
+    pub processed_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const REPORT_COLUMNS: &str = "id, room_id, shift_id, work_date, source, revision, input, input_sha256, report, \
+    binance_rate_cop, gross_tokens, gross_usd, studio_revenue_cop, total_penalties_cop, penalties_via_closeout, \
+    processed_by, created_at, updated_at";
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PostingRow {
+    pub id: Uuid,
+    pub revision: i32,
+    pub user_id: Uuid,
+    pub kind: String,
+    pub amount: f64,
+    pub transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct RevisionRow {
+    pub revision: i32,
+    pub diff: Option<serde_json::Value>,
+    pub reason: Option<String>,
+    pub processed_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Turno al que pertenece un reporte
+#[derive(Debug, Clone, Copy)]
+struct ShiftKey {
+    room_id: i32,
+    shift_id: i32,
+    work_date: NaiveDate,
+}
+
+/// Quién procesa, desde dónde y por qué
+struct ProcessContext {
+    source: &'static str,
+    actor: Uuid,
+    reason: Option<String>,
+    dry_run: bool,
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("DB error in production reports: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn stored_report(row: &ProductionReportRow) -> Result<(ProductionInput, ProcessedReport), (StatusCode, String)> {
+    let input = serde_json::from_value(row.input.clone());
+    let report = serde_json::from_value(row.report.clone());
+    match (input, report) {
+        (Ok(input), Ok(report)) => Ok((input, report)),
+        _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Reporte {} almacenado ilegible", row.id))),
+    }
+}
+
+async fn fetch_report(pool: &PgPool, id: Uuid) -> Result<ProductionReportRow, (StatusCode, String)> {
+    sqlx::query_as::<_, ProductionReportRow>(&format!("SELECT {REPORT_COLUMNS} FROM production_reports WHERE id = $1"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Reporte no encontrado".to_string()))
+}
+
+async fn find_report(pool: &PgPool, key: ShiftKey) -> Result<Option<ProductionReportRow>, (StatusCode, String)> {
+    sqlx::query_as::<_, ProductionReportRow>(&format!(
+        "SELECT {REPORT_COLUMNS} FROM production_reports WHERE room_id = $1 AND shift_id = $2 AND work_date = $3"
+    ))
+    .bind(key.room_id)
+    .bind(key.shift_id)
+    .bind(key.work_date)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)
+}
+
+async fn default_binance_rate(pool: &PgPool) -> Result<f64, (StatusCode, String)> {
+    sqlx::query_scalar::<_, f64>("SELECT current_dollar_rate::DOUBLE PRECISION FROM system_settings WHERE id = 1")
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "system_settings row missing".to_string()))
+}
+
+/// ¿Hay cierre de turno confirmado? En ese caso las multas y el veredicto de room sucio
+/// ya los aplicó operations::room y el reporte no los vuelve a cobrar.
+async fn closeout_confirmed(pool: &PgPool, key: ShiftKey) -> Result<bool, (StatusCode, String)> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM shift_closeouts
+            WHERE room_id = $1 AND shift_id = $2 AND work_date = $3 AND status = 'CONFIRMED'
+        )
+        "#,
+    )
+    .bind(key.room_id)
+    .bind(key.shift_id)
+    .bind(key.work_date)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error)
+}
+
+/// XP actual de las modelos, la base sobre la que el motor calcula quemas
+async fn current_xp(pool: &PgPool, users: &[Uuid]) -> Result<HashMap<Uuid, i64>, (StatusCode, String)> {
+    let rows = sqlx::query_as::<_, (Uuid, i64)>("SELECT id, COALESCE(xp, 0)::BIGINT FROM users WHERE id = ANY($1)")
+        .bind(users)
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)?;
+    let found: HashMap<Uuid, i64> = rows.into_iter().collect();
+    if let Some(missing) = users.iter().find(|u| !found.contains_key(u)) {
+        return Err((StatusCode::NOT_FOUND, format!("Usuario {} no encontrado", missing)));
+    }
+    Ok(found)
+}
+
+/// Arma la entrada del motor desde los deltas atribuidos al room/turno y el roster.
+/// Los strikes no se pasan al motor: su quema de XP ya la aplica operations::strikes.
+pub async fn build_input_from_telemetry(
+    pool: &PgPool,
+    room_id: i32,
+    shift_id: i32,
+    work_date: NaiveDate,
+    room_dirty: bool,
+    binance_rate_cop: Option<f64>,
+) -> Result<ProductionInput, (StatusCode, String)> {
+    let room_name = sqlx::query_scalar::<_, String>("SELECT name FROM rooms WHERE id = $1")
+        .bind(room_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Room no encontrado".to_string()))?;
+
+    let pages: HashMap<String, f64> = sqlx::query_as::<_, (String, f64)>(
+        r#"
+        SELECT platform, SUM(tokens_delta)::DOUBLE PRECISION
+        FROM telemetry_token_deltas
+        WHERE studio_room_id = $1 AND shift_id = $2 AND work_date = $3
+        GROUP BY platform
+        "#,
+    )
+    .bind(room_id)
+    .bind(shift_id)
+    .bind(work_date)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .collect();
+
+    let week_id = format!("{}-W{:02}", work_date.iso_week().year(), work_date.iso_week().week());
+    let roster = sqlx::query_as::<_, (Uuid, String)>(
+        r#"
+        SELECT us.user_id, COALESCE(u.username, u.email)
+        FROM user_shifts us
+        JOIN users u ON u.id = us.user_id
+        WHERE us.assigned_room = $1 AND us.assigned_shift = $2 AND us.week_id = $3
+        ORDER BY us.created_at
+        "#,
+    )
+    .bind(room_id)
+    .bind(shift_id)
+    .bind(&week_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)?;
+
+    let ids: Vec<Uuid> = roster.iter().map(|(id, _)| *id).collect();
+    let on_leave = leave::members_on_leave(&ids, work_date, pool).await.map_err(db_error)?;
+    let members: Vec<MemberInput> = roster
+        .into_iter()
+        .filter(|(id, _)| !on_leave.contains(id))
+        .enumerate()
+        .map(|(i, (user_id, name))| MemberInput {
+            model_id: i as i32 + 1,
+            user_id: Some(user_id),
+            name,
+            strikes: 0,
+            current_xp: 0,
+        })
+        .collect();
+
+    if members.is_empty() {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "El roster del turno no tiene modelos disponibles".to_string(),
+        ));
+    }
+
+    Ok(ProductionInput {
+        room_id,
+        room_name: Some(room_name),
+        room_dirty,
+        pages,
+        members,
+        binance_rate_cop,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProcessOutcome {
+    /// None en simulación (dry_run) de un reporte nuevo
+    pub report_id: Option<Uuid>,
+    pub revision: i32,
+    /// false si la entrada es idéntica a la revisión vigente (nada se asentó)
+    pub changed: bool,
+    pub dry_run: bool,
+    pub penalties_via_closeout: bool,
+    pub report: ProcessedReport,
+    pub diff: Option<ReportDiff>,
+    pub postings: Vec<PlannedPosting>,
+}
+
+async fn posted_totals(
+    tx: &mut Transaction<'_, Postgres>,
+    report_id: Uuid,
+) -> Result<BTreeMap<(Uuid, PostingKind), f64>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (Uuid, String, f64)>(
+        "SELECT user_id, kind, SUM(amount)::DOUBLE PRECISION FROM production_report_postings WHERE report_id = $1 GROUP BY user_id, kind",
+    )
+    .bind(report_id)
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(user_id, kind, amount)| kind.parse::<PostingKind>().ok().map(|k| ((user_id, k), amount)))
+        .collect())
+}
+
+/// Asienta una diferencia: fila en el libro de asientos y su efecto en finanzas o XP.
+/// Si la fila ya existe (reintento de la misma revisión) no se repite el efecto; devuelve si se asentó.
+/// Las quemas pasan por gamificación (`xp_burn_log`); el rango de las ganancias se sube tras el commit.
+async fn apply_posting(
+    tx: &mut Transaction<'_, Postgres>,
+    report_id: Uuid,
+    revision: i32,
+    posting: &PlannedPosting,
+) -> Result<bool, (StatusCode, String)> {
+    let posting_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO production_report_postings (report_id, revision, user_id, kind, amount)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (report_id, revision, user_id, kind) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(report_id)
+    .bind(revision)
+    .bind(posting.user_id)
+    .bind(posting.kind.as_str())
+    .bind(posting.amount)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(db_error)?;
+    let Some(posting_id) = posting_id else {
+        return Ok(false);
+    };
+
+    match posting.kind {
+        PostingKind::Earning | PostingKind::Penalty => {
+            let tx_type = if posting.kind == PostingKind::Earning { "EARNING" } else { "PENALTY" };
+            let transaction_id = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO transactions (user_id, amount, type, reference, status)
+                VALUES ($1, $2, $3::transaction_type, $4, 'CONFIRMED')
+                RETURNING id
+                "#,
+            )
+            .bind(posting.user_id)
+            .bind(posting.amount)
+            .bind(tx_type)
+            .bind(format!("production_report:{}:r{}", report_id, revision))
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(db_error)?;
+            sqlx::query("UPDATE production_report_postings SET transaction_id = $2 WHERE id = $1")
+                .bind(posting_id)
+                .bind(transaction_id)
+                .execute(&mut **tx)
+                .await
+                .map_err(db_error)?;
+        }
+        PostingKind::XpGain => {
+            sqlx::query(
+                r#"
+                UPDATE users
+                SET xp = GREATEST(COALESCE(xp, 0) + $2, 0),
+                    total_xp_earned = COALESCE(total_xp_earned, 0) + $2,
+                    updated_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(posting.user_id)
+            .bind(posting.amount as i64)
+            .execute(&mut **tx)
+            .await
+            .map_err(db_error)?;
+        }
+        // Una corrección a la baja de la quema devuelve XP por la misma vía que las apelaciones
+        PostingKind::XpBurn => {
+            let amount = posting.amount as i64;
+            let result = if amount >= 0 {
+                gamification::burn_xp_amount(posting.user_id, amount, XP_POSTING_REASON, tx).await
+            } else {
+                gamification::restore_burned_xp(posting.user_id, -amount, XP_POSTING_REASON, tx).await
+            };
+            result.map_err(|e| {
+                tracing::error!("XP burn failed for production report {}: {}", report_id, e);
+                (StatusCode::INTERNAL_SERVER_ERROR, e)
+            })?;
+        }
+    }
+    Ok(true)
+}
+
+/// Procesa (o reprocesa) el reporte de un turno y asienta sus efectos en una sola transacción
+async fn process_report(
+    state: &AppState,
+    key: ShiftKey,
+    existing: Option<ProductionReportRow>,
+    mut input: ProductionInput,
+    ctx: ProcessContext,
+) -> Result<ProcessOutcome, (StatusCode, String)> {
+    let ProcessContext { source, actor, reason, dry_run } = ctx;
+    let pool = &state.db;
+    if input.room_id != key.room_id {
+        return Err((StatusCode::BAD_REQUEST, "input.room_id does not match the report room".to_string()));
+    }
+    let users = validate_members(&input.members).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let previous = existing.as_ref().map(stored_report).transpose()?;
+
+    // La base de XP se fija en el primer procesamiento; una corrección no vuelve a quemar sobre XP ya quemado
+    let previous_xp: HashMap<Uuid, i64> = previous
+        .as_ref()
+        .map(|(old_input, _)| {
+            old_input
+                .members
+                .iter()
+                .filter_map(|m| m.user_id.map(|id| (id, m.current_xp)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let live_xp = current_xp(pool, &users).await?;
+    for member in input.members.iter_mut() {
+        let user_id = member.user_id.unwrap_or_default();
+        member.current_xp = previous_xp.get(&user_id).or(live_xp.get(&user_id)).copied().unwrap_or(0);
+        // Igual que desde telemetría: operations::strikes ya quemó el XP de cada strike al aplicarlo
+        member.strikes = 0;
+    }
+
+    let penalties_via_closeout = closeout_confirmed(pool, key).await?;
+    if penalties_via_closeout {
+        input.room_dirty = false;
+    }
+
+    let binance_rate_cop = match input.binance_rate_cop.filter(|r| *r > 0.0) {
+        Some(rate) => rate,
+        None => default_binance_rate(pool).await?,
+    };
+    input.binance_rate_cop = Some(binance_rate_cop);
+    let net_rate = binance_rate_cop - SPREAD_COP;
+    if net_rate <= 0.0 {
+        return Err((StatusCode::BAD_REQUEST, format!("Binance rate must exceed the {} COP spread", SPREAD_COP)));
+    }
+
+    let fingerprint = input_fingerprint(&input);
+    if let (Some(row), Some((_, old_report))) = (&existing, &previous) {
+        if row.input_sha256 == fingerprint {
+            return Ok(ProcessOutcome {
+                report_id: Some(row.id),
+                revision: row.revision,
+                changed: false,
+                dry_run,
+                penalties_via_closeout: row.penalties_via_closeout,
+                report: old_report.clone(),
+                diff: None,
+                postings: Vec::new(),
+            });
+        }
+    }
+
+    let report = process_production_input(input.clone(), binance_rate_cop, platforms::registry())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let diff = previous.as_ref().map(|(_, old_report)| diff_reports(old_report, &report));
+    let revision = existing.as_ref().map_or(1, |r| r.revision + 1);
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let report_id = match &existing {
+        Some(row) => {
+            // Bloquea la fila y verifica que nadie haya reprocesado en paralelo
+            let locked = sqlx::query_scalar::<_, i32>("SELECT revision FROM production_reports WHERE id = $1 FOR UPDATE")
+                .bind(row.id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            if locked != Some(row.revision) {
+                return Err((StatusCode::CONFLICT, "El reporte cambió mientras se procesaba; reintenta".to_string()));
+            }
+            row.id
+        }
+        None => {
+            if dry_run {
+                Uuid::nil()
+            } else {
+                sqlx::query_scalar::<_, Uuid>(
+                    r#"
+                    INSERT INTO production_reports
+                        (room_id, shift_id, work_date, source, input, input_sha256, report, binance_rate_cop,
+                         gross_tokens, gross_usd, studio_revenue_cop, total_penalties_cop, penalties_via_closeout, processed_by)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                    ON CONFLICT (room_id, shift_id, work_date) DO NOTHING
+                    RETURNING id
+                    "#,
+                )
+                .bind(key.room_id)
+                .bind(key.shift_id)
+                .bind(key.work_date)
+                .bind(source)
+                .bind(serde_json::to_value(&input).unwrap_or_default())
+                .bind(&fingerprint)
+                .bind(serde_json::to_value(&report).unwrap_or_default())
+                .bind(binance_rate_cop)
+                .bind(report.gross_tokens)
+                .bind(report.gross_usd)
+                .bind(report.studio_revenue_cop)
+                .bind(report.total_penalties_cop)
+                .bind(penalties_via_closeout)
+                .bind(actor)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db_error)?
+                .ok_or((StatusCode::CONFLICT, "El turno ya tiene reporte; usa reprocess".to_string()))?
+            }
+        }
+    };
+
+    let posted = if existing.is_some() {
+        posted_totals(&mut tx, report_id).await.map_err(db_error)?
+    } else {
+        BTreeMap::new()
+    };
+    let postings = plan_postings(&report, net_rate, penalties_via_closeout, &posted);
+
+    if dry_run {
+        tx.rollback().await.map_err(db_error)?;
+        return Ok(ProcessOutcome {
+            report_id: existing.as_ref().map(|r| r.id),
+            revision,
+            changed: true,
+            dry_run,
+            penalties_via_closeout,
+            report,
+            diff,
+            postings,
+        });
+    }
+
+    if existing.is_some() {
+        sqlx::query(
+            r#"
+            UPDATE production_reports
+            SET revision = $2, source = $3, input = $4, input_sha256 = $5, report = $6, binance_rate_cop = $7,
+                gross_tokens = $8, gross_usd = $9, studio_revenue_cop = $10, total_penalties_cop = $11,
+                penalties_via_closeout = $12, processed_by = $13
+            WHERE id = $1
+            "#,
+        )
+        .bind(report_id)
+        .bind(revision)
+        .bind(source)
+        .bind(serde_json::to_value(&input).unwrap_or_default())
+        .bind(&fingerprint)
+        .bind(serde_json::to_value(&report).unwrap_or_default())
+        .bind(binance_rate_cop)
+        .bind(report.gross_tokens)
+        .bind(report.gross_usd)
+        .bind(report.studio_revenue_cop)
+        .bind(report.total_penalties_cop)
+        .bind(penalties_via_closeout)
+        .bind(actor)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO production_report_revisions (report_id, revision, input, report, diff, reason, processed_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(report_id)
+    .bind(revision)
+    .bind(serde_json::to_value(&input).unwrap_or_default())
+    .bind(serde_json::to_value(&report).unwrap_or_default())
+    .bind(diff.as_ref().and_then(|d| serde_json::to_value(d).ok()))
+    .bind(&reason)
+    .bind(actor)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    sqlx::query("DELETE FROM production_report_members WHERE report_id = $1")
+        .bind(report_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    for payout in &report.members {
+        sqlx::query(
+            r#"
+            INSERT INTO production_report_members
+                (report_id, user_id, model_id, name, strikes_applied, tokens_net, usd_net, money_cop,
+                 penalties_cop, net_money_cop, xp_gained, xp_burned, xp_after_burn)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+        )
+        .bind(report_id)
+        .bind(payout.user_id)
+        .bind(payout.model_id)
+        .bind(&payout.name)
+        .bind(payout.strikes_applied as i16)
+        .bind(payout.tokens_net)
+        .bind(payout.usd_net)
+        .bind(payout.money_cop)
+        .bind(payout.penalties_cop)
+        .bind(payout.net_money_cop)
+        .bind(payout.xp_gained)
+        .bind(payout.xp_burned)
+        .bind(payout.xp_after_burn)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    }
+
+    let mut xp_gains = Vec::new();
+    for posting in &postings {
+        if apply_posting(&mut tx, report_id, revision, posting).await? && posting.kind == PostingKind::XpGain {
+            xp_gains.push((posting.user_id, posting.amount as i64));
+        }
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO audit_trail (entity_type, entity_id, action, old_value, new_value, user_id)
+        VALUES ('production_report', $1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(report_id)
+    .bind(if existing.is_some() { "update" } else { "create" })
+    .bind(existing.as_ref().map(|r| serde_json::json!({ "revision": r.revision, "input_sha256": r.input_sha256 })))
+    .bind(serde_json::json!({
+        "revision": revision,
+        "input_sha256": fingerprint,
+        "reason": reason,
+        "postings": postings,
+    }))
+    .bind(actor)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    // Rango (user_levels) y aviso de rank_up, como cualquier otra ganancia de XP
+    let engine = GamificationEngine::new(pool.clone());
+    let xp_reason = format!("production_report:{}:r{}", report_id, revision);
+    for (user_id, amount) in xp_gains {
+        match engine.add_xp(user_id, amount, &xp_reason).await {
+            Ok(Some(level_up)) => notifications::events::publish(&state.nats, &DomainEvent::from(&level_up)).await,
+            Ok(None) => {}
+            Err(e) => tracing::warn!("No se pudo sumar XP de rango a {} ({}): {}", user_id, xp_reason, e),
+        }
+    }
+
+    tracing::info!(
+        "🧾 Reporte de producción room {} turno {} {} r{}: {} tokens, {} asientos",
+        key.room_id,
+        key.shift_id,
+        key.work_date,
+        revision,
+        report.gross_tokens,
+        postings.len()
+    );
+
+    Ok(ProcessOutcome {
+        report_id: Some(report_id),
+        revision,
+        changed: true,
+        dry_run,
+        penalties_via_closeout,
+        report,
+        diff,
+        postings,
+    })
+}
+
+// ============================================================================
+// HANDLERS
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitReportRequest {
+    pub shift_id: i32,
+    pub work_date: NaiveDate,
+    pub input: ProductionInput,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn validate_shift(shift_id: i32, work_date: NaiveDate) -> Result<(), (StatusCode, String)> {
+    if !(1..=4).contains(&shift_id) {
+        return Err((StatusCode::BAD_REQUEST, "shift_id must be between 1 and 4".to_string()));
+    }
+    if work_date > Utc::now().date_naive() {
+        return Err((StatusCode::BAD_REQUEST, "Date cannot be in the future".to_string()));
+    }
+    Ok(())
+}
+
+/// POST /api/admin/production/reports
+/// Reporte de fin de turno enviado por un moderador. Si el turno ya tiene reporte: 409 (usar reprocess).
+pub async fn submit_report_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Json(payload): Json<SubmitReportRequest>,
+) -> Result<Json<ProcessOutcome>, (StatusCode, String)> {
+    let actor = parse_user_id(&moderator.user_id)?;
+    validate_shift(payload.shift_id, payload.work_date)?;
+    let key = ShiftKey { room_id: payload.input.room_id, shift_id: payload.shift_id, work_date: payload.work_date };
+
+    if let Some(existing) = find_report(&state.db, key).await? {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("El turno ya tiene reporte {}; usa /reprocess para corregirlo", existing.id),
+        ));
+    }
+    let ctx = ProcessContext { source: SOURCE_SUBMITTED, actor, reason: None, dry_run: payload.dry_run };
+    let outcome = process_report(&state, key, None, payload.input, ctx).await?;
+    Ok(Json(outcome))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelemetryReportRequest {
+    pub room_id: i32,
+    pub shift_id: i32,
+    pub work_date: NaiveDate,
+    #[serde(default)]
+    pub room_dirty: bool,
+    pub binance_rate_cop: Option<f64>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// POST /api/admin/production/reports/from-telemetry
+/// Arma la entrada desde telemetría + roster; si el turno ya tiene reporte lo reprocesa.
+pub async fn telemetry_report_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Json(payload): Json<TelemetryReportRequest>,
+) -> Result<Json<ProcessOutcome>, (StatusCode, String)> {
+    let actor = parse_user_id(&moderator.user_id)?;
+    validate_shift(payload.shift_id, payload.work_date)?;
+    let key = ShiftKey { room_id: payload.room_id, shift_id: payload.shift_id, work_date: payload.work_date };
+
+    let input = build_input_from_telemetry(
+        &state.db,
+        payload.room_id,
+        payload.shift_id,
+        payload.work_date,
+        payload.room_dirty,
+        payload.binance_rate_cop,
+    )
+    .await?;
+    let existing = find_report(&state.db, key).await?;
+    let reason = existing.as_ref().map(|_| "Reconstruido desde telemetría".to_string());
+    let ctx = ProcessContext { source: SOURCE_TELEMETRY, actor, reason, dry_run: payload.dry_run };
+    let outcome = process_report(&state, key, existing, input, ctx).await?;
+    Ok(Json(outcome))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReprocessRequest {
+    pub input: ProductionInput,
+    pub reason: String,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// POST /api/admin/production/reports/:id/reprocess
+/// Corrige la entrada; responde el diff contra la revisión vigente y asienta solo la diferencia.
+pub async fn reprocess_report_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ReprocessRequest>,
+) -> Result<Json<ProcessOutcome>, (StatusCode, String)> {
+    let actor = parse_user_id(&moderator.user_id)?;
+    if payload.reason.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "reason is required".to_string()));
+    }
+    let existing = fetch_report(&state.db, id).await?;
+    let key = ShiftKey { room_id: existing.room_id, shift_id: existing.shift_id, work_date: existing.work_date };
+    let ctx = ProcessContext { source: SOURCE_SUBMITTED, actor, reason: Some(payload.reason), dry_run: payload.dry_run };
+    let outcome = process_report(&state, key, Some(existing), payload.input, ctx).await?;
+    Ok(Json(outcome))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportsQuery {
+    pub date: Option<NaiveDate>,
+    pub room_id: Option<i32>,
+}
+
+/// GET /api/admin/production/reports?date=&room_id=
+pub async fn list_reports_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Query(query): Query<ReportsQuery>,
+) -> Result<Json<Vec<ProductionReportRow>>, (StatusCode, String)> {
+    let reports = sqlx::query_as::<_, ProductionReportRow>(&format!(
+        r#"
+        SELECT {REPORT_COLUMNS} FROM production_reports
+        WHERE ($1::DATE IS NULL OR work_date = $1) AND ($2::INT IS NULL OR room_id = $2)
+        ORDER BY work_date DESC, room_id, shift_id
+        LIMIT 200
+        "#
+    ))
+    .bind(query.date)
+    .bind(query.room_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(reports))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportDetail {
+    pub report: ProductionReportRow,
+    pub revisions: Vec<RevisionRow>,
+    pub postings: Vec<PostingRow>,
+}
+
+/// GET /api/admin/production/reports/:id
+pub async fn get_report_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReportDetail>, (StatusCode, String)> {
+    let report = fetch_report(&state.db, id).await?;
+    let revisions = sqlx::query_as::<_, RevisionRow>(
+        "SELECT revision, diff, reason, processed_by, created_at FROM production_report_revisions WHERE report_id = $1 ORDER BY revision",
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    let postings = sqlx::query_as::<_, PostingRow>(
+        r#"
+        SELECT id, revision, user_id, kind, amount, transaction_id, created_at
+        FROM production_report_postings
+        WHERE report_id = $1
+        ORDER BY revision, user_id, kind
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(ReportDetail { report, revisions, postings }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payout(user_id: Uuid, usd_net: f64, penalties_cop: f64, xp_gained: i64, xp_burned: i64) -> MemberPayout {
+        MemberPayout {
+            model_id: 1,
+            user_id: Some(user_id),
+            name: "A".to_string(),
+            strikes_applied: 0,
+            tokens_net: usd_net * 20.0,
+            usd_net,
+            money_cop: usd_net * 3800.0,
+            xp_gained,
+            xp_burned,
+            xp_after_burn: xp_gained - xp_burned,
+            penalties_cop,
+            net_money_cop: usd_net * 3800.0 - penalties_cop,
+        }
+    }
+
+    fn report(members: Vec<MemberPayout>) -> ProcessedReport {
+        ProcessedReport {
+            room_id: 1,
+            room_name: None,
+            gross_tokens: members.iter().map(|m| m.tokens_net).sum::<f64>() / 0.6,
+            pages_usd: HashMap::new(),
+            gross_usd: members.iter().map(|m| m.usd_net).sum::<f64>() / 0.6,
+            studio_tokens: 0.0,
+            group_pool_tokens: 0.0,
+            studio_revenue_cop: 0.0,
+            total_penalties_cop: members.iter().map(|m| m.penalties_cop).sum(),
+            members,
+            low_production_penalty: false,
+            room_dirty_penalty: false,
+        }
+    }
+
+    #[test]
+    fn first_processing_posts_full_targets() {
+        let a = Uuid::new_v4();
+        let postings = plan_postings(&report(vec![payout(a, 30.0, 38_000.0, 600, 60)]), 3800.0, false, &BTreeMap::new());
+        assert_eq!(postings.len(), 4);
+        assert_eq!(postings[0], PlannedPosting { user_id: a, kind: PostingKind::Earning, amount: 30.0 });
+        assert!((postings[1].amount + 10.0).abs() < 1e-9);
+        assert_eq!(postings[2].amount, 600.0);
+        assert_eq!(postings[3].amount, 60.0);
+    }
+
+    #[test]
+    fn reprocessing_same_report_posts_nothing() {
+        let a = Uuid::new_v4();
+        let r = report(vec![payout(a, 30.0, 38_000.0, 600, 60)]);
+        let posted: BTreeMap<_, _> = plan_postings(&r, 3800.0, false, &BTreeMap::new())
+            .into_iter()
+            .map(|p| ((p.user_id, p.kind), p.amount))
+            .collect();
+        assert!(plan_postings(&r, 3800.0, false, &posted).is_empty());
+    }
+
+    #[test]
+    fn correction_posts_only_the_difference_and_reverses_removed_members() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut posted = BTreeMap::new();
+        posted.insert((a, PostingKind::Earning), 30.0);
+        posted.insert((a, PostingKind::XpGain), 600.0);
+        posted.insert((b, PostingKind::Earning), 30.0);
+        posted.insert((b, PostingKind::XpGain), 600.0);
+
+        let postings = plan_postings(&report(vec![payout(a, 45.0, 0.0, 900, 0)]), 3800.0, false, &posted);
+        let find = |user_id, kind| postings.iter().find(|p| p.user_id == user_id && p.kind == kind).map(|p| p.amount);
+        assert_eq!(postings.len(), 4);
+        assert_eq!(find(a, PostingKind::Earning), Some(15.0));
+        assert_eq!(find(a, PostingKind::XpGain), Some(300.0));
+        assert_eq!(find(b, PostingKind::Earning), Some(-30.0));
+        assert_eq!(find(b, PostingKind::XpGain), Some(-600.0));
+    }
+
+    #[test]
+    fn closeout_penalties_are_not_posted_twice() {
+        let a = Uuid::new_v4();
+        let postings = plan_postings(&report(vec![payout(a, 30.0, 550_000.0, 600, 0)]), 3800.0, true, &BTreeMap::new());
+        assert!(postings.iter().all(|p| p.kind != PostingKind::Penalty));
+    }
+
+    #[test]
+    fn diff_reports_lists_changed_added_and_removed_members() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let old = report(vec![payout(a, 30.0, 0.0, 600, 0), payout(b, 30.0, 0.0, 600, 0)]);
+        let new = report(vec![payout(a, 30.0, 0.0, 600, 0), payout(c, 40.0, 0.0, 800, 0)]);
+
+        let diff = diff_reports(&old, &new);
+        assert_eq!(diff.members.len(), 2);
+        let removed = diff.members.iter().find(|m| m.user_id == b).unwrap();
+        assert_eq!(removed.change, MemberChange::Removed);
+        assert_eq!(removed.xp_gained_delta, -600);
+        let added = diff.members.iter().find(|m| m.user_id == c).unwrap();
+        assert_eq!(added.change, MemberChange::Added);
+        assert!((added.usd_net_delta - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn members_need_unique_user_ids() {
+        let id = Uuid::new_v4();
+        let member = |user_id| MemberInput { model_id: 1, user_id, name: "A".to_string(), strikes: 0, current_xp: 0 };
+        assert!(validate_members(&[member(None)]).is_err());
+        assert!(validate_members(&[member(Some(id)), member(Some(id))]).is_err());
+        assert_eq!(validate_members(&[member(Some(id))]).unwrap(), vec![id]);
+    }
+
+    #[test]
+    fn fingerprint_ignores_page_order() {
+        let input = |pages: Vec<(&str, f64)>| ProductionInput {
+            room_id: 1,
+            room_name: None,
+            room_dirty: false,
+            pages: pages.into_iter().map(|(p, t)| (p.to_string(), t)).collect(),
+            members: Vec::new(),
+            binance_rate_cop: Some(4100.0),
+        };
+        assert_eq!(
+            input_fingerprint(&input(vec![("chaturbate", 1.0), ("stripchat", 2.0)])),
+            input_fingerprint(&input(vec![("stripchat", 2.0), ("chaturbate", 1.0)]))
+        );
+    }
+}
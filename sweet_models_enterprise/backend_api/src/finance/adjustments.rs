@@ -0,0 +1,149 @@
+/// Ajustes de nómina persistidos: factores de pago por día/semana y
+/// deducciones fijas. Se aplican en el cálculo del desprendible (payslip)
+/// y se revierten cuando se anula su origen (ej. un strike).
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AdjustmentKind {
+    /// Multiplica la producción de `work_date`
+    DayPayFactor,
+    /// Multiplica la producción de toda la semana ISO de `work_date`
+    WeekPayFactor,
+    /// Resta `amount_cop` del neto
+    FixedDeduction,
+}
+
+impl AdjustmentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdjustmentKind::DayPayFactor => "DAY_PAY_FACTOR",
+            AdjustmentKind::WeekPayFactor => "WEEK_PAY_FACTOR",
+            AdjustmentKind::FixedDeduction => "FIXED_DEDUCTION",
+        }
+    }
+
+}
+
+impl std::str::FromStr for AdjustmentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "DAY_PAY_FACTOR" => Ok(AdjustmentKind::DayPayFactor),
+            "WEEK_PAY_FACTOR" => Ok(AdjustmentKind::WeekPayFactor),
+            "FIXED_DEDUCTION" => Ok(AdjustmentKind::FixedDeduction),
+            other => Err(format!("Invalid adjustment kind: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PayrollAdjustment {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub strike_id: Option<Uuid>,
+    pub kind: String,
+    pub work_date: NaiveDate,
+    pub factor: Option<f64>,
+    pub amount_cop: Option<f64>,
+    pub reason: String,
+    pub status: String,
+    pub reversed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PayrollAdjustment {
+    pub fn kind(&self) -> Option<AdjustmentKind> {
+        self.kind.parse().ok()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == "ACTIVE"
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewAdjustment {
+    pub user_id: Uuid,
+    pub strike_id: Option<Uuid>,
+    pub kind: AdjustmentKind,
+    pub work_date: NaiveDate,
+    pub factor: Option<f64>,
+    pub amount_cop: Option<f64>,
+    pub reason: String,
+}
+
+const ADJUSTMENT_COLUMNS: &str =
+    "id, user_id, strike_id, kind, work_date, factor, amount_cop, reason, status, reversed_at, created_at";
+
+/// Registra un ajuste de nómina
+/// Recibe la conexión para ir en la misma transacción que el strike que lo origina
+pub async fn create_adjustment(adj: NewAdjustment, conn: &mut PgConnection) -> Result<PayrollAdjustment, String> {
+    let row = sqlx::query_as::<_, PayrollAdjustment>(&format!(
+        r#"
+        INSERT INTO payroll_adjustments (user_id, strike_id, kind, work_date, factor, amount_cop, reason)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {ADJUSTMENT_COLUMNS}
+        "#
+    ))
+    .bind(adj.user_id)
+    .bind(adj.strike_id)
+    .bind(adj.kind.as_str())
+    .bind(adj.work_date)
+    .bind(adj.factor)
+    .bind(adj.amount_cop)
+    .bind(&adj.reason)
+    .fetch_one(conn)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        "Ajuste de nómina {} para {} el {} ({})",
+        row.kind, row.user_id, row.work_date, row.reason
+    );
+    Ok(row)
+}
+
+/// Revierte todos los ajustes activos generados por un strike
+pub async fn reverse_strike_adjustments(strike_id: Uuid, conn: &mut PgConnection) -> Result<u64, String> {
+    let updated = sqlx::query(
+        r#"
+        UPDATE payroll_adjustments
+        SET status = 'REVERSED', reversed_at = NOW()
+        WHERE strike_id = $1 AND status = 'ACTIVE'
+        "#,
+    )
+    .bind(strike_id)
+    .execute(conn)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(updated.rows_affected())
+}
+
+/// Ajustes (activos y revertidos) de varios usuarios en un rango de fechas
+pub async fn adjustments_for_users(
+    user_ids: &[Uuid],
+    start: NaiveDate,
+    end: NaiveDate,
+    conn: &mut PgConnection,
+) -> Result<Vec<PayrollAdjustment>, String> {
+    sqlx::query_as::<_, PayrollAdjustment>(&format!(
+        r#"
+        SELECT {ADJUSTMENT_COLUMNS}
+        FROM payroll_adjustments
+        WHERE user_id = ANY($1) AND work_date BETWEEN $2 AND $3
+        ORDER BY work_date, created_at
+        "#
+    ))
+    .bind(user_ids)
+    .bind(start)
+    .bind(end)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| e.to_string())
+}
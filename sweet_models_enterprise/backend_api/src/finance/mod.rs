@@ -6,6 +6,10 @@ pub mod treasury;
 pub mod calculate_payout;
 pub mod payroll;
 pub mod penalties;
+pub mod adjustments;
+pub mod payslip;
+pub mod statements;
+pub mod simulator;
 
 pub use ledger::{Block, TransactionData, seal_transaction, verify_chain_integrity, get_user_transaction_history};
 pub use handlers::{
@@ -38,9 +42,23 @@ pub use treasury::{
 pub use penalties::{
     downgrade_user_week,
     create_penalty,
+    void_penalty,
     apply_group_shortfall_penalty,
     apply_dirty_room_penalty,
 };
+pub use payslip::{
+    my_payslip_handler,
+    admin_payslip_handler,
+    Payslip,
+};
+pub use statements::{
+    import_statement_handler,
+    list_statements_handler,
+    statement_reconciliation_handler,
+    list_platform_accounts_handler,
+    upsert_platform_account_handler,
+};
+pub use simulator::{simulate_payroll_handler, simulate_week, SimulationRules, SimulationResult};
 pub use calculate_payout::{
     calculate_payout,
     PayoutInput,
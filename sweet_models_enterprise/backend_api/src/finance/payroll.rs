@@ -6,6 +6,8 @@ use sqlx::types::chrono::{DateTime, Utc};
 use uuid::Uuid;
 use chrono::Datelike;
 
+use super::payslip::{load_payslips, Payslip};
+use crate::notifications::{self, DomainEvent};
 use crate::state::AppState;
 
 // Penalizaciones y metas de producción
@@ -31,6 +33,32 @@ pub struct PendingPayrollItem {
     pub account: String,
     pub amount_cop: Option<f64>,
     pub amount_usdt: Option<f64>,
+    /// Factor combinado de los ajustes de nómina de la semana (1.0 = sin ajuste)
+    pub pay_factor: f64,
+    /// Deducciones fijas por ajustes, ya restadas del monto
+    pub adjustment_deductions_cop: f64,
+}
+
+/// Pago aprobado tras aplicar los ajustes de nómina de su semana
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdjustedPayout {
+    pub pay_factor: f64,
+    pub deduction_cop: f64,
+    pub amount_cop: f64,
+    pub amount_usdt: f64,
+}
+
+/// Aplica factores por strike y deducciones fijas sobre lo aprobado. En USDT la deducción
+/// se convierte con la tasa de la modelo; ningún monto queda negativo.
+pub fn adjust_payout(amount_cop: f64, amount_usdt: f64, slip: &Payslip) -> AdjustedPayout {
+    let (pay_factor, deduction_cop) = slip.adjustment_effect();
+    let deduction_usdt = if slip.tasa_modelo > 0.0 { deduction_cop / slip.tasa_modelo } else { 0.0 };
+    AdjustedPayout {
+        pay_factor,
+        deduction_cop,
+        amount_cop: (amount_cop * pay_factor - deduction_cop).max(0.0),
+        amount_usdt: (amount_usdt * pay_factor - deduction_usdt).max(0.0),
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -54,6 +82,9 @@ pub struct MarkPaidRequest {
 pub struct MarkPaidResponse {
     pub user_id: Uuid,
     pub paid_at: DateTime<Utc>,
+    /// Montos efectivamente pagados, con los ajustes de nómina aplicados
+    pub amount_cop: f64,
+    pub amount_usdt: f64,
     pub message: String,
 }
 
@@ -74,6 +105,7 @@ pub async fn pending_payroll_handler(
         user_name: String,
         account_number: String,
         payment_method: PaymentMethodDb,
+        week_start: chrono::NaiveDate,
         amount_cop: f64,
         amount_usdt: f64,
     }
@@ -84,6 +116,7 @@ pub async fn pending_payroll_handler(
                COALESCE(u.display_name, u.username, 'Modelo') AS user_name,
                p.account_number,
                p.payment_method,
+               p.week_start,
                COALESCE(p.amount_cop::DOUBLE PRECISION, 0) AS amount_cop,
                COALESCE(p.amount_usdt::DOUBLE PRECISION, 0) AS amount_usdt
         FROM payroll_payouts p
@@ -102,22 +135,36 @@ pub async fn pending_payroll_handler(
         (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
     })?;
 
+    // Los ajustes de nómina (factores por strike, deducciones fijas) se aplican sobre el pago aprobado
+    let weeks: Vec<(Uuid, chrono::NaiveDate)> = rows.iter().map(|r| (r.user_id, r.week_start)).collect();
+    let mut conn = pool.acquire().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let slips = load_payslips(&mut conn, &weeks).await?;
+
     let mut buckets: HashMap<PaymentMethodDb, Vec<PendingPayrollItem>> = HashMap::new();
     let mut total_cop = 0.0;
     let mut total_usdt = 0.0;
 
     for r in rows {
+        let Some(slip) = slips.get(&(r.user_id, r.week_start)) else {
+            continue;
+        };
+        let adjusted = adjust_payout(r.amount_cop, r.amount_usdt, slip);
+        let is_usdt = r.payment_method == PaymentMethodDb::USDT;
+        let (amount_cop, amount_usdt) = (adjusted.amount_cop, adjusted.amount_usdt);
+
         let item = PendingPayrollItem {
             user_id: r.user_id,
             user: r.user_name,
             account: r.account_number,
-            amount_cop: if r.payment_method == PaymentMethodDb::USDT { None } else { Some(r.amount_cop) },
-            amount_usdt: if r.payment_method == PaymentMethodDb::USDT { Some(r.amount_usdt) } else { None },
+            amount_cop: if is_usdt { None } else { Some(amount_cop) },
+            amount_usdt: if is_usdt { Some(amount_usdt) } else { None },
+            pay_factor: adjusted.pay_factor,
+            adjustment_deductions_cop: adjusted.deduction_cop,
         };
-        if r.payment_method == PaymentMethodDb::USDT {
-            total_usdt += r.amount_usdt;
+        if is_usdt {
+            total_usdt += amount_usdt;
         } else {
-            total_cop += r.amount_cop;
+            total_cop += amount_cop;
         }
         buckets.entry(r.payment_method).or_default().push(item);
     }
@@ -140,49 +187,129 @@ pub async fn mark_paid_handler(
     State(state): State<std::sync::Arc<AppState>>,
     Json(req): Json<MarkPaidRequest>,
 ) -> Result<Json<MarkPaidResponse>, (StatusCode, String)> {
-    let pool = &state.db;
+    let db_error = |e: sqlx::Error| {
+        tracing::error!("DB error marking paid: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    let mut tx = state.db.begin().await.map_err(db_error)?;
 
-    let row = sqlx::query_as::<_, (DateTime<Utc>,)>(
+    let payouts = sqlx::query_as::<_, (Uuid, chrono::NaiveDate, f64, f64)>(
         r#"
-        UPDATE payroll_payouts
-        SET paid_at = NOW(),
-            status = 'PAID',
-            payment_reference = COALESCE($2, payment_reference)
+        SELECT id, week_start,
+               COALESCE(amount_cop::DOUBLE PRECISION, 0),
+               COALESCE(amount_usdt::DOUBLE PRECISION, 0)
+        FROM payroll_payouts
         WHERE user_id = $1 AND paid_at IS NULL AND status = 'APPROVED'
-        RETURNING paid_at
+        ORDER BY week_start
+        FOR UPDATE
         "#,
     )
     .bind(req.user_id)
-    .bind(&req.payment_reference)
-    .fetch_optional(pool)
+    .fetch_all(&mut *tx)
     .await
-    .map_err(|e| {
-        tracing::error!("DB error marking paid: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-    })?;
-
-    let Some((paid_at,)) = row else {
+    .map_err(db_error)?;
+    if payouts.is_empty() {
         return Err((StatusCode::NOT_FOUND, "No pending payout for user".to_string()));
-    };
+    }
 
-    // Notificar vía NATS para que el servicio de notificaciones envíe push
-    let payload = serde_json::json!({
-        "type": "payment_sent",
-        "user_id": req.user_id,
-        "message": "¡Tu pago ha sido enviado!",
-        "reference": req.payment_reference,
-    });
-    if let Err(e) = state
-        .nats
-        .publish("notifications.payment_sent", serde_json::to_vec(&payload).unwrap_or_default().into())
+    // Lo que se paga es lo aprobado con los ajustes de su semana, igual que en la vista pendiente
+    let weeks: Vec<(Uuid, chrono::NaiveDate)> = payouts.iter().map(|(_, week, _, _)| (req.user_id, *week)).collect();
+    let slips = load_payslips(&mut tx, &weeks).await?;
+
+    let mut paid_at = Utc::now();
+    let (mut total_cop, mut total_usdt) = (0.0, 0.0);
+    for (payout_id, week_start, amount_cop, amount_usdt) in payouts {
+        let slip = slips
+            .get(&(req.user_id, week_start))
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Payslip not built".to_string()))?;
+        let adjusted = adjust_payout(amount_cop, amount_usdt, slip);
+        paid_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            UPDATE payroll_payouts
+            SET paid_at = NOW(),
+                status = 'PAID',
+                payment_reference = COALESCE($2, payment_reference),
+                approved_amount_cop = amount_cop,
+                approved_amount_usdt = amount_usdt,
+                amount_cop = $3,
+                amount_usdt = $4,
+                pay_factor = $5,
+                adjustment_deductions_cop = $6
+            WHERE id = $1
+            RETURNING paid_at
+            "#,
+        )
+        .bind(payout_id)
+        .bind(&req.payment_reference)
+        .bind(adjusted.amount_cop)
+        .bind(adjusted.amount_usdt)
+        .bind(adjusted.pay_factor)
+        .bind(adjusted.deduction_cop)
+        .fetch_one(&mut *tx)
         .await
-    {
-        tracing::warn!("Failed to publish notification: {}", e);
+        .map_err(db_error)?;
+        total_cop += adjusted.amount_cop;
+        total_usdt += adjusted.amount_usdt;
     }
+    tx.commit().await.map_err(db_error)?;
+
+    // Notificar vía NATS; el consumidor de notificaciones arma bandeja + push
+    let event = DomainEvent::PaymentSent {
+        user_id: req.user_id,
+        reference: req.payment_reference.clone(),
+        paid_at: Some(paid_at),
+    };
+    notifications::events::publish(&state.nats, &event).await;
 
     Ok(Json(MarkPaidResponse {
         user_id: req.user_id,
         paid_at,
+        amount_cop: total_cop,
+        amount_usdt: total_usdt,
         message: "Pago marcado como enviado".to_string(),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finance::adjustments::{AdjustmentKind, PayrollAdjustment};
+    use crate::finance::payslip::{build_payslip, DailyProduction};
+
+    fn adjustment(kind: AdjustmentKind, date: chrono::NaiveDate, factor: Option<f64>, amount: Option<f64>) -> PayrollAdjustment {
+        PayrollAdjustment {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            strike_id: None,
+            kind: kind.as_str().to_string(),
+            work_date: date,
+            factor,
+            amount_cop: amount,
+            reason: "test".to_string(),
+            status: "ACTIVE".to_string(),
+            reversed_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn paid_amount_applies_factor_and_deductions_in_both_currencies() {
+        let monday = chrono::NaiveDate::from_ymd_opt(2025, 12, 8).unwrap();
+        let daily = vec![DailyProduction { date: monday, tokens: 1000.0, tokens_usd: 50.0 }];
+        let adjustments = vec![
+            adjustment(AdjustmentKind::WeekPayFactor, monday, Some(0.5), None),
+            adjustment(AdjustmentKind::FixedDeduction, monday, None, Some(10_000.0)),
+        ];
+        let slip = build_payslip(Uuid::nil(), monday, &daily, adjustments, Vec::new(), 4100.0);
+
+        let cop = adjust_payout(500_000.0, 0.0, &slip);
+        assert!((cop.pay_factor - 0.5).abs() < 1e-9);
+        assert!((cop.amount_cop - 240_000.0).abs() < 0.01);
+
+        let usdt = adjust_payout(0.0, 100.0, &slip);
+        assert!((usdt.amount_usdt - (50.0 - 10_000.0 / slip.tasa_modelo)).abs() < 1e-9);
+
+        // La deducción nunca deja el pago en negativo
+        assert_eq!(adjust_payout(5_000.0, 0.0, &slip).amount_cop, 0.0);
+    }
+}
@@ -0,0 +1,427 @@
+/// Desprendible semanal: producción diaria × reglas de pago, con los ajustes
+/// de nómina (factores por día/semana) y multas desglosadas.
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::adjustments::{adjustments_for_users, AdjustmentKind, PayrollAdjustment};
+use super::calculate_payout::{DEFAULT_TOKEN_USD_VALUE, MODEL_SHARE, SPREAD_COP};
+use crate::{
+    middleware::auth::{AdminOnly, AuthenticatedUser},
+    state::AppState,
+    tracking::platforms,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyProduction {
+    pub date: NaiveDate,
+    pub tokens: f64,
+    /// Valor USD de los tokens del día según la plataforma de cada registro
+    pub tokens_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PayslipLine {
+    pub date: NaiveDate,
+    pub tokens: f64,
+    pub gross_cop: f64,
+    /// Factor combinado de los ajustes del día (1.0 = sin ajuste)
+    pub day_factor: f64,
+    pub net_cop: f64,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PayslipDeduction {
+    pub source_id: Uuid,
+    pub description: String,
+    pub amount_cop: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Payslip {
+    pub user_id: Uuid,
+    pub week_start: NaiveDate,
+    pub week_end: NaiveDate,
+    pub tasa_modelo: f64,
+    /// Valor efectivo del token en la semana (promedio ponderado por plataforma)
+    pub token_usd_value: f64,
+    pub lines: Vec<PayslipLine>,
+    pub gross_cop: f64,
+    /// Subtotal tras factores diarios
+    pub after_day_factors_cop: f64,
+    /// Factor combinado de la semana (1.0 = sin degradación)
+    pub week_factor: f64,
+    pub week_notes: Vec<String>,
+    pub deductions: Vec<PayslipDeduction>,
+    pub total_deductions_cop: f64,
+    pub net_cop: f64,
+    /// Ajustes considerados (incluye revertidos para trazabilidad)
+    pub adjustments: Vec<PayrollAdjustment>,
+}
+
+impl Payslip {
+    /// Efecto de los ajustes sobre un pago ya calculado: (factor combinado día + semana,
+    /// deducciones fijas COP). Las multas no se incluyen: van como filas PENALTY aparte.
+    pub fn adjustment_effect(&self) -> (f64, f64) {
+        let pay_factor = if self.gross_cop > 0.0 {
+            self.after_day_factors_cop * self.week_factor / self.gross_cop
+        } else {
+            self.week_factor
+        };
+        let deduction_cop = self
+            .deductions
+            .iter()
+            .filter(|d| self.adjustments.iter().any(|a| a.id == d.source_id))
+            .map(|d| d.amount_cop)
+            .sum();
+        (pay_factor, deduction_cop)
+    }
+}
+
+/// Lunes de la semana ISO que contiene `date`
+pub fn week_start_of(date: NaiveDate) -> NaiveDate {
+    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Construye el desprendible. Función pura: recibe producción, ajustes y multas ya cargados.
+///
+/// - Bruto diario COP = valor USD de los tokens (según plataforma) × MODEL_SHARE × tasa_modelo
+/// - Factores diarios (DAY_PAY_FACTOR) se multiplican entre sí sobre el día
+/// - Factores semanales (WEEK_PAY_FACTOR) se multiplican sobre el subtotal de la semana
+/// - Deducciones fijas y multas se restan al final
+pub fn build_payslip(
+    user_id: Uuid,
+    week_start: NaiveDate,
+    daily: &[DailyProduction],
+    adjustments: Vec<PayrollAdjustment>,
+    penalties: Vec<PayslipDeduction>,
+    admin_base_rate: f64,
+) -> Payslip {
+    let week_end = week_start + chrono::Duration::days(6);
+    let tasa_modelo = (admin_base_rate - SPREAD_COP).max(0.0);
+    let cop_per_usd = MODEL_SHARE * tasa_modelo;
+
+    let active: Vec<&PayrollAdjustment> = adjustments
+        .iter()
+        .filter(|a| a.is_active() && a.work_date >= week_start && a.work_date <= week_end)
+        .collect();
+
+    let mut lines = Vec::new();
+    for day in daily.iter().filter(|d| d.date >= week_start && d.date <= week_end) {
+        let gross_cop = day.tokens_usd.max(0.0) * cop_per_usd;
+        let mut day_factor = 1.0;
+        let mut notes = Vec::new();
+        for adj in active
+            .iter()
+            .filter(|a| a.kind() == Some(AdjustmentKind::DayPayFactor) && a.work_date == day.date)
+        {
+            let factor = adj.factor.unwrap_or(1.0);
+            day_factor *= factor;
+            notes.push(format!("{} (x{:.2})", adj.reason, factor));
+        }
+        lines.push(PayslipLine {
+            date: day.date,
+            tokens: day.tokens,
+            gross_cop,
+            day_factor,
+            net_cop: gross_cop * day_factor,
+            notes,
+        });
+    }
+
+    let gross_cop: f64 = lines.iter().map(|l| l.gross_cop).sum();
+    let week_tokens: f64 = lines.iter().map(|l| l.tokens.max(0.0)).sum();
+    let token_usd_value = if week_tokens > 0.0 && cop_per_usd > 0.0 {
+        gross_cop / cop_per_usd / week_tokens
+    } else {
+        DEFAULT_TOKEN_USD_VALUE
+    };
+    let after_day_factors_cop: f64 = lines.iter().map(|l| l.net_cop).sum();
+
+    let mut week_factor = 1.0;
+    let mut week_notes = Vec::new();
+    for adj in active.iter().filter(|a| a.kind() == Some(AdjustmentKind::WeekPayFactor)) {
+        let factor = adj.factor.unwrap_or(1.0);
+        week_factor *= factor;
+        week_notes.push(format!("{} (x{:.2})", adj.reason, factor));
+    }
+
+    let mut deductions = penalties;
+    for adj in active.iter().filter(|a| a.kind() == Some(AdjustmentKind::FixedDeduction)) {
+        deductions.push(PayslipDeduction {
+            source_id: adj.id,
+            description: adj.reason.clone(),
+            amount_cop: adj.amount_cop.unwrap_or(0.0),
+        });
+    }
+    let total_deductions_cop: f64 = deductions.iter().map(|d| d.amount_cop).sum();
+
+    let net_cop = after_day_factors_cop * week_factor - total_deductions_cop;
+
+    Payslip {
+        user_id,
+        week_start,
+        week_end,
+        tasa_modelo,
+        token_usd_value,
+        lines,
+        gross_cop,
+        after_day_factors_cop,
+        week_factor,
+        week_notes,
+        deductions,
+        total_deductions_cop,
+        net_cop,
+        adjustments,
+    }
+}
+
+/// Carga producción, ajustes y multas de la semana y arma el desprendible
+pub async fn load_payslip(
+    pool: &PgPool,
+    user_id: Uuid,
+    week_start: NaiveDate,
+) -> Result<Payslip, (StatusCode, String)> {
+    let mut conn = pool.acquire().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut slips = load_payslips(&mut conn, &[(user_id, week_start)]).await?;
+    slips
+        .remove(&(user_id, week_start))
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Payslip not built".to_string()))
+}
+
+/// Desprendibles de varias (modelo, semana) con una consulta por tabla en vez de una por modelo.
+/// Recibe la conexión para poder usarse dentro de la transacción que marca el pago.
+pub async fn load_payslips(
+    conn: &mut PgConnection,
+    weeks: &[(Uuid, NaiveDate)],
+) -> Result<HashMap<(Uuid, NaiveDate), Payslip>, (StatusCode, String)> {
+    let (Some(first), Some(last)) = (
+        weeks.iter().map(|(_, start)| *start).min(),
+        weeks.iter().map(|(_, start)| *start).max(),
+    ) else {
+        return Ok(HashMap::new());
+    };
+    let range_end = last + chrono::Duration::days(6);
+    let user_ids: Vec<Uuid> = weeks.iter().map(|(user_id, _)| *user_id).collect();
+
+    let admin_base_rate = sqlx::query_scalar::<_, f64>(
+        "SELECT admin_base_rate::DOUBLE PRECISION FROM system_settings WHERE id = 1",
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "system_settings row missing".to_string()))?;
+
+    let rows = sqlx::query_as::<_, (Uuid, NaiveDate, Option<String>, f64)>(
+        r#"
+        SELECT model_id, production_date, platform, COALESCE(SUM(tokens_earned), 0)::DOUBLE PRECISION
+        FROM production_logs
+        WHERE model_id = ANY($1) AND production_date BETWEEN $2 AND $3 AND superseded_by_draft IS NULL
+        GROUP BY model_id, production_date, platform
+        ORDER BY model_id, production_date
+        "#,
+    )
+    .bind(&user_ids)
+    .bind(first)
+    .bind(range_end)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("DB error fetching production for payslip: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    // Registros manuales sin plataforma se valoran con el token por defecto
+    let registry = platforms::registry();
+    let mut daily: HashMap<Uuid, Vec<DailyProduction>> = HashMap::new();
+    for (user_id, date, platform, tokens) in rows {
+        let usd = tokens * registry.token_usd_value(platform.as_deref());
+        let days = daily.entry(user_id).or_default();
+        match days.last_mut() {
+            Some(day) if day.date == date => {
+                day.tokens += tokens;
+                day.tokens_usd += usd;
+            }
+            _ => days.push(DailyProduction { date, tokens, tokens_usd: usd }),
+        }
+    }
+
+    let mut adjustments: HashMap<Uuid, Vec<PayrollAdjustment>> = HashMap::new();
+    for adj in adjustments_for_users(&user_ids, first, range_end, conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+    {
+        adjustments.entry(adj.user_id).or_default().push(adj);
+    }
+
+    let penalties = sqlx::query_as::<_, (Uuid, NaiveDate, Uuid, Option<String>, f64)>(
+        r#"
+        SELECT user_id, week_start, id, notes, ABS(COALESCE(amount_cop, 0))::DOUBLE PRECISION
+        FROM payroll_payouts
+        WHERE user_id = ANY($1) AND status = 'PENALTY' AND week_start BETWEEN $2 AND $3
+        ORDER BY created_at
+        "#,
+    )
+    .bind(&user_ids)
+    .bind(first)
+    .bind(range_end)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut slips = HashMap::new();
+    for &(user_id, week_start) in weeks {
+        let week_end = week_start + chrono::Duration::days(6);
+        let week_penalties = penalties
+            .iter()
+            .filter(|(owner, start, ..)| *owner == user_id && *start >= week_start && *start <= week_end)
+            .map(|(_, _, id, notes, amount)| PayslipDeduction {
+                source_id: *id,
+                description: notes.clone().unwrap_or_else(|| "Multa".to_string()),
+                amount_cop: *amount,
+            })
+            .collect();
+        let week_adjustments = adjustments
+            .get(&user_id)
+            .map(|all| {
+                all.iter()
+                    .filter(|a| a.work_date >= week_start && a.work_date <= week_end)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        let slip = build_payslip(
+            user_id,
+            week_start,
+            daily.get(&user_id).map(Vec::as_slice).unwrap_or_default(),
+            week_adjustments,
+            week_penalties,
+            admin_base_rate,
+        );
+        slips.insert((user_id, week_start), slip);
+    }
+    Ok(slips)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PayslipQuery {
+    /// Cualquier fecha de la semana deseada (por defecto, la semana actual)
+    pub week_of: Option<NaiveDate>,
+}
+
+/// GET /api/finance/payslip?week_of=YYYY-MM-DD
+/// Desprendible de la modelo autenticada
+pub async fn my_payslip_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Query(query): Query<PayslipQuery>,
+) -> Result<Json<Payslip>, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&auth.user_id)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user id in token".to_string()))?;
+    let week_start = week_start_of(query.week_of.unwrap_or_else(|| Utc::now().date_naive()));
+    Ok(Json(load_payslip(&state.db, user_id, week_start).await?))
+}
+
+/// GET /api/admin/finance/payslip/:user_id?week_of=YYYY-MM-DD
+pub async fn admin_payslip_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<PayslipQuery>,
+) -> Result<Json<Payslip>, (StatusCode, String)> {
+    let week_start = week_start_of(query.week_of.unwrap_or_else(|| Utc::now().date_naive()));
+    Ok(Json(load_payslip(&state.db, user_id, week_start).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adjustment(kind: AdjustmentKind, date: NaiveDate, factor: Option<f64>, amount: Option<f64>, status: &str) -> PayrollAdjustment {
+        PayrollAdjustment {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            strike_id: None,
+            kind: kind.as_str().to_string(),
+            work_date: date,
+            factor,
+            amount_cop: amount,
+            reason: "test".to_string(),
+            status: status.to_string(),
+            reversed_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn half_day_factor_only_affects_that_day() {
+        let monday = NaiveDate::from_ymd_opt(2025, 12, 8).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let daily = vec![
+            DailyProduction { date: monday, tokens: 1000.0, tokens_usd: 50.0 },
+            DailyProduction { date: tuesday, tokens: 1000.0, tokens_usd: 50.0 },
+        ];
+        let adjustments = vec![adjustment(AdjustmentKind::DayPayFactor, tuesday, Some(0.5), None, "ACTIVE")];
+
+        let slip = build_payslip(Uuid::nil(), monday, &daily, adjustments, vec![], 4100.0);
+        let per_day = 1000.0 * 0.05 * MODEL_SHARE * 3800.0;
+        assert!((slip.lines[0].net_cop - per_day).abs() < 0.01);
+        assert!((slip.lines[1].net_cop - per_day * 0.5).abs() < 0.01);
+        assert!((slip.net_cop - per_day * 1.5).abs() < 0.01);
+        assert!((slip.token_usd_value - 0.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reversed_adjustments_are_ignored_and_deductions_subtract() {
+        let monday = NaiveDate::from_ymd_opt(2025, 12, 8).unwrap();
+        let daily = vec![DailyProduction { date: monday, tokens: 2000.0, tokens_usd: 100.0 }];
+        let adjustments = vec![
+            adjustment(AdjustmentKind::WeekPayFactor, monday, Some(0.5), None, "REVERSED"),
+            adjustment(AdjustmentKind::FixedDeduction, monday, None, Some(10_000.0), "ACTIVE"),
+        ];
+        let penalties = vec![PayslipDeduction {
+            source_id: Uuid::nil(),
+            description: "Multa".to_string(),
+            amount_cop: 50_000.0,
+        }];
+
+        let slip = build_payslip(Uuid::nil(), monday, &daily, adjustments, penalties, 4100.0);
+        assert_eq!(slip.week_factor, 1.0);
+        assert!((slip.total_deductions_cop - 60_000.0).abs() < 0.01);
+        assert!((slip.net_cop - (slip.gross_cop - 60_000.0)).abs() < 0.01);
+    }
+
+    #[test]
+    fn adjustment_effect_excludes_penalties() {
+        let monday = NaiveDate::from_ymd_opt(2025, 12, 8).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let daily = vec![
+            DailyProduction { date: monday, tokens: 1000.0, tokens_usd: 50.0 },
+            DailyProduction { date: tuesday, tokens: 1000.0, tokens_usd: 50.0 },
+        ];
+        let adjustments = vec![
+            adjustment(AdjustmentKind::DayPayFactor, tuesday, Some(0.5), None, "ACTIVE"),
+            adjustment(AdjustmentKind::WeekPayFactor, monday, Some(0.5), None, "ACTIVE"),
+            adjustment(AdjustmentKind::FixedDeduction, monday, None, Some(10_000.0), "ACTIVE"),
+        ];
+        let penalties = vec![PayslipDeduction {
+            source_id: Uuid::nil(),
+            description: "Multa".to_string(),
+            amount_cop: 50_000.0,
+        }];
+
+        let slip = build_payslip(Uuid::nil(), monday, &daily, adjustments, penalties, 4100.0);
+        let (pay_factor, deduction_cop) = slip.adjustment_effect();
+        assert!((pay_factor - 0.375).abs() < 1e-9);
+        assert!((deduction_cop - 10_000.0).abs() < 0.01);
+    }
+}
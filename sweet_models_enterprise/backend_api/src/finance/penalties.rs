@@ -1,5 +1,5 @@
 use sqlx::types::Uuid;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use chrono::Utc;
 use super::payroll::{GROUP_QUOTA, GROUP_SHORTFALL_PENALTY_COP, DIRTY_ROOM_PENALTY_COP};
 
@@ -25,30 +25,62 @@ pub async fn downgrade_user_week(user_id: Uuid, factor: f64, pool: &PgPool) -> R
 }
 
 /// Crea una multa directa (inserción en payroll_payouts como descuento negativo).
-pub async fn create_penalty(user_id: Uuid, amount_cop: f64, pool: &PgPool) -> Result<(), String> {
+/// `note` identifica la regla que la generó. Retorna el id del registro para poder anularlo luego.
+pub async fn create_penalty(
+    user_id: Uuid,
+    amount_cop: f64,
+    note: &str,
+    conn: &mut PgConnection,
+) -> Result<Uuid, String> {
     let week_start = Utc::now().date_naive();
     let week_end = week_start + chrono::Duration::days(6);
 
-    sqlx::query(
+    let id = sqlx::query_scalar::<_, Uuid>(
         r#"
         INSERT INTO payroll_payouts (user_id, week_start, week_end, amount_cop, amount_usdt, payment_method, account_number, status, notes)
-        VALUES ($1, $2, $3, $4, 0, 'EFECTIVO', 'N/A', 'PENALTY', 'Multa por 3+ tardanzas')
+        VALUES ($1, $2, $3, $4, 0, 'EFECTIVO', 'N/A', 'PENALTY', $5)
+        RETURNING id
         "#,
     )
     .bind(user_id)
     .bind(week_start)
     .bind(week_end)
     .bind(-amount_cop)
-    .execute(pool)
+    .bind(note)
+    .fetch_one(conn)
     .await
     .map_err(|e| e.to_string())?;
 
     tracing::info!("Multa creada para {} por {} COP", user_id, amount_cop);
-    Ok(())
+    Ok(id)
+}
+
+/// Anula una multa no cobrada (ej. strike revertido en apelación).
+pub async fn void_penalty(payout_id: Uuid, reason: &str, conn: &mut PgConnection) -> Result<bool, String> {
+    let updated = sqlx::query(
+        r#"
+        UPDATE payroll_payouts
+        SET status = 'REJECTED',
+            notes = COALESCE(notes || ' ', '') || 'Anulada: ' || $2
+        WHERE id = $1 AND status = 'PENALTY' AND paid_at IS NULL
+        "#,
+    )
+    .bind(payout_id)
+    .bind(reason)
+    .execute(conn)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tracing::info!("Multa {} anulada ({} filas)", payout_id, updated.rows_affected());
+    Ok(updated.rows_affected() > 0)
 }
 
 /// Aplica multa grupal por no alcanzar la cuota de producción del room (1500 tokens).
-pub async fn apply_group_shortfall_penalty(room_members: &[Uuid], total_tokens: f64, pool: &PgPool) -> Result<(), String> {
+pub async fn apply_group_shortfall_penalty(
+    room_members: &[Uuid],
+    total_tokens: f64,
+    conn: &mut PgConnection,
+) -> Result<(), String> {
     if total_tokens >= GROUP_QUOTA {
         return Ok(()); // No hay multa si alcanzaron la meta
     }
@@ -67,7 +99,7 @@ pub async fn apply_group_shortfall_penalty(room_members: &[Uuid], total_tokens:
         .bind(week_start)
         .bind(week_end)
         .bind(-GROUP_SHORTFALL_PENALTY_COP)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .map_err(|e| e.to_string())?;
     }
@@ -78,7 +110,11 @@ pub async fn apply_group_shortfall_penalty(room_members: &[Uuid], total_tokens:
 }
 
 /// Aplica multa por room sucio a todos los integrantes del turno.
-pub async fn apply_dirty_room_penalty(room_members: &[Uuid], room_id: i32, pool: &PgPool) -> Result<(), String> {
+pub async fn apply_dirty_room_penalty(
+    room_members: &[Uuid],
+    room_id: i32,
+    conn: &mut PgConnection,
+) -> Result<(), String> {
     let week_start = Utc::now().date_naive();
     let week_end = week_start + chrono::Duration::days(6);
 
@@ -94,7 +130,7 @@ pub async fn apply_dirty_room_penalty(room_members: &[Uuid], room_id: i32, pool:
         .bind(week_end)
         .bind(-DIRTY_ROOM_PENALTY_COP)
         .bind(format!("Multa: Room {} dejado sucio", room_id))
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .map_err(|e| e.to_string())?;
     }
@@ -0,0 +1,770 @@
+/// Simulador de nómina "qué pasaría si".
+///
+/// Generaliza la simulación semanal de `tests/matrix_simulation.rs`: producción diaria,
+/// strikes, rooms sucios y fallas de cuota grupal (por room) para modelos reales o sintéticas. Las
+/// reglas vigentes salen de la política de strikes activa; con `rules` distintas devuelve ambos
+/// escenarios y su diferencia, para evaluar un cambio de política antes de activarlo.
+/// No escribe nada en la base.
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{Datelike, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::calculate_payout::{MODEL_SHARE, SPREAD_COP};
+use super::payroll::{DIRTY_ROOM_PENALTY_COP, GROUP_QUOTA, GROUP_SHORTFALL_PENALTY_COP};
+use crate::{
+    gamification::engine::FRAGILITY_BURNS,
+    middleware::auth::AdminOnly,
+    operations::strikes::{self, StrikeConsequence, StrikeLevel, StrikePolicy},
+    state::AppState,
+    tracking::platforms,
+};
+
+pub const DAYS_PER_WEEK: usize = 7;
+const MAX_MODELS: usize = 200;
+
+fn burn_pct(reason: &str) -> f64 {
+    FRAGILITY_BURNS
+        .iter()
+        .find(|(r, _, _)| *r == reason)
+        .map(|(_, pct, _)| *pct)
+        .unwrap_or(0.0)
+}
+
+/// Reglas de la simulación. `Default` usa la política de strikes del código; las vigentes
+/// se arman con `from_policy` sobre la política activa.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationRules {
+    pub model_share: f64,
+    pub spread_cop: f64,
+    /// Valor fijo del token para el escenario; sin él cada modelo usa el de su plataforma
+    pub token_usd_value: Option<f64>,
+    /// Tokens diarios que debe sumar el room para no pagar multa grupal
+    pub group_quota: f64,
+    pub group_shortfall_penalty_cop: f64,
+    pub dirty_room_penalty_cop: f64,
+    /// Escalera de strikes: el strike n aplica las consecuencias y la quema de su escalón
+    pub strike_ladder: Vec<StrikeLevel>,
+    pub dirty_room_burn_pct: f64,
+    pub low_production_burn_pct: f64,
+    /// Bono sobre el pago base para modelos marcadas con excelencia (lo paga el estudio)
+    pub excellence_bonus_pct: f64,
+    /// XP por token neto (1:1 en engine::core)
+    pub xp_per_net_token: f64,
+}
+
+impl Default for SimulationRules {
+    fn default() -> Self {
+        Self::from_policy(&StrikePolicy::default())
+    }
+}
+
+impl SimulationRules {
+    /// Reglas vigentes: constantes de nómina del código y la escalera de la política dada
+    pub fn from_policy(policy: &StrikePolicy) -> Self {
+        Self {
+            model_share: MODEL_SHARE,
+            spread_cop: SPREAD_COP,
+            token_usd_value: None,
+            group_quota: GROUP_QUOTA,
+            group_shortfall_penalty_cop: GROUP_SHORTFALL_PENALTY_COP,
+            dirty_room_penalty_cop: DIRTY_ROOM_PENALTY_COP,
+            strike_ladder: policy.ladder.clone(),
+            dirty_room_burn_pct: burn_pct("DIRTY_ROOM"),
+            low_production_burn_pct: burn_pct("LOW_PRODUCTION"),
+            excellence_bonus_pct: 5.0,
+            xp_per_net_token: 1.0,
+        }
+    }
+
+    /// Reglas propuestas sobre las vigentes: los campos que no vienen se mantienen
+    pub fn with_overrides(&self, overrides: serde_json::Value) -> Result<Self, String> {
+        let serde_json::Value::Object(overrides) = overrides else {
+            return Err("rules must be an object".to_string());
+        };
+        let mut merged = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        if let serde_json::Value::Object(fields) = &mut merged {
+            fields.extend(overrides);
+        }
+        serde_json::from_value(merged).map_err(|e| format!("Invalid rules: {}", e))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.model_share) {
+            return Err("model_share must be between 0 and 1".to_string());
+        }
+        strikes::validate_ladder(&self.strike_ladder)?;
+        if [self.dirty_room_burn_pct, self.low_production_burn_pct]
+            .iter()
+            .any(|p| !(0.0..=100.0).contains(p))
+        {
+            return Err("Burn percentages must be between 0 and 100".to_string());
+        }
+        let amounts = [
+            self.spread_cop,
+            self.token_usd_value.unwrap_or(0.0),
+            self.group_quota,
+            self.group_shortfall_penalty_cop,
+            self.dirty_room_penalty_cop,
+            self.excellence_bonus_pct,
+            self.xp_per_net_token,
+        ];
+        if amounts.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err("Rule amounts must be non-negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Modelo de la simulación. Con `user_id` se completan nombre, XP, producción y strikes
+/// de la última semana real cuando no se indican.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimModelInput {
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Tokens de la semana (se reparten parejo en los 7 días)
+    #[serde(default)]
+    pub weekly_tokens: Option<f64>,
+    /// Tokens por día (lunes a domingo); tiene prioridad sobre weekly_tokens
+    #[serde(default)]
+    pub daily_tokens: Option<Vec<f64>>,
+    #[serde(default)]
+    pub strikes: Option<u8>,
+    #[serde(default)]
+    pub start_xp: Option<i64>,
+    #[serde(default)]
+    pub excellence: bool,
+    /// Room del turno; la cuota grupal se mide por room
+    #[serde(default)]
+    pub room_id: Option<i32>,
+    /// Plataforma para valorar los tokens (registro de plataformas)
+    #[serde(default)]
+    pub platform: Option<String>,
+}
+
+/// Modelo ya resuelta (sin huecos)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimModel {
+    pub user_id: Option<Uuid>,
+    pub name: String,
+    /// Modelos sin room cuentan juntas para la cuota grupal
+    pub room_id: Option<i32>,
+    pub daily_tokens: [f64; DAYS_PER_WEEK],
+    /// Valor USD del token según la plataforma de la modelo
+    pub token_usd_value: f64,
+    pub strikes: u8,
+    pub start_xp: i64,
+    pub excellence: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SimWeekEvents {
+    /// Días (0 = lunes) en que el room se entregó sucio
+    #[serde(default)]
+    pub dirty_days: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoomShortfall {
+    pub room_id: Option<i32>,
+    /// Días (0 = lunes) en que el room no alcanzó la cuota grupal
+    pub failed_days: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelProjection {
+    pub user_id: Option<Uuid>,
+    pub name: String,
+    pub room_id: Option<i32>,
+    pub tokens: f64,
+    pub gross_usd: f64,
+    pub base_payout_cop: f64,
+    pub payout_factor: f64,
+    pub excellence_bonus_cop: f64,
+    pub dirty_room_penalties_cop: f64,
+    pub group_penalties_cop: f64,
+    pub strike_penalty_cop: f64,
+    /// Multas que no alcanzaron a cobrarse porque el pago quedó en cero
+    pub uncollected_penalties_cop: f64,
+    pub payout_cop: f64,
+    pub payout_usd: f64,
+    pub xp_start: i64,
+    pub xp_gained: i64,
+    pub xp_burned: i64,
+    pub xp_end: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulationResult {
+    pub rules: SimulationRules,
+    pub admin_base_rate: f64,
+    pub tasa_modelo: f64,
+    pub total_tokens: f64,
+    pub gross_usd: f64,
+    /// Producción total valorada a la tasa de la modelo
+    pub gross_cop: f64,
+    pub room_shortfalls: Vec<RoomShortfall>,
+    pub models: Vec<ModelProjection>,
+    pub total_payout_cop: f64,
+    pub studio_base_share_cop: f64,
+    pub penalties_collected_cop: f64,
+    pub strike_reductions_cop: f64,
+    pub excellence_bonuses_cop: f64,
+    /// 40% + multas cobradas + recorte por strikes − bonos de excelencia
+    pub studio_wallet_cop: f64,
+    pub total_xp_burned: i64,
+}
+
+/// Efecto de los strikes de la semana sobre una modelo
+#[derive(Debug, Clone, PartialEq)]
+struct StrikeEffect {
+    payout_factor: f64,
+    fines_cop: f64,
+    /// % de XP quemado por cada strike, en orden
+    burn_pcts: Vec<f64>,
+}
+
+/// Aplica la escalera como al emitir cada strike: el n-ésimo usa su escalón. Como en el
+/// desprendible, los días a medio pago (se asumen los primeros de la semana) pesan según la
+/// producción de ese día y los factores semanales se multiplican encima.
+fn strike_effect(ladder: &[StrikeLevel], model: &SimModel) -> StrikeEffect {
+    let (mut half_pay_days, mut week_factor, mut fines_cop) = (0usize, 1.0, 0.0);
+    let mut burn_pcts = Vec::new();
+    for ordinal in 1..=i64::from(model.strikes) {
+        let Some(level) = strikes::level_in(ladder, ordinal) else {
+            continue;
+        };
+        for consequence in &level.consequences {
+            match consequence {
+                StrikeConsequence::HalfPayToday => half_pay_days += 1,
+                StrikeConsequence::DowngradeWeek { factor } => week_factor *= factor,
+                StrikeConsequence::Fine { amount_cop } => fines_cop += amount_cop,
+            }
+        }
+        if let Some(reason) = &level.xp_burn_reason {
+            burn_pcts.push(burn_pct(reason));
+        }
+    }
+
+    let tokens: f64 = model.daily_tokens.iter().sum();
+    let day_factor = if tokens > 0.0 {
+        model
+            .daily_tokens
+            .iter()
+            .enumerate()
+            .map(|(day, t)| if day < half_pay_days { t * 0.5 } else { *t })
+            .sum::<f64>()
+            / tokens
+    } else {
+        1.0
+    };
+    StrikeEffect { payout_factor: day_factor * week_factor, fines_cop, burn_pcts }
+}
+
+/// Corre una semana con las reglas dadas. Invariante: pagos + billetera del estudio = gross_cop.
+pub fn simulate_week(
+    models: &[SimModel],
+    events: &SimWeekEvents,
+    rules: &SimulationRules,
+    admin_base_rate: f64,
+) -> SimulationResult {
+    let tasa_modelo = (admin_base_rate - rules.spread_cop).max(0.0);
+    let total_tokens: f64 = models.iter().flat_map(|m| m.daily_tokens).sum();
+    let model_usd: Vec<f64> = models
+        .iter()
+        .map(|m| m.daily_tokens.iter().sum::<f64>() * rules.token_usd_value.unwrap_or(m.token_usd_value))
+        .collect();
+    let gross_usd: f64 = model_usd.iter().sum();
+    let gross_cop = gross_usd * tasa_modelo;
+    let model_pool_cop = gross_cop * rules.model_share;
+
+    // Como en el cierre de turno: cada room suma solo la producción de sus integrantes
+    let mut rooms: Vec<Option<i32>> = models.iter().map(|m| m.room_id).collect();
+    rooms.sort();
+    rooms.dedup();
+    let room_shortfalls: Vec<RoomShortfall> = rooms
+        .into_iter()
+        .map(|room_id| RoomShortfall {
+            room_id,
+            failed_days: (0..DAYS_PER_WEEK)
+                .filter(|day| {
+                    let room_tokens: f64 =
+                        models.iter().filter(|m| m.room_id == room_id).map(|m| m.daily_tokens[*day]).sum();
+                    room_tokens < rules.group_quota
+                })
+                .collect(),
+        })
+        .collect();
+    let dirty_days: Vec<usize> = {
+        let mut days: Vec<usize> = events.dirty_days.iter().copied().filter(|d| *d < DAYS_PER_WEEK).collect();
+        days.sort();
+        days.dedup();
+        days
+    };
+
+    let mut projections = Vec::with_capacity(models.len());
+    let (mut penalties_collected, mut strike_reductions, mut excellence_bonuses) = (0.0, 0.0, 0.0);
+
+    for (model, usd) in models.iter().zip(model_usd) {
+        let tokens: f64 = model.daily_tokens.iter().sum();
+        let weight = if gross_usd > 0.0 { usd / gross_usd } else { 0.0 };
+        let group_failed_days = room_shortfalls
+            .iter()
+            .find(|r| r.room_id == model.room_id)
+            .map_or(&[][..], |r| r.failed_days.as_slice());
+        let base_payout_cop = model_pool_cop * weight;
+        let strike_effect = strike_effect(&rules.strike_ladder, model);
+        let payout_factor = strike_effect.payout_factor;
+        let excellence_bonus_cop = if model.excellence {
+            base_payout_cop * payout_factor * rules.excellence_bonus_pct / 100.0
+        } else {
+            0.0
+        };
+
+        let dirty_room_penalties_cop = rules.dirty_room_penalty_cop * dirty_days.len() as f64;
+        let group_penalties_cop = rules.group_shortfall_penalty_cop * group_failed_days.len() as f64;
+        let strike_penalty_cop = strike_effect.fines_cop;
+        let penalties = dirty_room_penalties_cop + group_penalties_cop + strike_penalty_cop;
+
+        let before_penalties = base_payout_cop * payout_factor + excellence_bonus_cop;
+        let payout_cop = (before_penalties - penalties).max(0.0);
+        let collected = before_penalties - payout_cop;
+
+        // XP: ganancia sobre tokens netos, luego quemas por strike, room sucio y baja producción
+        let xp_gained = (tokens * rules.model_share * rules.xp_per_net_token).round() as i64;
+        let mut xp = model.start_xp + xp_gained;
+        let burn = |xp: &mut i64, pct: f64| {
+            let loss = ((*xp as f64) * pct / 100.0).round() as i64;
+            *xp = (*xp - loss).max(0);
+        };
+        for pct in &strike_effect.burn_pcts {
+            burn(&mut xp, *pct);
+        }
+        for _ in &dirty_days {
+            burn(&mut xp, rules.dirty_room_burn_pct);
+        }
+        for _ in group_failed_days {
+            burn(&mut xp, rules.low_production_burn_pct);
+        }
+        let xp_burned = model.start_xp + xp_gained - xp;
+
+        penalties_collected += collected;
+        strike_reductions += base_payout_cop * (1.0 - payout_factor);
+        excellence_bonuses += excellence_bonus_cop;
+
+        projections.push(ModelProjection {
+            user_id: model.user_id,
+            name: model.name.clone(),
+            room_id: model.room_id,
+            tokens,
+            gross_usd: usd,
+            base_payout_cop,
+            payout_factor,
+            excellence_bonus_cop,
+            dirty_room_penalties_cop,
+            group_penalties_cop,
+            strike_penalty_cop,
+            uncollected_penalties_cop: penalties - collected,
+            payout_cop,
+            payout_usd: if tasa_modelo > 0.0 { payout_cop / tasa_modelo } else { 0.0 },
+            xp_start: model.start_xp,
+            xp_gained,
+            xp_burned,
+            xp_end: xp,
+        });
+    }
+
+    let studio_base_share_cop = gross_cop - model_pool_cop;
+    let total_payout_cop = projections.iter().map(|p| p.payout_cop).sum();
+    let total_xp_burned = projections.iter().map(|p| p.xp_burned).sum();
+
+    SimulationResult {
+        rules: rules.clone(),
+        admin_base_rate,
+        tasa_modelo,
+        total_tokens,
+        gross_usd,
+        gross_cop,
+        room_shortfalls,
+        models: projections,
+        total_payout_cop,
+        studio_base_share_cop,
+        penalties_collected_cop: penalties_collected,
+        strike_reductions_cop: strike_reductions,
+        excellence_bonuses_cop: excellence_bonuses,
+        studio_wallet_cop: studio_base_share_cop + penalties_collected + strike_reductions - excellence_bonuses,
+        total_xp_burned,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelDelta {
+    pub name: String,
+    pub user_id: Option<Uuid>,
+    pub payout_cop_delta: f64,
+    pub xp_end_delta: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulationDelta {
+    pub total_payout_cop_delta: f64,
+    pub studio_wallet_cop_delta: f64,
+    pub penalties_collected_cop_delta: f64,
+    pub total_xp_burned_delta: i64,
+    pub models: Vec<ModelDelta>,
+}
+
+/// Escenario propuesto menos el vigente (mismas modelos, mismo orden)
+pub fn compare(baseline: &SimulationResult, scenario: &SimulationResult) -> SimulationDelta {
+    SimulationDelta {
+        total_payout_cop_delta: scenario.total_payout_cop - baseline.total_payout_cop,
+        studio_wallet_cop_delta: scenario.studio_wallet_cop - baseline.studio_wallet_cop,
+        penalties_collected_cop_delta: scenario.penalties_collected_cop - baseline.penalties_collected_cop,
+        total_xp_burned_delta: scenario.total_xp_burned - baseline.total_xp_burned,
+        models: baseline
+            .models
+            .iter()
+            .zip(&scenario.models)
+            .map(|(before, after)| ModelDelta {
+                name: after.name.clone(),
+                user_id: after.user_id,
+                payout_cop_delta: after.payout_cop - before.payout_cop,
+                xp_end_delta: after.xp_end - before.xp_end,
+            })
+            .collect(),
+    }
+}
+
+// ============================================================================
+// HANDLER
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct SimulationRequest {
+    pub models: Vec<SimModelInput>,
+    #[serde(default)]
+    pub events: SimWeekEvents,
+    /// Reglas propuestas sobre las vigentes (solo los campos que cambian); si se omiten
+    /// solo se simulan las vigentes
+    #[serde(default)]
+    pub rules: Option<serde_json::Value>,
+    /// Tasa COP/USD; por defecto la configurada por el admin
+    #[serde(default)]
+    pub admin_base_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimulationResponse {
+    pub baseline: SimulationResult,
+    pub scenario: Option<SimulationResult>,
+    pub delta: Option<SimulationDelta>,
+}
+
+/// Datos reales de la última semana para una modelo existente
+#[derive(Debug, sqlx::FromRow)]
+struct RealModelRow {
+    name: String,
+    xp: i64,
+    week_tokens: f64,
+    active_strikes: i64,
+    room_id: Option<i32>,
+}
+
+async fn resolve_model(
+    state: &AppState,
+    input: SimModelInput,
+    index: usize,
+    strike_window_days: i32,
+) -> Result<SimModel, (StatusCode, String)> {
+    let real = match input.user_id {
+        Some(user_id) => {
+            let since = Utc::now().date_naive() - Duration::days(DAYS_PER_WEEK as i64);
+            let strikes_since = Utc::now() - Duration::days(i64::from(strike_window_days));
+            let row = sqlx::query_as::<_, RealModelRow>(
+                r#"
+                SELECT COALESCE(u.username, u.email) AS name,
+                       COALESCE(u.xp, 0)::BIGINT AS xp,
+                       (SELECT COALESCE(SUM(tokens_earned), 0)::DOUBLE PRECISION FROM production_logs
+                         WHERE model_id = u.id AND production_date > $2 AND superseded_by_draft IS NULL) AS week_tokens,
+                       (SELECT COUNT(*) FROM strikes
+                         WHERE user_id = u.id AND status = 'ACTIVE' AND issued_at >= $3) AS active_strikes,
+                       (SELECT assigned_room FROM user_shifts
+                         WHERE user_id = u.id ORDER BY week_id DESC LIMIT 1) AS room_id
+                FROM users u
+                WHERE u.id = $1
+                "#,
+            )
+            .bind(user_id)
+            .bind(since)
+            .bind(strikes_since)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| {
+                tracing::error!("DB error loading model for simulation: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            })?
+            .ok_or((StatusCode::NOT_FOUND, format!("Modelo {} no encontrada", user_id)))?;
+
+            // Valor USD de la semana según la plataforma de cada registro
+            let by_platform = sqlx::query_as::<_, (Option<String>, f64)>(
+                r#"
+                SELECT platform, COALESCE(SUM(tokens_earned), 0)::DOUBLE PRECISION
+                FROM production_logs
+                WHERE model_id = $1 AND production_date > $2 AND superseded_by_draft IS NULL
+                GROUP BY platform
+                "#,
+            )
+            .bind(user_id)
+            .bind(since)
+            .fetch_all(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            let week_usd: f64 = by_platform
+                .iter()
+                .map(|(platform, tokens)| tokens * platforms::registry().token_usd_value(platform.as_deref()))
+                .sum();
+            Some((row, week_usd))
+        }
+        None => None,
+    };
+    let token_usd_value = match (&input.platform, &real) {
+        (Some(platform), _) => platforms::registry().token_usd_value(Some(platform)),
+        (None, Some((row, week_usd))) if row.week_tokens > 0.0 => week_usd / row.week_tokens,
+        _ => platforms::registry().token_usd_value(None),
+    };
+    let real = real.map(|(row, _)| row);
+
+    let daily_tokens = match (&input.daily_tokens, input.weekly_tokens) {
+        (Some(days), _) => <[f64; DAYS_PER_WEEK]>::try_from(days.clone())
+            .map_err(|_| (StatusCode::BAD_REQUEST, format!("daily_tokens must have {} values", DAYS_PER_WEEK)))?,
+        (None, weekly) => {
+            let weekly = weekly.or(real.as_ref().map(|r| r.week_tokens)).ok_or((
+                StatusCode::BAD_REQUEST,
+                format!("Model #{} needs weekly_tokens, daily_tokens or a user_id", index + 1),
+            ))?;
+            [weekly / DAYS_PER_WEEK as f64; DAYS_PER_WEEK]
+        }
+    };
+    if daily_tokens.iter().any(|t| !t.is_finite() || *t < 0.0) {
+        return Err((StatusCode::BAD_REQUEST, "Tokens must be non-negative".to_string()));
+    }
+
+    Ok(SimModel {
+        user_id: input.user_id,
+        name: input
+            .name
+            .or(real.as_ref().map(|r| r.name.clone()))
+            .unwrap_or_else(|| format!("Modelo {}", index + 1)),
+        room_id: input.room_id.or(real.as_ref().and_then(|r| r.room_id)),
+        daily_tokens,
+        token_usd_value,
+        strikes: input
+            .strikes
+            .or(real.as_ref().map(|r| r.active_strikes.min(i64::from(u8::MAX)) as u8))
+            .unwrap_or(0),
+        start_xp: input.start_xp.or(real.as_ref().map(|r| r.xp)).unwrap_or(0),
+        excellence: input.excellence,
+    })
+}
+
+/// POST /api/admin/finance/simulate
+/// Proyección semanal de pagos, multas, XP e ingreso del estudio. Solo lectura.
+pub async fn simulate_payroll_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Json(payload): Json<SimulationRequest>,
+) -> Result<Json<SimulationResponse>, (StatusCode, String)> {
+    if payload.models.is_empty() || payload.models.len() > MAX_MODELS {
+        return Err((StatusCode::BAD_REQUEST, format!("Between 1 and {} models are required", MAX_MODELS)));
+    }
+    // Reglas vigentes: la escalera de la política de strikes activa (o la del código si no hay)
+    let policy = strikes::load_active_policy(&state.db).await.map_err(|e| {
+        tracing::error!("Loading strike policy for simulation failed: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e)
+    })?;
+    let current = SimulationRules::from_policy(&policy);
+    let proposed = match payload.rules {
+        Some(overrides) => {
+            let rules = current.with_overrides(overrides).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            rules.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            Some(rules)
+        }
+        None => None,
+    };
+
+    let admin_base_rate = match payload.admin_base_rate.filter(|r| *r > 0.0) {
+        Some(rate) => rate,
+        None => sqlx::query_scalar::<_, f64>("SELECT admin_base_rate::DOUBLE PRECISION FROM system_settings WHERE id = 1")
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "system_settings row missing".to_string()))?,
+    };
+
+    let mut models = Vec::with_capacity(payload.models.len());
+    for (index, input) in payload.models.into_iter().enumerate() {
+        models.push(resolve_model(&state, input, index, policy.window_days).await?);
+    }
+
+    let baseline = simulate_week(&models, &payload.events, &current, admin_base_rate);
+    let scenario = proposed
+        .filter(|rules| *rules != current)
+        .map(|rules| simulate_week(&models, &payload.events, &rules, admin_base_rate));
+    let delta = scenario.as_ref().map(|s| compare(&baseline, s));
+
+    tracing::info!(
+        "🧮 Simulación de nómina: {} modelos, semana {} ({})",
+        models.len(),
+        Utc::now().date_naive().iso_week().week(),
+        if scenario.is_some() { "con escenario" } else { "reglas vigentes" }
+    );
+
+    Ok(Json(SimulationResponse { baseline, scenario, delta }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(name: &str, weekly_tokens: f64, strikes: u8, start_xp: i64, excellence: bool) -> SimModel {
+        SimModel {
+            user_id: None,
+            name: name.to_string(),
+            room_id: None,
+            daily_tokens: [weekly_tokens / 7.0; DAYS_PER_WEEK],
+            token_usd_value: platforms::registry().token_usd_value(None),
+            strikes,
+            start_xp,
+            excellence,
+        }
+    }
+
+    fn matrix_models() -> Vec<SimModel> {
+        vec![
+            model("Modelo A", 30_000.0, 0, 10_000, true),
+            model("Modelo B", 500.0, 2, 8_000, false),
+            model("Modelo C", 1_200.0, 0, 9_000, false),
+        ]
+    }
+
+    #[test]
+    fn payouts_and_studio_wallet_add_up_to_gross() {
+        let events = SimWeekEvents { dirty_days: vec![3] };
+        let result = simulate_week(&matrix_models(), &events, &SimulationRules::default(), 4100.0);
+
+        let accounted = result.total_payout_cop + result.studio_wallet_cop;
+        assert!((accounted - result.gross_cop).abs() < 1e-6, "{accounted} vs {}", result.gross_cop);
+        assert!(result.room_shortfalls.iter().all(|r| r.failed_days.is_empty()));
+        // Strike 1: un día a medio pago; strike 2: semana al 50%
+        assert!((result.models[1].payout_factor - 0.5 * 6.5 / 7.0).abs() < 1e-9);
+        assert!(result.models[0].excellence_bonus_cop > 0.0);
+        assert!(result.models.iter().all(|m| m.dirty_room_penalties_cop == DIRTY_ROOM_PENALTY_COP));
+    }
+
+    #[test]
+    fn penalties_never_drive_payout_negative() {
+        let events = SimWeekEvents { dirty_days: vec![0, 1] };
+        let result = simulate_week(&[model("Solo", 700.0, 3, 1_000, false)], &events, &SimulationRules::default(), 4100.0);
+
+        let m = &result.models[0];
+        assert_eq!(m.payout_cop, 0.0);
+        assert!(m.uncollected_penalties_cop > 0.0);
+        assert_eq!(result.room_shortfalls[0].failed_days.len(), 7);
+        assert_eq!(m.xp_end, 0); // strike 3 quema el 100%
+    }
+
+    #[test]
+    fn each_strike_burns_xp_at_its_level() {
+        let rules = SimulationRules { group_quota: 0.0, ..SimulationRules::default() };
+        let result = simulate_week(&[model("B", 0.0, 2, 8_000, false)], &SimWeekEvents::default(), &rules, 4100.0);
+        // 10% del strike 1 y luego 30% del strike 2: 8000 → 7200 → 5040
+        assert_eq!(result.models[0].xp_burned, 2_960);
+    }
+
+    #[test]
+    fn current_rules_follow_the_active_strike_policy() {
+        let policy = StrikePolicy {
+            ladder: vec![
+                StrikeLevel { level: 1, xp_burn_reason: None, consequences: vec![] },
+                StrikeLevel {
+                    level: 2,
+                    xp_burn_reason: None,
+                    consequences: vec![
+                        StrikeConsequence::DowngradeWeek { factor: 0.8 },
+                        StrikeConsequence::Fine { amount_cop: 20_000.0 },
+                    ],
+                },
+            ],
+            ..StrikePolicy::default()
+        };
+        let rules = SimulationRules { group_quota: 0.0, ..SimulationRules::from_policy(&policy) };
+        let result = simulate_week(&[model("B", 7_000.0, 3, 1_000, false)], &SimWeekEvents::default(), &rules, 4100.0);
+
+        // El strike 3 repite el último escalón
+        let m = &result.models[0];
+        assert!((m.payout_factor - 0.64).abs() < 1e-9);
+        assert_eq!(m.strike_penalty_cop, 40_000.0);
+        assert_eq!(m.xp_burned, 0);
+    }
+
+    #[test]
+    fn proposed_rules_only_change_the_given_fields() {
+        let policy = StrikePolicy { ladder: StrikePolicy::default().ladder[..2].to_vec(), ..StrikePolicy::default() };
+        let current = SimulationRules::from_policy(&policy);
+        let proposed = current.with_overrides(serde_json::json!({ "dirty_room_penalty_cop": 250_000.0 })).unwrap();
+        assert_eq!(proposed.strike_ladder, policy.ladder);
+        assert_eq!(proposed.dirty_room_penalty_cop, 250_000.0);
+        assert!(current.with_overrides(serde_json::json!({ "group_quota": "mucho" })).is_err());
+    }
+
+    #[test]
+    fn scenario_delta_reflects_rule_change() {
+        let models = matrix_models();
+        let events = SimWeekEvents { dirty_days: vec![3] };
+        let baseline = simulate_week(&models, &events, &SimulationRules::default(), 4100.0);
+        let softer = SimulationRules { dirty_room_penalty_cop: 250_000.0, ..SimulationRules::default() };
+        let scenario = simulate_week(&models, &events, &softer, 4100.0);
+
+        // B y C no alcanzan a cubrir ni la multa reducida: solo cambia lo cobrado a A
+        let delta = compare(&baseline, &scenario);
+        assert!((delta.penalties_collected_cop_delta + 250_000.0).abs() < 1e-6);
+        assert!((delta.total_payout_cop_delta - 250_000.0).abs() < 1e-6);
+        assert!((delta.studio_wallet_cop_delta + 250_000.0).abs() < 1e-6);
+        assert!((delta.models[0].payout_cop_delta - 250_000.0).abs() < 1e-6);
+        assert!(delta.models[1..].iter().all(|m| m.payout_cop_delta.abs() < 1e-6));
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(SimulationRules { model_share: 1.5, ..SimulationRules::default() }.validate().is_err());
+        assert!(SimulationRules { dirty_room_burn_pct: 120.0, ..SimulationRules::default() }.validate().is_err());
+        assert!(SimulationRules::default().validate().is_ok());
+    }
+
+    #[test]
+    fn group_quota_is_measured_per_room() {
+        let strong = SimModel { room_id: Some(1), ..model("Fuerte", 30_000.0, 0, 0, false) };
+        let weak = SimModel { room_id: Some(2), ..model("Débil", 700.0, 0, 0, false) };
+        let result = simulate_week(&[strong, weak], &SimWeekEvents::default(), &SimulationRules::default(), 4100.0);
+
+        // La producción del room 1 no cubre la cuota del room 2
+        assert_eq!(result.models[0].group_penalties_cop, 0.0);
+        assert_eq!(result.models[1].group_penalties_cop, GROUP_SHORTFALL_PENALTY_COP * 7.0);
+        assert_eq!(result.room_shortfalls.len(), 2);
+    }
+
+    #[test]
+    fn tokens_are_valued_per_model_platform() {
+        let cheap = SimModel { token_usd_value: 0.05, ..model("A", 7_000.0, 0, 0, false) };
+        let pricey = SimModel { token_usd_value: 0.10, ..model("B", 7_000.0, 0, 0, false) };
+        let result = simulate_week(&[cheap, pricey], &SimWeekEvents::default(), &SimulationRules::default(), 4100.0);
+
+        assert!((result.gross_usd - 1_050.0).abs() < 1e-6);
+        assert!((result.models[1].base_payout_cop - 2.0 * result.models[0].base_payout_cop).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,805 @@
+/// Extractos oficiales de ganancias de las plataformas (CSV/XLSX).
+///
+/// El extracto es la fuente autoritativa de lo que cada modelo ganó en un periodo.
+/// Cada fila se asocia a una modelo mediante `platform_accounts` (username por
+/// plataforma) y el reporte de conciliación compara por modelo contra la telemetría
+/// (`telemetry_token_deltas`) y la producción digitada por moderadores.
+use std::{collections::BTreeMap, io::Cursor, sync::Arc};
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use calamine::Reader;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::AdminOnly,
+    state::AppState,
+    tracking::{platforms::{self, StatementFormat}, reconcile::ReconcileConfig},
+};
+
+/// Filas iniciales donde se busca la fila de encabezados (algunos extractos traen un título arriba)
+const HEADER_SEARCH_ROWS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementRow {
+    /// Fila en el archivo (1 = primera)
+    pub row_number: i32,
+    pub username: String,
+    pub tokens: f64,
+    pub usd: f64,
+}
+
+/// Lee la primera hoja (XLSX/XLS/ODS) o el CSV como tabla de textos
+pub fn read_table(bytes: &[u8], file_name: &str) -> Result<Vec<Vec<String>>, String> {
+    let ext = std::path::Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match ext.as_str() {
+        "xlsx" | "xlsm" | "xls" | "ods" => {
+            let mut workbook = calamine::open_workbook_auto_from_rs(Cursor::new(bytes.to_vec()))
+                .map_err(|e| format!("Invalid spreadsheet: {}", e))?;
+            let range = workbook
+                .worksheet_range_at(0)
+                .ok_or("Spreadsheet has no sheets")?
+                .map_err(|e| format!("Invalid spreadsheet: {}", e))?;
+            Ok(range
+                .rows()
+                .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+                .collect())
+        }
+        "csv" | "txt" | "" => {
+            // Algunos portales exportan con ';' (configuración regional)
+            let first_line = bytes.split(|b| *b == b'\n').next().unwrap_or_default();
+            let delimiter = if first_line.iter().filter(|b| **b == b';').count() > first_line.iter().filter(|b| **b == b',').count() {
+                b';'
+            } else {
+                b','
+            };
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .delimiter(delimiter)
+                .from_reader(bytes);
+            reader
+                .records()
+                .map(|r| {
+                    r.map(|rec| rec.iter().map(|c| c.to_string()).collect())
+                        .map_err(|e| format!("Invalid CSV: {}", e))
+                })
+                .collect()
+        }
+        other => Err(format!("Unsupported statement file type '.{}'", other)),
+    }
+}
+
+/// "$1,234.50", "1.234,50", "1234", "USD 12.00" -> número.
+/// El separador decimal es el último '.' o ',' si lo siguen a lo sumo dos dígitos;
+/// cualquier otro separador es de miles ("1.234" = 1234 en formato colombiano).
+pub fn parse_amount(raw: &str) -> Option<f64> {
+    let trimmed = raw.trim();
+    let negative = (trimmed.starts_with('(') && trimmed.ends_with(')')) || trimmed.starts_with('-');
+    let clean: String = trimmed.chars().filter(|c| c.is_ascii_digit() || *c == '.' || *c == ',').collect();
+    let decimal_at = clean
+        .rfind(['.', ','])
+        .filter(|&i| (1..=2).contains(&(clean.len() - i - 1)));
+    let (integer, fraction) = match decimal_at {
+        Some(i) => (&clean[..i], &clean[i + 1..]),
+        None => (clean.as_str(), ""),
+    };
+    let integer: String = integer.chars().filter(char::is_ascii_digit).collect();
+    if integer.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    format!("{}.{}", integer, fraction)
+        .parse::<f64>()
+        .ok()
+        .map(|v| if negative { -v } else { v })
+}
+
+fn find_column(headers: &[String], candidates: &[String]) -> Option<usize> {
+    // Excel antepone un BOM UTF-8 a la primera celda de los CSV
+    let normalized: Vec<String> =
+        headers.iter().map(|h| h.trim_start_matches('\u{feff}').trim().to_lowercase()).collect();
+    candidates
+        .iter()
+        .find_map(|c| normalized.iter().position(|h| *h == c.to_lowercase()))
+        .or_else(|| {
+            candidates
+                .iter()
+                .find_map(|c| normalized.iter().position(|h| h.starts_with(&c.to_lowercase())))
+        })
+}
+
+/// Convierte la tabla en filas del extracto según el formato de la plataforma.
+/// Las filas sin username y las de totales se ignoran; sin columna USD se usa el valor del token.
+pub fn parse_statement(
+    table: &[Vec<String>],
+    format: &StatementFormat,
+    token_usd_value: f64,
+) -> Result<Vec<StatementRow>, String> {
+    let (header_idx, user_col, tokens_col, usd_col) = table
+        .iter()
+        .take(HEADER_SEARCH_ROWS)
+        .enumerate()
+        .find_map(|(i, row)| {
+            let user = find_column(row, &format.username)?;
+            let tokens = find_column(row, &format.tokens)?;
+            Some((i, user, tokens, find_column(row, &format.usd)))
+        })
+        .ok_or("Statement headers not recognised for this platform")?;
+
+    let mut rows = Vec::new();
+    for (i, row) in table.iter().enumerate().skip(header_idx + 1) {
+        let row_number = (i + 1) as i32;
+        let username = row.get(user_col).map(|s| s.trim()).unwrap_or_default();
+        if username.is_empty() || username.to_lowercase().starts_with("total") {
+            continue;
+        }
+        let tokens = row
+            .get(tokens_col)
+            .and_then(|v| parse_amount(v))
+            .ok_or(format!("Row {}: invalid tokens value", row_number))?;
+        let usd = usd_col
+            .and_then(|c| row.get(c))
+            .and_then(|v| parse_amount(v))
+            .unwrap_or(tokens * token_usd_value);
+        rows.push(StatementRow { row_number, username: username.to_string(), tokens, usd });
+    }
+
+    if rows.is_empty() {
+        return Err("Statement has no rows".to_string());
+    }
+    Ok(rows)
+}
+
+/// Diferencia porcentual de `value` respecto al extracto; None si el extracto está en cero
+pub fn variance_pct(value: f64, statement: f64) -> Option<f64> {
+    (statement > 0.0).then(|| (value - statement) / statement * 100.0)
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModelVariance {
+    pub model_id: Uuid,
+    pub statement_tokens: f64,
+    pub statement_usd: f64,
+    pub telemetry_tokens: f64,
+    pub telemetry_variance_pct: Option<f64>,
+    pub manual_tokens: f64,
+    pub manual_variance_pct: Option<f64>,
+    pub flagged: bool,
+}
+
+/// Cruza por modelo el extracto (tokens, usd) con telemetría y carga manual
+pub fn build_reconciliation(
+    statement: &BTreeMap<Uuid, (f64, f64)>,
+    telemetry: &BTreeMap<Uuid, f64>,
+    manual: &BTreeMap<Uuid, f64>,
+    threshold_pct: f64,
+) -> Vec<ModelVariance> {
+    let mut models: Vec<Uuid> = statement.keys().chain(telemetry.keys()).chain(manual.keys()).copied().collect();
+    models.sort();
+    models.dedup();
+
+    models
+        .into_iter()
+        .map(|model_id| {
+            let (statement_tokens, statement_usd) = statement.get(&model_id).copied().unwrap_or((0.0, 0.0));
+            let telemetry_tokens = telemetry.get(&model_id).copied().unwrap_or(0.0);
+            let manual_tokens = manual.get(&model_id).copied().unwrap_or(0.0);
+            let telemetry_variance_pct = variance_pct(telemetry_tokens, statement_tokens);
+            let manual_variance_pct = variance_pct(manual_tokens, statement_tokens);
+            let off = |pct: Option<f64>| pct.is_some_and(|p| p.abs() > threshold_pct);
+            // Sin extracto pero con producción registrada: la plataforma no la pagó
+            let missing_in_statement = statement_tokens <= 0.0 && (telemetry_tokens > 0.0 || manual_tokens > 0.0);
+            ModelVariance {
+                model_id,
+                statement_tokens,
+                statement_usd,
+                telemetry_tokens,
+                telemetry_variance_pct,
+                manual_tokens,
+                manual_variance_pct,
+                flagged: missing_in_statement || off(telemetry_variance_pct) || off(manual_variance_pct),
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// PERSISTENCIA
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EarningsStatement {
+    pub id: Uuid,
+    pub platform: String,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub file_name: String,
+    pub row_count: i32,
+    pub unmatched_count: i32,
+    pub total_tokens: f64,
+    pub total_usd: f64,
+    pub status: String,
+    pub imported_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+const STATEMENT_COLUMNS: &str = "id, platform, period_start, period_end, file_name, row_count, unmatched_count, \
+    total_tokens::DOUBLE PRECISION AS total_tokens, total_usd::DOUBLE PRECISION AS total_usd, status, imported_by, created_at";
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UnmatchedLine {
+    pub row_number: i32,
+    pub username: String,
+    pub tokens: f64,
+    pub usd: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResponse {
+    pub statement: EarningsStatement,
+    pub superseded: Vec<Uuid>,
+    pub unmatched: Vec<UnmatchedLine>,
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("DB error in earnings statements: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn parse_admin_id(raw: &str) -> Result<Uuid, (StatusCode, String)> {
+    Uuid::parse_str(raw).map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user id in token".to_string()))
+}
+
+async fn fetch_statement(pool: &PgPool, id: Uuid) -> Result<EarningsStatement, (StatusCode, String)> {
+    sqlx::query_as::<_, EarningsStatement>(&format!("SELECT {STATEMENT_COLUMNS} FROM earnings_statements WHERE id = $1"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Extracto no encontrado".to_string()))
+}
+
+/// POST /api/admin/finance/statements (multipart: file, platform, period_start, period_end)
+/// Reimportar el mismo periodo deja el extracto anterior como SUPERSEDED.
+pub async fn import_statement_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminOnly,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<ImportResponse>), (StatusCode, String)> {
+    let admin_id = parse_admin_id(&admin.user_id)?;
+    let bad_request = |msg: String| (StatusCode::BAD_REQUEST, msg);
+
+    let mut file: Option<(String, Vec<u8>)> = None;
+    let mut platform: Option<String> = None;
+    let mut period_start: Option<NaiveDate> = None;
+    let mut period_end: Option<NaiveDate> = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| bad_request(e.to_string()))?
+    {
+        match field.name() {
+            Some("file") => {
+                let name = field.file_name().unwrap_or("statement.csv").to_string();
+                let data = field.bytes().await.map_err(|e| bad_request(e.to_string()))?;
+                file = Some((name, data.to_vec()));
+            }
+            Some(name @ ("platform" | "period_start" | "period_end")) => {
+                let name = name.to_string();
+                let value = field.text().await.map_err(|e| bad_request(e.to_string()))?;
+                match name.as_str() {
+                    "platform" => platform = Some(value),
+                    other => {
+                        let date = NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+                            .map_err(|e| bad_request(format!("Invalid {}: {}", other, e)))?;
+                        if other == "period_start" {
+                            period_start = Some(date);
+                        } else {
+                            period_end = Some(date);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let (file_name, bytes) = file.ok_or(bad_request("file field is required".to_string()))?;
+    let platform = platform.ok_or(bad_request("platform field is required".to_string()))?;
+    let (period_start, period_end) = match (period_start, period_end) {
+        (Some(start), Some(end)) if end >= start => (start, end),
+        (Some(_), Some(_)) => return Err(bad_request("period_end must be on or after period_start".to_string())),
+        _ => return Err(bad_request("period_start and period_end are required".to_string())),
+    };
+
+    let adapter = platforms::registry()
+        .resolve(&platform)
+        .ok_or(bad_request(format!("Unknown platform '{}'", platform)))?;
+    let platform = adapter.id().to_string();
+
+    let table = read_table(&bytes, &file_name).map_err(bad_request)?;
+    let rows = parse_statement(&table, &adapter.statement_format(), adapter.token_usd_value())
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    let sha = hex::encode(Sha256::digest(&bytes));
+
+    let accounts: BTreeMap<String, Uuid> = sqlx::query_as::<_, (String, Uuid)>(
+        "SELECT LOWER(username), model_id FROM platform_accounts WHERE platform = $1 AND active = TRUE",
+    )
+    .bind(&platform)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .collect();
+
+    let total_tokens: f64 = rows.iter().map(|r| r.tokens).sum();
+    let total_usd: f64 = rows.iter().map(|r| r.usd).sum();
+    let unmatched: Vec<UnmatchedLine> = rows
+        .iter()
+        .filter(|r| !accounts.contains_key(&r.username.to_lowercase()))
+        .map(|r| UnmatchedLine { row_number: r.row_number, username: r.username.clone(), tokens: r.tokens, usd: r.usd })
+        .collect();
+
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+
+    let duplicate = sqlx::query_scalar::<_, Uuid>("SELECT id FROM earnings_statements WHERE platform = $1 AND file_sha256 = $2")
+        .bind(&platform)
+        .bind(&sha)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    if let Some(existing) = duplicate {
+        return Err((StatusCode::CONFLICT, format!("Este archivo ya fue importado (extracto {})", existing)));
+    }
+
+    let superseded = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE earnings_statements SET status = 'SUPERSEDED'
+        WHERE platform = $1 AND period_start = $2 AND period_end = $3 AND status = 'ACTIVE'
+        RETURNING id
+        "#,
+    )
+    .bind(&platform)
+    .bind(period_start)
+    .bind(period_end)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let statement_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO earnings_statements
+            (platform, period_start, period_end, file_name, file_sha256, row_count, unmatched_count, total_tokens, total_usd, imported_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING id
+        "#,
+    )
+    .bind(&platform)
+    .bind(period_start)
+    .bind(period_end)
+    .bind(&file_name)
+    .bind(&sha)
+    .bind(rows.len() as i32)
+    .bind(unmatched.len() as i32)
+    .bind(total_tokens)
+    .bind(total_usd)
+    .bind(admin_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    for row in &rows {
+        sqlx::query(
+            r#"
+            INSERT INTO earnings_statement_lines (statement_id, row_number, username, model_id, tokens, usd)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(statement_id)
+        .bind(row.row_number)
+        .bind(&row.username)
+        .bind(accounts.get(&row.username.to_lowercase()).copied())
+        .bind(row.tokens)
+        .bind(row.usd)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    }
+
+    sqlx::query(
+        "INSERT INTO audit_trail (entity_type, entity_id, action, new_value, user_id) VALUES ('earnings_statement', $1, 'create', $2, $3)",
+    )
+    .bind(statement_id)
+    .bind(serde_json::json!({
+        "platform": platform,
+        "period_start": period_start,
+        "period_end": period_end,
+        "file_name": file_name,
+        "file_sha256": sha,
+        "rows": rows.len(),
+        "unmatched": unmatched.len(),
+        "total_tokens": total_tokens,
+        "total_usd": total_usd,
+        "superseded": superseded,
+    }))
+    .bind(admin_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    tracing::info!(
+        "📄 Extracto {} {}..{} importado por {}: {} filas, {} sin modelo",
+        platform,
+        period_start,
+        period_end,
+        admin.email,
+        rows.len(),
+        unmatched.len()
+    );
+
+    let statement = fetch_statement(&state.db, statement_id).await?;
+    Ok((StatusCode::CREATED, Json(ImportResponse { statement, superseded, unmatched })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatementsQuery {
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub include_superseded: bool,
+}
+
+/// GET /api/admin/finance/statements?platform=&include_superseded=
+pub async fn list_statements_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Query(query): Query<StatementsQuery>,
+) -> Result<Json<Vec<EarningsStatement>>, (StatusCode, String)> {
+    let platform = query
+        .platform
+        .map(|p| platforms::registry().resolve(&p).map_or(p, |a| a.id().to_string()));
+    let statements = sqlx::query_as::<_, EarningsStatement>(&format!(
+        r#"
+        SELECT {STATEMENT_COLUMNS} FROM earnings_statements
+        WHERE ($1::TEXT IS NULL OR platform = $1) AND ($2 OR status = 'ACTIVE')
+        ORDER BY period_start DESC, created_at DESC
+        LIMIT 200
+        "#
+    ))
+    .bind(platform)
+    .bind(query.include_superseded)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(statements))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReconciliationReport {
+    pub statement: EarningsStatement,
+    pub threshold_pct: f64,
+    /// Siempre `false`: la carga manual comparada es solo la de esta plataforma; los registros
+    /// manuales sin plataforma no se pueden atribuir a un extracto y quedan fuera
+    pub manual_includes_unassigned_platform: bool,
+    pub models: Vec<ModelVariance>,
+    pub unmatched: Vec<UnmatchedLine>,
+    pub flagged_models: usize,
+}
+
+/// GET /api/admin/finance/statements/:id/reconciliation
+/// Varianza por modelo: extracto vs telemetría vs producción digitada por moderadores
+pub async fn statement_reconciliation_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReconciliationReport>, (StatusCode, String)> {
+    let statement = fetch_statement(&state.db, id).await?;
+
+    let by_model: BTreeMap<Uuid, (f64, f64)> = sqlx::query_as::<_, (Uuid, f64, f64)>(
+        r#"
+        SELECT model_id, SUM(tokens)::DOUBLE PRECISION, SUM(usd)::DOUBLE PRECISION
+        FROM earnings_statement_lines
+        WHERE statement_id = $1 AND model_id IS NOT NULL
+        GROUP BY model_id
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .map(|(model_id, tokens, usd)| (model_id, (tokens, usd)))
+    .collect();
+
+    let telemetry: BTreeMap<Uuid, f64> = sqlx::query_as::<_, (Uuid, f64)>(
+        r#"
+        SELECT model_id, SUM(tokens_delta)::DOUBLE PRECISION
+        FROM telemetry_token_deltas
+        WHERE platform = $1 AND work_date BETWEEN $2 AND $3 AND model_id IS NOT NULL
+        GROUP BY model_id
+        "#,
+    )
+    .bind(&statement.platform)
+    .bind(statement.period_start)
+    .bind(statement.period_end)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .collect();
+
+    let manual: BTreeMap<Uuid, f64> = sqlx::query_as::<_, (Uuid, f64)>(
+        r#"
+        SELECT model_id, COALESCE(SUM(tokens_earned), 0)::DOUBLE PRECISION
+        FROM production_logs
+        WHERE source = 'MANUAL' AND superseded_by_draft IS NULL AND model_id IS NOT NULL
+          AND production_date BETWEEN $2 AND $3
+          AND platform = $1
+        GROUP BY model_id
+        "#,
+    )
+    .bind(&statement.platform)
+    .bind(statement.period_start)
+    .bind(statement.period_end)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .collect();
+
+    let unmatched = sqlx::query_as::<_, UnmatchedLine>(
+        r#"
+        SELECT row_number, username, tokens::DOUBLE PRECISION AS tokens, usd::DOUBLE PRECISION AS usd
+        FROM earnings_statement_lines
+        WHERE statement_id = $1 AND model_id IS NULL
+        ORDER BY row_number
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    let threshold_pct = ReconcileConfig::from_env().discrepancy_threshold_pct;
+    let models = build_reconciliation(&by_model, &telemetry, &manual, threshold_pct);
+    let flagged_models = models.iter().filter(|m| m.flagged).count();
+
+    Ok(Json(ReconciliationReport {
+        statement,
+        threshold_pct,
+        manual_includes_unassigned_platform: false,
+        models,
+        unmatched,
+        flagged_models,
+    }))
+}
+
+// ============================================================================
+// CUENTAS POR PLATAFORMA
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PlatformAccount {
+    pub id: Uuid,
+    pub platform: String,
+    pub username: String,
+    pub model_id: Uuid,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountsQuery {
+    pub platform: Option<String>,
+    pub model_id: Option<Uuid>,
+}
+
+/// GET /api/admin/platform-accounts?platform=&model_id=
+pub async fn list_platform_accounts_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Query(query): Query<AccountsQuery>,
+) -> Result<Json<Vec<PlatformAccount>>, (StatusCode, String)> {
+    let platform = query
+        .platform
+        .map(|p| platforms::registry().resolve(&p).map_or(p, |a| a.id().to_string()));
+    let accounts = sqlx::query_as::<_, PlatformAccount>(
+        r#"
+        SELECT id, platform, username, model_id, active, created_at
+        FROM platform_accounts
+        WHERE ($1::TEXT IS NULL OR platform = $1) AND ($2::UUID IS NULL OR model_id = $2)
+        ORDER BY platform, username
+        "#,
+    )
+    .bind(platform)
+    .bind(query.model_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(accounts))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertAccountRequest {
+    pub platform: String,
+    pub username: String,
+    pub model_id: Uuid,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpsertAccountResponse {
+    pub account: PlatformAccount,
+    /// Filas de extractos sin modelo que quedaron asociadas con esta cuenta
+    pub rematched_lines: u64,
+}
+
+/// PUT /api/admin/platform-accounts
+/// Crea o reasigna el username de una plataforma; las filas huérfanas de extractos se re-asocian.
+pub async fn upsert_platform_account_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminOnly,
+    Json(payload): Json<UpsertAccountRequest>,
+) -> Result<Json<UpsertAccountResponse>, (StatusCode, String)> {
+    let admin_id = parse_admin_id(&admin.user_id)?;
+    let username = payload.username.trim();
+    if username.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "username is required".to_string()));
+    }
+    let platform = platforms::registry()
+        .resolve(&payload.platform)
+        .ok_or((StatusCode::BAD_REQUEST, format!("Unknown platform '{}'", payload.platform)))?
+        .id()
+        .to_string();
+
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+    let account = sqlx::query_as::<_, PlatformAccount>(
+        r#"
+        INSERT INTO platform_accounts (platform, username, model_id, active, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (platform, LOWER(username)) DO UPDATE
+        SET username = EXCLUDED.username, model_id = EXCLUDED.model_id, active = EXCLUDED.active
+        RETURNING id, platform, username, model_id, active, created_at
+        "#,
+    )
+    .bind(&platform)
+    .bind(username)
+    .bind(payload.model_id)
+    .bind(payload.active)
+    .bind(admin_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        let missing_model = e
+            .as_database_error()
+            .and_then(|d| d.code())
+            .is_some_and(|code| code == "23503");
+        if missing_model {
+            (StatusCode::NOT_FOUND, "Modelo no encontrada".to_string())
+        } else {
+            db_error(e)
+        }
+    })?;
+
+    let rematched_lines = if account.active {
+        sqlx::query(
+            r#"
+            UPDATE earnings_statement_lines l SET model_id = $3
+            FROM earnings_statements s
+            WHERE l.statement_id = s.id AND s.platform = $1 AND l.model_id IS NULL AND LOWER(l.username) = LOWER($2)
+            "#,
+        )
+        .bind(&platform)
+        .bind(username)
+        .bind(account.model_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .rows_affected()
+    } else {
+        0
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE earnings_statements s
+        SET unmatched_count = (SELECT COUNT(*) FROM earnings_statement_lines l WHERE l.statement_id = s.id AND l.model_id IS NULL)
+        WHERE s.platform = $1
+        "#,
+    )
+    .bind(&platform)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+    Ok(Json(UpsertAccountResponse { account, rematched_lines }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracking::platforms::PlatformAdapter;
+
+    fn table(csv: &str) -> Vec<Vec<String>> {
+        read_table(csv.as_bytes(), "statement.csv").unwrap()
+    }
+
+    #[test]
+    fn parses_amounts_with_currency_and_thousands() {
+        assert_eq!(parse_amount("$1,234.50"), Some(1234.5));
+        assert_eq!(parse_amount(" 980 "), Some(980.0));
+        assert_eq!(parse_amount("(12.00)"), Some(-12.0));
+        assert_eq!(parse_amount("n/a"), None);
+        assert_eq!(parse_amount("1.234,50"), Some(1234.5));
+        assert_eq!(parse_amount("1.234"), Some(1234.0));
+        assert_eq!(parse_amount("1,234"), Some(1234.0));
+        assert_eq!(parse_amount("12,5"), Some(12.5));
+        assert_eq!(parse_amount("COP 1.250.000"), Some(1_250_000.0));
+    }
+
+    #[test]
+    fn finds_headers_below_a_title_and_skips_totals() {
+        let rows = table(
+            "Earnings report,,\nPeriod 2025-12-01 - 2025-12-15,,\nUsername,Tokens Earned,Payout (USD)\n\
+             lunaxx,\"12,000\",$600.00\nsofi_cam,3000,150\nTotal,15000,750\n",
+        );
+        let format = platforms::Chaturbate.statement_format();
+        let parsed = parse_statement(&rows, &format, 0.05).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0], StatementRow { row_number: 4, username: "lunaxx".to_string(), tokens: 12000.0, usd: 600.0 });
+        assert_eq!(parsed[1].usd, 150.0);
+    }
+
+    #[test]
+    fn missing_usd_column_uses_token_value_and_semicolons_are_detected() {
+        let rows = table("Model;Tokens\nlunaxx;1000\n");
+        let parsed = parse_statement(&rows, &StatementFormat::default(), 0.05).unwrap();
+        assert_eq!(parsed[0].usd, 50.0);
+
+        let with_bom = table("\u{feff}Model;Tokens\nlunaxx;1.500\n");
+        assert_eq!(parse_statement(&with_bom, &StatementFormat::default(), 0.05).unwrap()[0].tokens, 1500.0);
+
+        let bad = table("Model,Tokens\nlunaxx,abc\n");
+        assert!(parse_statement(&bad, &StatementFormat::default(), 0.05).unwrap_err().contains("Row 2"));
+        assert!(parse_statement(&table("foo,bar\n1,2\n"), &StatementFormat::default(), 0.05).is_err());
+    }
+
+    #[test]
+    fn reconciliation_flags_variances_and_unpaid_production() {
+        let a = Uuid::from_u128(1);
+        let b = Uuid::from_u128(2);
+        let c = Uuid::from_u128(3);
+        let statement = BTreeMap::from([(a, (1000.0, 50.0)), (b, (2000.0, 100.0))]);
+        let telemetry = BTreeMap::from([(a, 980.0), (b, 1500.0), (c, 300.0)]);
+        let manual = BTreeMap::from([(a, 1000.0)]);
+
+        let report = build_reconciliation(&statement, &telemetry, &manual, 5.0);
+        assert_eq!(report.len(), 3);
+        assert!(!report[0].flagged);
+        assert_eq!(report[0].telemetry_variance_pct, Some(-2.0));
+        assert!(report[1].flagged);
+        assert_eq!(report[1].manual_variance_pct, Some(-100.0));
+        assert!(report[2].flagged);
+        assert_eq!(report[2].telemetry_variance_pct, None);
+    }
+}
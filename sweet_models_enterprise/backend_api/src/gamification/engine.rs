@@ -1,6 +1,6 @@
 // Módulo de gamificación: Motor de XP y rangos
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{Connection, PgConnection, PgPool};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -293,75 +293,159 @@ pub const FRAGILITY_BURNS: &[(&str, f64, &str)] = &[
     ("LOW_PRODUCTION", 5.0, "Perdiste 5% XP por baja producción (<1500 tokens)"),
 ];
 
-/// Quema XP del usuario por infracciones
+/// Quema XP del usuario por infracciones.
+/// Recibe la conexión para que el llamador la incluya en su transacción (strike, cierre de turno).
 pub async fn burn_xp(
     user_id: Uuid,
     reason: &str,
-    pool: &PgPool,
+    conn: &mut PgConnection,
 ) -> Result<BurnResult, String> {
     let (_, burn_percentage, description) = FRAGILITY_BURNS
         .iter()
         .find(|&(r, _, _)| r == &reason)
         .ok_or_else(|| format!("Regla de fragilidad desconocida: {}", reason))?;
 
-    // Obtener XP actual
-    let current_xp = sqlx::query_scalar::<_, i64>(
-        "SELECT COALESCE(xp, 0) FROM users WHERE id = $1",
-    )
-    .bind(user_id)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    let xp_loss = ((current_xp as f64) * (burn_percentage / 100.0)) as i64;
-    let new_xp = (current_xp - xp_loss).max(0);
-
-    // Actualizar base de datos
-    sqlx::query(
+    // Un solo UPDATE: dos quemas simultáneas no pueden partir del mismo XP
+    let (previous_xp, new_xp) = sqlx::query_as::<_, (i64, i64)>(
         r#"
-        UPDATE users
-        SET xp = $2,
+        UPDATE users u
+        SET xp = GREATEST(COALESCE(old.xp, 0) - FLOOR(COALESCE(old.xp, 0) * $2 / 100.0)::BIGINT, 0),
             updated_at = NOW()
-        WHERE id = $1
+        FROM (SELECT id, xp FROM users WHERE id = $1 FOR UPDATE) old
+        WHERE u.id = old.id
+        RETURNING COALESCE(old.xp, 0), u.xp
         "#,
     )
     .bind(user_id)
-    .bind(new_xp)
-    .execute(pool)
+    .bind(burn_percentage)
+    .fetch_one(&mut *conn)
     .await
     .map_err(|e| e.to_string())?;
+    let xp_loss = previous_xp - new_xp;
 
-    // Registrar evento en audit
-    let _ = sqlx::query(
-        r#"
-        INSERT INTO xp_burn_log (user_id, reason, xp_loss, previous_xp, new_xp, timestamp)
-        VALUES ($1, $2, $3, $4, $5, NOW())
-        ON CONFLICT DO NOTHING
-        "#,
-    )
-    .bind(user_id)
-    .bind(reason)
-    .bind(xp_loss)
-    .bind(current_xp)
-    .bind(new_xp)
-    .execute(pool)
-    .await;
+    log_xp_change(conn, user_id, reason, xp_loss, previous_xp, new_xp).await;
 
     tracing::warn!(
         "🔥 XP QUEMADO: {} perdió {} XP ({:.0}%) por {} | {}/{} XP",
-        user_id, xp_loss, burn_percentage, reason, new_xp, current_xp
+        user_id, xp_loss, burn_percentage, reason, new_xp, previous_xp
     );
 
     Ok(BurnResult {
         user_id,
         xp_loss,
-        previous_xp: current_xp,
+        previous_xp,
         new_xp,
         percentage: *burn_percentage,
         description: description.to_string(),
     })
 }
 
+/// Quema una cantidad fija de XP (ej. asientos de reportes de producción), con el mismo log que `burn_xp`.
+/// Devuelve el XP realmente perdido: nunca deja el saldo por debajo de cero.
+pub async fn burn_xp_amount(
+    user_id: Uuid,
+    amount: i64,
+    reason: &str,
+    conn: &mut PgConnection,
+) -> Result<i64, String> {
+    if amount <= 0 {
+        return Ok(0);
+    }
+
+    let (previous_xp, new_xp) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        UPDATE users u
+        SET xp = GREATEST(COALESCE(old.xp, 0) - $2, 0),
+            updated_at = NOW()
+        FROM (SELECT id, xp FROM users WHERE id = $1 FOR UPDATE) old
+        WHERE u.id = old.id
+        RETURNING COALESCE(old.xp, 0), u.xp
+        "#,
+    )
+    .bind(user_id)
+    .bind(amount)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    let xp_loss = previous_xp - new_xp;
+
+    log_xp_change(conn, user_id, reason, xp_loss, previous_xp, new_xp).await;
+
+    tracing::warn!(
+        "🔥 XP QUEMADO: {} perdió {} XP por {} | {}/{} XP",
+        user_id, xp_loss, reason, new_xp, previous_xp
+    );
+
+    Ok(xp_loss)
+}
+
+/// Devuelve XP quemado previamente (ej. strike anulado en apelación).
+/// No suma a `total_xp_earned` porque no es XP nuevo.
+pub async fn restore_burned_xp(
+    user_id: Uuid,
+    amount: i64,
+    reason: &str,
+    conn: &mut PgConnection,
+) -> Result<i64, String> {
+    if amount <= 0 {
+        return Ok(0);
+    }
+
+    let new_xp = sqlx::query_scalar::<_, i64>(
+        "UPDATE users SET xp = COALESCE(xp, 0) + $2, updated_at = NOW() WHERE id = $1 RETURNING xp",
+    )
+    .bind(user_id)
+    .bind(amount)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    let previous_xp = new_xp - amount;
+
+    // Registrar la reversión en el mismo log de quemas (pérdida negativa)
+    log_xp_change(conn, user_id, reason, -amount, previous_xp, new_xp).await;
+
+    tracing::info!(
+        "♻️ XP RESTAURADO: {} recuperó {} XP por {} | {}/{} XP",
+        user_id, amount, reason, new_xp, previous_xp
+    );
+
+    Ok(new_xp)
+}
+
+/// Auditoría de mejor esfuerzo en `xp_burn_log`. Va en un savepoint: si el insert falla
+/// no debe abortar la transacción del llamador.
+async fn log_xp_change(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    reason: &str,
+    xp_loss: i64,
+    previous_xp: i64,
+    new_xp: i64,
+) {
+    let result = async {
+        let mut savepoint = conn.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO xp_burn_log (user_id, reason, xp_loss, previous_xp, new_xp, timestamp)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(reason)
+        .bind(xp_loss)
+        .bind(previous_xp)
+        .bind(new_xp)
+        .execute(&mut *savepoint)
+        .await?;
+        savepoint.commit().await
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("No se pudo registrar el cambio de XP de {} ({}): {}", user_id, reason, e);
+    }
+}
+
 /// Añade XP al usuario (recompensa)
 pub async fn add_xp_reward(
     user_id: Uuid,
@@ -22,7 +22,7 @@ pub async fn process_payment_with_gamification(
                 user_id,
                 level_up.new_rank
             );
-            // TODO: Enviar notificación push, actualizar chat, etc.
+            // El hook ya publicó el evento rank_up (bandeja + push)
         }
         Ok(None) => {
             tracing::debug!("[GAMIFICATION] User {} gained XP but no level up", user_id);
@@ -1,14 +1,23 @@
 // Integraciones de gamificación con otros módulos
 use crate::gamification::engine::{GamificationEngine, LevelUpEvent};
+use crate::notifications::{self, DomainEvent};
 use uuid::Uuid;
 
 pub struct GamificationHooks {
     gamification: GamificationEngine,
+    nats: async_nats::Client,
 }
 
 impl GamificationHooks {
-    pub fn new(gamification: GamificationEngine) -> Self {
-        GamificationHooks { gamification }
+    pub fn new(gamification: GamificationEngine, nats: async_nats::Client) -> Self {
+        GamificationHooks { gamification, nats }
+    }
+
+    /// Toda subida de rango se publica como `rank_up` para el consumidor de notificaciones
+    async fn announce(&self, level_up: &Option<LevelUpEvent>) {
+        if let Some(event) = level_up {
+            notifications::events::publish(&self.nats, &DomainEvent::from(event)).await;
+        }
     }
 
     /// Hook: Usuario gana dinero en finance (1 USDT = +10 XP)
@@ -31,6 +40,7 @@ impl GamificationHooks {
                 event.new_rank
             );
         }
+        self.announce(&level_up).await;
 
         Ok(level_up)
     }
@@ -52,6 +62,7 @@ impl GamificationHooks {
                 .award_achievement(user_id, "photographer")
                 .await;
         }
+        self.announce(&level_up).await;
 
         Ok(level_up)
     }
@@ -61,10 +72,9 @@ impl GamificationHooks {
         &self,
         user_id: Uuid,
     ) -> Result<Option<LevelUpEvent>, Box<dyn std::error::Error>> {
-        self.gamification
-            .add_xp(user_id, 20, "profile_completion")
-            .await
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+        let level_up = self.gamification.add_xp(user_id, 20, "profile_completion").await?;
+        self.announce(&level_up).await;
+        Ok(level_up)
     }
 
     /// Hook: Usuario hace referral (+50 XP)
@@ -72,9 +82,8 @@ impl GamificationHooks {
         &self,
         user_id: Uuid,
     ) -> Result<Option<LevelUpEvent>, Box<dyn std::error::Error>> {
-        self.gamification
-            .add_xp(user_id, 50, "referral_success")
-            .await
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+        let level_up = self.gamification.add_xp(user_id, 50, "referral_success").await?;
+        self.announce(&level_up).await;
+        Ok(level_up)
     }
 }
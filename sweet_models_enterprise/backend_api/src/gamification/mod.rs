@@ -9,5 +9,7 @@ pub mod store;
 
 pub use engine::GamificationEngine;
 pub use hooks::GamificationHooks;
-pub use engine::{burn_xp, add_xp_reward};
-pub use store::{get_catalog_handler, get_user_balance_handler, redeem_reward_handler, get_reward_catalog};
+pub use engine::{burn_xp, burn_xp_amount, add_xp_reward, restore_burned_xp};
+pub use store::{
+    approve_redemption_handler, get_catalog_handler, get_user_balance_handler, redeem_reward_handler, get_reward_catalog,
+};
@@ -22,7 +22,7 @@ pub async fn process_photo_upload_with_gamification(
                 user_id,
                 level_up.new_rank
             );
-            // TODO: Enviar notificación, achievement badge, etc.
+            // El hook ya publicó el evento rank_up (bandeja + push)
         }
         Ok(None) => {
             tracing::debug!("[GAMIFICATION] User {} uploaded photo, +5 XP", user_id);
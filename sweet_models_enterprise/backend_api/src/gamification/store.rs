@@ -1,8 +1,14 @@
 /// Tienda de Premios: Sistema de canje de XP por recompensas
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
 
+use crate::middleware::auth::AdminOnly;
+use crate::notifications::{self, DomainEvent};
 use crate::state::AppState;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -256,3 +262,39 @@ pub async fn redeem_reward_handler(
         status: "PENDING_APPROVAL".to_string(),
     }))
 }
+
+/// POST /api/admin/gamification/redemptions/:id/approve
+/// Aprueba un canje pendiente y avisa a la modelo
+pub async fn approve_redemption_handler(
+    State(state): State<std::sync::Arc<AppState>>,
+    _admin: AdminOnly,
+    Path(ticket_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let approved = sqlx::query_as::<_, (Uuid, String)>(
+        r#"
+        UPDATE reward_redemptions
+        SET status = 'APPROVED'
+        WHERE id = $1 AND status = 'PENDING_APPROVAL'
+        RETURNING user_id, reward_name
+        "#,
+    )
+    .bind(ticket_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let Some((user_id, reward_name)) = approved else {
+        return Err((StatusCode::NOT_FOUND, "No pending redemption with that ticket".to_string()));
+    };
+
+    tracing::info!("🎁 Canje {} aprobado para {}", ticket_id, user_id);
+    let event = DomainEvent::RewardApproved { user_id, ticket_id, reward_name: reward_name.clone() };
+    notifications::events::publish(&state.nats, &event).await;
+
+    Ok(Json(serde_json::json!({
+        "ticket_id": ticket_id,
+        "user_id": user_id,
+        "reward_name": reward_name,
+        "status": "APPROVED",
+    })))
+}
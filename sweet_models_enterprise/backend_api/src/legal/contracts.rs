@@ -8,6 +8,10 @@ use std::io::BufWriter;
 use image::{GenericImageView};
 
 use crate::state::AppState;
+use crate::storage::{
+    private::{discard_private, store_private, NewPrivateObject},
+    BucketType,
+};
 
 /// Tipos de contrato soportados
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -53,8 +57,11 @@ pub struct LegalDocument {
     pub user_id: Uuid,
     pub document_type: String,
     pub signed_at: DateTime<Utc>,
-    pub signature_url: String,
-    pub pdf_url: String,
+    /// Solo en contratos antiguos; los nuevos se leen por URL firmada
+    pub signature_url: Option<String>,
+    pub pdf_url: Option<String>,
+    pub signature_object_id: Option<Uuid>,
+    pub pdf_object_id: Option<Uuid>,
     pub ip_address: Option<String>,
     pub created_at: DateTime<Utc>,
 }
@@ -90,43 +97,88 @@ const REGLAS_DE_HIERRO: &str = "1. Puntualidad: La modelo debe iniciar su turno
 6. Conducta: Respeto absoluto a políticas de seguridad y buenas prácticas.\n\
 7. Penalidades: Conductas graves podrán derivar en terminación inmediata del contrato.";
 
-/// Genera el PDF, guarda firma + PDF en MinIO y persiste el registro en la BD
+/// Genera el PDF, guarda firma + PDF en el bucket legal (privado) y persiste el registro en la BD
 pub async fn generate_contract_pdf(
     state: &AppState,
     payload: ContractGenerationPayload,
 ) -> Result<LegalDocument, ContractError> {
-    // 1) Subir la firma a MinIO (para trazabilidad)
-    let signature_url = state
-        .storage
-        .upload_file(payload.signature_image.clone(), "png")
-        .await?;
+    // 1) Subir la firma al bucket legal (para trazabilidad)
+    let signature = store_private(
+        &state.storage,
+        &state.db,
+        NewPrivateObject {
+            bucket: BucketType::Legal,
+            owner_id: Some(payload.user_id),
+            uploaded_by: Some(payload.user_id),
+            purpose: "signature",
+            data: &payload.signature_image,
+            content_type: "image/png",
+            extension: "png",
+            original_name: None,
+        },
+    )
+    .await?;
 
     // 2) Construir el PDF en memoria
-    let pdf_bytes = build_pdf_bytes(&payload).map_err(ContractError::Pdf)?;
+    let pdf_bytes = match build_pdf_bytes(&payload) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            discard_private(&state.storage, &state.db, &signature).await;
+            return Err(ContractError::Pdf(e));
+        }
+    };
 
-    // 3) Subir el PDF final a MinIO
-    let pdf_url = state
-        .storage
-        .upload_file(Bytes::from(pdf_bytes.clone()), "pdf")
-        .await?;
+    // 3) Subir el PDF final al bucket legal
+    let pdf = match store_private(
+        &state.storage,
+        &state.db,
+        NewPrivateObject {
+            bucket: BucketType::Legal,
+            owner_id: Some(payload.user_id),
+            uploaded_by: Some(payload.user_id),
+            purpose: "contract_pdf",
+            data: &pdf_bytes,
+            content_type: "application/pdf",
+            extension: "pdf",
+            original_name: Some(format!("{}.pdf", payload.contract_type.as_str().to_lowercase())),
+        },
+    )
+    .await
+    {
+        Ok(pdf) => pdf,
+        Err(e) => {
+            // Sin PDF la firma suelta no sirve: no dejarla en el bucket legal
+            discard_private(&state.storage, &state.db, &signature).await;
+            return Err(e.into());
+        }
+    };
 
     let now = Utc::now();
 
     // 4) Persistir registro
     let document = sqlx::query_as::<_, LegalDocument>(
         r#"INSERT INTO legal_documents
-        (user_id, document_type, signed_at, signature_url, pdf_url, ip_address)
+        (user_id, document_type, signed_at, signature_object_id, pdf_object_id, ip_address)
         VALUES ($1, $2, $3, $4, $5, $6)
-        RETURNING id, user_id, document_type, signed_at, signature_url, pdf_url, ip_address, created_at"#,
+        RETURNING id, user_id, document_type, signed_at, signature_url, pdf_url,
+                  signature_object_id, pdf_object_id, ip_address, created_at"#,
     )
     .bind(payload.user_id)
     .bind(payload.contract_type.as_str())
     .bind(now)
-    .bind(&signature_url)
-    .bind(&pdf_url)
+    .bind(signature.id)
+    .bind(pdf.id)
     .bind(&payload.ip_address)
     .fetch_one(&state.db)
-    .await?;
+    .await;
+    let document = match document {
+        Ok(document) => document,
+        Err(e) => {
+            discard_private(&state.storage, &state.db, &pdf).await;
+            discard_private(&state.storage, &state.db, &signature).await;
+            return Err(e.into());
+        }
+    };
 
     // 5) Flag para middleware: si firma los términos marcamos el flag
     if matches!(payload.contract_type, ContractType::TermsV1) {
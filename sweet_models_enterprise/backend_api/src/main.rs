@@ -2,7 +2,7 @@ mod ai;
 
 use std::{net::SocketAddr, sync::Arc, time::Duration, io};
 
-use axum::{routing::{get, post}, body::Bytes, Json, Router, middleware};
+use axum::{routing::{get, patch, post, put}, body::Bytes, Json, Router, middleware};
 use deadpool_redis::redis::AsyncCommands;
 use serde_json::json;
 use sqlx::postgres::PgPoolOptions;
@@ -29,6 +29,8 @@ use backend_api::tls::TlsConfiguration;
 use backend_api::realtime::{self, RealtimeHub};
 use backend_api::tracking;
 use backend_api::emergency;
+use backend_api::engine;
+use backend_api::notifications::{self, NotificationService, Notifier};
 
 type DynError = Box<dyn std::error::Error + Send + Sync>;
 #[tokio::main]
@@ -70,33 +72,76 @@ async fn main() -> Result<(), DynError> {
     // Crear el Hub realtime global
     let realtime_hub = Arc::new(RealtimeHub::new(128));
 
-    let state = Arc::new(AppState { db, redis, nats, storage, realtime_hub: realtime_hub.clone() });
+    // Réplica actual del cluster: hub y chat se reparten entre nodos vía NATS
+    let cluster_node = realtime::ClusterNode::new(nats.clone());
+    let hub_bridge = realtime::bridge_hub(realtime_hub.clone(), cluster_node.clone()).await?;
 
-    // Iniciar Beyorder AI Observer - DESACTIVADO para debugging
-    // tokio::spawn(ai::spawn_beyorder_observer(state.clone()));
+    // Transporte push (solo si FCM está configurado); sin él las notificaciones quedan solo in-app
+    let push_service = match NotificationService::transport_from_env()? {
+        Some(transport) => {
+            tracing::info!("📲 Push transport: {}", transport.name());
+            Some(Arc::new(NotificationService::new(db.clone(), transport)))
+        }
+        None => {
+            tracing::warn!("FCM_SERVICE_ACCOUNT_FILE not set, push notifications disabled");
+            None
+        }
+    };
+    let chat_notifier = push_service
+        .clone()
+        .map(|service| Arc::new(social::chat_notifications::ChatNotificationManager::new(service)));
+    let notifier = Arc::new(Notifier::new(db.clone(), realtime_hub.clone(), push_service));
 
     let (tx, _rx) = broadcast::channel(100);
+    let chat_bridge = social::peers::bridge_chat(tx.clone(), cluster_node.clone()).await?;
     let chat_state = Arc::new(social::ChatState {
+        db: db.clone(),
         tx,
-        peers: Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new())),
-        presence: Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new())),
+        peers: Arc::new(social::PeerRegistry::new(Some(cluster_node.clone()))),
+        cluster: Some(cluster_node),
+        notifier: chat_notifier,
+        hub: realtime_hub.clone(),
+    });
+
+    let state = Arc::new(AppState {
+        db,
+        redis,
+        nats,
+        storage,
+        realtime_hub: realtime_hub.clone(),
+        chat: chat_state,
+        notifier: notifier.clone(),
     });
 
+    // Iniciar Beyorder AI Observer - DESACTIVADO para debugging
+    // tokio::spawn(ai::spawn_beyorder_observer(state.clone()));
+
     let (shutdown_tx, _) = tokio::sync::broadcast::channel::<()>(1);
 
-    let http_handle = spawn_http_server(state.clone(), chat_state.clone(), shutdown_tx.subscribe());
+    let http_handle = spawn_http_server(state.clone(), shutdown_tx.subscribe());
     let grpc_handle = spawn_grpc_server(state.clone(), shutdown_tx.subscribe());
     let ledger_handle = spawn_ledger_worker(state.clone(), shutdown_tx.subscribe());
+    let telemetry_handle = tokio::spawn(tracking::timeseries::run_worker(state.db.clone(), shutdown_tx.subscribe()));
+    let call_sweeper = tokio::spawn(social::calls::run_call_sweeper(state.clone(), shutdown_tx.subscribe()));
+    let notification_consumer = {
+        let (nats, shutdown) = (state.nats.clone(), shutdown_tx.subscribe());
+        tokio::spawn(async move {
+            if let Err(e) = notifications::events::run_consumer(notifier, nats, shutdown).await {
+                tracing::error!("Notification consumer no pudo suscribirse: {}", e);
+            }
+        })
+    };
 
     tokio::select! {
         _ = signal::ctrl_c() => {
             tracing::info!("Ctrl+C recibido, cerrando Colmena...");
         }
         res = async {
-            let (r1, r2, r3) = tokio::join!(http_handle, grpc_handle, ledger_handle);
+            let (r1, r2, r3, r4) = tokio::join!(http_handle, grpc_handle, ledger_handle, telemetry_handle);
             r1??;
             r2??;
             r3??;
+            r4?;
             Ok::<(), DynError>(())
         } => {
             if let Err(e) = res {
@@ -106,13 +151,16 @@ async fn main() -> Result<(), DynError> {
     }
 
     let _ = shutdown_tx.send(());
+    hub_bridge.abort();
+    notification_consumer.abort();
+    call_sweeper.abort();
+    chat_bridge.abort();
 
     Ok(())
 }
 
 fn spawn_http_server(
     state: Arc<AppState>,
-    chat_state: Arc<social::ChatState>,
     mut shutdown: tokio::sync::broadcast::Receiver<()>,
 ) -> JoinHandle<Result<(), DynError>> {
     tokio::spawn(async move {
@@ -123,7 +171,21 @@ fn spawn_http_server(
             .route("/api/ledger/seal", post(seal_transaction_handler))
             .route("/api/ledger/verify", get(verify_chain_handler))
             .route("/api/ledger/history/:user_id", get(user_transaction_history_handler))
-            .route("/api/storage/upload", post(storage::upload_handler))
+            .route(
+                "/api/storage/upload",
+                post(storage::upload_handler)
+                    .layer(axum::extract::DefaultBodyLimit::max(storage::media::limits().max_request_bytes())),
+            )
+            .route("/api/storage/media/:id", get(storage::get_media_handler))
+            .route(
+                "/api/storage/private",
+                post(storage::private::upload_private_handler)
+                    .layer(axum::extract::DefaultBodyLimit::max(storage::media::limits().max_request_bytes())),
+            )
+            .route("/api/storage/private/:id", get(storage::private::get_private_handler))
+            .route("/api/storage/private/:id/url", get(storage::private::presign_handler))
+            .route("/api/storage/private/:id/access-log", get(storage::private::access_log_handler))
+            .route("/api/admin/storage/migrate-legacy", post(storage::private::migrate_legacy_handler))
             .route("/api/finance/balance", get(finance::get_balance_handler))
             .route("/api/finance/withdraw", post(finance::request_withdraw_handler))
             .route("/api/finance/withdrawals", get(finance::list_withdrawals_handler))
@@ -131,22 +193,82 @@ fn spawn_http_server(
             .route("/api/admin/finance/rate", get(finance::get_admin_rate_handler))
             .route("/api/admin/finance/payroll/pending", get(finance::pending_payroll_handler))
             .route("/api/admin/finance/payroll/mark-paid", post(finance::mark_paid_handler))
+            .route("/api/finance/payslip", get(finance::my_payslip_handler))
+            .route("/api/admin/finance/payslip/:user_id", get(finance::admin_payslip_handler))
+            .route("/api/admin/finance/statements", get(finance::list_statements_handler).post(finance::import_statement_handler))
+            .route("/api/admin/finance/statements/:id/reconciliation", get(finance::statement_reconciliation_handler))
+            .route("/api/admin/platform-accounts", get(finance::list_platform_accounts_handler).put(finance::upsert_platform_account_handler))
+            .route("/api/admin/finance/simulate", post(finance::simulate_payroll_handler))
             .route("/api/operations/attendance/clock-in", post(operations::attendance::clock_in_handler))
             .route("/api/attendance/status", get(operations::attendance_status::attendance_status_handler))
+            .route("/api/attendance/strikes/:id", get(operations::strikes::strike_history_handler))
+            .route("/api/attendance/strikes/:id/appeal", post(operations::strikes::appeal_strike_handler))
+            .route("/api/admin/strikes/appeals", get(operations::strikes::pending_appeals_handler))
+            .route("/api/admin/strikes/appeals/:appeal_id/resolve", post(operations::strikes::resolve_appeal_handler))
+            .route("/api/admin/strikes/:strike_id/void", post(operations::strikes::void_strike_handler))
+            .route("/api/admin/strikes/policy", get(operations::strikes::get_policy_handler).put(operations::strikes::update_policy_handler))
             .route("/api/operations/room/close-shift", post(operations::room::close_shift_handler))
+            .route("/api/operations/room/closeouts/:id", get(operations::room::get_closeout_handler))
+            .route("/api/operations/room/closeouts/:id/confirm", post(operations::room::confirm_closeout_handler))
+            .route("/api/operations/room/closeouts/:id/dispute", post(operations::room::dispute_closeout_handler))
+            .route("/api/admin/room/closeouts", get(operations::room::closeout_queue_handler))
+            .route("/api/admin/studios", get(operations::studio::list_studios_handler).post(operations::studio::create_studio_handler))
+            .route("/api/studio/rooms", get(operations::studio::list_rooms_handler))
+            .route("/api/studio/rooms/availability", get(operations::roster::room_availability_handler))
+            .route("/api/admin/studio/rooms", post(operations::studio::create_room_handler))
+            .route("/api/admin/studio/rooms/:id", patch(operations::studio::update_room_handler))
+            .route("/api/admin/studio/assets", get(operations::studio::list_assets_handler).post(operations::studio::create_asset_handler))
+            .route("/api/admin/studio/assets/:id", patch(operations::studio::update_asset_handler))
+            .route("/api/admin/studio/assets/:id/assign", post(operations::studio::assign_asset_handler))
+            .route("/api/studio/maintenance", post(operations::studio::open_ticket_handler))
+            .route("/api/admin/studio/maintenance", get(operations::studio::list_tickets_handler))
+            .route("/api/admin/studio/maintenance/:id", patch(operations::studio::update_ticket_handler))
+            .route("/api/admin/roster/assignments", put(operations::roster::assign_shift_handler))
+            .route("/api/leave/requests", get(operations::leave::my_leaves_handler).post(operations::leave::create_leave_handler))
+            .route("/api/leave/requests/:id/cancel", post(operations::leave::cancel_leave_handler))
+            .route("/api/leave/balance", get(operations::leave::leave_balance_handler))
+            .route("/api/admin/leave/requests", get(operations::leave::leave_queue_handler))
+            .route("/api/admin/leave/requests/:id/review", post(operations::leave::review_leave_handler))
+            .route("/api/admin/leave/balances", put(operations::leave::set_balance_handler))
             .route("/api/gamification/catalog", get(gamification::get_catalog_handler))
             .route("/api/gamification/balance", get(gamification::get_user_balance_handler))
             .route("/api/gamification/redeem", post(gamification::redeem_reward_handler))
+            .route("/api/admin/gamification/redemptions/:id/approve", post(gamification::approve_redemption_handler))
+            .route("/api/notifications", get(notifications::inbox::inbox_handler))
+            .route("/api/notifications/unread-count", get(notifications::inbox::unread_count_handler))
+            .route("/api/notifications/read-all", post(notifications::inbox::mark_all_read_handler))
+            .route("/api/notifications/:id/read", post(notifications::inbox::mark_read_handler))
+            .route(
+                "/api/notifications/preferences",
+                get(notifications::preferences::get_preferences_handler)
+                    .put(notifications::preferences::update_preferences_handler),
+            )
+            .route("/api/admin/notifications/send", post(notifications::inbox::send_template_handler))
             .route("/api/admin/pulse", get(backend_api::admin::get_system_pulse_handler))
             .route("/api/admin/emergency/freeze", post(emergency::freeze_handler))
             .route("/api/admin/emergency/status", get(emergency::status_handler))
+            .route("/api/tracking/platforms", get(tracking::list_platforms_handler))
             .route("/api/tracking/telemetry", post(tracking::telemetry_handler))
+            .route("/api/tracking/telemetry/batch", get(tracking::telemetry_ack_handler).post(tracking::telemetry_batch_handler))
             .route("/api/tracking/telemetry/:room_id/:platform", get(tracking::get_telemetry_handler))
+            .route("/api/tracking/telemetry/:room_id/:platform/series", get(tracking::telemetry_series_handler))
+            .route("/api/admin/tracking/devices", get(tracking::list_devices_handler).post(tracking::register_device_handler))
+            .route("/api/admin/tracking/devices/:id/rotate", post(tracking::rotate_key_handler))
+            .route("/api/admin/tracking/devices/:id/revoke", post(tracking::revoke_device_handler))
+            .route("/api/admin/production/drafts", get(tracking::list_drafts_handler))
+            .route("/api/admin/production/drafts/generate", post(tracking::generate_drafts_handler))
+            .route("/api/admin/production/drafts/:id", get(tracking::get_draft_handler))
+            .route("/api/admin/production/drafts/:id/confirm", post(tracking::confirm_draft_handler))
+            .route("/api/admin/production/drafts/:id/reject", post(tracking::reject_draft_handler))
+            .route("/api/admin/production/reports", get(engine::list_reports_handler).post(engine::submit_report_handler))
+            .route("/api/admin/production/reports/from-telemetry", post(engine::telemetry_report_handler))
+            .route("/api/admin/production/reports/:id", get(engine::get_report_handler))
+            .route("/api/admin/production/reports/:id/reprocess", post(engine::reprocess_report_handler))
             .route("/api/ai/chat", post(ai::beyorder_chat_handler))
             .route("/api/ai/chat/history/:user_id", get(ai::beyorder_chat_history_handler))
             .route("/ws/dashboard", get(realtime::ws_dashboard_handler))
             .nest("/api/social", social::social_routes().with_state(state.clone()))
-            .nest("/api/chat", social::social_routes_chat().with_state(chat_state))
+            .nest("/api/chat", social::social_routes_chat().with_state(state.clone()))
             .nest("/api/web3", auth::web3::web3_routes())
             .nest("/api/zk", zk_router)
             .layer(middleware::from_fn_with_state(state.clone(), emergency::enforce_emergency_stop))
@@ -179,7 +301,8 @@ fn spawn_http_server(
         let listener = TcpListener::bind(addr).await?;
         tracing::info!("🌐 HTTP/WebSocket server escuchando en http://0.0.0.0:3000");
 
-        axum::serve(listener, app)
+        // ConnectInfo: la IP real del peer para auditoría (ver storage::private::client_ip)
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
             .with_graceful_shutdown(async move {
                 let _ = shutdown.recv().await;
             })
@@ -153,24 +153,24 @@ where
 }
 
 // ============================================================================
-// AUTHENTICATED USER (cualquier usuario autenticado)
+// MODERATOR GUARD (moderator, admin o super_admin)
 // ============================================================================
 
-/// Extractor que valida que el usuario está autenticado (cualquier rol)
-pub struct AuthenticatedUser {
+/// Extractor que valida que el usuario es MODERATOR, ADMIN o SUPER_ADMIN
+pub struct ModeratorOnly {
     pub user_id: String,
     pub email: String,
     pub role: String,
 }
 
 #[async_trait]
-impl<S> FromRequestParts<S> for AuthenticatedUser
+impl<S> FromRequestParts<S> for ModeratorOnly
 where
-    S: Send + Sync + AsRef<AppState>,
+    S: Send + Sync,
 {
     type Rejection = (StatusCode, String);
 
-    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
         let auth_header = parts
             .headers
             .get("authorization")
@@ -200,41 +200,182 @@ where
         ))?;
 
         let claims = token_data.claims;
+        let role_upper = claims.role.to_uppercase();
 
-        // Bloqueo legal: si el usuario no ha firmado términos, solo permitimos /api/legal/sign
-        let path = parts.uri.path();
-        if !path.starts_with("/api/legal/sign") {
-            let user_id = Uuid::parse_str(&claims.sub).map_err(|_| {
-                (
-                    StatusCode::UNAUTHORIZED,
-                    "Invalid user id in token".to_string(),
-                )
-            })?;
-
-            let state_ref: &AppState = state.as_ref();
-            let row = sqlx::query("SELECT has_signed_terms FROM users WHERE id = $1")
-                .bind(user_id)
-                .fetch_optional(&state_ref.db)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-            match row {
-                Some(record) => {
-                    let has_signed: bool = record.try_get("has_signed_terms").unwrap_or(false);
-                    if !has_signed {
-                        return Err((StatusCode::FORBIDDEN, "MUST_SIGN_CONTRACT".to_string()));
-                    }
-                }
-                None => {
-                    return Err((StatusCode::UNAUTHORIZED, "User not found".to_string()));
-                }
-            }
+        if !matches!(role_upper.as_str(), "MODERATOR" | "ADMIN" | "SUPER_ADMIN") {
+            return Err((
+                StatusCode::FORBIDDEN,
+                format!(
+                    "Access denied: MODERATOR role required (found: {})",
+                    claims.role
+                ),
+            ));
         }
 
-        Ok(AuthenticatedUser {
+        Ok(ModeratorOnly {
             user_id: claims.sub,
             email: claims.email,
             role: claims.role,
         })
     }
 }
+
+// ============================================================================
+// AUTHENTICATED USER (cualquier usuario autenticado)
+// ============================================================================
+
+/// Extractor que valida que el usuario está autenticado (cualquier rol)
+pub struct AuthenticatedUser {
+    pub user_id: String,
+    pub email: String,
+    pub role: String,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync + AsRef<AppState>,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let auth_header = parts
+            .headers
+            .get("authorization")
+            .and_then(|h| h.to_str().ok())
+            .ok_or_else(|| (
+                StatusCode::UNAUTHORIZED,
+                "Missing Authorization header".to_string(),
+            ))?;
+
+        let token = auth_header
+            .strip_prefix("Bearer ")
+            .ok_or_else(|| (
+                StatusCode::UNAUTHORIZED,
+                "Invalid Authorization format".to_string(),
+            ))?;
+
+        authenticate_bearer(token, parts.uri.path(), state.as_ref()).await
+    }
+}
+
+/// Valida el JWT y el bloqueo legal; compartido por el header `Authorization`
+/// y por los WebSockets que mandan el token en `Sec-WebSocket-Protocol`
+pub async fn authenticate_bearer(
+    token: &str,
+    path: &str,
+    state: &AppState,
+) -> Result<AuthenticatedUser, (StatusCode, String)> {
+    let jwt_secret = std::env::var("JWT_SECRET")
+        .expect("JWT_SECRET environment variable must be set");
+    let token_data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|e| (
+        StatusCode::UNAUTHORIZED,
+        format!("Invalid token: {}", e),
+    ))?;
+
+    let claims = token_data.claims;
+
+    // Bloqueo legal: si el usuario no ha firmado términos, solo permitimos /api/legal/sign
+    if !path.starts_with("/api/legal/sign") {
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| {
+            (
+                StatusCode::UNAUTHORIZED,
+                "Invalid user id in token".to_string(),
+            )
+        })?;
+
+        let row = sqlx::query("SELECT has_signed_terms FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        match row {
+            Some(record) => {
+                let has_signed: bool = record.try_get("has_signed_terms").unwrap_or(false);
+                if !has_signed {
+                    return Err((StatusCode::FORBIDDEN, "MUST_SIGN_CONTRACT".to_string()));
+                }
+            }
+            None => {
+                return Err((StatusCode::UNAUTHORIZED, "User not found".to_string()));
+            }
+        }
+    }
+
+    Ok(AuthenticatedUser {
+        user_id: claims.sub,
+        email: claims.email,
+        role: claims.role,
+    })
+}
+
+// ============================================================================
+// WEBSOCKET USER (header o Sec-WebSocket-Protocol)
+// ============================================================================
+
+/// Subprotocolo que anuncia el token: `Sec-WebSocket-Protocol: bearer, <jwt>`.
+/// El servidor debe responder eligiendo este mismo subprotocolo.
+pub const WS_BEARER_PROTOCOL: &str = "bearer";
+
+/// Extractor para WebSockets: los navegadores no pueden mandar `Authorization`,
+/// así que también se acepta el JWT como segundo subprotocolo después de `bearer`
+pub struct WebSocketUser {
+    pub user: AuthenticatedUser,
+    /// El token llegó por subprotocolo (hay que aceptarlo en el upgrade)
+    pub via_protocol: bool,
+}
+
+/// Token que sigue a `bearer` en la lista de subprotocolos
+pub fn token_from_protocols(header: &str) -> Option<&str> {
+    let mut protocols = header.split(',').map(str::trim);
+    protocols.find(|p| p.eq_ignore_ascii_case(WS_BEARER_PROTOCOL))?;
+    protocols.next().filter(|token| !token.is_empty())
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for WebSocketUser
+where
+    S: Send + Sync + AsRef<AppState>,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if parts.headers.contains_key("authorization") {
+            let user = AuthenticatedUser::from_request_parts(parts, state).await?;
+            return Ok(WebSocketUser { user, via_protocol: false });
+        }
+
+        let token = parts
+            .headers
+            .get("sec-websocket-protocol")
+            .and_then(|h| h.to_str().ok())
+            .and_then(token_from_protocols)
+            .ok_or_else(|| (
+                StatusCode::UNAUTHORIZED,
+                "Missing bearer token".to_string(),
+            ))?
+            .to_string();
+
+        let user = authenticate_bearer(&token, parts.uri.path(), state.as_ref()).await?;
+        Ok(WebSocketUser { user, via_protocol: true })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_follows_the_bearer_protocol() {
+        assert_eq!(token_from_protocols("bearer, abc.def.ghi"), Some("abc.def.ghi"));
+        assert_eq!(token_from_protocols("chat.v1, Bearer,tok"), Some("tok"));
+        assert_eq!(token_from_protocols("bearer"), None);
+        assert_eq!(token_from_protocols("chat.v1, tok"), None);
+    }
+}
@@ -2,4 +2,4 @@ pub mod rate_limit;
 pub mod auth;
 
 pub use rate_limit::{rate_limit_middleware, RateLimitExceeded};
-pub use auth::{SuperAdminOnly, AdminOnly, AuthenticatedUser, Claims};
+pub use auth::{SuperAdminOnly, AdminOnly, ModeratorOnly, AuthenticatedUser, Claims};
@@ -9,7 +9,6 @@ use std::sync::Arc;
 use backend_api::notifications::NotificationService;
 use backend_api::notifications::handlers::{
     register_device_handler,
-    send_notification_handler,
     get_notification_history_handler,
     cleanup_tokens_handler,
 };
@@ -21,15 +20,9 @@ async fn main() -> Result<(), DynError> {
     // ... código existente ...
 
     // 1. Crear servicio de notificaciones
-    let notification_service = Arc::new(
-        NotificationService::new(
-            db.clone(),
-            std::env::var("FCM_PROJECT_ID")
-                .expect("FCM_PROJECT_ID environment variable must be set"),
-            std::env::var("FCM_API_KEY")
-                .expect("FCM_API_KEY environment variable must be set"),
-        )
-    );
+    let transport = NotificationService::transport_from_env()?
+        .expect("FCM_SERVICE_ACCOUNT_FILE (o FCM_TRANSPORT=mock) must be set");
+    let notification_service = Arc::new(NotificationService::new(db.clone(), transport));
 
     // 2. Crear rutas de notificaciones
     let notifications_routes = Router::new()
@@ -38,11 +31,6 @@ async fn main() -> Result<(), DynError> {
             post(register_device_handler)
                 .with_state(notification_service.clone()),
         )
-        .route(
-            "/api/notifications/send",
-            post(send_notification_handler)
-                .with_state(notification_service.clone()),
-        )
         .route(
             "/api/notifications/:user_id/history/:limit",
             get(get_notification_history_handler)
@@ -228,11 +216,16 @@ void main() async {
 
 /*
 # Firebase Cloud Messaging Configuration
-FCM_PROJECT_ID=my-firebase-project
-FCM_API_KEY=AIzaSyD...rest-of-api-key
+# Cuenta de servicio (HTTP v1 + OAuth2); también vale GOOGLE_APPLICATION_CREDENTIALS
+# o el JSON completo en FCM_SERVICE_ACCOUNT_JSON
+FCM_SERVICE_ACCOUNT_FILE=/path/to/service-account-key.json
+
+# Envíos simultáneos y reintentos (QUOTA_EXCEEDED, UNAVAILABLE, INTERNAL)
+FCM_SEND_CONCURRENCY=16
+FCM_MAX_ATTEMPTS=3
 
-# O usar Service Account JSON (alternativa más segura):
-GOOGLE_APPLICATION_CREDENTIALS=/path/to/service-account-key.json
+# Desarrollo local: no llama a Google, guarda los push en memoria
+# FCM_TRANSPORT=mock
 */
 
 // ============================================================================
@@ -0,0 +1,246 @@
+//! Punto único de envío: plantilla → preferencias → bandeja in-app → push.
+//! Todo lo que quiera avisar a un usuario (eventos de NATS, admin) pasa por `Notifier::notify`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::preferences::{self, PushDecision};
+use super::templates::{self, Rendered, Template, TemplateError, Vars};
+use super::NotificationService;
+use crate::realtime::{RealtimeEvent, RealtimeHub, Topic};
+
+/// Una notificación a entregar
+#[derive(Debug, Clone)]
+pub struct NotificationRequest {
+    pub user_id: Uuid,
+    pub template: String,
+    pub vars: Vars,
+    /// Clave de idempotencia: el mismo evento entregado dos veces solo crea una notificación
+    pub event_id: Option<String>,
+}
+
+/// Resultado del push, también guardado en `notifications.push_status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PushStatus {
+    Sent,
+    Failed,
+    Disabled,
+    QuietHours,
+    NoDevice,
+    /// FCM sin configurar en este despliegue
+    NoTransport,
+}
+
+impl PushStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PushStatus::Sent => "SENT",
+            PushStatus::Failed => "FAILED",
+            PushStatus::Disabled => "DISABLED",
+            PushStatus::QuietHours => "QUIET_HOURS",
+            PushStatus::NoDevice => "NO_DEVICE",
+            PushStatus::NoTransport => "NO_TRANSPORT",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    pub user_id: Uuid,
+    pub notification_id: Option<Uuid>,
+    pub in_app: bool,
+    pub push: PushStatus,
+    /// El evento ya se había notificado; no se hizo nada
+    pub duplicate: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum NotifyError {
+    #[error(transparent)]
+    Template(#[from] TemplateError),
+    #[error("db error: {0}")]
+    Db(#[from] sqlx::Error),
+}
+
+pub struct Notifier {
+    db: PgPool,
+    hub: Arc<RealtimeHub>,
+    push: Option<Arc<NotificationService>>,
+}
+
+impl Notifier {
+    pub fn new(db: PgPool, hub: Arc<RealtimeHub>, push: Option<Arc<NotificationService>>) -> Self {
+        Self { db, hub, push }
+    }
+
+    pub async fn notify(&self, request: NotificationRequest) -> Result<Delivery, NotifyError> {
+        let template =
+            templates::template(&request.template).ok_or_else(|| TemplateError::Unknown(request.template.clone()))?;
+        let prefs = preferences::load(&self.db, request.user_id).await?;
+        let rendered = template.render(prefs.locale(), &request.vars)?;
+        let channels = prefs.channels(template.category, template.priority, Utc::now());
+
+        let mut delivery = Delivery {
+            user_id: request.user_id,
+            notification_id: None,
+            in_app: channels.in_app,
+            push: PushStatus::Disabled,
+            duplicate: false,
+        };
+
+        // La deduplicación no depende de la bandeja: con in-app desactivado también hay que evitar el doble push.
+        // Reclamo y bandeja van en la misma transacción: si algo falla, la reentrega de NATS vuelve a intentarlo
+        let mut tx = self.db.begin().await?;
+        if let Some(event_id) = &request.event_id {
+            if !claim_event(request.user_id, event_id, &mut tx).await? {
+                tracing::debug!("Notificación duplicada ignorada: {}", event_id);
+                delivery.duplicate = true;
+                return Ok(delivery);
+            }
+        }
+
+        if channels.in_app {
+            match store(&request, template, &rendered, &mut tx).await? {
+                Some(id) => delivery.notification_id = Some(id),
+                None => {
+                    tracing::debug!("Notificación duplicada ignorada: {:?}", request.event_id);
+                    delivery.duplicate = true;
+                    return Ok(delivery);
+                }
+            }
+        }
+        tx.commit().await?;
+
+        delivery.push = match channels.push {
+            PushDecision::Disabled => PushStatus::Disabled,
+            PushDecision::QuietHours => PushStatus::QuietHours,
+            PushDecision::Send => self.send_push(&request, template, &rendered, delivery.notification_id).await,
+        };
+
+        if let Some(id) = delivery.notification_id {
+            if let Err(e) = sqlx::query("UPDATE notifications SET push_status = $2 WHERE id = $1")
+                .bind(id)
+                .bind(delivery.push.as_str())
+                .execute(&self.db)
+                .await
+            {
+                tracing::warn!("No se pudo guardar el estado push de {}: {}", id, e);
+            }
+            self.hub.publish_to(
+                &[Topic::User(request.user_id.to_string().to_lowercase())],
+                RealtimeEvent {
+                    event_type: "NOTIFICATION".to_string(),
+                    room_id: String::new(),
+                    data: serde_json::json!({
+                        "id": id,
+                        "template": template.name,
+                        "category": template.category,
+                        "priority": template.priority,
+                        "title": rendered.title,
+                        "body": rendered.body,
+                    }),
+                    timestamp: Utc::now().timestamp(),
+                },
+            );
+        }
+
+        tracing::info!(
+            "🔔 {} → {} (in-app: {}, push: {})",
+            template.name,
+            request.user_id,
+            delivery.in_app,
+            delivery.push.as_str()
+        );
+        Ok(delivery)
+    }
+
+    async fn send_push(
+        &self,
+        request: &NotificationRequest,
+        template: &Template,
+        rendered: &Rendered,
+        notification_id: Option<Uuid>,
+    ) -> PushStatus {
+        let Some(service) = &self.push else {
+            return PushStatus::NoTransport;
+        };
+        match service.get_user_tokens(request.user_id).await {
+            Ok(tokens) if tokens.is_empty() => return PushStatus::NoDevice,
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("No se pudieron leer los dispositivos de {}: {}", request.user_id, e);
+                return PushStatus::Failed;
+            }
+        }
+
+        let mut data = HashMap::from([
+            ("template".to_string(), template.name.to_string()),
+            ("category".to_string(), template.category.as_str().to_string()),
+        ]);
+        if let Some(id) = notification_id {
+            data.insert("notification_id".to_string(), id.to_string());
+        }
+        match service
+            .send_alert(
+                request.user_id,
+                rendered.title.clone(),
+                rendered.body.clone(),
+                Some(data),
+                template.category.as_str(),
+            )
+            .await
+        {
+            Ok(sent) if !sent.is_empty() => PushStatus::Sent,
+            Ok(_) => PushStatus::Failed,
+            Err(e) => {
+                tracing::warn!("Push {} a {} falló: {}", template.name, request.user_id, e);
+                PushStatus::Failed
+            }
+        }
+    }
+}
+
+/// Registra el evento; `false` si ya se había notificado
+async fn claim_event(user_id: Uuid, event_id: &str, conn: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    let claimed =
+        sqlx::query("INSERT INTO notification_events (user_id, event_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(user_id)
+            .bind(event_id)
+            .execute(conn)
+            .await?;
+    Ok(claimed.rows_affected() == 1)
+}
+
+/// Guarda en la bandeja; `None` si el evento ya estaba
+async fn store(
+    request: &NotificationRequest,
+    template: &Template,
+    rendered: &Rendered,
+    conn: &mut PgConnection,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO notifications (user_id, title, body, type, priority, data, template, event_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (user_id, event_id) WHERE event_id IS NOT NULL DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(request.user_id)
+    .bind(&rendered.title)
+    .bind(&rendered.body)
+    .bind(template.category.as_str())
+    .bind(template.priority.as_str())
+    .bind(serde_json::json!({ "vars": request.vars }))
+    .bind(template.name)
+    .bind(&request.event_id)
+    .fetch_optional(conn)
+    .await
+}
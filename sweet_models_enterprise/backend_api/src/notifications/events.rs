@@ -0,0 +1,220 @@
+//! Eventos de dominio publicados en NATS (`notifications.<tipo>`) y el consumidor
+//! que los convierte en notificaciones. El consumidor usa un grupo de cola, así que
+//! con varias réplicas cada evento se procesa una sola vez.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::dispatch::{NotificationRequest, Notifier};
+use super::templates::Vars;
+use crate::gamification::engine::LevelUpEvent;
+
+pub const SUBJECT_PREFIX: &str = "notifications";
+pub const QUEUE_GROUP: &str = "notification-workers";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    PaymentSent {
+        user_id: Uuid,
+        #[serde(default)]
+        reference: Option<String>,
+        #[serde(default)]
+        paid_at: Option<DateTime<Utc>>,
+    },
+    StrikeApplied {
+        user_id: Uuid,
+        strike_id: Uuid,
+        level: i32,
+    },
+    RankUp {
+        user_id: Uuid,
+        old_rank: String,
+        new_rank: String,
+        total_xp: i64,
+    },
+    RewardApproved {
+        user_id: Uuid,
+        ticket_id: Uuid,
+        reward_name: String,
+    },
+}
+
+impl DomainEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DomainEvent::PaymentSent { .. } => "payment_sent",
+            DomainEvent::StrikeApplied { .. } => "strike_applied",
+            DomainEvent::RankUp { .. } => "rank_up",
+            DomainEvent::RewardApproved { .. } => "reward_approved",
+        }
+    }
+
+    pub fn subject(&self) -> String {
+        format!("{}.{}", SUBJECT_PREFIX, self.kind())
+    }
+
+    /// Cada evento se llama igual que su plantilla
+    pub fn into_request(self) -> NotificationRequest {
+        let template = self.kind().to_string();
+        let (user_id, event_id, vars): (Uuid, String, Vec<(&str, String)>) = match self {
+            DomainEvent::PaymentSent { user_id, reference, paid_at } => (
+                user_id,
+                format!("{}:{}", reference.as_deref().unwrap_or("-"), paid_at.map(|t| t.timestamp()).unwrap_or(0)),
+                vec![("reference", reference.unwrap_or_else(|| "-".to_string()))],
+            ),
+            DomainEvent::StrikeApplied { user_id, strike_id, level } => {
+                (user_id, strike_id.to_string(), vec![("level", level.to_string())])
+            }
+            // Con el XP: si la modelo baja de rango y vuelve a subir, es otra notificación
+            DomainEvent::RankUp { user_id, new_rank, total_xp, .. } => {
+                let rank = display_rank(&new_rank);
+                (user_id, format!("{}:{}", new_rank, total_xp), vec![("rank", rank), ("xp", total_xp.to_string())])
+            }
+            DomainEvent::RewardApproved { user_id, ticket_id, reward_name } => {
+                (user_id, ticket_id.to_string(), vec![("reward", reward_name), ("ticket", ticket_id.to_string())])
+            }
+        };
+        NotificationRequest {
+            user_id,
+            event_id: Some(format!("{}:{}", template, event_id)),
+            template,
+            vars: vars.into_iter().map(|(k, v)| (k.to_string(), v)).collect::<Vars>(),
+        }
+    }
+}
+
+impl From<&LevelUpEvent> for DomainEvent {
+    fn from(event: &LevelUpEvent) -> Self {
+        DomainEvent::RankUp {
+            user_id: event.user_id,
+            old_rank: event.old_rank.as_str().to_string(),
+            new_rank: event.new_rank.as_str().to_string(),
+            total_xp: event.total_xp,
+        }
+    }
+}
+
+/// `RISING_STAR` → `Rising Star`
+fn display_rank(rank: &str) -> String {
+    rank.split('_')
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let lower = w.to_lowercase();
+            let mut chars = lower.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Publica el evento; un fallo de NATS se registra pero no rompe la operación que lo origina
+pub async fn publish(nats: &async_nats::Client, event: &DomainEvent) {
+    let payload = match serde_json::to_vec(event) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::warn!("Failed to encode {} event: {}", event.kind(), e);
+            return;
+        }
+    };
+    if let Err(e) = nats.publish(event.subject(), payload.into()).await {
+        tracing::warn!("Failed to publish notification: {}", e);
+    }
+}
+
+/// Consume `notifications.>` hasta el apagado
+pub async fn run_consumer(
+    notifier: Arc<Notifier>,
+    nats: async_nats::Client,
+    mut shutdown: tokio::sync::broadcast::Receiver<()>,
+) -> Result<(), async_nats::SubscribeError> {
+    let mut inbound = nats.queue_subscribe(format!("{}.>", SUBJECT_PREFIX), QUEUE_GROUP.to_string()).await?;
+    tracing::info!("🔔 Notification consumer escuchando {}.>", SUBJECT_PREFIX);
+
+    loop {
+        tokio::select! {
+            message = inbound.next() => {
+                let Some(message) = message else { break };
+                let event = match serde_json::from_slice::<DomainEvent>(&message.payload) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        tracing::warn!("Evento de notificación inválido en {}: {}", message.subject, e);
+                        continue;
+                    }
+                };
+                let kind = event.kind();
+                if let Err(e) = notifier.notify(event.into_request()).await {
+                    tracing::error!("No se pudo notificar {}: {}", kind, e);
+                }
+            }
+            _ = shutdown.recv() => {
+                tracing::info!("Notification consumer apagado");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamification::engine::UserRank;
+    use crate::notifications::templates::{template, Locale};
+
+    #[test]
+    fn payroll_payload_still_parses() {
+        let user_id = Uuid::new_v4();
+        let raw = serde_json::json!({
+            "type": "payment_sent",
+            "user_id": user_id,
+            "message": "¡Tu pago ha sido enviado!",
+            "reference": null,
+        });
+        let event: DomainEvent = serde_json::from_value(raw).unwrap();
+        assert_eq!(event, DomainEvent::PaymentSent { user_id, reference: None, paid_at: None });
+        assert_eq!(event.subject(), "notifications.payment_sent");
+    }
+
+    #[test]
+    fn every_event_renders_its_template() {
+        let user_id = Uuid::new_v4();
+        let events = [
+            DomainEvent::PaymentSent { user_id, reference: Some("TRX-9".to_string()), paid_at: Some(Utc::now()) },
+            DomainEvent::StrikeApplied { user_id, strike_id: Uuid::new_v4(), level: 2 },
+            DomainEvent::RankUp {
+                user_id,
+                old_rank: "NOVICE".to_string(),
+                new_rank: "RISING_STAR".to_string(),
+                total_xp: 1200,
+            },
+            DomainEvent::RewardApproved { user_id, ticket_id: Uuid::new_v4(), reward_name: "Spa".to_string() },
+        ];
+        for event in events {
+            let request = event.into_request();
+            let t = template(&request.template).expect("template exists");
+            for locale in [Locale::Es, Locale::En] {
+                t.render(locale, &request.vars).unwrap_or_else(|e| panic!("{}: {}", request.template, e));
+            }
+            assert!(request.event_id.unwrap().starts_with(&request.template));
+        }
+    }
+
+    #[test]
+    fn rank_up_from_level_up_event_is_humanized() {
+        let level_up = LevelUpEvent {
+            user_id: Uuid::new_v4(),
+            old_rank: UserRank::Novice,
+            new_rank: UserRank::RisingStar,
+            total_xp: 1000,
+            reward: None,
+        };
+        let request = DomainEvent::from(&level_up).into_request();
+        assert_eq!(request.vars["rank"], "Rising Star");
+        assert_eq!(request.event_id.as_deref(), Some("rank_up:RISING_STAR:1000"));
+    }
+}
@@ -0,0 +1,774 @@
+//! Cliente FCM HTTP v1: token OAuth2 de la cuenta de servicio (JWT-bearer, cacheado),
+//! errores estructurados de FCM y envío en lotes con concurrencia limitada.
+//! `PushTransport` permite cambiar FCM por `MockTransport` en tests o en local.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex as StdMutex;
+use std::time::Duration;
+
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use super::{
+    FcmAndroidConfig, FcmAndroidNotification, FcmApnsAlert, FcmApnsConfig, FcmApnsPayload, FcmAps, FcmMessage,
+    FcmNotification, FcmWebpushConfig, FcmWebpushNotification,
+};
+
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const JWT_BEARER_GRANT: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+/// Margen para renovar el access token antes de que caduque
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 60;
+
+// ============================================================================
+// MENSAJES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Android,
+    Ios,
+    Web,
+}
+
+impl Platform {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_uppercase().as_str() {
+            "ANDROID" => Some(Platform::Android),
+            "IOS" => Some(Platform::Ios),
+            "WEB" => Some(Platform::Web),
+            _ => None,
+        }
+    }
+}
+
+/// Un push a un dispositivo concreto
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushMessage {
+    pub token: String,
+    pub platform: Platform,
+    pub title: String,
+    pub body: String,
+    pub data: HashMap<String, String>,
+    /// Tipo de notificación (acción al pulsar en Android, categoría en iOS)
+    pub notification_type: String,
+}
+
+/// Arma el mensaje v1 según la plataforma del dispositivo
+pub fn build_message(message: &PushMessage) -> FcmMessage {
+    let data = (!message.data.is_empty()).then(|| message.data.clone());
+    let notification =
+        FcmNotification { title: message.title.clone(), body: message.body.clone(), image: None };
+    let mut fcm = FcmMessage {
+        token: message.token.clone(),
+        notification,
+        data: data.clone(),
+        android: None,
+        apns: None,
+        webpush: None,
+    };
+
+    match message.platform {
+        Platform::Android => {
+            fcm.android = Some(FcmAndroidConfig {
+                priority: "HIGH".to_string(),
+                notification: Some(FcmAndroidNotification {
+                    title: message.title.clone(),
+                    body: message.body.clone(),
+                    sound: "default".to_string(),
+                    click_action: match message.notification_type.as_str() {
+                        "message" => Some("FLUTTER_NOTIFICATION_CLICK".to_string()),
+                        "payment" => Some("PAYMENT_ACTION".to_string()),
+                        "security" => Some("SECURITY_ACTION".to_string()),
+                        _ => None,
+                    },
+                }),
+            });
+        }
+        Platform::Ios => {
+            fcm.apns = Some(FcmApnsConfig {
+                headers: HashMap::from([("apns-priority".to_string(), "10".to_string())]),
+                payload: Some(FcmApnsPayload {
+                    aps: Some(FcmAps {
+                        alert: Some(FcmApnsAlert { title: message.title.clone(), body: message.body.clone() }),
+                        sound: "default".to_string(),
+                        badge: Some(1),
+                        mutable_content: true,
+                        custom_key: Some(json!({
+                            "type": message.notification_type,
+                            "category": message.notification_type
+                        })),
+                    }),
+                }),
+            });
+        }
+        Platform::Web => {
+            fcm.webpush = Some(FcmWebpushConfig {
+                headers: HashMap::from([("TTL".to_string(), "86400".to_string())]),
+                data,
+                notification: Some(FcmWebpushNotification {
+                    title: message.title.clone(),
+                    body: message.body.clone(),
+                    icon: None,
+                    badge: None,
+                }),
+            });
+        }
+    }
+    fcm
+}
+
+// ============================================================================
+// ERRORES
+// ============================================================================
+
+/// Códigos de error de FCM v1 (`google.firebase.fcm.v1.FcmError.errorCode`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FcmErrorCode {
+    /// El token ya no es válido (app desinstalada, token rotado)
+    Unregistered,
+    /// Token mal formado o mensaje inválido
+    InvalidArgument,
+    SenderIdMismatch,
+    QuotaExceeded,
+    Unavailable,
+    Internal,
+    ThirdPartyAuthError,
+    /// Credenciales de la cuenta de servicio rechazadas
+    Unauthenticated,
+    /// Error de red o respuesta sin formato reconocible
+    Transport,
+    Other(String),
+}
+
+impl FcmErrorCode {
+    pub fn parse(raw: &str) -> Self {
+        match raw {
+            "UNREGISTERED" => FcmErrorCode::Unregistered,
+            "INVALID_ARGUMENT" => FcmErrorCode::InvalidArgument,
+            "SENDER_ID_MISMATCH" => FcmErrorCode::SenderIdMismatch,
+            "QUOTA_EXCEEDED" | "RESOURCE_EXHAUSTED" => FcmErrorCode::QuotaExceeded,
+            "UNAVAILABLE" => FcmErrorCode::Unavailable,
+            "INTERNAL" => FcmErrorCode::Internal,
+            "THIRD_PARTY_AUTH_ERROR" => FcmErrorCode::ThirdPartyAuthError,
+            "UNAUTHENTICATED" => FcmErrorCode::Unauthenticated,
+            other => FcmErrorCode::Other(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            FcmErrorCode::Unregistered => "UNREGISTERED",
+            FcmErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            FcmErrorCode::SenderIdMismatch => "SENDER_ID_MISMATCH",
+            FcmErrorCode::QuotaExceeded => "QUOTA_EXCEEDED",
+            FcmErrorCode::Unavailable => "UNAVAILABLE",
+            FcmErrorCode::Internal => "INTERNAL",
+            FcmErrorCode::ThirdPartyAuthError => "THIRD_PARTY_AUTH_ERROR",
+            FcmErrorCode::Unauthenticated => "UNAUTHENTICATED",
+            FcmErrorCode::Transport => "TRANSPORT",
+            FcmErrorCode::Other(code) => code,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error, PartialEq)]
+#[error("FCM {}: {message}", .code.as_str())]
+pub struct SendError {
+    pub code: FcmErrorCode,
+    pub message: String,
+    /// `Retry-After` de la respuesta, si vino
+    pub retry_after: Option<Duration>,
+    /// Campos que FCM señala como inválidos (`google.rpc.BadRequest.fieldViolations`)
+    pub fields: Vec<String>,
+}
+
+impl SendError {
+    pub fn new(code: FcmErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), retry_after: None, fields: Vec::new() }
+    }
+
+    /// El token no sirve más: hay que desactivarlo.
+    /// INVALID_ARGUMENT también llega por mensajes inválidos (payload grande, clave de data reservada);
+    /// solo cuenta si FCM culpa al campo `token`, si no una plantilla mala desactivaría todos los dispositivos.
+    pub fn deactivates_token(&self) -> bool {
+        match self.code {
+            FcmErrorCode::Unregistered | FcmErrorCode::SenderIdMismatch => true,
+            FcmErrorCode::InvalidArgument => self.fields.iter().any(|f| f == "message.token" || f == "token"),
+            _ => false,
+        }
+    }
+
+    /// Vale la pena reintentar con espera
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.code,
+            FcmErrorCode::QuotaExceeded | FcmErrorCode::Unavailable | FcmErrorCode::Internal | FcmErrorCode::Transport
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorEnvelope {
+    error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    details: Vec<ErrorDetail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorDetail {
+    #[serde(rename = "@type", default)]
+    kind: String,
+    #[serde(rename = "errorCode")]
+    error_code: Option<String>,
+    #[serde(rename = "fieldViolations", default)]
+    field_violations: Vec<FieldViolation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldViolation {
+    #[serde(default)]
+    field: String,
+}
+
+/// Interpreta el cuerpo de error de FCM; prioriza el `errorCode` del detalle FcmError sobre el `status` genérico.
+/// Solo el detalle puede decir UNREGISTERED: un 404 / NOT_FOUND a secas suele ser un `project_id` o una URL
+/// equivocados, y tomarlo como token muerto desactivaría todos los dispositivos.
+pub fn parse_error(http_status: u16, body: &str) -> SendError {
+    let Ok(envelope) = serde_json::from_str::<ErrorEnvelope>(body) else {
+        let code = match http_status {
+            401 => FcmErrorCode::Unauthenticated,
+            429 => FcmErrorCode::QuotaExceeded,
+            503 => FcmErrorCode::Unavailable,
+            500..=599 => FcmErrorCode::Internal,
+            _ => FcmErrorCode::Other(http_status.to_string()),
+        };
+        return SendError::new(code, body.chars().take(300).collect::<String>());
+    };
+    let error = envelope.error;
+    let detail_code = error
+        .details
+        .iter()
+        .filter(|d| d.kind.ends_with("google.firebase.fcm.v1.FcmError"))
+        .find_map(|d| d.error_code.clone());
+    let code = match (detail_code, error.status) {
+        (Some(detail), _) => FcmErrorCode::parse(&detail),
+        (None, Some(status)) => match FcmErrorCode::parse(&status) {
+            FcmErrorCode::Unregistered => FcmErrorCode::Other(status),
+            code => code,
+        },
+        (None, None) => FcmErrorCode::Other(http_status.to_string()),
+    };
+    let fields = error
+        .details
+        .iter()
+        .filter(|d| d.kind.ends_with("google.rpc.BadRequest"))
+        .flat_map(|d| d.field_violations.iter().map(|v| v.field.clone()))
+        .collect();
+    SendError { fields, ..SendError::new(code, error.message) }
+}
+
+// ============================================================================
+// TRANSPORTE
+// ============================================================================
+
+#[async_trait]
+pub trait PushTransport: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Envía un mensaje; devuelve el id que asigna FCM (`projects/.../messages/...`)
+    async fn send(&self, message: &PushMessage) -> Result<String, SendError>;
+}
+
+/// Cuenta de servicio de Firebase (el JSON que descarga la consola)
+#[derive(Clone, Deserialize)]
+pub struct ServiceAccount {
+    pub project_id: String,
+    pub client_email: String,
+    pub private_key: String,
+    #[serde(default = "default_token_uri")]
+    pub token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    DEFAULT_TOKEN_URI.to_string()
+}
+
+impl std::fmt::Debug for ServiceAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceAccount")
+            .field("project_id", &self.project_id)
+            .field("client_email", &self.client_email)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FcmConfigError {
+    #[error("cannot read service account file {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("invalid service account JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid service account private key: {0}")]
+    Key(#[from] jsonwebtoken::errors::Error),
+    #[error("FCM_PROJECT_ID/FCM_API_KEY are no longer supported; set FCM_SERVICE_ACCOUNT_FILE (HTTP v1 service account)")]
+    LegacyConfig,
+}
+
+impl ServiceAccount {
+    /// FCM_SERVICE_ACCOUNT_JSON (contenido) o FCM_SERVICE_ACCOUNT_FILE / GOOGLE_APPLICATION_CREDENTIALS (ruta)
+    pub fn from_env() -> Result<Option<Self>, FcmConfigError> {
+        let raw = match std::env::var("FCM_SERVICE_ACCOUNT_JSON") {
+            Ok(json) if !json.trim().is_empty() => json,
+            _ => {
+                let path = std::env::var("FCM_SERVICE_ACCOUNT_FILE")
+                    .or_else(|_| std::env::var("GOOGLE_APPLICATION_CREDENTIALS"));
+                let Ok(path) = path else {
+                    return Ok(None);
+                };
+                std::fs::read_to_string(&path).map_err(|e| FcmConfigError::Io(path, e))?
+            }
+        };
+        let account: ServiceAccount = serde_json::from_str(&raw)?;
+        // Validar la llave ya, no en el primer push
+        EncodingKey::from_rsa_pem(account.private_key.as_bytes())?;
+        Ok(Some(account))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AssertionClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: i64,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Hay que pedir token nuevo si no hay o si caduca dentro del margen
+fn needs_refresh(cached: Option<&CachedToken>, now: DateTime<Utc>) -> bool {
+    match cached {
+        Some(token) => token.expires_at - chrono::Duration::seconds(TOKEN_REFRESH_MARGIN_SECS) <= now,
+        None => true,
+    }
+}
+
+pub struct FcmTransport {
+    http: Client,
+    account: ServiceAccount,
+    key: EncodingKey,
+    token: Mutex<Option<CachedToken>>,
+}
+
+impl FcmTransport {
+    pub fn new(account: ServiceAccount) -> Result<Self, FcmConfigError> {
+        let key = EncodingKey::from_rsa_pem(account.private_key.as_bytes())?;
+        let http = Client::builder().timeout(Duration::from_secs(10)).build().unwrap_or_default();
+        Ok(Self { http, account, key, token: Mutex::new(None) })
+    }
+
+    /// Access token OAuth2 cacheado; el Mutex evita que varios envíos lo pidan a la vez
+    async fn access_token(&self) -> Result<String, SendError> {
+        let mut cached = self.token.lock().await;
+        let now = Utc::now();
+        if let Some(token) = cached.as_ref().filter(|t| !needs_refresh(Some(t), now)) {
+            return Ok(token.access_token.clone());
+        }
+
+        let claims = AssertionClaims {
+            iss: &self.account.client_email,
+            scope: FCM_SCOPE,
+            aud: &self.account.token_uri,
+            iat: now.timestamp(),
+            exp: now.timestamp() + 3600,
+        };
+        let assertion = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.key)
+            .map_err(|e| SendError::new(FcmErrorCode::Unauthenticated, e.to_string()))?;
+
+        let response = self
+            .http
+            .post(&self.account.token_uri)
+            .form(&[("grant_type", JWT_BEARER_GRANT), ("assertion", assertion.as_str())])
+            .send()
+            .await
+            .map_err(|e| SendError::new(FcmErrorCode::Transport, e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("OAuth token de FCM rechazado ({}): {}", status, body);
+            let code = if status.is_server_error() { FcmErrorCode::Unavailable } else { FcmErrorCode::Unauthenticated };
+            return Err(SendError::new(code, body));
+        }
+        let token: TokenResponse =
+            response.json().await.map_err(|e| SendError::new(FcmErrorCode::Transport, e.to_string()))?;
+
+        let access_token = token.access_token.clone();
+        *cached = Some(CachedToken {
+            access_token: token.access_token,
+            expires_at: now + chrono::Duration::seconds(token.expires_in),
+        });
+        tracing::debug!("Nuevo access token de FCM (expira en {}s)", token.expires_in);
+        Ok(access_token)
+    }
+
+    async fn forget_token(&self) {
+        *self.token.lock().await = None;
+    }
+}
+
+#[async_trait]
+impl PushTransport for FcmTransport {
+    fn name(&self) -> &'static str {
+        "fcm"
+    }
+
+    async fn send(&self, message: &PushMessage) -> Result<String, SendError> {
+        let access_token = self.access_token().await?;
+        let url = format!("https://fcm.googleapis.com/v1/projects/{}/messages:send", self.account.project_id);
+        let response = self
+            .http
+            .post(&url)
+            .bearer_auth(access_token)
+            .json(&json!({ "message": build_message(message) }))
+            .send()
+            .await
+            .map_err(|e| SendError::new(FcmErrorCode::Transport, e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            return Ok(body.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string());
+        }
+
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+        let mut error = parse_error(status.as_u16(), &body);
+        error.retry_after = retry_after;
+        if error.code == FcmErrorCode::Unauthenticated {
+            // Token revocado o rotado: el siguiente intento pide uno nuevo
+            self.forget_token().await;
+        }
+        Err(error)
+    }
+}
+
+/// Transporte en memoria: guarda lo enviado y devuelve los fallos programados por token
+#[derive(Default)]
+pub struct MockTransport {
+    sent: StdMutex<Vec<PushMessage>>,
+    failures: StdMutex<HashMap<String, VecDeque<SendError>>>,
+    in_flight: StdMutex<(usize, usize)>,
+    latency: Option<Duration>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cada envío tarda `latency` (para comprobar la concurrencia)
+    pub fn with_latency(latency: Duration) -> Self {
+        Self { latency: Some(latency), ..Self::default() }
+    }
+
+    /// Los próximos envíos a `token` fallan con estos errores, en orden
+    pub fn fail_next(&self, token: &str, errors: impl IntoIterator<Item = SendError>) {
+        self.failures.lock().unwrap().entry(token.to_string()).or_default().extend(errors);
+    }
+
+    pub fn sent(&self) -> Vec<PushMessage> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Máximo de envíos simultáneos observados
+    pub fn max_in_flight(&self) -> usize {
+        self.in_flight.lock().unwrap().1
+    }
+}
+
+#[async_trait]
+impl PushTransport for MockTransport {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn send(&self, message: &PushMessage) -> Result<String, SendError> {
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            in_flight.0 += 1;
+            in_flight.1 = in_flight.1.max(in_flight.0);
+        }
+        if let Some(latency) = self.latency {
+            tokio::time::sleep(latency).await;
+        }
+        let failure = self.failures.lock().unwrap().get_mut(&message.token).and_then(VecDeque::pop_front);
+        self.in_flight.lock().unwrap().0 -= 1;
+
+        if let Some(error) = failure {
+            return Err(error);
+        }
+        let mut sent = self.sent.lock().unwrap();
+        sent.push(message.clone());
+        tracing::debug!("📭 [mock push] {} → {}: {}", message.notification_type, message.token, message.title);
+        Ok(format!("projects/mock/messages/{}", sent.len()))
+    }
+}
+
+// ============================================================================
+// LOTES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchConfig {
+    /// Envíos simultáneos como máximo
+    pub concurrency: usize,
+    pub max_attempts: u32,
+    /// Espera base del backoff exponencial (si FCM no manda `Retry-After`)
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 16,
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl BatchConfig {
+    /// FCM_SEND_CONCURRENCY y FCM_MAX_ATTEMPTS
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.trim().parse::<u32>().ok());
+        Self {
+            concurrency: var("FCM_SEND_CONCURRENCY").map_or(defaults.concurrency, |v| v.clamp(1, 256) as usize),
+            max_attempts: var("FCM_MAX_ATTEMPTS").map_or(defaults.max_attempts, |v| v.clamp(1, 10)),
+            ..defaults
+        }
+    }
+
+    fn backoff(&self, attempt: u32, error: &SendError) -> Duration {
+        let exponential = self.base_backoff.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        error.retry_after.unwrap_or(exponential).min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendOutcome {
+    pub token: String,
+    pub attempts: u32,
+    pub result: Result<String, SendError>,
+}
+
+/// Envía todos los mensajes con como mucho `concurrency` en vuelo; reintenta solo los errores transitorios.
+/// El resultado conserva el orden de entrada.
+pub async fn send_batch(
+    transport: &dyn PushTransport,
+    messages: &[PushMessage],
+    config: BatchConfig,
+) -> Vec<SendOutcome> {
+    // Mensajes propios en cada future: con `&PushMessage` el future deja de ser `Send` para todo lifetime
+    // y no se puede hacer `tokio::spawn` de quien llame a `send_batch`
+    let mut outcomes: Vec<(usize, SendOutcome)> = stream::iter(messages.iter().cloned().enumerate())
+        .map(move |(index, message)| async move {
+            let mut attempts = 0;
+            loop {
+                attempts += 1;
+                match transport.send(&message).await {
+                    Err(error) if error.is_retryable() && attempts < config.max_attempts => {
+                        let wait = config.backoff(attempts, &error);
+                        tracing::debug!("FCM {} para {}; reintento en {:?}", error.code.as_str(), message.token, wait);
+                        tokio::time::sleep(wait).await;
+                    }
+                    result => return (index, SendOutcome { token: message.token, attempts, result }),
+                }
+            }
+        })
+        .buffer_unordered(config.concurrency.max(1))
+        .collect()
+        .await;
+    outcomes.sort_by_key(|(index, _)| *index);
+    outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(token: &str, platform: Platform) -> PushMessage {
+        PushMessage {
+            token: token.to_string(),
+            platform,
+            title: "Hola".to_string(),
+            body: "Mundo".to_string(),
+            data: HashMap::from([("template".to_string(), "rank_up".to_string())]),
+            notification_type: "payment".to_string(),
+        }
+    }
+
+    fn fast() -> BatchConfig {
+        BatchConfig {
+            base_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            ..BatchConfig::default()
+        }
+    }
+
+    #[test]
+    fn detail_error_code_wins_over_status() {
+        let body = r#"{"error":{"code":404,"message":"Requested entity was not found.","status":"NOT_FOUND",
+            "details":[{"@type":"type.googleapis.com/google.firebase.fcm.v1.FcmError","errorCode":"UNREGISTERED"}]}}"#;
+        let error = parse_error(404, body);
+        assert_eq!(error.code, FcmErrorCode::Unregistered);
+        assert!(error.deactivates_token() && !error.is_retryable());
+
+        let quota = parse_error(429, r#"{"error":{"code":429,"message":"quota","status":"RESOURCE_EXHAUSTED"}}"#);
+        assert_eq!(quota.code, FcmErrorCode::QuotaExceeded);
+        assert!(quota.is_retryable() && !quota.deactivates_token());
+
+        let bad_payload = parse_error(400, r#"{"error":{"message":"too big","status":"INVALID_ARGUMENT"}}"#);
+        assert_eq!(bad_payload.code, FcmErrorCode::InvalidArgument);
+        assert!(!bad_payload.deactivates_token());
+
+        let bad_token = parse_error(
+            400,
+            r#"{"error":{"message":"bad token","status":"INVALID_ARGUMENT","details":[
+                {"@type":"type.googleapis.com/google.rpc.BadRequest",
+                 "fieldViolations":[{"field":"message.token","description":"Invalid registration token"}]}]}}"#,
+        );
+        assert!(bad_token.deactivates_token());
+    }
+
+    #[test]
+    fn unparseable_errors_fall_back_to_http_status() {
+        assert_eq!(parse_error(503, "<html>").code, FcmErrorCode::Unavailable);
+        assert_eq!(parse_error(401, "").code, FcmErrorCode::Unauthenticated);
+        assert_eq!(parse_error(418, "teapot").code, FcmErrorCode::Other("418".to_string()));
+    }
+
+    #[test]
+    fn not_found_without_fcm_detail_keeps_the_token() {
+        // project_id o URL equivocados: 404 sin detalle FcmError
+        let wrong_project = parse_error(
+            404,
+            r#"{"error":{"code":404,"message":"Requested entity was not found.","status":"NOT_FOUND"}}"#,
+        );
+        assert_eq!(wrong_project.code, FcmErrorCode::Other("NOT_FOUND".to_string()));
+        assert!(!wrong_project.deactivates_token());
+
+        let bare = parse_error(404, "<html>Not Found</html>");
+        assert_eq!(bare.code, FcmErrorCode::Other("404".to_string()));
+        assert!(!bare.deactivates_token());
+    }
+
+    #[test]
+    fn token_is_refreshed_before_it_expires() {
+        let now = Utc::now();
+        let token = |secs: i64| CachedToken {
+            access_token: "t".to_string(),
+            expires_at: now + chrono::Duration::seconds(secs),
+        };
+        assert!(needs_refresh(None, now));
+        assert!(needs_refresh(Some(&token(30)), now));
+        assert!(!needs_refresh(Some(&token(3000)), now));
+    }
+
+    #[test]
+    fn messages_only_carry_their_platform_block() {
+        let android = serde_json::to_value(build_message(&message("a", Platform::Android))).unwrap();
+        assert_eq!(android["android"]["notification"]["click_action"], "PAYMENT_ACTION");
+        assert!(android.get("apns").is_none() && android.get("webpush").is_none());
+
+        let ios = serde_json::to_value(build_message(&message("i", Platform::Ios))).unwrap();
+        assert_eq!(ios["apns"]["headers"]["apns-priority"], "10");
+        assert!(ios.get("android").is_none());
+
+        let web = serde_json::to_value(build_message(&message("w", Platform::Web))).unwrap();
+        assert_eq!(web["webpush"]["data"]["template"], "rank_up");
+    }
+
+    #[tokio::test]
+    async fn batch_retries_transient_errors_only() {
+        let mock = MockTransport::new();
+        mock.fail_next("busy", [SendError::new(FcmErrorCode::QuotaExceeded, "slow down")]);
+        mock.fail_next("gone", [SendError::new(FcmErrorCode::Unregistered, "gone")]);
+        let messages =
+            [message("busy", Platform::Android), message("gone", Platform::Ios), message("ok", Platform::Web)];
+
+        let outcomes = send_batch(&mock, &messages, fast()).await;
+        assert_eq!(outcomes.iter().map(|o| o.token.as_str()).collect::<Vec<_>>(), ["busy", "gone", "ok"]);
+        assert_eq!((outcomes[0].attempts, outcomes[0].result.is_ok()), (2, true));
+        assert_eq!(outcomes[1].attempts, 1);
+        assert!(outcomes[1].result.as_ref().unwrap_err().deactivates_token());
+        assert_eq!(mock.sent().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn batch_gives_up_after_max_attempts() {
+        let mock = MockTransport::new();
+        mock.fail_next("down", (0..5).map(|_| SendError::new(FcmErrorCode::Unavailable, "down")));
+        let config = BatchConfig { max_attempts: 3, ..fast() };
+        let outcomes = send_batch(&mock, &[message("down", Platform::Android)], config).await;
+        assert_eq!(outcomes[0].attempts, 3);
+        assert_eq!(outcomes[0].result.as_ref().unwrap_err().code, FcmErrorCode::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn batch_respects_concurrency_limit() {
+        let mock = MockTransport::with_latency(Duration::from_millis(5));
+        let messages: Vec<_> = (0..12).map(|i| message(&format!("t{i}"), Platform::Android)).collect();
+        let outcomes = send_batch(&mock, &messages, BatchConfig { concurrency: 3, ..fast() }).await;
+        assert!(outcomes.iter().all(|o| o.result.is_ok()));
+        assert!(mock.max_in_flight() <= 3 && mock.max_in_flight() > 1);
+    }
+
+    #[test]
+    fn retry_after_caps_the_backoff() {
+        let config = BatchConfig {
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let plain = SendError::new(FcmErrorCode::Unavailable, "");
+        assert_eq!(config.backoff(1, &plain), Duration::from_secs(1));
+        assert_eq!(config.backoff(3, &plain), Duration::from_secs(4));
+        let told = SendError { retry_after: Some(Duration::from_secs(60)), ..plain };
+        assert_eq!(config.backoff(1, &told), Duration::from_secs(10));
+    }
+}
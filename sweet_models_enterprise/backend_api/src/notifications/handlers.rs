@@ -6,7 +6,6 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::notifications::{NotificationService};
-use std::collections::HashMap;
 
 /// Request para registrar un dispositivo
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,16 +15,6 @@ pub struct RegisterDeviceRequest {
     pub device_name: Option<String>,
 }
 
-/// Request para enviar notificación
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SendNotificationRequest {
-    pub user_id: Uuid,
-    pub title: String,
-    pub body: String,
-    pub data: Option<HashMap<String, String>>,
-    pub notification_type: String,
-}
-
 /// Response genérico
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
@@ -86,38 +75,6 @@ pub async fn register_device_handler(
     }
 }
 
-/// Handler para enviar notificación
-pub async fn send_notification_handler(
-    State(notification_service): State<std::sync::Arc<NotificationService>>,
-    Json(payload): Json<SendNotificationRequest>,
-) -> impl IntoResponse {
-    match notification_service
-        .send_alert(
-            payload.user_id,
-            payload.title,
-            payload.body,
-            payload.data,
-            &payload.notification_type,
-        )
-        .await
-    {
-        Ok(sent_tokens) => {
-            let response = ApiResponse::ok(
-                format!("Notificación enviada a {} dispositivos", sent_tokens.len()),
-                serde_json::json!({
-                    "tokens_sent": sent_tokens.len(),
-                    "devices": sent_tokens,
-                }),
-            );
-            (StatusCode::OK, Json(response)).into_response()
-        }
-        Err(e) => {
-            let response: ApiResponse<()> = ApiResponse::error(format!("Error al enviar notificación: {}", e));
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(response)).into_response()
-        }
-    }
-}
-
 /// Handler para obtener historial de notificaciones
 pub async fn get_notification_history_handler(
     State(notification_service): State<std::sync::Arc<NotificationService>>,
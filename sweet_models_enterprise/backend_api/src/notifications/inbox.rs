@@ -0,0 +1,209 @@
+//! Bandeja in-app: listado paginado, no leídas y envío manual por plantilla (admin).
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::dispatch::{Delivery, NotificationRequest, NotifyError};
+use super::templates::{self, Locale, TemplateError, Vars};
+use crate::middleware::auth::{AdminOnly, AuthenticatedUser};
+use crate::operations::parse_user_id;
+use crate::state::AppState;
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+/// Envíos manuales por petición
+const MAX_RECIPIENTS: usize = 500;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct InboxNotification {
+    pub id: Uuid,
+    pub title: String,
+    pub body: String,
+    pub category: String,
+    pub priority: String,
+    pub template: Option<String>,
+    pub data: Option<serde_json::Value>,
+    pub push_status: Option<String>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InboxQuery {
+    #[serde(default)]
+    pub unread_only: bool,
+    /// Id de la última notificación de la página anterior
+    pub before: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InboxPage {
+    pub notifications: Vec<InboxNotification>,
+    pub unread: i64,
+    pub next_cursor: Option<Uuid>,
+}
+
+/// GET /api/notifications
+pub async fn inbox_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Query(query): Query<InboxQuery>,
+) -> Result<Json<InboxPage>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let mut notifications = sqlx::query_as::<_, InboxNotification>(
+        r#"
+        SELECT id, title, body, type AS category, COALESCE(priority, 'normal') AS priority, template, data,
+               push_status, read_at, COALESCE(created_at, NOW()) AS created_at
+        FROM notifications
+        WHERE user_id = $1
+          AND (expires_at IS NULL OR expires_at > NOW())
+          AND (NOT $2 OR read_at IS NULL)
+          AND ($3::UUID IS NULL OR (created_at, id) < (
+              SELECT created_at, id FROM notifications WHERE id = $3 AND user_id = $1
+          ))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $4
+        "#,
+    )
+    .bind(user_id)
+    .bind(query.unread_only)
+    .bind(query.before)
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await
+    .map_err(internal_error)?;
+
+    let next_cursor = if notifications.len() as i64 > limit {
+        notifications.truncate(limit as usize);
+        notifications.last().map(|n| n.id)
+    } else {
+        None
+    };
+    let unread = unread_count(&state, user_id).await?;
+
+    Ok(Json(InboxPage { notifications, unread, next_cursor }))
+}
+
+async fn unread_count(state: &AppState, user_id: Uuid) -> Result<i64, (StatusCode, String)> {
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM notifications
+        WHERE user_id = $1 AND read_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(internal_error)
+}
+
+/// GET /api/notifications/unread-count
+pub async fn unread_count_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let unread = unread_count(&state, user_id).await?;
+    Ok(Json(serde_json::json!({ "unread": unread })))
+}
+
+/// POST /api/notifications/:id/read
+pub async fn mark_read_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(notification_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let found = sqlx::query_scalar::<_, Uuid>(
+        "UPDATE notifications SET read_at = COALESCE(read_at, NOW()) WHERE id = $1 AND user_id = $2 RETURNING id",
+    )
+    .bind(notification_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(internal_error)?;
+    match found {
+        Some(_) => Ok(StatusCode::NO_CONTENT),
+        None => Err((StatusCode::NOT_FOUND, "Notification not found".to_string())),
+    }
+}
+
+/// POST /api/notifications/read-all
+pub async fn mark_all_read_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let result = sqlx::query("UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL")
+        .bind(user_id)
+        .execute(&state.db)
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(serde_json::json!({ "marked": result.rows_affected() })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SendTemplateRequest {
+    pub user_ids: Vec<Uuid>,
+    pub template: String,
+    #[serde(default)]
+    pub vars: Vars,
+}
+
+/// POST /api/admin/notifications/send
+/// Sustituye los envíos con título/cuerpo libres: para texto libre usar la plantilla `announcement`
+pub async fn send_template_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Json(req): Json<SendTemplateRequest>,
+) -> Result<Json<Vec<Delivery>>, (StatusCode, String)> {
+    if req.user_ids.is_empty() || req.user_ids.len() > MAX_RECIPIENTS {
+        return Err((StatusCode::BAD_REQUEST, format!("user_ids must have 1..={} entries", MAX_RECIPIENTS)));
+    }
+    if req.vars.get("title").is_some_and(|t| t.chars().count() > 200) {
+        return Err((StatusCode::BAD_REQUEST, "title is limited to 200 characters".to_string()));
+    }
+
+    // Se valida en ambos idiomas antes de enviar: un error a mitad de la lista dejaría avisos parciales
+    let template =
+        templates::template(&req.template).ok_or_else(|| template_error(TemplateError::Unknown(req.template.clone())))?;
+    for locale in [Locale::Es, Locale::En] {
+        template.render(locale, &req.vars).map_err(template_error)?;
+    }
+
+    let mut deliveries = Vec::with_capacity(req.user_ids.len());
+    for user_id in req.user_ids {
+        let request =
+            NotificationRequest { user_id, template: req.template.clone(), vars: req.vars.clone(), event_id: None };
+        match state.notifier.notify(request).await {
+            Ok(delivery) => deliveries.push(delivery),
+            Err(NotifyError::Template(e)) => return Err(template_error(e)),
+            Err(e) => return Err(internal_error(e)),
+        }
+    }
+    Ok(Json(deliveries))
+}
+
+fn template_error(err: TemplateError) -> (StatusCode, String) {
+    let status = match err {
+        TemplateError::Unknown(_) => StatusCode::NOT_FOUND,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    (status, err.to_string())
+}
+
+fn internal_error<E: std::fmt::Display>(err: E) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
@@ -3,11 +3,21 @@ use sqlx::PgPool;
 use uuid::Uuid;
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
-use reqwest::Client;
-use serde_json::{json, Value};
+use fcm::{BatchConfig, FcmConfigError, FcmTransport, Platform, PushMessage, ServiceAccount};
+use serde_json::Value;
+use std::sync::Arc;
 
+pub mod dispatch;
+pub mod events;
+pub mod fcm;
 pub mod handlers;
+pub mod inbox;
+pub mod preferences;
+pub mod templates;
 
+pub use dispatch::{Delivery, NotificationRequest, Notifier};
+pub use events::DomainEvent;
+pub use fcm::{MockTransport, PushTransport};
 pub use handlers::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -49,9 +59,13 @@ pub struct FcmNotificationPayload {
 pub struct FcmMessage {
     pub token: String,
     pub notification: FcmNotification,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub android: Option<FcmAndroidConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub apns: Option<FcmApnsConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub webpush: Option<FcmWebpushConfig>,
 }
 
@@ -59,12 +73,14 @@ pub struct FcmMessage {
 pub struct FcmNotification {
     pub title: String,
     pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub image: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FcmAndroidConfig {
     pub priority: String,  // HIGH, NORMAL
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub notification: Option<FcmAndroidNotification>,
 }
 
@@ -73,26 +89,32 @@ pub struct FcmAndroidNotification {
     pub title: String,
     pub body: String,
     pub sound: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub click_action: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FcmApnsConfig {
     pub headers: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub payload: Option<FcmApnsPayload>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FcmApnsPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub aps: Option<FcmAps>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FcmAps {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub alert: Option<FcmApnsAlert>,
     pub sound: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub badge: Option<i32>,
     pub mutable_content: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub custom_key: Option<Value>,
 }
 
@@ -105,7 +127,9 @@ pub struct FcmApnsAlert {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FcmWebpushConfig {
     pub headers: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub notification: Option<FcmWebpushNotification>,
 }
 
@@ -113,33 +137,44 @@ pub struct FcmWebpushConfig {
 pub struct FcmWebpushNotification {
     pub title: String,
     pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub badge: Option<String>,
 }
 
 /// Struct Principal del Servicio de Notificaciones
 pub struct NotificationService {
     db: PgPool,
-    http_client: Client,
-    fcm_project_id: String,
-    fcm_api_key: String,
+    transport: Arc<dyn PushTransport>,
+    batch: BatchConfig,
 }
 
 impl NotificationService {
-    /// Crear nueva instancia del servicio
-    pub fn new(
-        db: PgPool,
-        fcm_project_id: String,
-        fcm_api_key: String,
-    ) -> Self {
-        Self {
-            db,
-            http_client: Client::new(),
-            fcm_project_id,
-            fcm_api_key,
+    /// Crear nueva instancia del servicio sobre un transporte (FCM v1 o mock)
+    pub fn new(db: PgPool, transport: Arc<dyn PushTransport>) -> Self {
+        Self { db, transport, batch: BatchConfig::from_env() }
+    }
+
+    /// Transporte desde el entorno: cuenta de servicio → FCM v1; `FCM_TRANSPORT=mock` → en memoria.
+    /// Con solo las variables antiguas (FCM_PROJECT_ID/FCM_API_KEY) falla en vez de arrancar sin push.
+    pub fn transport_from_env() -> Result<Option<Arc<dyn PushTransport>>, FcmConfigError> {
+        if std::env::var("FCM_TRANSPORT").is_ok_and(|t| t.eq_ignore_ascii_case("mock")) {
+            return Ok(Some(Arc::new(MockTransport::new())));
+        }
+        match ServiceAccount::from_env()? {
+            Some(account) => Ok(Some(Arc::new(FcmTransport::new(account)?))),
+            None if std::env::var("FCM_PROJECT_ID").is_ok() || std::env::var("FCM_API_KEY").is_ok() => {
+                Err(FcmConfigError::LegacyConfig)
+            }
+            None => Ok(None),
         }
     }
 
+    pub fn transport_name(&self) -> &'static str {
+        self.transport.name()
+    }
+
     /// Registrar un token FCM para un usuario
     pub async fn register_device(
         &self,
@@ -194,7 +229,8 @@ impl NotificationService {
         Ok(tokens)
     }
 
-    /// Enviar alerta a un usuario
+    /// Enviar alerta a todos los dispositivos activos de un usuario.
+    /// Los tokens que FCM da por muertos se desactivan; devuelve los tokens a los que llegó.
     pub async fn send_alert(
         &self,
         user_id: Uuid,
@@ -210,257 +246,67 @@ impl NotificationService {
             return Err("El usuario no tiene dispositivos registrados".to_string());
         }
 
-        let mut sent_tokens = Vec::new();
-
-        // Enviar a cada dispositivo
+        let mut targets = Vec::with_capacity(devices.len());
+        let mut messages = Vec::with_capacity(devices.len());
         for device in devices {
-            match self
-                .send_to_device(&device, &title, &body, data.clone(), notification_type)
-                .await
-            {
+            let Some(platform) = Platform::parse(&device.platform) else {
+                tracing::warn!("Plataforma no soportada en token {}: {}", device.id, device.platform);
+                continue;
+            };
+            messages.push(PushMessage {
+                token: device.fcm_token.clone(),
+                platform,
+                title: title.clone(),
+                body: body.clone(),
+                data: data.clone().unwrap_or_default(),
+                notification_type: notification_type.to_string(),
+            });
+            targets.push(device);
+        }
+
+        let outcomes = fcm::send_batch(self.transport.as_ref(), &messages, self.batch).await;
+
+        let mut sent_tokens = Vec::new();
+        for (device, outcome) in targets.iter().zip(outcomes) {
+            let (status, error) = match outcome.result {
                 Ok(_) => {
                     sent_tokens.push(device.fcm_token.clone());
 
                     // Actualizar last_used
-                    let _ = sqlx::query(
-                        "UPDATE device_tokens SET last_used = CURRENT_TIMESTAMP WHERE id = $1",
-                    )
-                    .bind(device.id)
-                    .execute(&self.db)
-                    .await;
-
-                    // Log exitoso
-                    let _ = self
-                        .log_notification(
-                            user_id,
-                            Some(device.id),
-                            notification_type.to_string(),
-                            title.clone(),
-                            body.clone(),
-                            data.clone(),
-                            "SENT".to_string(),
-                            None,
-                        )
+                    let _ = sqlx::query("UPDATE device_tokens SET last_used = CURRENT_TIMESTAMP WHERE id = $1")
+                        .bind(device.id)
+                        .execute(&self.db)
                         .await;
+                    ("SENT", None)
                 }
                 Err(e) => {
-                    // Si el error es por token inválido/expirado, desactivar
-                    if e.contains("invalid registration token")
-                        || e.contains("registration token is invalid")
-                    {
+                    if e.deactivates_token() {
+                        tracing::info!("Token {} desactivado: {}", device.id, e.code.as_str());
                         let _ = self.deactivate_token(&device.id).await;
+                    } else {
+                        tracing::warn!("Push a {} falló tras {} intentos: {}", device.id, outcome.attempts, e);
                     }
-
-                    // Log de error
-                    let _ = self
-                        .log_notification(
-                            user_id,
-                            Some(device.id),
-                            notification_type.to_string(),
-                            title.clone(),
-                            body.clone(),
-                            data.clone(),
-                            "FAILED".to_string(),
-                            Some(e),
-                        )
-                        .await;
+                    ("FAILED", Some(e.to_string()))
                 }
-            }
+            };
+
+            let _ = self
+                .log_notification(
+                    user_id,
+                    Some(device.id),
+                    notification_type.to_string(),
+                    title.clone(),
+                    body.clone(),
+                    data.clone(),
+                    status.to_string(),
+                    error,
+                )
+                .await;
         }
 
         Ok(sent_tokens)
     }
 
-    /// Enviar notificación a un dispositivo específico
-    async fn send_to_device(
-        &self,
-        device: &DeviceToken,
-        title: &str,
-        body: &str,
-        data: Option<HashMap<String, String>>,
-        notification_type: &str,
-    ) -> Result<(), String> {
-        // Construir payload según plataforma
-        let message = match device.platform.as_str() {
-            "ANDROID" => self.build_android_message(
-                &device.fcm_token,
-                title,
-                body,
-                data,
-                notification_type,
-            ),
-            "IOS" => self.build_ios_message(
-                &device.fcm_token,
-                title,
-                body,
-                data,
-                notification_type,
-            ),
-            "WEB" => self.build_web_message(
-                &device.fcm_token,
-                title,
-                body,
-                data,
-                notification_type,
-            ),
-            _ => return Err("Plataforma no soportada".to_string()),
-        };
-
-        let payload = FcmNotificationPayload { message };
-
-        // URL de FCM HTTP v1
-        let url = format!(
-            "https://fcm.googleapis.com/v1/projects/{}/messages:send",
-            self.fcm_project_id
-        );
-
-        // Enviar request
-        let response = self
-            .http_client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.fcm_api_key))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| format!("Error al enviar solicitud: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Error desconocido".to_string());
-
-            return Err(format!(
-                "FCM error ({}): {}",
-                status, error_text
-            ));
-        }
-
-        Ok(())
-    }
-
-    /// Construir mensaje para Android
-    fn build_android_message(
-        &self,
-        token: &str,
-        title: &str,
-        body: &str,
-        data: Option<HashMap<String, String>>,
-        notification_type: &str,
-    ) -> FcmMessage {
-        let mut headers = HashMap::new();
-        headers.insert(
-            "x-goog-api-key".to_string(),
-            self.fcm_api_key.clone(),
-        );
-
-        FcmMessage {
-            token: token.to_string(),
-            notification: FcmNotification {
-                title: title.to_string(),
-                body: body.to_string(),
-                image: None,
-            },
-            data,
-            android: Some(FcmAndroidConfig {
-                priority: "HIGH".to_string(),
-                notification: Some(FcmAndroidNotification {
-                    title: title.to_string(),
-                    body: body.to_string(),
-                    sound: "default".to_string(),
-                    click_action: match notification_type {
-                        "message" => Some("FLUTTER_NOTIFICATION_CLICK".to_string()),
-                        "payment" => Some("PAYMENT_ACTION".to_string()),
-                        "security" => Some("SECURITY_ACTION".to_string()),
-                        _ => None,
-                    },
-                }),
-            }),
-            apns: None,
-            webpush: None,
-        }
-    }
-
-    /// Construir mensaje para iOS
-    fn build_ios_message(
-        &self,
-        token: &str,
-        title: &str,
-        body: &str,
-        data: Option<HashMap<String, String>>,
-        notification_type: &str,
-    ) -> FcmMessage {
-        let mut headers = HashMap::new();
-        headers.insert("apns-priority".to_string(), "10".to_string());
-
-        FcmMessage {
-            token: token.to_string(),
-            notification: FcmNotification {
-                title: title.to_string(),
-                body: body.to_string(),
-                image: None,
-            },
-            data,
-            android: None,
-            apns: Some(FcmApnsConfig {
-                headers,
-                payload: Some(FcmApnsPayload {
-                    aps: Some(FcmAps {
-                        alert: Some(FcmApnsAlert {
-                            title: title.to_string(),
-                            body: body.to_string(),
-                        }),
-                        sound: "default".to_string(),
-                        badge: Some(1),
-                        mutable_content: true,
-                        custom_key: Some(json!({
-                            "type": notification_type,
-                            "category": notification_type
-                        })),
-                    }),
-                }),
-            }),
-            webpush: None,
-        }
-    }
-
-    /// Construir mensaje para Web
-    fn build_web_message(
-        &self,
-        token: &str,
-        title: &str,
-        body: &str,
-        data: Option<HashMap<String, String>>,
-        _notification_type: &str,
-    ) -> FcmMessage {
-        let mut headers = HashMap::new();
-        headers.insert("TTL".to_string(), "86400".to_string());
-
-        FcmMessage {
-            token: token.to_string(),
-            notification: FcmNotification {
-                title: title.to_string(),
-                body: body.to_string(),
-                image: None,
-            },
-            data: data.clone(),
-            android: None,
-            apns: None,
-            webpush: Some(FcmWebpushConfig {
-                headers,
-                data,
-                notification: Some(FcmWebpushNotification {
-                    title: title.to_string(),
-                    body: body.to_string(),
-                    icon: None,
-                    badge: None,
-                }),
-            }),
-        }
-    }
-
     /// Desactivar un token (cuando está expirado)
     async fn deactivate_token(&self, token_id: &Uuid) -> Result<(), String> {
         sqlx::query("UPDATE device_tokens SET is_active = false WHERE id = $1")
@@ -0,0 +1,344 @@
+//! Preferencias de notificación por usuario: canales, categorías, idioma y horas de silencio.
+
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use super::templates::{Category, Locale, Priority};
+use crate::middleware::auth::AuthenticatedUser;
+use crate::operations::parse_user_id;
+use crate::state::AppState;
+
+/// Colombia (UTC-5) mientras el usuario no diga otra cosa
+pub const DEFAULT_UTC_OFFSET_MINUTES: i16 = -300;
+
+const PREFERENCE_COLUMNS: &str = r#"
+    COALESCE(push_enabled, TRUE) AS push_enabled,
+    COALESCE(in_app_enabled, TRUE) AS in_app_enabled,
+    COALESCE(payment_notifications, TRUE) AS payment_notifications,
+    strike_notifications,
+    COALESCE(achievement_notifications, TRUE) AS achievement_notifications,
+    reward_notifications,
+    locale,
+    quiet_hours_start,
+    quiet_hours_end,
+    utc_offset_minutes
+"#;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromRow)]
+pub struct NotificationPreferences {
+    pub push_enabled: bool,
+    pub in_app_enabled: bool,
+    pub payment_notifications: bool,
+    pub strike_notifications: bool,
+    pub achievement_notifications: bool,
+    pub reward_notifications: bool,
+    pub locale: String,
+    pub quiet_hours_start: Option<NaiveTime>,
+    pub quiet_hours_end: Option<NaiveTime>,
+    pub utc_offset_minutes: i16,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            push_enabled: true,
+            in_app_enabled: true,
+            payment_notifications: true,
+            strike_notifications: true,
+            achievement_notifications: true,
+            reward_notifications: true,
+            locale: Locale::Es.as_str().to_string(),
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            utc_offset_minutes: DEFAULT_UTC_OFFSET_MINUTES,
+        }
+    }
+}
+
+/// Qué pasa con el push de una notificación concreta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushDecision {
+    Send,
+    /// Push apagado en general o para esta categoría
+    Disabled,
+    /// Dentro de las horas de silencio; queda solo en la bandeja
+    QuietHours,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Channels {
+    pub in_app: bool,
+    pub push: PushDecision,
+}
+
+impl NotificationPreferences {
+    pub fn locale(&self) -> Locale {
+        Locale::parse(&self.locale)
+    }
+
+    pub fn category_enabled(&self, category: Category) -> bool {
+        match category {
+            Category::Payment => self.payment_notifications,
+            Category::Strike => self.strike_notifications,
+            Category::Achievement => self.achievement_notifications,
+            Category::Reward => self.reward_notifications,
+            Category::System => true,
+        }
+    }
+
+    /// Las horas de silencio se evalúan en la hora local del usuario; cruzan medianoche si inicio > fin
+    pub fn in_quiet_hours(&self, now: DateTime<Utc>) -> bool {
+        let (Some(start), Some(end)) = (self.quiet_hours_start, self.quiet_hours_end) else {
+            return false;
+        };
+        let local = (now + Duration::minutes(i64::from(self.utc_offset_minutes))).time();
+        match start.cmp(&end) {
+            std::cmp::Ordering::Equal => false,
+            std::cmp::Ordering::Less => local >= start && local < end,
+            std::cmp::Ordering::Greater => local >= start || local < end,
+        }
+    }
+
+    pub fn channels(&self, category: Category, priority: Priority, now: DateTime<Utc>) -> Channels {
+        let enabled = self.category_enabled(category);
+        let push = if !(enabled && self.push_enabled) {
+            PushDecision::Disabled
+        } else if priority < Priority::Urgent && self.in_quiet_hours(now) {
+            PushDecision::QuietHours
+        } else {
+            PushDecision::Send
+        };
+        Channels { in_app: enabled && self.in_app_enabled, push }
+    }
+}
+
+/// Preferencias guardadas o las de por defecto si el usuario nunca las tocó
+pub async fn load(db: &PgPool, user_id: Uuid) -> Result<NotificationPreferences, sqlx::Error> {
+    let stored = sqlx::query_as::<_, NotificationPreferences>(&format!(
+        "SELECT {} FROM notification_preferences WHERE user_id = $1",
+        PREFERENCE_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(stored.unwrap_or_default())
+}
+
+/// GET /api/notifications/preferences
+pub async fn get_preferences_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<NotificationPreferences>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let prefs = load(&state.db, user_id).await.map_err(internal_error)?;
+    Ok(Json(prefs))
+}
+
+/// Cambios parciales; `clear_quiet_hours` desactiva el silencio
+#[derive(Debug, Deserialize)]
+pub struct UpdatePreferencesRequest {
+    pub push_enabled: Option<bool>,
+    pub in_app_enabled: Option<bool>,
+    pub payment_notifications: Option<bool>,
+    pub strike_notifications: Option<bool>,
+    pub achievement_notifications: Option<bool>,
+    pub reward_notifications: Option<bool>,
+    pub locale: Option<String>,
+    pub quiet_hours_start: Option<NaiveTime>,
+    pub quiet_hours_end: Option<NaiveTime>,
+    #[serde(default)]
+    pub clear_quiet_hours: bool,
+    pub utc_offset_minutes: Option<i16>,
+}
+
+impl UpdatePreferencesRequest {
+    fn apply(self, mut prefs: NotificationPreferences) -> Result<NotificationPreferences, String> {
+        if let Some(offset) = self.utc_offset_minutes {
+            if !(-720..=840).contains(&offset) {
+                return Err("utc_offset_minutes must be between -720 and 840".to_string());
+            }
+            prefs.utc_offset_minutes = offset;
+        }
+        if self.clear_quiet_hours {
+            prefs.quiet_hours_start = None;
+            prefs.quiet_hours_end = None;
+        } else if self.quiet_hours_start.is_some() || self.quiet_hours_end.is_some() {
+            let (Some(start), Some(end)) = (self.quiet_hours_start, self.quiet_hours_end) else {
+                return Err("quiet_hours_start and quiet_hours_end go together".to_string());
+            };
+            prefs.quiet_hours_start = Some(start);
+            prefs.quiet_hours_end = Some(end);
+        }
+        if let Some(locale) = self.locale {
+            prefs.locale = Locale::parse(&locale).as_str().to_string();
+        }
+        let set = |target: &mut bool, value: Option<bool>| {
+            if let Some(value) = value {
+                *target = value;
+            }
+        };
+        set(&mut prefs.push_enabled, self.push_enabled);
+        set(&mut prefs.in_app_enabled, self.in_app_enabled);
+        set(&mut prefs.payment_notifications, self.payment_notifications);
+        set(&mut prefs.strike_notifications, self.strike_notifications);
+        set(&mut prefs.achievement_notifications, self.achievement_notifications);
+        set(&mut prefs.reward_notifications, self.reward_notifications);
+        Ok(prefs)
+    }
+}
+
+/// PUT /api/notifications/preferences
+pub async fn update_preferences_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Json(req): Json<UpdatePreferencesRequest>,
+) -> Result<Json<NotificationPreferences>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let current = load(&state.db, user_id).await.map_err(internal_error)?;
+    let prefs = req.apply(current).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let saved = sqlx::query_as::<_, NotificationPreferences>(&format!(
+        r#"
+        INSERT INTO notification_preferences
+            (user_id, push_enabled, in_app_enabled, payment_notifications, strike_notifications,
+             achievement_notifications, reward_notifications, locale, quiet_hours_start, quiet_hours_end,
+             utc_offset_minutes, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW())
+        ON CONFLICT (user_id) DO UPDATE SET
+            push_enabled = EXCLUDED.push_enabled,
+            in_app_enabled = EXCLUDED.in_app_enabled,
+            payment_notifications = EXCLUDED.payment_notifications,
+            strike_notifications = EXCLUDED.strike_notifications,
+            achievement_notifications = EXCLUDED.achievement_notifications,
+            reward_notifications = EXCLUDED.reward_notifications,
+            locale = EXCLUDED.locale,
+            quiet_hours_start = EXCLUDED.quiet_hours_start,
+            quiet_hours_end = EXCLUDED.quiet_hours_end,
+            utc_offset_minutes = EXCLUDED.utc_offset_minutes,
+            updated_at = NOW()
+        RETURNING {}
+        "#,
+        PREFERENCE_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(prefs.push_enabled)
+    .bind(prefs.in_app_enabled)
+    .bind(prefs.payment_notifications)
+    .bind(prefs.strike_notifications)
+    .bind(prefs.achievement_notifications)
+    .bind(prefs.reward_notifications)
+    .bind(&prefs.locale)
+    .bind(prefs.quiet_hours_start)
+    .bind(prefs.quiet_hours_end)
+    .bind(prefs.utc_offset_minutes)
+    .fetch_one(&state.db)
+    .await
+    .map_err(internal_error)?;
+    Ok(Json(saved))
+}
+
+fn internal_error<E: std::fmt::Display>(err: E) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn quiet(start: (u32, u32), end: (u32, u32)) -> NotificationPreferences {
+        NotificationPreferences {
+            quiet_hours_start: NaiveTime::from_hms_opt(start.0, start.1, 0),
+            quiet_hours_end: NaiveTime::from_hms_opt(end.0, end.1, 0),
+            ..Default::default()
+        }
+    }
+
+    /// Hora UTC que corresponde a `hour` en Colombia (UTC-5)
+    fn bogota(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 10, 0, 0, 0).unwrap() + Duration::hours(i64::from(hour) + 5)
+    }
+
+    #[test]
+    fn overnight_quiet_hours_use_local_time() {
+        let prefs = quiet((22, 0), (7, 0));
+        assert!(prefs.in_quiet_hours(bogota(23)));
+        assert!(prefs.in_quiet_hours(bogota(3)));
+        assert!(!prefs.in_quiet_hours(bogota(7)));
+        assert!(!prefs.in_quiet_hours(bogota(12)));
+    }
+
+    #[test]
+    fn same_day_window_and_empty_window() {
+        let prefs = quiet((13, 0), (15, 0));
+        assert!(prefs.in_quiet_hours(bogota(14)));
+        assert!(!prefs.in_quiet_hours(bogota(15)));
+        assert!(!quiet((9, 0), (9, 0)).in_quiet_hours(bogota(9)));
+        assert!(!NotificationPreferences::default().in_quiet_hours(bogota(3)));
+    }
+
+    #[test]
+    fn quiet_hours_hold_push_but_not_inbox_unless_urgent() {
+        let prefs = quiet((22, 0), (7, 0));
+        let night = bogota(2);
+        assert_eq!(
+            prefs.channels(Category::Payment, Priority::High, night),
+            Channels { in_app: true, push: PushDecision::QuietHours }
+        );
+        assert_eq!(prefs.channels(Category::System, Priority::Urgent, night).push, PushDecision::Send);
+    }
+
+    #[test]
+    fn disabled_category_silences_both_channels_except_system() {
+        let prefs = NotificationPreferences { strike_notifications: false, push_enabled: false, ..Default::default() };
+        assert_eq!(
+            prefs.channels(Category::Strike, Priority::High, bogota(12)),
+            Channels { in_app: false, push: PushDecision::Disabled }
+        );
+        assert_eq!(
+            prefs.channels(Category::System, Priority::Normal, bogota(12)),
+            Channels { in_app: true, push: PushDecision::Disabled }
+        );
+    }
+
+    #[test]
+    fn partial_update_validates_quiet_hours_pairs() {
+        let half = UpdatePreferencesRequest {
+            push_enabled: None,
+            in_app_enabled: None,
+            payment_notifications: None,
+            strike_notifications: None,
+            achievement_notifications: None,
+            reward_notifications: Some(false),
+            locale: Some("en-GB".to_string()),
+            quiet_hours_start: NaiveTime::from_hms_opt(22, 0, 0),
+            quiet_hours_end: None,
+            clear_quiet_hours: false,
+            utc_offset_minutes: None,
+        };
+        assert!(half.apply(NotificationPreferences::default()).is_err());
+
+        let ok = UpdatePreferencesRequest {
+            push_enabled: None,
+            in_app_enabled: None,
+            payment_notifications: None,
+            strike_notifications: None,
+            achievement_notifications: None,
+            reward_notifications: Some(false),
+            locale: Some("en-GB".to_string()),
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            clear_quiet_hours: true,
+            utc_offset_minutes: Some(60),
+        };
+        let prefs = ok.apply(quiet((22, 0), (7, 0))).unwrap();
+        assert_eq!(prefs.locale(), Locale::En);
+        assert!(!prefs.reward_notifications && prefs.payment_notifications);
+        assert_eq!((prefs.quiet_hours_start, prefs.utc_offset_minutes), (None, 60));
+    }
+}
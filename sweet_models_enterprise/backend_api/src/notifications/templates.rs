@@ -0,0 +1,257 @@
+//! Plantillas de notificación con nombre, traducidas a español e inglés.
+//! Los textos usan `{{variable}}`; renderizar con una variable ausente es un error
+//! para no mandar nunca un "{{reference}}" literal a un teléfono.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Variables con las que se rellena una plantilla
+pub type Vars = HashMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    Es,
+    En,
+}
+
+impl Locale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::Es => "es",
+            Locale::En => "en",
+        }
+    }
+
+    /// Acepta también etiquetas completas (`en-US`, `es_CO`); cualquier otra cosa cae a español
+    pub fn parse(raw: &str) -> Self {
+        match raw.trim().to_lowercase().get(..2) {
+            Some("en") => Locale::En,
+            _ => Locale::Es,
+        }
+    }
+}
+
+/// Categoría: decide qué preferencia del usuario aplica
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Payment,
+    Strike,
+    Achievement,
+    Reward,
+    /// Avisos del estudio: no se pueden desactivar
+    System,
+}
+
+impl Category {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Payment => "payment",
+            Category::Strike => "strike",
+            Category::Achievement => "achievement",
+            Category::Reward => "reward",
+            Category::System => "system",
+        }
+    }
+}
+
+/// Prioridad; solo `Urgent` atraviesa las horas de silencio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Normal,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+}
+
+/// Texto en cada idioma soportado
+#[derive(Debug, Clone, Copy)]
+pub struct Localized {
+    pub es: &'static str,
+    pub en: &'static str,
+}
+
+impl Localized {
+    pub fn get(&self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::Es => self.es,
+            Locale::En => self.en,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Template {
+    pub name: &'static str,
+    pub category: Category,
+    pub priority: Priority,
+    pub title: Localized,
+    pub body: Localized,
+}
+
+pub const TEMPLATES: &[Template] = &[
+    Template {
+        name: "payment_sent",
+        category: Category::Payment,
+        priority: Priority::High,
+        title: Localized { es: "💸 ¡Tu pago fue enviado!", en: "💸 Your payment is on its way!" },
+        body: Localized {
+            es: "Ya enviamos tu pago. Referencia: {{reference}}",
+            en: "We just sent your payment. Reference: {{reference}}",
+        },
+    },
+    Template {
+        name: "strike_applied",
+        category: Category::Strike,
+        priority: Priority::High,
+        title: Localized { es: "⚠️ Strike {{level}} registrado", en: "⚠️ Strike {{level}} recorded" },
+        body: Localized {
+            es: "Se registró un strike de nivel {{level}} por tardanza. Puedes apelarlo desde la app.",
+            en: "A level {{level}} strike was recorded for lateness. You can appeal it from the app.",
+        },
+    },
+    Template {
+        name: "rank_up",
+        category: Category::Achievement,
+        priority: Priority::Normal,
+        title: Localized { es: "🏆 ¡Subiste a {{rank}}!", en: "🏆 You reached {{rank}}!" },
+        body: Localized {
+            es: "Llegaste a {{xp}} XP y ya eres {{rank}}. ¡Sigue así!",
+            en: "You hit {{xp}} XP and you are now {{rank}}. Keep it up!",
+        },
+    },
+    Template {
+        name: "reward_approved",
+        category: Category::Reward,
+        priority: Priority::Normal,
+        title: Localized { es: "🎁 Canje aprobado", en: "🎁 Reward approved" },
+        body: Localized {
+            es: "Tu canje de {{reward}} fue aprobado. Ticket: {{ticket}}",
+            en: "Your {{reward}} redemption was approved. Ticket: {{ticket}}",
+        },
+    },
+    Template {
+        name: "announcement",
+        category: Category::System,
+        priority: Priority::Normal,
+        title: Localized { es: "{{title}}", en: "{{title}}" },
+        body: Localized { es: "{{body}}", en: "{{body}}" },
+    },
+];
+
+pub fn template(name: &str) -> Option<&'static Template> {
+    TEMPLATES.iter().find(|t| t.name == name)
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("unknown template: {0}")]
+    Unknown(String),
+    #[error("template {template} is missing variable {variable}")]
+    MissingVariable { template: &'static str, variable: String },
+    #[error("template {0} has an unclosed placeholder")]
+    Unclosed(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rendered {
+    pub title: String,
+    pub body: String,
+}
+
+impl Template {
+    pub fn render(&self, locale: Locale, vars: &Vars) -> Result<Rendered, TemplateError> {
+        Ok(Rendered {
+            title: fill(self.name, self.title.get(locale), vars)?,
+            body: fill(self.name, self.body.get(locale), vars)?,
+        })
+    }
+}
+
+fn fill(name: &'static str, text: &str, vars: &Vars) -> Result<String, TemplateError> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or(TemplateError::Unclosed(name))?;
+        let key = after[..end].trim();
+        let value = vars
+            .get(key)
+            .ok_or_else(|| TemplateError::MissingVariable { template: name, variable: key.to_string() })?;
+        out.push_str(value);
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vars {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn renders_in_each_locale() {
+        let t = template("reward_approved").unwrap();
+        let v = vars(&[("reward", "Spa day"), ("ticket", "T-1")]);
+        assert_eq!(t.render(Locale::Es, &v).unwrap().body, "Tu canje de Spa day fue aprobado. Ticket: T-1");
+        assert_eq!(t.render(Locale::En, &v).unwrap().body, "Your Spa day redemption was approved. Ticket: T-1");
+    }
+
+    #[test]
+    fn missing_variable_is_an_error() {
+        let t = template("strike_applied").unwrap();
+        assert_eq!(
+            t.render(Locale::Es, &Vars::new()),
+            Err(TemplateError::MissingVariable { template: "strike_applied", variable: "level".to_string() })
+        );
+    }
+
+    #[test]
+    fn values_are_not_expanded_again() {
+        let t = template("announcement").unwrap();
+        let rendered = t.render(Locale::En, &vars(&[("title", "{{body}}"), ("body", "hi")])).unwrap();
+        assert_eq!(rendered.title, "{{body}}");
+    }
+
+    #[test]
+    fn locale_falls_back_to_spanish() {
+        assert_eq!(Locale::parse("en-US"), Locale::En);
+        assert_eq!(Locale::parse("es_CO"), Locale::Es);
+        assert_eq!(Locale::parse("pt"), Locale::Es);
+        assert_eq!(Locale::parse(""), Locale::Es);
+    }
+
+    #[test]
+    fn every_template_has_both_locales_with_the_same_variables() {
+        fn placeholders(text: &str) -> Vec<String> {
+            let mut keys: Vec<String> =
+                text.split("{{").skip(1).filter_map(|s| s.split("}}").next()).map(|s| s.trim().to_string()).collect();
+            keys.sort();
+            keys
+        }
+        for t in TEMPLATES {
+            assert!(!t.title.es.is_empty() && !t.title.en.is_empty(), "{}", t.name);
+            assert_eq!(placeholders(t.title.es), placeholders(t.title.en), "{}", t.name);
+            assert_eq!(placeholders(t.body.es), placeholders(t.body.en), "{}", t.name);
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use axum::{extract::State, http::StatusCode, Json};
-use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc, Datelike};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc, Datelike, Timelike};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
-use crate::{state::AppState, finance, gamification};
+use crate::state::AppState;
+use super::{leave, strikes};
 
 // Config del estudio
 const STUDIO_LAT: f64 = 4.7010; // ejemplo Bogotá
@@ -55,6 +56,27 @@ impl Shift {
         (DateTime::<Utc>::from_naive_utc_and_offset(start_local, Utc), DateTime::<Utc>::from_naive_utc_and_offset(end_local, Utc))
     }
 
+    /// Turno en curso en `ts` y su día de inicio (de 00:00 a 02:00 sigue el turno 4 del día anterior)
+    pub fn at(ts: DateTime<Utc>) -> (Self, NaiveDate) {
+        let day = ts.date_naive();
+        match ts.hour() {
+            0..=1 => (Shift::Shift4, day.pred_opt().unwrap_or(day)),
+            2..=7 => (Shift::Shift1, day),
+            8..=13 => (Shift::Shift2, day),
+            14..=19 => (Shift::Shift3, day),
+            _ => (Shift::Shift4, day),
+        }
+    }
+
+    pub fn as_int(&self) -> i32 {
+        match self {
+            Shift::Shift1 => 1,
+            Shift::Shift2 => 2,
+            Shift::Shift3 => 3,
+            Shift::Shift4 => 4,
+        }
+    }
+
     pub fn start_with_grace(&self, day: chrono::NaiveDate) -> DateTime<Utc> {
         let (start, _) = self.window_utc(day);
         start + chrono::Duration::minutes(GRACE_MINUTES)
@@ -172,7 +194,15 @@ pub async fn clock_in_handler(
     })?;
 
     if is_late {
-        apply_strike(&state, req.user_id, now).await?;
+        // Ausencia aprobada ese día: se registra la llegada pero no hay strike
+        let exempt = leave::is_on_leave(req.user_id, leave::studio_date(now), pool)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        if exempt {
+            tracing::info!("Tardanza de {} exenta por ausencia aprobada", req.user_id);
+        } else {
+            strikes::apply_strike(&state, req.user_id, Some(row), now).await?;
+        }
     }
 
     Ok(Json(ClockInResponse {
@@ -181,68 +211,3 @@ pub async fn clock_in_handler(
         message: if is_late { "Llegaste tarde".to_string() } else { "Check-in registrado".to_string() },
     }))
 }
-
-async fn apply_strike(
-    state: &std::sync::Arc<AppState>,
-    user_id: Uuid,
-    check_in_time: DateTime<Utc>,
-) -> Result<(), (StatusCode, String)> {
-    let pool = &state.db;
-    let week_start = check_in_time - Duration::days((check_in_time.weekday().num_days_from_monday()) as i64);
-
-    let late_count: i64 = sqlx::query_scalar(
-        r#"
-        SELECT COUNT(*)
-        FROM attendance_logs
-        WHERE user_id = $1
-          AND is_late = TRUE
-          AND date_trunc('week', check_in) = date_trunc('week', $2)
-        "#,
-    )
-    .bind(user_id)
-    .bind(week_start)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("DB error counting lates: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-    })?;
-
-    if late_count == 1 {
-        // Strike 1: 10% XP burn + mark 50% pay today
-        let mut conn = state.redis.get().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-        let key = format!("penalty:half_today:{}", user_id);
-        let ttl = 24 * 3600;
-        let _: () = deadpool_redis::redis::cmd("SETEX")
-            .arg(&key)
-            .arg(ttl)
-            .arg("1")
-            .query_async(&mut conn)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-        
-        // Burn 10% XP
-        if let Err(e) = gamification::burn_xp(user_id, "STRIKE_1", pool).await {
-            tracing::warn!("XP burn failed for strike 1: {}", e);
-        }
-        tracing::info!("Strike 1 aplicado: {} cobra al 50% hoy", user_id);
-    } else if late_count == 2 {
-        // Strike 2: 30% XP burn + degrade week
-        if let Err(e) = gamification::burn_xp(user_id, "STRIKE_2", pool).await {
-            tracing::warn!("XP burn failed for strike 2: {}", e);
-        }
-        if let Err(e) = finance::penalties::downgrade_user_week(user_id, 0.50, pool).await {
-            tracing::warn!("No se pudo aplicar downgrade semanal: {}", e);
-        }
-    } else if late_count >= 3 {
-        // Strike 3: 100% XP burn + penalty
-        if let Err(e) = gamification::burn_xp(user_id, "STRIKE_3", pool).await {
-            tracing::warn!("XP burn failed for strike 3: {}", e);
-        }
-        if let Err(e) = finance::penalties::create_penalty(user_id, finance::payroll::STRIKE3_PENALTY_COP, pool).await {
-            tracing::warn!("No se pudo crear multa: {}", e);
-        }
-    }
-
-    Ok(())
-}
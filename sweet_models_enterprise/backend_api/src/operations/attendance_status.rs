@@ -1,9 +1,10 @@
 use axum::{extract::{State, Query}, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
-use chrono::{DateTime, Utc, Datelike};
+use chrono::{DateTime, Utc};
 
 use crate::state::AppState;
+use super::strikes;
 
 #[derive(Debug, Deserialize)]
 pub struct StatusQuery {
@@ -20,7 +21,7 @@ pub struct AttendanceStatusResponse {
 }
 
 /// GET /api/attendance/status?user_id=xxx
-/// Retorna el estado disciplinario del usuario (strikes semanales)
+/// Retorna el estado disciplinario del usuario (strikes en la ventana de la política)
 pub async fn attendance_status_handler(
     State(state): State<std::sync::Arc<AppState>>,
     Query(params): Query<StatusQuery>,
@@ -28,26 +29,18 @@ pub async fn attendance_status_handler(
     let pool = &state.db;
     let user_id = params.user_id;
     let now = Utc::now();
-    let week_start = now - chrono::Duration::days((now.weekday().num_days_from_monday()) as i64);
 
-    // Contar tardanzas esta semana
-    let late_count = sqlx::query_scalar::<_, i64>(
-        r#"
-        SELECT COUNT(*)
-        FROM attendance_logs
-        WHERE user_id = $1
-          AND is_late = TRUE
-          AND date_trunc('week', check_in) = date_trunc('week', $2)
-        "#,
-    )
-    .bind(user_id)
-    .bind(week_start)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("DB error counting lates: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-    })?;
+    let policy = strikes::load_active_policy(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    // Strikes vigentes dentro de la ventana móvil de la política
+    let active_strikes = strikes::count_active_strikes(user_id, now, policy.window_days, pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("DB error counting strikes: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
 
     // Obtener última tardanza
     let last_late = sqlx::query_as::<_, (DateTime<Utc>, i32)>(
@@ -85,18 +78,13 @@ pub async fn attendance_status_handler(
     .await
     .unwrap_or(false);
 
-    let note = if late_count >= 3 {
-        Some("Multa aplicada por 3+ tardanzas".to_string())
-    } else if late_count == 2 {
-        Some("Semana degradada al 50%".to_string())
-    } else if late_count == 1 {
-        Some("Hoy cobras al 50%".to_string())
-    } else {
-        None
-    };
+    let note = policy.level_for(active_strikes).and_then(|level| {
+        let parts: Vec<String> = level.consequences.iter().map(|c| c.describe()).collect();
+        if parts.is_empty() { None } else { Some(parts.join(". ")) }
+    });
 
     Ok(Json(AttendanceStatusResponse {
-        strikes: late_count as i32,
+        strikes: active_strikes as i32,
         penalty_active,
         last_late_at,
         last_late_shift,
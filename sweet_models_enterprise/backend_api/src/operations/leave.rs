@@ -0,0 +1,599 @@
+/// Ausencias justificadas (vacaciones, incapacidad médica, permiso personal).
+///
+/// Una ausencia aprobada exime a la modelo de strikes por tardanza y de las
+/// multas de cierre de turno (cuota grupal / room sucio) en esas fechas. Si se
+/// aprueba de forma retroactiva, los strikes emitidos en el rango se anulan.
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Uuid, FromRow, PgExecutor, PgPool};
+
+use crate::{
+    middleware::auth::{AdminOnly, AuthenticatedUser, ModeratorOnly},
+    state::AppState,
+    storage::{private::find_owned_object, BucketType},
+};
+
+use super::{parse_user_id, strikes};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LeaveType {
+    Vacation,
+    Medical,
+    Personal,
+}
+
+impl LeaveType {
+    pub const ALL: [LeaveType; 3] = [LeaveType::Vacation, LeaveType::Medical, LeaveType::Personal];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeaveType::Vacation => "VACATION",
+            LeaveType::Medical => "MEDICAL",
+            LeaveType::Personal => "PERSONAL",
+        }
+    }
+
+    /// Cupo anual por defecto (días calendario). Incapacidad médica con soporte no tiene tope.
+    pub fn default_allowance(&self) -> Option<i32> {
+        match self {
+            LeaveType::Vacation => Some(15),
+            LeaveType::Medical => None,
+            LeaveType::Personal => Some(3),
+        }
+    }
+
+    pub fn requires_document(&self) -> bool {
+        matches!(self, LeaveType::Medical)
+    }
+}
+
+impl std::str::FromStr for LeaveType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "VACATION" => Ok(LeaveType::Vacation),
+            "MEDICAL" => Ok(LeaveType::Medical),
+            "PERSONAL" => Ok(LeaveType::Personal),
+            other => Err(format!("Invalid leave type: {}", other)),
+        }
+    }
+}
+
+/// Días calendario incluidos en el rango (el estudio opera todos los días)
+pub fn leave_days(start: NaiveDate, end: NaiveDate) -> i32 {
+    ((end - start).num_days() + 1).max(0) as i32
+}
+
+/// Verifica que la solicitud quepa en el cupo restante
+pub fn check_balance(allowance: Option<i32>, used: i32, requested: i32) -> Result<(), String> {
+    match allowance {
+        Some(allowance) if used + requested > allowance => Err(format!(
+            "Cupo insuficiente: {} días disponibles, se solicitan {}",
+            (allowance - used).max(0),
+            requested
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LeaveRequest {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub leave_type: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub days: i32,
+    pub reason: Option<String>,
+    /// Solo en solicitudes antiguas; los soportes nuevos se leen por URL firmada
+    pub document_url: Option<String>,
+    pub document_object_id: Option<Uuid>,
+    pub status: String,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+const LEAVE_COLUMNS: &str = "id, user_id, leave_type, start_date, end_date, days, reason, document_url, \
+     document_object_id, status, reviewed_by, reviewed_at, review_note, created_at";
+
+/// Desfase del estudio: Colombia, UTC-5 todo el año
+pub const STUDIO_UTC_OFFSET_HOURS: i64 = -5;
+
+/// Fecha calendario en el estudio para `ts`; las ausencias se piden por fecha local
+pub fn studio_date(ts: DateTime<Utc>) -> NaiveDate {
+    (ts + chrono::Duration::hours(STUDIO_UTC_OFFSET_HOURS)).date_naive()
+}
+
+/// Instantes UTC que cubren los días locales `start..=end` del estudio (fin exclusivo)
+pub fn studio_day_bounds(start: NaiveDate, end: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let local_midnight = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc() - chrono::Duration::hours(STUDIO_UTC_OFFSET_HOURS)
+    };
+    (local_midnight(start), local_midnight(end + chrono::Duration::days(1)))
+}
+
+/// ¿Tiene la modelo una ausencia aprobada que cubra `date` (fecha local del estudio)?
+pub async fn is_on_leave(user_id: Uuid, date: NaiveDate, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM leave_requests
+            WHERE user_id = $1 AND status = 'APPROVED' AND $2 BETWEEN start_date AND end_date
+        )
+        "#,
+    )
+    .bind(user_id)
+    .bind(date)
+    .fetch_one(pool)
+    .await
+}
+
+/// Subconjunto de `members` con ausencia aprobada en `date`
+pub async fn members_on_leave<'e, E: PgExecutor<'e>>(
+    members: &[Uuid],
+    date: NaiveDate,
+    executor: E,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT DISTINCT user_id FROM leave_requests
+        WHERE user_id = ANY($1) AND status = 'APPROVED' AND $2 BETWEEN start_date AND end_date
+        "#,
+    )
+    .bind(members)
+    .bind(date)
+    .fetch_all(executor)
+    .await
+}
+
+async fn allowance_for(user_id: Uuid, year: i32, leave_type: LeaveType, pool: &PgPool) -> Result<Option<i32>, sqlx::Error> {
+    let custom = sqlx::query_scalar::<_, i32>(
+        "SELECT allowance_days FROM leave_balances WHERE user_id = $1 AND year = $2 AND leave_type = $3",
+    )
+    .bind(user_id)
+    .bind(year)
+    .bind(leave_type.as_str())
+    .fetch_optional(pool)
+    .await?;
+    Ok(custom.or(leave_type.default_allowance()))
+}
+
+/// Días aprobados (o también pendientes) del año para un tipo de ausencia
+async fn days_taken(
+    user_id: Uuid,
+    year: i32,
+    leave_type: LeaveType,
+    statuses: &[&str],
+    pool: &PgPool,
+) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COALESCE(SUM(days), 0)::BIGINT
+        FROM leave_requests
+        WHERE user_id = $1 AND leave_type = $3 AND status = ANY($4)
+          AND EXTRACT(YEAR FROM start_date)::INT = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(year)
+    .bind(leave_type.as_str())
+    .bind(statuses)
+    .fetch_one(pool)
+    .await
+    .map(|d| d as i32)
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("DB error in leave management: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+async fn fetch_leave(id: Uuid, pool: &PgPool) -> Result<LeaveRequest, (StatusCode, String)> {
+    sqlx::query_as::<_, LeaveRequest>(&format!("SELECT {LEAVE_COLUMNS} FROM leave_requests WHERE id = $1"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Solicitud de ausencia no encontrada".to_string()))
+}
+
+/// Inserta la solicitud ya validada; el soporte va solo como documento privado
+pub async fn insert_leave<'e, E: PgExecutor<'e>>(
+    user_id: Uuid,
+    req: &CreateLeaveRequest,
+    days: i32,
+    executor: E,
+) -> Result<LeaveRequest, sqlx::Error> {
+    sqlx::query_as::<_, LeaveRequest>(&format!(
+        r#"
+        INSERT INTO leave_requests (user_id, leave_type, start_date, end_date, days, reason, document_object_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {LEAVE_COLUMNS}
+        "#
+    ))
+    .bind(user_id)
+    .bind(req.leave_type.as_str())
+    .bind(req.start_date)
+    .bind(req.end_date)
+    .bind(days)
+    .bind(&req.reason)
+    .bind(req.document_object_id)
+    .fetch_one(executor)
+    .await
+}
+
+// ============================================================================
+// HANDLERS
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct CreateLeaveRequest {
+    pub leave_type: LeaveType,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub reason: Option<String>,
+    /// Obligatorio para MEDICAL: subir antes a /api/storage/private?bucket=evidence&purpose=leave_support
+    pub document_object_id: Option<Uuid>,
+}
+
+/// POST /api/leave/requests
+pub async fn create_leave_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Json(req): Json<CreateLeaveRequest>,
+) -> Result<(StatusCode, Json<LeaveRequest>), (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let pool = &state.db;
+
+    if req.end_date < req.start_date {
+        return Err((StatusCode::BAD_REQUEST, "La fecha final no puede ser anterior a la inicial".to_string()));
+    }
+    if req.start_date.year() != req.end_date.year() {
+        return Err((StatusCode::BAD_REQUEST, "Divide la ausencia por año calendario".to_string()));
+    }
+    match req.document_object_id {
+        None if req.leave_type.requires_document() => {
+            return Err((StatusCode::BAD_REQUEST, "La incapacidad médica requiere soporte".to_string()));
+        }
+        Some(object_id) => {
+            // Datos médicos: solo soportes privados (EVIDENCE) que subió la propia modelo
+            find_owned_object(pool, object_id, BucketType::Evidence, user_id)
+                .await
+                .map_err(db_error)?
+                .ok_or((StatusCode::BAD_REQUEST, "El soporte debe subirse como evidencia privada".to_string()))?;
+        }
+        None => {}
+    }
+
+    let overlapping = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM leave_requests
+            WHERE user_id = $1 AND status IN ('PENDING', 'APPROVED')
+              AND start_date <= $3 AND end_date >= $2
+        )
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.start_date)
+    .bind(req.end_date)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error)?;
+    if overlapping {
+        return Err((StatusCode::CONFLICT, "Ya existe una ausencia en esas fechas".to_string()));
+    }
+
+    let days = leave_days(req.start_date, req.end_date);
+    let year = req.start_date.year();
+    let allowance = allowance_for(user_id, year, req.leave_type, pool).await.map_err(db_error)?;
+    let committed = days_taken(user_id, year, req.leave_type, &["PENDING", "APPROVED"], pool)
+        .await
+        .map_err(db_error)?;
+    check_balance(allowance, committed, days).map_err(|e| (StatusCode::CONFLICT, e))?;
+
+    let leave = insert_leave(user_id, &req, days, pool).await.map_err(db_error)?;
+
+    Ok((StatusCode::CREATED, Json(leave)))
+}
+
+/// GET /api/leave/requests
+/// Ausencias de la modelo autenticada
+pub async fn my_leaves_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<Vec<LeaveRequest>>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let rows = sqlx::query_as::<_, LeaveRequest>(&format!(
+        "SELECT {LEAVE_COLUMNS} FROM leave_requests WHERE user_id = $1 ORDER BY start_date DESC"
+    ))
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(rows))
+}
+
+/// POST /api/leave/requests/:id/cancel
+/// Cancela una solicitud pendiente, o una aprobada que aún no empieza
+pub async fn cancel_leave_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LeaveRequest>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let leave = sqlx::query_as::<_, LeaveRequest>(&format!(
+        r#"
+        UPDATE leave_requests
+        SET status = 'CANCELLED'
+        WHERE id = $1 AND user_id = $2
+          AND (status = 'PENDING' OR (status = 'APPROVED' AND start_date > CURRENT_DATE))
+        RETURNING {LEAVE_COLUMNS}
+        "#
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::CONFLICT, "La ausencia no se puede cancelar".to_string()))?;
+    Ok(Json(leave))
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeaveBalance {
+    pub leave_type: LeaveType,
+    /// `None` = sin tope
+    pub allowance_days: Option<i32>,
+    pub used_days: i32,
+    pub pending_days: i32,
+    pub remaining_days: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceQuery {
+    pub year: Option<i32>,
+    /// Solo staff puede consultar a otra modelo
+    pub user_id: Option<Uuid>,
+}
+
+/// GET /api/leave/balance?year=2025
+pub async fn leave_balance_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Query(query): Query<BalanceQuery>,
+) -> Result<Json<Vec<LeaveBalance>>, (StatusCode, String)> {
+    let me = parse_user_id(&auth.user_id)?;
+    let user_id = match query.user_id {
+        Some(other) if other != me && !super::is_staff(&auth.role) => {
+            return Err((StatusCode::FORBIDDEN, "No puedes ver el cupo de otra modelo".to_string()));
+        }
+        Some(other) => other,
+        None => me,
+    };
+    let year = query.year.unwrap_or_else(|| Utc::now().year());
+    let pool = &state.db;
+
+    let mut balances = Vec::new();
+    for leave_type in LeaveType::ALL {
+        let allowance = allowance_for(user_id, year, leave_type, pool).await.map_err(db_error)?;
+        let used_days = days_taken(user_id, year, leave_type, &["APPROVED"], pool).await.map_err(db_error)?;
+        let pending_days = days_taken(user_id, year, leave_type, &["PENDING"], pool).await.map_err(db_error)?;
+        balances.push(LeaveBalance {
+            leave_type,
+            allowance_days: allowance,
+            used_days,
+            pending_days,
+            remaining_days: allowance.map(|a| (a - used_days).max(0)),
+        });
+    }
+    Ok(Json(balances))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaveQueueQuery {
+    pub status: Option<String>,
+}
+
+/// GET /api/admin/leave/requests
+pub async fn leave_queue_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Query(query): Query<LeaveQueueQuery>,
+) -> Result<Json<Vec<LeaveRequest>>, (StatusCode, String)> {
+    let status = query.status.map(|s| s.to_uppercase()).unwrap_or_else(|| "PENDING".to_string());
+    let rows = sqlx::query_as::<_, LeaveRequest>(&format!(
+        "SELECT {LEAVE_COLUMNS} FROM leave_requests WHERE status = $1 ORDER BY start_date ASC"
+    ))
+    .bind(status)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(rows))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewLeaveRequest {
+    pub approve: bool,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewLeaveResponse {
+    pub leave: LeaveRequest,
+    /// Strikes anulados por caer dentro de la ausencia aprobada
+    pub voided_strikes: Vec<Uuid>,
+}
+
+/// POST /api/admin/leave/requests/:id/review
+pub async fn review_leave_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ReviewLeaveRequest>,
+) -> Result<Json<ReviewLeaveResponse>, (StatusCode, String)> {
+    let moderator_id = parse_user_id(&moderator.user_id)?;
+    let pool = &state.db;
+    let pending = fetch_leave(id, pool).await?;
+
+    if pending.status != "PENDING" {
+        return Err((StatusCode::CONFLICT, "La solicitud ya fue revisada".to_string()));
+    }
+
+    if req.approve {
+        let leave_type: LeaveType = pending
+            .leave_type
+            .parse()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Tipo de ausencia desconocido".to_string()))?;
+        let year = pending.start_date.year();
+        let allowance = allowance_for(pending.user_id, year, leave_type, pool).await.map_err(db_error)?;
+        let used = days_taken(pending.user_id, year, leave_type, &["APPROVED"], pool)
+            .await
+            .map_err(db_error)?;
+        check_balance(allowance, used, pending.days).map_err(|e| (StatusCode::CONFLICT, e))?;
+    }
+
+    let status = if req.approve { "APPROVED" } else { "REJECTED" };
+    let leave = sqlx::query_as::<_, LeaveRequest>(&format!(
+        r#"
+        UPDATE leave_requests
+        SET status = $2, reviewed_by = $3, reviewed_at = NOW(), review_note = $4
+        WHERE id = $1 AND status = 'PENDING'
+        RETURNING {LEAVE_COLUMNS}
+        "#
+    ))
+    .bind(id)
+    .bind(status)
+    .bind(moderator_id)
+    .bind(&req.note)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::CONFLICT, "La solicitud ya fue revisada".to_string()))?;
+
+    // Aprobación retroactiva: anular strikes emitidos durante la ausencia
+    let mut voided_strikes = Vec::new();
+    if req.approve {
+        // Días locales del estudio, no los de la zona horaria de la sesión
+        let (from, until) = studio_day_bounds(leave.start_date, leave.end_date);
+        let strike_ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM strikes
+            WHERE user_id = $1 AND status IN ('ACTIVE', 'APPEALED')
+              AND issued_at >= $2 AND issued_at < $3
+            "#,
+        )
+        .bind(leave.user_id)
+        .bind(from)
+        .bind(until)
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)?;
+
+        for strike_id in strike_ids {
+            match strikes::void_strike(&state, strike_id, moderator_id, "Ausencia justificada aprobada").await {
+                Ok(_) => voided_strikes.push(strike_id),
+                Err((_, e)) => tracing::warn!("No se pudo anular strike {} por ausencia {}: {}", strike_id, id, e),
+            }
+        }
+    }
+
+    tracing::info!("Ausencia {} de {} {} por {}", id, leave.user_id, status, moderator_id);
+    Ok(Json(ReviewLeaveResponse { leave, voided_strikes }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetBalanceRequest {
+    pub user_id: Uuid,
+    pub year: i32,
+    pub leave_type: LeaveType,
+    pub allowance_days: i32,
+}
+
+/// PUT /api/admin/leave/balances
+/// Ajusta el cupo anual de una modelo para un tipo de ausencia
+pub async fn set_balance_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Json(req): Json<SetBalanceRequest>,
+) -> Result<Json<LeaveBalance>, (StatusCode, String)> {
+    if req.allowance_days < 0 {
+        return Err((StatusCode::BAD_REQUEST, "El cupo no puede ser negativo".to_string()));
+    }
+    let pool = &state.db;
+    sqlx::query(
+        r#"
+        INSERT INTO leave_balances (user_id, year, leave_type, allowance_days)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id, year, leave_type)
+        DO UPDATE SET allowance_days = EXCLUDED.allowance_days, updated_at = NOW()
+        "#,
+    )
+    .bind(req.user_id)
+    .bind(req.year)
+    .bind(req.leave_type.as_str())
+    .bind(req.allowance_days)
+    .execute(pool)
+    .await
+    .map_err(db_error)?;
+
+    let used_days = days_taken(req.user_id, req.year, req.leave_type, &["APPROVED"], pool)
+        .await
+        .map_err(db_error)?;
+    let pending_days = days_taken(req.user_id, req.year, req.leave_type, &["PENDING"], pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(LeaveBalance {
+        leave_type: req.leave_type,
+        allowance_days: Some(req.allowance_days),
+        used_days,
+        pending_days,
+        remaining_days: Some((req.allowance_days - used_days).max(0)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leave_days_are_inclusive() {
+        let d = |day| NaiveDate::from_ymd_opt(2025, 12, day).unwrap();
+        assert_eq!(leave_days(d(10), d(10)), 1);
+        assert_eq!(leave_days(d(10), d(14)), 5);
+    }
+
+    #[test]
+    fn balance_check_respects_allowance_and_unlimited_types() {
+        assert!(check_balance(Some(15), 10, 5).is_ok());
+        assert!(check_balance(Some(15), 10, 6).is_err());
+        assert!(check_balance(LeaveType::Medical.default_allowance(), 100, 10).is_ok());
+    }
+
+    #[test]
+    fn studio_date_is_colombian_local_date() {
+        let d = |day| NaiveDate::from_ymd_opt(2025, 12, day).unwrap();
+        let utc_on = |day, h| d(day).and_hms_opt(h, 0, 0).unwrap().and_utc();
+        let utc = |h| d(11).and_hms_opt(h, 30, 0).unwrap().and_utc();
+        // 02:30 UTC del 11 todavía es la noche del 10 en Bogotá
+        assert_eq!(studio_date(utc(2)), NaiveDate::from_ymd_opt(2025, 12, 10).unwrap());
+        assert_eq!(studio_date(utc(5)), NaiveDate::from_ymd_opt(2025, 12, 11).unwrap());
+        let (from, until) = studio_day_bounds(d(10), d(10));
+        assert_eq!(from, utc_on(10, 5));
+        assert_eq!(until, utc_on(11, 5));
+        assert_eq!("MEDICAL".parse::<LeaveType>(), Ok(LeaveType::Medical));
+        assert!("SICK".parse::<LeaveType>().is_err());
+    }
+}
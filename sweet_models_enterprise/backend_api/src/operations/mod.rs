@@ -1,3 +1,19 @@
+use axum::http::StatusCode;
+use uuid::Uuid;
+
 pub mod attendance;
 pub mod attendance_status;
-pub mod room;
\ No newline at end of file
+pub mod leave;
+pub mod room;
+pub mod roster;
+pub mod strikes;
+pub mod studio;
+
+/// Roles con permisos de supervisión operativa
+pub(crate) fn is_staff(role: &str) -> bool {
+    matches!(role.to_uppercase().as_str(), "MODERATOR" | "ADMIN" | "SUPER_ADMIN")
+}
+
+pub(crate) fn parse_user_id(raw: &str) -> Result<Uuid, (StatusCode, String)> {
+    Uuid::parse_str(raw).map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user id in token".to_string()))
+}
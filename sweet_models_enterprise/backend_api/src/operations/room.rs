@@ -1,97 +1,537 @@
-use axum::{extract::State, http::StatusCode, Json};
+/// Cierre de turno por room.
+///
+/// Flujo: el turno saliente envía checklist + fotos (`PENDING_REVIEW`), el turno
+/// entrante confirma limpieza o abre disputa (`DISPUTED`), y un moderador puede
+/// confirmar con veredicto en cualquiera de los dos estados. Las multas (cuota
+/// grupal y room sucio) solo se aplican al pasar a `CONFIRMED`. Los tokens del
+/// turno salen de `production_logs`, nunca del cliente.
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::types::Uuid;
-use chrono::{Utc, Datelike};
+use sqlx::{types::Uuid, FromRow, PgConnection, PgExecutor, PgPool};
 
-use crate::{state::AppState, finance, gamification};
+use crate::{
+    finance, gamification,
+    middleware::auth::{AuthenticatedUser, ModeratorOnly},
+    state::AppState,
+};
 
-#[derive(Debug, Deserialize)]
-pub struct CloseShiftRequest {
+use super::{attendance::Shift, is_staff, leave, parse_user_id};
+
+pub const PENALTY_GROUP_SHORTFALL: &str = "GROUP_SHORTFALL";
+pub const PENALTY_DIRTY_ROOM: &str = "DIRTY_ROOM";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub item: String,
+    pub ok: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Verdict {
+    Clean,
+    Dirty,
+}
+
+impl Verdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Clean => "CLEAN",
+            Verdict::Dirty => "DIRTY",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ShiftCloseout {
+    pub id: Uuid,
     pub room_id: i32,
     pub shift_id: i32,
+    pub work_date: NaiveDate,
+    pub week_id: String,
+    pub members: Vec<Uuid>,
     pub total_tokens: f64,
-    pub is_dirty: bool,
+    pub checklist: serde_json::Value,
+    pub photo_urls: Vec<String>,
+    pub notes: Option<String>,
+    pub submitted_by: Uuid,
+    pub submitted_at: DateTime<Utc>,
+    pub status: String,
+    pub disputed_by: Option<Uuid>,
+    pub disputed_at: Option<DateTime<Utc>>,
+    pub dispute_reason: Option<String>,
+    pub dispute_photo_urls: Vec<String>,
+    pub verdict: Option<String>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_notes: Option<String>,
+    pub penalties_applied: serde_json::Value,
 }
 
-#[derive(Debug, Serialize)]
-pub struct CloseShiftResponse {
-    pub message: String,
-    pub penalty_applied: bool,
-    pub penalty_type: Option<String>,
+const CLOSEOUT_COLUMNS: &str = "id, room_id, shift_id, work_date, week_id, members, total_tokens, checklist, \
+     photo_urls, notes, submitted_by, submitted_at, status, disputed_by, disputed_at, dispute_reason, \
+     dispute_photo_urls, verdict, reviewed_by, reviewed_at, review_notes, penalties_applied";
+
+fn week_id_for(date: NaiveDate) -> String {
+    format!("{}-W{:02}", date.iso_week().year(), date.iso_week().week())
 }
 
-/// POST /api/operations/room/close-shift
-/// Cierra un turno de room: aplica multas por cuota grupal o limpieza.
-pub async fn close_shift_handler(
-    State(state): State<std::sync::Arc<AppState>>,
-    Json(req): Json<CloseShiftRequest>,
-) -> Result<Json<CloseShiftResponse>, (StatusCode, String)> {
-    let pool = &state.db;
+/// Turno que recibe el room después de `shift_id` (el turno 4 entrega al turno 1 del día siguiente)
+pub fn incoming_shift(shift_id: i32, work_date: NaiveDate) -> Option<(i32, NaiveDate)> {
+    Shift::from_int(shift_id)?;
+    if shift_id == 4 {
+        Some((1, work_date.succ_opt()?))
+    } else {
+        Some((shift_id + 1, work_date))
+    }
+}
+
+/// Multas que corresponden al confirmar el cierre
+pub fn penalties_for(total_tokens: f64, verdict: Verdict) -> Vec<&'static str> {
+    let mut penalties = Vec::new();
+    if total_tokens < finance::payroll::GROUP_QUOTA {
+        penalties.push(PENALTY_GROUP_SHORTFALL);
+    }
+    if verdict == Verdict::Dirty {
+        penalties.push(PENALTY_DIRTY_ROOM);
+    }
+    penalties
+}
+
+pub fn validate_checklist(checklist: &[ChecklistItem], photo_urls: &[String]) -> Result<(), String> {
+    if checklist.is_empty() {
+        return Err("El checklist no puede estar vacío".to_string());
+    }
+    if checklist.iter().any(|c| c.item.trim().is_empty()) {
+        return Err("Cada ítem del checklist necesita nombre".to_string());
+    }
+    if photo_urls.is_empty() {
+        return Err("Se requiere al menos una foto del room".to_string());
+    }
+    Ok(())
+}
 
-    // Obtener integrantes del room en este turno (semana actual)
-    let today = Utc::now().date_naive();
-    let week_id = format!("{}-W{:02}", today.iso_week().year(), today.iso_week().week());
+fn validate_photos(state: &AppState, photo_urls: &[String]) -> Result<(), (StatusCode, String)> {
+    if let Some(bad) = photo_urls.iter().find(|u| !state.storage.owns_url(u)) {
+        return Err((StatusCode::BAD_REQUEST, format!("Foto no subida al almacenamiento del estudio: {}", bad)));
+    }
+    Ok(())
+}
 
-    let members = sqlx::query_scalar::<_, Uuid>(
+async fn shift_members(
+    room_id: i32,
+    shift_id: i32,
+    date: NaiveDate,
+    pool: &PgPool,
+) -> Result<Vec<Uuid>, (StatusCode, String)> {
+    sqlx::query_scalar::<_, Uuid>(
         r#"
         SELECT user_id
         FROM user_shifts
         WHERE assigned_room = $1 AND assigned_shift = $2 AND week_id = $3
         "#,
     )
-    .bind(req.room_id)
-    .bind(req.shift_id)
-    .bind(&week_id)
+    .bind(room_id)
+    .bind(shift_id)
+    .bind(week_id_for(date))
     .fetch_all(pool)
     .await
     .map_err(|e| {
         tracing::error!("DB error fetching room members: {}", e);
         (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-    })?;
+    })
+}
 
-    if members.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "No hay miembros asignados a este room/shift".to_string()));
+/// Tokens registrados en production_logs por los integrantes ese día en este room.
+/// La telemetría de otro room no cuenta; la carga manual (sin room) es del room asignado,
+/// que es de donde salen los integrantes.
+async fn recorded_tokens<'e, E: PgExecutor<'e>>(
+    room_id: i32,
+    members: &[Uuid],
+    date: NaiveDate,
+    executor: E,
+) -> Result<f64, (StatusCode, String)> {
+    sqlx::query_scalar::<_, f64>(
+        r#"
+        SELECT COALESCE(SUM(tokens_earned), 0)::DOUBLE PRECISION
+        FROM production_logs
+        WHERE model_id = ANY($1) AND production_date = $2 AND superseded_by_draft IS NULL
+          AND (studio_room_id = $3 OR (studio_room_id IS NULL AND source = 'MANUAL'))
+        "#,
+    )
+    .bind(members)
+    .bind(date)
+    .bind(room_id)
+    .fetch_one(executor)
+    .await
+    .map_err(|e| {
+        tracing::error!("DB error summing room production: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })
+}
+
+async fn fetch_closeout(id: Uuid, pool: &PgPool) -> Result<ShiftCloseout, (StatusCode, String)> {
+    sqlx::query_as::<_, ShiftCloseout>(&format!("SELECT {CLOSEOUT_COLUMNS} FROM shift_closeouts WHERE id = $1"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Cierre de turno no encontrado".to_string()))
+}
+
+/// ¿Pertenece el usuario al turno que recibe el room?
+async fn is_incoming_member(closeout: &ShiftCloseout, user_id: Uuid, pool: &PgPool) -> Result<bool, (StatusCode, String)> {
+    let Some((shift_id, date)) = incoming_shift(closeout.shift_id, closeout.work_date) else {
+        return Ok(false);
+    };
+    let members = shift_members(closeout.room_id, shift_id, date, pool).await?;
+    Ok(members.contains(&user_id))
+}
+
+/// Aplica multas del cierre confirmado dentro de la transacción de la confirmación:
+/// si alguna falla, el cierre no queda confirmado. Devuelve las aplicadas.
+/// Las integrantes con ausencia aprobada ese día quedan exentas.
+async fn apply_closeout_penalties(
+    closeout: &ShiftCloseout,
+    verdict: Verdict,
+    conn: &mut PgConnection,
+) -> Result<Vec<&'static str>, String> {
+    let on_leave = leave::members_on_leave(&closeout.members, closeout.work_date, &mut *conn)
+        .await
+        .map_err(|e| format!("Error consultando ausencias del cierre {}: {}", closeout.id, e))?;
+    let liable: Vec<Uuid> = closeout.members.iter().copied().filter(|m| !on_leave.contains(m)).collect();
+    if liable.is_empty() {
+        return Ok(Vec::new());
     }
 
-    let mut penalty_applied = false;
-    let mut penalty_type = None;
+    let penalties = penalties_for(closeout.total_tokens, verdict);
+    for penalty in &penalties {
+        match *penalty {
+            PENALTY_GROUP_SHORTFALL => {
+                finance::apply_group_shortfall_penalty(&liable, closeout.total_tokens, conn).await?
+            }
+            _ => finance::apply_dirty_room_penalty(&liable, closeout.room_id, conn).await?,
+        }
+    }
 
-    // 1. Revisar cuota grupal
-    if req.total_tokens < finance::payroll::GROUP_QUOTA {
-        if let Err(e) = finance::apply_group_shortfall_penalty(&members, req.total_tokens, pool).await {
-            tracing::error!("Error aplicando multa grupal: {}", e);
-        } else {
-            penalty_applied = true;
-            penalty_type = Some("group_shortfall".to_string());
+    if penalties.contains(&PENALTY_DIRTY_ROOM) {
+        // Burn 20% XP for dirty room penalty
+        for member_id in &liable {
+            gamification::burn_xp(*member_id, "DIRTY_ROOM", conn).await?;
         }
     }
+    Ok(penalties)
+}
 
-    // 2. Revisar limpieza
-    if req.is_dirty {
-        if let Err(e) = finance::apply_dirty_room_penalty(&members, req.room_id, pool).await {
-            tracing::error!("Error aplicando multa por room sucio: {}", e);
-        } else {
-            penalty_applied = true;
-            penalty_type = Some(if penalty_type.is_some() { "both".to_string() } else { "dirty_room".to_string() });
-            
-            // Burn 20% XP for dirty room penalty
-            for member_id in &members {
-                if let Err(e) = gamification::burn_xp(*member_id, "DIRTY_ROOM", pool).await {
-                    tracing::warn!("XP burn failed for dirty room penalty: {}", e);
-                }
-            }
+// ============================================================================
+// HANDLERS
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct CloseShiftRequest {
+    pub room_id: i32,
+    pub shift_id: i32,
+    /// Día del turno (por defecto hoy)
+    #[serde(default)]
+    pub work_date: Option<NaiveDate>,
+    pub checklist: Vec<ChecklistItem>,
+    /// URLs devueltas por /api/upload
+    pub photo_urls: Vec<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// POST /api/operations/room/close-shift
+/// El turno saliente entrega el room: checklist + fotos. No aplica multas.
+pub async fn close_shift_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Json(req): Json<CloseShiftRequest>,
+) -> Result<(StatusCode, Json<ShiftCloseout>), (StatusCode, String)> {
+    let pool = &state.db;
+    let submitted_by = parse_user_id(&auth.user_id)?;
+
+    if Shift::from_int(req.shift_id).is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Turno inválido".to_string()));
+    }
+    validate_checklist(&req.checklist, &req.photo_urls).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    validate_photos(&state, &req.photo_urls)?;
+
+    let work_date = req.work_date.unwrap_or_else(|| super::leave::studio_date(Utc::now()));
+    let members = shift_members(req.room_id, req.shift_id, work_date, pool).await?;
+    if members.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "No hay miembros asignados a este room/shift".to_string()));
+    }
+    if !members.contains(&submitted_by) && !is_staff(&auth.role) {
+        return Err((StatusCode::FORBIDDEN, "Solo el turno saliente puede cerrar el room".to_string()));
+    }
+
+    let total_tokens = recorded_tokens(req.room_id, &members, work_date, pool).await?;
+
+    let closeout = sqlx::query_as::<_, ShiftCloseout>(&format!(
+        r#"
+        INSERT INTO shift_closeouts (room_id, shift_id, work_date, week_id, members, total_tokens, checklist, photo_urls, notes, submitted_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (room_id, shift_id, work_date) DO NOTHING
+        RETURNING {CLOSEOUT_COLUMNS}
+        "#
+    ))
+    .bind(req.room_id)
+    .bind(req.shift_id)
+    .bind(work_date)
+    .bind(week_id_for(work_date))
+    .bind(&members)
+    .bind(total_tokens)
+    .bind(serde_json::to_value(&req.checklist).unwrap_or_default())
+    .bind(&req.photo_urls)
+    .bind(&req.notes)
+    .bind(submitted_by)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("DB error inserting shift closeout: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?
+    .ok_or((StatusCode::CONFLICT, "Este turno ya fue cerrado".to_string()))?;
+
+    tracing::info!(
+        "Room {} turno {} ({}) cerrado por {}: {} tokens registrados",
+        closeout.room_id, closeout.shift_id, closeout.work_date, submitted_by, closeout.total_tokens
+    );
+    Ok((StatusCode::CREATED, Json(closeout)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmCloseoutRequest {
+    /// El turno entrante solo puede confirmar CLEAN; DIRTY exige moderador
+    pub verdict: Verdict,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfirmCloseoutResponse {
+    pub closeout: ShiftCloseout,
+    pub penalties_applied: Vec<String>,
+    pub message: String,
+}
+
+/// POST /api/operations/room/closeouts/:id/confirm
+pub async fn confirm_closeout_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ConfirmCloseoutRequest>,
+) -> Result<Json<ConfirmCloseoutResponse>, (StatusCode, String)> {
+    let pool = &state.db;
+    let reviewer = parse_user_id(&auth.user_id)?;
+    let closeout = fetch_closeout(id, pool).await?;
+    let staff = is_staff(&auth.role);
+
+    if !staff {
+        if closeout.status != "PENDING_REVIEW" {
+            return Err((StatusCode::CONFLICT, "Solo un moderador puede resolver este cierre".to_string()));
+        }
+        if req.verdict == Verdict::Dirty {
+            return Err((StatusCode::FORBIDDEN, "Para reportar suciedad abre una disputa con evidencia".to_string()));
+        }
+        if closeout.members.contains(&reviewer) || !is_incoming_member(&closeout, reviewer, pool).await? {
+            return Err((StatusCode::FORBIDDEN, "Solo el turno entrante puede confirmar".to_string()));
         }
     }
 
-    let message = if penalty_applied {
-        "Turno cerrado con multa(s) aplicada(s)".to_string()
-    } else {
+    // Transición, tokens y multas en una transacción: o queda todo confirmado o nada
+    let internal = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let mut tx = pool.begin().await.map_err(internal)?;
+    let mut confirmed = sqlx::query_as::<_, ShiftCloseout>(&format!(
+        r#"
+        UPDATE shift_closeouts
+        SET status = 'CONFIRMED', verdict = $2, reviewed_by = $3, reviewed_at = NOW(), review_notes = $4
+        WHERE id = $1 AND status IN ('PENDING_REVIEW', 'DISPUTED')
+        RETURNING {CLOSEOUT_COLUMNS}
+        "#
+    ))
+    .bind(id)
+    .bind(req.verdict.as_str())
+    .bind(reviewer)
+    .bind(&req.notes)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(internal)?
+    .ok_or((StatusCode::CONFLICT, "El cierre ya fue confirmado".to_string()))?;
+
+    // La producción pudo corregirse (o confirmarse desde telemetría) después de entregar el room
+    confirmed.total_tokens = recorded_tokens(confirmed.room_id, &confirmed.members, confirmed.work_date, &mut *tx).await?;
+
+    let applied = apply_closeout_penalties(&confirmed, req.verdict, &mut tx).await.map_err(|e| {
+        tracing::error!("Error aplicando multas del cierre {}: {}", id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e)
+    })?;
+
+    let closeout = sqlx::query_as::<_, ShiftCloseout>(&format!(
+        r#"
+        UPDATE shift_closeouts SET total_tokens = $2, penalties_applied = $3
+        WHERE id = $1
+        RETURNING {CLOSEOUT_COLUMNS}
+        "#
+    ))
+    .bind(id)
+    .bind(confirmed.total_tokens)
+    .bind(serde_json::json!(applied))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+
+    let message = if applied.is_empty() {
         "Turno cerrado exitosamente".to_string()
+    } else {
+        "Turno cerrado con multa(s) aplicada(s)".to_string()
     };
 
-    Ok(Json(CloseShiftResponse {
+    Ok(Json(ConfirmCloseoutResponse {
+        closeout,
+        penalties_applied: applied.into_iter().map(String::from).collect(),
         message,
-        penalty_applied,
-        penalty_type,
     }))
 }
+
+#[derive(Debug, Deserialize)]
+pub struct DisputeCloseoutRequest {
+    pub reason: String,
+    pub photo_urls: Vec<String>,
+}
+
+/// POST /api/operations/room/closeouts/:id/dispute
+/// El turno entrante reporta el room sucio; queda pendiente de moderador.
+pub async fn dispute_closeout_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    Json(req): Json<DisputeCloseoutRequest>,
+) -> Result<Json<ShiftCloseout>, (StatusCode, String)> {
+    let pool = &state.db;
+    let user_id = parse_user_id(&auth.user_id)?;
+
+    if req.reason.trim().is_empty() || req.photo_urls.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "La disputa requiere motivo y fotos".to_string()));
+    }
+    validate_photos(&state, &req.photo_urls)?;
+
+    let closeout = fetch_closeout(id, pool).await?;
+    if !is_staff(&auth.role) && !is_incoming_member(&closeout, user_id, pool).await? {
+        return Err((StatusCode::FORBIDDEN, "Solo el turno entrante puede disputar".to_string()));
+    }
+
+    let disputed = sqlx::query_as::<_, ShiftCloseout>(&format!(
+        r#"
+        UPDATE shift_closeouts
+        SET status = 'DISPUTED', disputed_by = $2, disputed_at = NOW(), dispute_reason = $3, dispute_photo_urls = $4
+        WHERE id = $1 AND status = 'PENDING_REVIEW'
+        RETURNING {CLOSEOUT_COLUMNS}
+        "#
+    ))
+    .bind(id)
+    .bind(user_id)
+    .bind(req.reason.trim())
+    .bind(&req.photo_urls)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::CONFLICT, "El cierre ya no admite disputas".to_string()))?;
+
+    tracing::warn!("Cierre {} (Room {}) disputado por {}", id, disputed.room_id, user_id);
+    Ok(Json(disputed))
+}
+
+/// GET /api/operations/room/closeouts/:id
+/// Moderadores, el turno que entregó el room o el que lo recibe
+pub async fn get_closeout_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ShiftCloseout>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let closeout = fetch_closeout(id, &state.db).await?;
+    if !is_staff(&auth.role)
+        && !closeout.members.contains(&user_id)
+        && !is_incoming_member(&closeout, user_id, &state.db).await?
+    {
+        return Err((StatusCode::NOT_FOUND, "Cierre de turno no encontrado".to_string()));
+    }
+    Ok(Json(closeout))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloseoutQueueQuery {
+    /// PENDING_REVIEW | DISPUTED | CONFIRMED (por defecto los abiertos)
+    pub status: Option<String>,
+}
+
+/// GET /api/admin/room/closeouts
+pub async fn closeout_queue_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Query(query): Query<CloseoutQueueQuery>,
+) -> Result<Json<Vec<ShiftCloseout>>, (StatusCode, String)> {
+    let statuses: Vec<String> = match query.status {
+        Some(s) => vec![s.to_uppercase()],
+        None => vec!["PENDING_REVIEW".to_string(), "DISPUTED".to_string()],
+    };
+
+    let rows = sqlx::query_as::<_, ShiftCloseout>(&format!(
+        r#"
+        SELECT {CLOSEOUT_COLUMNS}
+        FROM shift_closeouts
+        WHERE status = ANY($1)
+        ORDER BY submitted_at ASC
+        LIMIT 200
+        "#
+    ))
+    .bind(&statuses)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incoming_shift_wraps_to_next_day() {
+        let day = NaiveDate::from_ymd_opt(2025, 12, 14).unwrap();
+        assert_eq!(incoming_shift(2, day), Some((3, day)));
+        assert_eq!(incoming_shift(4, day), Some((1, day.succ_opt().unwrap())));
+        assert_eq!(incoming_shift(5, day), None);
+    }
+
+    #[test]
+    fn penalties_depend_on_quota_and_verdict() {
+        assert!(penalties_for(2000.0, Verdict::Clean).is_empty());
+        assert_eq!(penalties_for(1000.0, Verdict::Clean), vec![PENALTY_GROUP_SHORTFALL]);
+        assert_eq!(
+            penalties_for(1000.0, Verdict::Dirty),
+            vec![PENALTY_GROUP_SHORTFALL, PENALTY_DIRTY_ROOM]
+        );
+    }
+
+    #[test]
+    fn checklist_requires_items_and_photos() {
+        let item = ChecklistItem { item: "Cama tendida".to_string(), ok: true, note: None };
+        assert!(validate_checklist(&[], &["x".to_string()]).is_err());
+        assert!(validate_checklist(&[item.clone()], &[]).is_err());
+        assert!(validate_checklist(&[item], &["x".to_string()]).is_ok());
+    }
+}
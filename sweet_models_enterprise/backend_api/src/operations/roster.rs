@@ -0,0 +1,252 @@
+/// Disponibilidad de rooms para el roster semanal y asignación de turnos.
+/// Un room se puede asignar si está ACTIVE, no tiene tickets que lo bloqueen
+/// y aún tiene cupo en el turno.
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Uuid, FromRow, PgExecutor};
+
+use crate::{
+    middleware::auth::{AuthenticatedUser, ModeratorOnly},
+    state::AppState,
+};
+
+use super::{attendance::Shift, studio::STATUS_ACTIVE};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomAvailability {
+    pub room_id: i32,
+    pub room_name: String,
+    pub shift_id: i32,
+    pub status: String,
+    pub capacity: i32,
+    pub assigned: i64,
+    pub blocking_tickets: i64,
+    /// Activos del room fuera de servicio (no bloquean, solo avisan)
+    pub assets_in_maintenance: i64,
+    pub available: bool,
+    pub reason: Option<String>,
+}
+
+/// Evalúa si un room admite una asignación más
+pub fn evaluate_availability(status: &str, blocking_tickets: i64, assigned: i64, capacity: i32) -> (bool, Option<String>) {
+    if status != STATUS_ACTIVE {
+        return (false, Some(format!("Room en estado {}", status)));
+    }
+    if blocking_tickets > 0 {
+        return (false, Some("Mantenimiento pendiente bloquea el room".to_string()));
+    }
+    if assigned >= i64::from(capacity) {
+        return (false, Some("Turno sin cupo".to_string()));
+    }
+    (true, None)
+}
+
+fn current_week_id() -> String {
+    let today = Utc::now().date_naive();
+    format!("{}-W{:02}", today.iso_week().year(), today.iso_week().week())
+}
+
+#[derive(FromRow)]
+struct AvailabilityRow {
+    room_id: i32,
+    room_name: String,
+    status: String,
+    capacity: i32,
+    assigned: i64,
+    blocking_tickets: i64,
+    assets_in_maintenance: i64,
+}
+
+/// Disponibilidad de los rooms (o de uno solo) para una semana y turno
+pub async fn room_availability<'e, E: PgExecutor<'e>>(
+    executor: E,
+    week_id: &str,
+    shift_id: i32,
+    room_id: Option<i32>,
+) -> Result<Vec<RoomAvailability>, (StatusCode, String)> {
+    let rows = sqlx::query_as::<_, AvailabilityRow>(
+        r#"
+        SELECT r.id AS room_id,
+               r.name AS room_name,
+               r.status,
+               r.capacity,
+               (SELECT COUNT(*) FROM user_shifts us
+                 WHERE us.assigned_room = r.id AND us.assigned_shift = $2 AND us.week_id = $1) AS assigned,
+               (SELECT COUNT(*) FROM maintenance_tickets t
+                 WHERE t.room_id = r.id AND t.blocks_room = TRUE AND t.status <> 'RESOLVED') AS blocking_tickets,
+               (SELECT COUNT(*) FROM studio_assets a
+                 WHERE a.room_id = r.id AND a.status = 'MAINTENANCE') AS assets_in_maintenance
+        FROM rooms r
+        WHERE r.status <> 'RETIRED' AND ($3::INT IS NULL OR r.id = $3)
+        ORDER BY r.id
+        "#,
+    )
+    .bind(week_id)
+    .bind(shift_id)
+    .bind(room_id)
+    .fetch_all(executor)
+    .await
+    .map_err(|e| {
+        tracing::error!("DB error fetching room availability: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let (available, reason) = evaluate_availability(&r.status, r.blocking_tickets, r.assigned, r.capacity);
+            RoomAvailability {
+                room_id: r.room_id,
+                room_name: r.room_name,
+                shift_id,
+                status: r.status,
+                capacity: r.capacity,
+                assigned: r.assigned,
+                blocking_tickets: r.blocking_tickets,
+                assets_in_maintenance: r.assets_in_maintenance,
+                available,
+                reason,
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AvailabilityQuery {
+    /// Semana ISO "YYYY-Www" (por defecto la actual)
+    pub week_id: Option<String>,
+    pub shift_id: i32,
+}
+
+/// GET /api/studio/rooms/availability?week_id=2025-W50&shift_id=2
+pub async fn room_availability_handler(
+    State(state): State<Arc<AppState>>,
+    _auth: AuthenticatedUser,
+    Query(query): Query<AvailabilityQuery>,
+) -> Result<Json<Vec<RoomAvailability>>, (StatusCode, String)> {
+    if Shift::from_int(query.shift_id).is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Turno inválido".to_string()));
+    }
+    let week_id = query.week_id.unwrap_or_else(current_week_id);
+    Ok(Json(room_availability(&state.db, &week_id, query.shift_id, None).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignShiftRequest {
+    pub user_id: Uuid,
+    pub week_id: Option<String>,
+    pub room_id: i32,
+    pub shift_id: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssignShiftResponse {
+    pub user_id: Uuid,
+    pub week_id: String,
+    pub room_id: i32,
+    pub shift_id: i32,
+}
+
+/// PUT /api/admin/roster/assignments
+/// Asigna (o mueve) a una modelo a un room/turno de la semana validando disponibilidad.
+pub async fn assign_shift_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Json(req): Json<AssignShiftRequest>,
+) -> Result<Json<AssignShiftResponse>, (StatusCode, String)> {
+    if Shift::from_int(req.shift_id).is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Turno inválido".to_string()));
+    }
+    let week_id = req.week_id.unwrap_or_else(current_week_id);
+    let db_error = |e: sqlx::Error| {
+        tracing::error!("DB error assigning shift: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+
+    // Bloquear el room serializa las asignaciones concurrentes: el cupo se cuenta y se ocupa en la misma transacción
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+    sqlx::query_scalar::<_, i32>("SELECT id FROM rooms WHERE id = $1 FOR UPDATE")
+        .bind(req.room_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Room no encontrado".to_string()))?;
+
+    let availability = room_availability(&mut *tx, &week_id, req.shift_id, Some(req.room_id)).await?;
+    let room = availability.first().ok_or((StatusCode::NOT_FOUND, "Room no encontrado".to_string()))?;
+
+    // Re-asignar a la misma modelo en su mismo room/turno no consume cupo
+    let already_here = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM user_shifts
+            WHERE user_id = $1 AND week_id = $2 AND assigned_room = $3 AND assigned_shift = $4
+        )
+        "#,
+    )
+    .bind(req.user_id)
+    .bind(&week_id)
+    .bind(req.room_id)
+    .bind(req.shift_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    if !room.available && !already_here {
+        return Err((
+            StatusCode::CONFLICT,
+            room.reason.clone().unwrap_or_else(|| "Room no disponible".to_string()),
+        ));
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_shifts (user_id, assigned_room, assigned_shift, week_id)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id, week_id)
+        DO UPDATE SET assigned_room = EXCLUDED.assigned_room, assigned_shift = EXCLUDED.assigned_shift
+        "#,
+    )
+    .bind(req.user_id)
+    .bind(req.room_id)
+    .bind(req.shift_id)
+    .bind(&week_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    tracing::info!("Roster {}: {} → room {} turno {}", week_id, req.user_id, req.room_id, req.shift_id);
+    Ok(Json(AssignShiftResponse {
+        user_id: req.user_id,
+        week_id,
+        room_id: req.room_id,
+        shift_id: req.shift_id,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maintenance_and_blocking_tickets_make_room_unavailable() {
+        assert!(!evaluate_availability("MAINTENANCE", 0, 0, 3).0);
+        assert!(!evaluate_availability("ACTIVE", 1, 0, 3).0);
+        assert_eq!(evaluate_availability("ACTIVE", 0, 2, 3), (true, None));
+    }
+
+    #[test]
+    fn full_shift_is_unavailable() {
+        let (available, reason) = evaluate_availability("ACTIVE", 0, 3, 3);
+        assert!(!available);
+        assert_eq!(reason.as_deref(), Some("Turno sin cupo"));
+    }
+}
@@ -0,0 +1,781 @@
+/// Motor de strikes: política configurable (ventana móvil + escalera de
+/// consecuencias), strikes persistidos y flujo de apelación.
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Uuid, FromRow, PgConnection, PgExecutor, PgPool};
+use std::sync::Arc;
+
+use crate::{
+    finance::{
+        self,
+        adjustments::{AdjustmentKind, NewAdjustment},
+    },
+    gamification,
+    middleware::auth::{AdminOnly, AuthenticatedUser, ModeratorOnly},
+    notifications::{self, DomainEvent},
+    state::AppState,
+};
+
+use super::{is_staff, parse_user_id};
+
+/// Ventana por defecto (equivale a la semana usada originalmente)
+pub const DEFAULT_WINDOW_DAYS: i32 = 7;
+
+// ============================================================================
+// POLÍTICA
+// ============================================================================
+
+/// Consecuencia económica/operativa asociada a un nivel de strike
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StrikeConsequence {
+    /// Cobra al 50% el día del strike
+    HalfPayToday,
+    /// Multiplica el pago de la semana por `factor`
+    DowngradeWeek { factor: f64 },
+    /// Multa directa en COP
+    Fine { amount_cop: f64 },
+}
+
+impl StrikeConsequence {
+    pub fn describe(&self) -> String {
+        match self {
+            StrikeConsequence::HalfPayToday => "Hoy cobras al 50%".to_string(),
+            StrikeConsequence::DowngradeWeek { factor } => {
+                format!("Semana degradada al {:.0}%", factor * 100.0)
+            }
+            StrikeConsequence::Fine { amount_cop } => {
+                format!("Multa de {:.0} COP", amount_cop)
+            }
+        }
+    }
+}
+
+/// Un escalón de la escalera de strikes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrikeLevel {
+    pub level: i32,
+    /// Regla de `FRAGILITY_BURNS` a aplicar (ej. "STRIKE_1")
+    #[serde(default)]
+    pub xp_burn_reason: Option<String>,
+    #[serde(default)]
+    pub consequences: Vec<StrikeConsequence>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrikePolicy {
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    pub name: String,
+    /// Días de la ventana móvil en que se acumulan strikes
+    pub window_days: i32,
+    pub ladder: Vec<StrikeLevel>,
+}
+
+impl Default for StrikePolicy {
+    fn default() -> Self {
+        Self {
+            id: None,
+            name: "default".to_string(),
+            window_days: DEFAULT_WINDOW_DAYS,
+            ladder: vec![
+                StrikeLevel {
+                    level: 1,
+                    xp_burn_reason: Some("STRIKE_1".to_string()),
+                    consequences: vec![StrikeConsequence::HalfPayToday],
+                },
+                StrikeLevel {
+                    level: 2,
+                    xp_burn_reason: Some("STRIKE_2".to_string()),
+                    consequences: vec![StrikeConsequence::DowngradeWeek { factor: 0.50 }],
+                },
+                StrikeLevel {
+                    level: 3,
+                    xp_burn_reason: Some("STRIKE_3".to_string()),
+                    consequences: vec![StrikeConsequence::Fine {
+                        amount_cop: finance::payroll::STRIKE3_PENALTY_COP,
+                    }],
+                },
+            ],
+        }
+    }
+}
+
+/// Escalón aplicable para el strike número `ordinal` dentro de la ventana.
+/// Si se supera la escalera se repite el último escalón.
+pub fn level_in(ladder: &[StrikeLevel], ordinal: i64) -> Option<&StrikeLevel> {
+    if ordinal <= 0 {
+        return None;
+    }
+    ladder
+        .iter()
+        .filter(|l| i64::from(l.level) <= ordinal)
+        .max_by_key(|l| l.level)
+}
+
+/// Niveles consecutivos desde 1, quemas conocidas y consecuencias con montos válidos
+pub fn validate_ladder(ladder: &[StrikeLevel]) -> Result<(), String> {
+    if ladder.is_empty() {
+        return Err("La escalera de strikes no puede estar vacía".to_string());
+    }
+    let mut levels: Vec<i32> = ladder.iter().map(|l| l.level).collect();
+    levels.sort_unstable();
+    if levels[0] != 1 || levels.windows(2).any(|w| w[1] != w[0] + 1) {
+        return Err("Los niveles deben ser consecutivos empezando en 1".to_string());
+    }
+    for level in ladder {
+        if let Some(reason) = &level.xp_burn_reason {
+            if !gamification::engine::FRAGILITY_BURNS.iter().any(|(r, _, _)| r == reason) {
+                return Err(format!("Regla de fragilidad desconocida: {}", reason));
+            }
+        }
+        for c in &level.consequences {
+            match c {
+                StrikeConsequence::DowngradeWeek { factor } if !(0.0..=1.0).contains(factor) => {
+                    return Err("El factor de degradación debe estar entre 0 y 1".to_string());
+                }
+                StrikeConsequence::Fine { amount_cop } if *amount_cop <= 0.0 => {
+                    return Err("La multa debe ser positiva".to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+impl StrikePolicy {
+    /// Escalón aplicable para el strike número `ordinal` dentro de la ventana
+    pub fn level_for(&self, ordinal: i64) -> Option<&StrikeLevel> {
+        level_in(&self.ladder, ordinal)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_days <= 0 {
+            return Err("window_days debe ser mayor a 0".to_string());
+        }
+        validate_ladder(&self.ladder)
+    }
+}
+
+/// Carga la política activa o la política por defecto si no hay ninguna
+pub async fn load_active_policy(pool: &PgPool) -> Result<StrikePolicy, String> {
+    let row = sqlx::query_as::<_, (Uuid, String, i32, serde_json::Value)>(
+        "SELECT id, name, window_days, ladder FROM strike_policies WHERE is_active = TRUE LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let Some((id, name, window_days, ladder)) = row else {
+        return Ok(StrikePolicy::default());
+    };
+
+    let ladder: Vec<StrikeLevel> = serde_json::from_value(ladder)
+        .map_err(|e| format!("Escalera de strikes inválida: {}", e))?;
+
+    Ok(StrikePolicy { id: Some(id), name, window_days, ladder })
+}
+
+// ============================================================================
+// STRIKES PERSISTIDOS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Strike {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub attendance_log_id: Option<Uuid>,
+    pub policy_id: Option<Uuid>,
+    pub level: i32,
+    pub reason: String,
+    pub status: String,
+    pub consequences: serde_json::Value,
+    pub xp_burned: i64,
+    pub penalty_payout_id: Option<Uuid>,
+    pub issued_at: DateTime<Utc>,
+    pub voided_at: Option<DateTime<Utc>>,
+    pub voided_by: Option<Uuid>,
+    pub void_reason: Option<String>,
+}
+
+const STRIKE_COLUMNS: &str = "id, user_id, attendance_log_id, policy_id, level, reason, status, \
+     consequences, xp_burned, penalty_payout_id, issued_at, voided_at, voided_by, void_reason";
+
+/// Cuenta strikes no anulados del usuario dentro de la ventana de la política
+pub async fn count_active_strikes<'e, E: PgExecutor<'e>>(
+    user_id: Uuid,
+    at: DateTime<Utc>,
+    window_days: i32,
+    executor: E,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM strikes
+        WHERE user_id = $1
+          AND status <> 'VOIDED'
+          AND issued_at > $2 - make_interval(days => $3)
+          AND issued_at <= $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(at)
+    .bind(window_days)
+    .fetch_one(executor)
+    .await
+}
+
+fn strike_db_error(context: &str) -> impl Fn(sqlx::Error) -> (StatusCode, String) + '_ {
+    move |e| {
+        tracing::error!("DB error {}: {}", context, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+/// Emite un strike por tardanza según la política activa y aplica sus consecuencias.
+/// Todo (quema de XP, multa, strike y ajustes) va en una transacción: o se aplica completo o nada.
+pub async fn apply_strike(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+    attendance_log_id: Option<Uuid>,
+    issued_at: DateTime<Utc>,
+) -> Result<Strike, (StatusCode, String)> {
+    let pool = &state.db;
+    let policy = load_active_policy(pool).await.map_err(|e| {
+        tracing::error!("Error cargando política de strikes: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e)
+    })?;
+
+    let mut tx = pool.begin().await.map_err(strike_db_error("starting strike"))?;
+
+    // Serializa los strikes de la misma modelo: dos tardanzas simultáneas no pueden leer el mismo nivel
+    sqlx::query("SELECT 1 FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(strike_db_error("locking user"))?;
+
+    let previous = count_active_strikes(user_id, issued_at, policy.window_days, &mut *tx)
+        .await
+        .map_err(strike_db_error("counting strikes"))?;
+
+    let level = policy
+        .level_for(previous + 1)
+        .cloned()
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Política de strikes sin niveles".to_string()))?;
+
+    let mut xp_burned = 0_i64;
+    if let Some(reason) = &level.xp_burn_reason {
+        let burn = gamification::burn_xp(user_id, reason, &mut tx).await.map_err(|e| {
+            tracing::error!("XP burn failed for strike {}: {}", level.level, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e)
+        })?;
+        xp_burned = burn.xp_loss;
+    }
+
+    // Multas se crean antes del strike (la referencia se guarda en la fila);
+    // los factores de pago se registran como ajustes ligados al strike.
+    let mut penalty_payout_id = None;
+    let mut pending_adjustments = Vec::new();
+    // Día local del estudio: un strike a las 21:00 en Bogotá es de ese día, no del siguiente en UTC
+    let work_date = super::leave::studio_date(issued_at);
+    for consequence in &level.consequences {
+        match consequence {
+            StrikeConsequence::HalfPayToday => {
+                pending_adjustments.push((AdjustmentKind::DayPayFactor, 0.5, consequence.describe()));
+            }
+            StrikeConsequence::DowngradeWeek { factor } => {
+                pending_adjustments.push((AdjustmentKind::WeekPayFactor, *factor, consequence.describe()));
+            }
+            StrikeConsequence::Fine { amount_cop } => {
+                let note = format!("Multa por strike nivel {} (política {})", level.level, policy.name);
+                let id = finance::penalties::create_penalty(user_id, *amount_cop, &note, &mut tx)
+                    .await
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("No se pudo crear multa: {}", e)))?;
+                penalty_payout_id = Some(id);
+            }
+        }
+    }
+
+    let strike = sqlx::query_as::<_, Strike>(&format!(
+        r#"
+        INSERT INTO strikes (user_id, attendance_log_id, policy_id, level, consequences, xp_burned, penalty_payout_id, issued_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING {STRIKE_COLUMNS}
+        "#
+    ))
+    .bind(user_id)
+    .bind(attendance_log_id)
+    .bind(policy.id)
+    .bind(level.level)
+    .bind(serde_json::to_value(&level.consequences).unwrap_or_default())
+    .bind(xp_burned)
+    .bind(penalty_payout_id)
+    .bind(issued_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(strike_db_error("inserting strike"))?;
+
+    for (kind, factor, description) in pending_adjustments {
+        let adjustment = NewAdjustment {
+            user_id,
+            strike_id: Some(strike.id),
+            kind,
+            work_date,
+            factor: Some(factor),
+            amount_cop: None,
+            reason: format!("Strike {}: {}", strike.level, description),
+        };
+        if let Err(e) = finance::adjustments::create_adjustment(adjustment, &mut tx).await {
+            tracing::error!("No se pudo registrar ajuste de nómina del strike {}: {}", strike.id, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e));
+        }
+    }
+
+    tx.commit().await.map_err(strike_db_error("committing strike"))?;
+
+    tracing::info!("Strike {} aplicado a {} (política {})", strike.level, user_id, policy.name);
+    let event = DomainEvent::StrikeApplied { user_id, strike_id: strike.id, level: strike.level };
+    notifications::events::publish(&state.nats, &event).await;
+    Ok(strike)
+}
+
+async fn fetch_strike(strike_id: Uuid, pool: &PgPool) -> Result<Strike, (StatusCode, String)> {
+    sqlx::query_as::<_, Strike>(&format!("SELECT {STRIKE_COLUMNS} FROM strikes WHERE id = $1"))
+        .bind(strike_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Strike no encontrado".to_string()))
+}
+
+/// Anula un strike y revierte sus efectos (XP quemado, multa y ajustes de nómina).
+pub async fn void_strike(
+    state: &Arc<AppState>,
+    strike_id: Uuid,
+    voided_by: Uuid,
+    reason: &str,
+) -> Result<Strike, (StatusCode, String)> {
+    let mut tx = state.db.begin().await.map_err(strike_db_error("starting void"))?;
+    let voided = void_strike_in(&mut tx, strike_id, voided_by, reason).await?;
+    tx.commit().await.map_err(strike_db_error("committing void"))?;
+    tracing::info!("Strike {} anulado por {}: {}", strike_id, voided_by, reason);
+    Ok(voided)
+}
+
+/// Anulación dentro de la transacción del llamador. El `voided_at IS NULL` hace que solo
+/// una de dos anulaciones concurrentes gane, así que el XP se restaura una sola vez.
+async fn void_strike_in(
+    conn: &mut PgConnection,
+    strike_id: Uuid,
+    voided_by: Uuid,
+    reason: &str,
+) -> Result<Strike, (StatusCode, String)> {
+    let voided = sqlx::query_as::<_, Strike>(&format!(
+        r#"
+        UPDATE strikes
+        SET status = 'VOIDED', voided_at = NOW(), voided_by = $2, void_reason = $3
+        WHERE id = $1 AND voided_at IS NULL
+        RETURNING {STRIKE_COLUMNS}
+        "#
+    ))
+    .bind(strike_id)
+    .bind(voided_by)
+    .bind(reason)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(strike_db_error("voiding strike"))?;
+
+    let Some(voided) = voided else {
+        let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM strikes WHERE id = $1)")
+            .bind(strike_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(strike_db_error("fetching strike"))?;
+        return Err(if exists {
+            (StatusCode::CONFLICT, "El strike ya fue anulado".to_string())
+        } else {
+            (StatusCode::NOT_FOUND, "Strike no encontrado".to_string())
+        });
+    };
+
+    let internal = |e: String| (StatusCode::INTERNAL_SERVER_ERROR, e);
+    gamification::restore_burned_xp(voided.user_id, voided.xp_burned, "STRIKE_VOIDED", conn)
+        .await
+        .map_err(internal)?;
+
+    if let Some(payout_id) = voided.penalty_payout_id {
+        finance::penalties::void_penalty(payout_id, "strike anulado", conn).await.map_err(internal)?;
+    }
+
+    let reversed = finance::adjustments::reverse_strike_adjustments(strike_id, conn).await.map_err(internal)?;
+    if reversed > 0 {
+        tracing::info!("{} ajuste(s) de nómina revertidos por strike {}", reversed, strike_id);
+    }
+
+    // Cerrar cualquier apelación abierta sobre este strike
+    sqlx::query(
+        r#"
+        UPDATE strike_appeals
+        SET status = 'APPROVED', reviewed_by = $2, reviewed_at = NOW(), resolution_note = $3
+        WHERE strike_id = $1 AND status = 'PENDING'
+        "#,
+    )
+    .bind(strike_id)
+    .bind(voided_by)
+    .bind(reason)
+    .execute(&mut *conn)
+    .await
+    .map_err(strike_db_error("closing appeals"))?;
+
+    Ok(voided)
+}
+
+// ============================================================================
+// ENDPOINTS
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct StrikeHistoryResponse {
+    pub user_id: Uuid,
+    pub window_days: i32,
+    pub active_in_window: i64,
+    pub strikes: Vec<Strike>,
+}
+
+/// GET /api/attendance/strikes/:id (id = user_id)
+/// Historial de strikes de una modelo (la propia modelo o staff)
+pub async fn strike_history_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<StrikeHistoryResponse>, (StatusCode, String)> {
+    if parse_user_id(&auth.user_id)? != user_id && !is_staff(&auth.role) {
+        return Err((StatusCode::FORBIDDEN, "No puedes ver strikes de otra modelo".to_string()));
+    }
+
+    let pool = &state.db;
+    let policy = load_active_policy(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let strikes = sqlx::query_as::<_, Strike>(&format!(
+        "SELECT {STRIKE_COLUMNS} FROM strikes WHERE user_id = $1 ORDER BY issued_at DESC"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("DB error fetching strikes: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let active_in_window = count_active_strikes(user_id, Utc::now(), policy.window_days, pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(StrikeHistoryResponse {
+        user_id,
+        window_days: policy.window_days,
+        active_in_window,
+        strikes,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppealRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct StrikeAppeal {
+    pub id: Uuid,
+    pub strike_id: Uuid,
+    pub user_id: Uuid,
+    pub reason: String,
+    pub status: String,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// POST /api/attendance/strikes/:id/appeal (id = strike_id)
+/// La modelo apela uno de sus strikes activos
+pub async fn appeal_strike_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(strike_id): Path<Uuid>,
+    Json(req): Json<AppealRequest>,
+) -> Result<(StatusCode, Json<StrikeAppeal>), (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let pool = &state.db;
+
+    if req.reason.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "reason is required".to_string()));
+    }
+
+    let strike = fetch_strike(strike_id, pool).await?;
+    if strike.user_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "Solo puedes apelar tus propios strikes".to_string()));
+    }
+    if strike.status != "ACTIVE" {
+        return Err((StatusCode::CONFLICT, format!("El strike no es apelable (estado {})", strike.status)));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let appeal = sqlx::query_as::<_, StrikeAppeal>(
+        r#"
+        INSERT INTO strike_appeals (strike_id, user_id, reason)
+        VALUES ($1, $2, $3)
+        RETURNING id, strike_id, user_id, reason, status, reviewed_by, reviewed_at, resolution_note, created_at
+        "#,
+    )
+    .bind(strike_id)
+    .bind(user_id)
+    .bind(req.reason.trim())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("DB error creating appeal: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    sqlx::query("UPDATE strikes SET status = 'APPEALED' WHERE id = $1")
+        .bind(strike_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok((StatusCode::CREATED, Json(appeal)))
+}
+
+/// GET /api/admin/strikes/appeals
+/// Cola de apelaciones pendientes
+pub async fn pending_appeals_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+) -> Result<Json<Vec<StrikeAppeal>>, (StatusCode, String)> {
+    let appeals = sqlx::query_as::<_, StrikeAppeal>(
+        r#"
+        SELECT id, strike_id, user_id, reason, status, reviewed_by, reviewed_at, resolution_note, created_at
+        FROM strike_appeals
+        WHERE status = 'PENDING'
+        ORDER BY created_at ASC
+        "#,
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("DB error fetching appeals: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    Ok(Json(appeals))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveAppealRequest {
+    pub approve: bool,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolveAppealResponse {
+    pub appeal_id: Uuid,
+    pub status: String,
+    pub strike: Strike,
+}
+
+/// POST /api/admin/strikes/appeals/:appeal_id/resolve
+/// Un moderador aprueba (anula el strike y revierte efectos) o rechaza la apelación
+pub async fn resolve_appeal_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Path(appeal_id): Path<Uuid>,
+    Json(req): Json<ResolveAppealRequest>,
+) -> Result<Json<ResolveAppealResponse>, (StatusCode, String)> {
+    let moderator_id = parse_user_id(&moderator.user_id)?;
+    let status = if req.approve { "APPROVED" } else { "REJECTED" };
+
+    // Resolver la apelación y anular el strike son una sola operación: si la anulación falla
+    // la apelación sigue PENDING, y el guard de estado impide que dos moderadores la resuelvan a la vez
+    let mut tx = state.db.begin().await.map_err(strike_db_error("starting appeal resolution"))?;
+    let strike_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE strike_appeals
+        SET status = $2, reviewed_by = $3, reviewed_at = NOW(), resolution_note = $4
+        WHERE id = $1 AND status = 'PENDING'
+        RETURNING strike_id
+        "#,
+    )
+    .bind(appeal_id)
+    .bind(status)
+    .bind(moderator_id)
+    .bind(&req.note)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(strike_db_error("resolving appeal"))?;
+
+    let Some(strike_id) = strike_id else {
+        let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM strike_appeals WHERE id = $1)")
+            .bind(appeal_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(strike_db_error("fetching appeal"))?;
+        return Err(if exists {
+            (StatusCode::CONFLICT, "La apelación ya fue resuelta".to_string())
+        } else {
+            (StatusCode::NOT_FOUND, "Apelación no encontrada".to_string())
+        });
+    };
+
+    let strike = if req.approve {
+        let reason = req.note.clone().unwrap_or_else(|| "Apelación aprobada".to_string());
+        void_strike_in(&mut tx, strike_id, moderator_id, &reason).await?
+    } else {
+        sqlx::query_as::<_, Strike>(&format!(
+            r#"
+            UPDATE strikes SET status = CASE WHEN status = 'APPEALED' THEN 'ACTIVE' ELSE status END
+            WHERE id = $1
+            RETURNING {STRIKE_COLUMNS}
+            "#
+        ))
+        .bind(strike_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(strike_db_error("restoring strike"))?
+    };
+    tx.commit().await.map_err(strike_db_error("committing appeal resolution"))?;
+    tracing::info!("Apelación {} {} por {}", appeal_id, status, moderator_id);
+
+    Ok(Json(ResolveAppealResponse {
+        appeal_id,
+        status: status.to_string(),
+        strike,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VoidStrikeRequest {
+    pub reason: String,
+}
+
+/// POST /api/admin/strikes/:strike_id/void
+/// Anulación directa por un moderador (sin apelación previa)
+pub async fn void_strike_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Path(strike_id): Path<Uuid>,
+    Json(req): Json<VoidStrikeRequest>,
+) -> Result<Json<Strike>, (StatusCode, String)> {
+    let moderator_id = parse_user_id(&moderator.user_id)?;
+
+    if req.reason.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "reason is required".to_string()));
+    }
+
+    let strike = void_strike(&state, strike_id, moderator_id, req.reason.trim()).await?;
+    Ok(Json(strike))
+}
+
+/// GET /api/admin/strikes/policy
+pub async fn get_policy_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+) -> Result<Json<StrikePolicy>, (StatusCode, String)> {
+    let policy = load_active_policy(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(policy))
+}
+
+/// PUT /api/admin/strikes/policy
+/// Reemplaza la política activa (la anterior queda en histórico)
+pub async fn update_policy_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminOnly,
+    Json(mut policy): Json<StrikePolicy>,
+) -> Result<Json<StrikePolicy>, (StatusCode, String)> {
+    policy.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let admin_id = Uuid::parse_str(&admin.user_id).ok();
+
+    let mut tx = state.db.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    sqlx::query("UPDATE strike_policies SET is_active = FALSE WHERE is_active = TRUE")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO strike_policies (name, window_days, ladder, is_active, created_by)
+        VALUES ($1, $2, $3, TRUE, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(&policy.name)
+    .bind(policy.window_days)
+    .bind(serde_json::to_value(&policy.ladder).unwrap_or_default())
+    .bind(admin_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("DB error saving strike policy: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    policy.id = Some(id);
+    tracing::info!("Nueva política de strikes '{}' activada por {}", policy.name, admin.email);
+    Ok(Json(policy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_for_escalates_and_caps_at_last_step() {
+        let policy = StrikePolicy::default();
+        assert!(policy.level_for(0).is_none());
+        assert_eq!(policy.level_for(1).unwrap().level, 1);
+        assert_eq!(policy.level_for(2).unwrap().level, 2);
+        assert_eq!(policy.level_for(3).unwrap().level, 3);
+        assert_eq!(policy.level_for(7).unwrap().level, 3);
+    }
+
+    #[test]
+    fn validate_rejects_gaps_and_bad_factors() {
+        assert!(StrikePolicy::default().validate().is_ok());
+
+        let mut gap = StrikePolicy::default();
+        gap.ladder.remove(1);
+        assert!(gap.validate().is_err());
+
+        let mut bad_factor = StrikePolicy::default();
+        bad_factor.ladder[1].consequences = vec![StrikeConsequence::DowngradeWeek { factor: 1.5 }];
+        assert!(bad_factor.validate().is_err());
+    }
+
+    #[test]
+    fn consequences_use_tagged_json() {
+        let json = serde_json::json!([
+            {"type": "HALF_PAY_TODAY"},
+            {"type": "FINE", "amount_cop": 1000000.0}
+        ]);
+        let parsed: Vec<StrikeConsequence> = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed[0], StrikeConsequence::HalfPayToday);
+        assert_eq!(parsed[1], StrikeConsequence::Fine { amount_cop: 1_000_000.0 });
+    }
+}
@@ -0,0 +1,649 @@
+/// Registro del estudio: sedes, rooms, activos (cámaras, iluminación,
+/// computadores) y tickets de mantenimiento.
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Uuid, FromRow, PgPool};
+
+use crate::{
+    middleware::auth::{AdminOnly, AuthenticatedUser, ModeratorOnly},
+    state::AppState,
+};
+
+use super::{is_staff, parse_user_id};
+
+pub const STATUS_ACTIVE: &str = "ACTIVE";
+pub const STATUS_MAINTENANCE: &str = "MAINTENANCE";
+pub const STATUS_RETIRED: &str = "RETIRED";
+
+const ASSET_KINDS: &[&str] = &["CAMERA", "LIGHTING", "COMPUTER"];
+const PRIORITIES: &[&str] = &["LOW", "MEDIUM", "HIGH"];
+
+fn normalize_status(raw: &str) -> Result<String, (StatusCode, String)> {
+    let status = raw.trim().to_uppercase();
+    match status.as_str() {
+        STATUS_ACTIVE | STATUS_MAINTENANCE | STATUS_RETIRED => Ok(status),
+        _ => Err((StatusCode::BAD_REQUEST, format!("Estado inválido: {}", raw))),
+    }
+}
+
+fn normalize_in(raw: &str, allowed: &[&str], label: &str) -> Result<String, (StatusCode, String)> {
+    let value = raw.trim().to_uppercase();
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err((StatusCode::BAD_REQUEST, format!("{} inválido: {}", label, raw)))
+    }
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("DB error in studio registry: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+// ============================================================================
+// ESTUDIOS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Studio {
+    pub id: Uuid,
+    pub name: String,
+    pub address: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateStudioRequest {
+    pub name: String,
+    pub address: Option<String>,
+}
+
+/// GET /api/admin/studios
+pub async fn list_studios_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+) -> Result<Json<Vec<Studio>>, (StatusCode, String)> {
+    let rows = sqlx::query_as::<_, Studio>(
+        "SELECT id, name, address, is_active, created_at FROM studios ORDER BY name",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(rows))
+}
+
+/// POST /api/admin/studios
+pub async fn create_studio_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Json(req): Json<CreateStudioRequest>,
+) -> Result<(StatusCode, Json<Studio>), (StatusCode, String)> {
+    if req.name.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "El nombre es obligatorio".to_string()));
+    }
+    let studio = sqlx::query_as::<_, Studio>(
+        r#"
+        INSERT INTO studios (name, address)
+        VALUES ($1, $2)
+        RETURNING id, name, address, is_active, created_at
+        "#,
+    )
+    .bind(req.name.trim())
+    .bind(&req.address)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok((StatusCode::CREATED, Json(studio)))
+}
+
+// ============================================================================
+// ROOMS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Room {
+    pub id: i32,
+    pub studio_id: Uuid,
+    pub name: String,
+    pub capacity: i32,
+    pub status: String,
+    pub notes: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const ROOM_COLUMNS: &str = "id, studio_id, name, capacity, status, notes, updated_at";
+
+pub async fn fetch_room(room_id: i32, pool: &PgPool) -> Result<Room, (StatusCode, String)> {
+    sqlx::query_as::<_, Room>(&format!("SELECT {ROOM_COLUMNS} FROM rooms WHERE id = $1"))
+        .bind(room_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Room no encontrado".to_string()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoomListQuery {
+    pub studio_id: Option<Uuid>,
+}
+
+/// GET /api/studio/rooms
+pub async fn list_rooms_handler(
+    State(state): State<Arc<AppState>>,
+    _auth: AuthenticatedUser,
+    Query(query): Query<RoomListQuery>,
+) -> Result<Json<Vec<Room>>, (StatusCode, String)> {
+    let rows = sqlx::query_as::<_, Room>(&format!(
+        "SELECT {ROOM_COLUMNS} FROM rooms WHERE ($1::UUID IS NULL OR studio_id = $1) ORDER BY id"
+    ))
+    .bind(query.studio_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(rows))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRoomRequest {
+    pub studio_id: Uuid,
+    pub name: String,
+    pub capacity: Option<i32>,
+    pub notes: Option<String>,
+}
+
+/// POST /api/admin/studio/rooms
+pub async fn create_room_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Json(req): Json<CreateRoomRequest>,
+) -> Result<(StatusCode, Json<Room>), (StatusCode, String)> {
+    let capacity = req.capacity.unwrap_or(3);
+    if req.name.trim().is_empty() || capacity <= 0 {
+        return Err((StatusCode::BAD_REQUEST, "Nombre y capacidad positiva son obligatorios".to_string()));
+    }
+    let room = sqlx::query_as::<_, Room>(&format!(
+        r#"
+        INSERT INTO rooms (studio_id, name, capacity, notes)
+        VALUES ($1, $2, $3, $4)
+        RETURNING {ROOM_COLUMNS}
+        "#
+    ))
+    .bind(req.studio_id)
+    .bind(req.name.trim())
+    .bind(capacity)
+    .bind(&req.notes)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok((StatusCode::CREATED, Json(room)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoomRequest {
+    pub name: Option<String>,
+    pub capacity: Option<i32>,
+    /// ACTIVE | MAINTENANCE | RETIRED
+    pub status: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// PATCH /api/admin/studio/rooms/:id
+pub async fn update_room_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Path(room_id): Path<i32>,
+    Json(req): Json<UpdateRoomRequest>,
+) -> Result<Json<Room>, (StatusCode, String)> {
+    let status = req.status.as_deref().map(normalize_status).transpose()?;
+    if matches!(req.capacity, Some(c) if c <= 0) {
+        return Err((StatusCode::BAD_REQUEST, "La capacidad debe ser positiva".to_string()));
+    }
+    let room = sqlx::query_as::<_, Room>(&format!(
+        r#"
+        UPDATE rooms
+        SET name = COALESCE($2, name),
+            capacity = COALESCE($3, capacity),
+            status = COALESCE($4, status),
+            notes = COALESCE($5, notes)
+        WHERE id = $1
+        RETURNING {ROOM_COLUMNS}
+        "#
+    ))
+    .bind(room_id)
+    .bind(req.name.as_deref().map(str::trim))
+    .bind(req.capacity)
+    .bind(status)
+    .bind(&req.notes)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Room no encontrado".to_string()))?;
+    Ok(Json(room))
+}
+
+// ============================================================================
+// ACTIVOS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct StudioAsset {
+    pub id: Uuid,
+    pub studio_id: Uuid,
+    pub room_id: Option<i32>,
+    pub kind: String,
+    pub name: String,
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub camera_id: Option<i32>,
+    pub status: String,
+    pub assigned_at: Option<DateTime<Utc>>,
+    pub purchased_at: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+const ASSET_COLUMNS: &str = "id, studio_id, room_id, kind, name, brand, model, serial_number, camera_id, \
+     status, assigned_at, purchased_at, notes";
+
+#[derive(Debug, Deserialize)]
+pub struct AssetListQuery {
+    pub room_id: Option<i32>,
+    pub kind: Option<String>,
+    pub status: Option<String>,
+}
+
+/// GET /api/admin/studio/assets
+pub async fn list_assets_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Query(query): Query<AssetListQuery>,
+) -> Result<Json<Vec<StudioAsset>>, (StatusCode, String)> {
+    let rows = sqlx::query_as::<_, StudioAsset>(&format!(
+        r#"
+        SELECT {ASSET_COLUMNS}
+        FROM studio_assets
+        WHERE ($1::INT IS NULL OR room_id = $1)
+          AND ($2::TEXT IS NULL OR kind = $2)
+          AND ($3::TEXT IS NULL OR status = $3)
+        ORDER BY kind, name
+        "#
+    ))
+    .bind(query.room_id)
+    .bind(query.kind.map(|k| k.to_uppercase()))
+    .bind(query.status.map(|s| s.to_uppercase()))
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(rows))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAssetRequest {
+    pub studio_id: Uuid,
+    /// CAMERA | LIGHTING | COMPUTER
+    pub kind: String,
+    pub name: String,
+    pub room_id: Option<i32>,
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub camera_id: Option<i32>,
+    pub purchased_at: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+/// POST /api/admin/studio/assets
+pub async fn create_asset_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Json(req): Json<CreateAssetRequest>,
+) -> Result<(StatusCode, Json<StudioAsset>), (StatusCode, String)> {
+    let kind = normalize_in(&req.kind, ASSET_KINDS, "Tipo de activo")?;
+    if req.camera_id.is_some() && kind != "CAMERA" {
+        return Err((StatusCode::BAD_REQUEST, "camera_id solo aplica a cámaras".to_string()));
+    }
+    if let Some(room_id) = req.room_id {
+        ensure_same_studio(&fetch_room(room_id, &state.db).await?, req.studio_id)?;
+    }
+    let asset = sqlx::query_as::<_, StudioAsset>(&format!(
+        r#"
+        INSERT INTO studio_assets (studio_id, room_id, kind, name, brand, model, serial_number, camera_id,
+                                   assigned_at, purchased_at, notes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CASE WHEN $2::INT IS NULL THEN NULL ELSE NOW() END, $9, $10)
+        RETURNING {ASSET_COLUMNS}
+        "#
+    ))
+    .bind(req.studio_id)
+    .bind(req.room_id)
+    .bind(&kind)
+    .bind(req.name.trim())
+    .bind(&req.brand)
+    .bind(&req.model)
+    .bind(&req.serial_number)
+    .bind(req.camera_id)
+    .bind(req.purchased_at)
+    .bind(&req.notes)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok((StatusCode::CREATED, Json(asset)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateAssetRequest {
+    pub name: Option<String>,
+    pub status: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// PATCH /api/admin/studio/assets/:id
+pub async fn update_asset_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Path(asset_id): Path<Uuid>,
+    Json(req): Json<UpdateAssetRequest>,
+) -> Result<Json<StudioAsset>, (StatusCode, String)> {
+    let status = req.status.as_deref().map(normalize_status).transpose()?;
+    let asset = sqlx::query_as::<_, StudioAsset>(&format!(
+        r#"
+        UPDATE studio_assets
+        SET name = COALESCE($2, name),
+            status = COALESCE($3, status),
+            notes = COALESCE($4, notes)
+        WHERE id = $1
+        RETURNING {ASSET_COLUMNS}
+        "#
+    ))
+    .bind(asset_id)
+    .bind(req.name.as_deref().map(str::trim))
+    .bind(status)
+    .bind(&req.notes)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Activo no encontrado".to_string()))?;
+    Ok(Json(asset))
+}
+
+/// Un activo solo puede estar en rooms de su propia sede
+fn ensure_same_studio(room: &Room, asset_studio: Uuid) -> Result<(), (StatusCode, String)> {
+    if room.studio_id != asset_studio {
+        return Err((StatusCode::CONFLICT, "El activo y el room pertenecen a sedes distintas".to_string()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignAssetRequest {
+    /// `null` devuelve el activo a bodega
+    pub room_id: Option<i32>,
+}
+
+/// POST /api/admin/studio/assets/:id/assign
+pub async fn assign_asset_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Path(asset_id): Path<Uuid>,
+    Json(req): Json<AssignAssetRequest>,
+) -> Result<Json<StudioAsset>, (StatusCode, String)> {
+    if let Some(room_id) = req.room_id {
+        let room = fetch_room(room_id, &state.db).await?;
+        if room.status == STATUS_RETIRED {
+            return Err((StatusCode::CONFLICT, "No se pueden asignar activos a un room retirado".to_string()));
+        }
+        let asset_studio = sqlx::query_scalar::<_, Uuid>("SELECT studio_id FROM studio_assets WHERE id = $1")
+            .bind(asset_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(db_error)?
+            .ok_or((StatusCode::NOT_FOUND, "Activo no encontrado o retirado".to_string()))?;
+        ensure_same_studio(&room, asset_studio)?;
+    }
+
+    let asset = sqlx::query_as::<_, StudioAsset>(&format!(
+        r#"
+        UPDATE studio_assets
+        SET room_id = $2,
+            assigned_at = CASE WHEN $2::INT IS NULL THEN NULL ELSE NOW() END
+        WHERE id = $1 AND status <> 'RETIRED'
+        RETURNING {ASSET_COLUMNS}
+        "#
+    ))
+    .bind(asset_id)
+    .bind(req.room_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Activo no encontrado o retirado".to_string()))?;
+
+    tracing::info!("Activo {} ({}) asignado a room {:?}", asset.name, asset.kind, asset.room_id);
+    Ok(Json(asset))
+}
+
+// ============================================================================
+// MANTENIMIENTO
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct MaintenanceTicket {
+    pub id: Uuid,
+    pub asset_id: Option<Uuid>,
+    pub room_id: Option<i32>,
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: String,
+    pub blocks_room: bool,
+    pub status: String,
+    pub opened_by: Option<Uuid>,
+    pub assigned_to: Option<Uuid>,
+    pub resolution: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+const TICKET_COLUMNS: &str = "id, asset_id, room_id, title, description, priority, blocks_room, status, \
+     opened_by, assigned_to, resolution, resolved_at, created_at";
+
+#[derive(Debug, Deserialize)]
+pub struct OpenTicketRequest {
+    pub asset_id: Option<Uuid>,
+    pub room_id: Option<i32>,
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub blocks_room: bool,
+}
+
+/// POST /api/studio/maintenance
+/// Cualquier usuario puede reportar una falla y el activo pasa a MAINTENANCE;
+/// bloquear el room (`blocks_room`) lo saca del roster, así que solo lo puede hacer un moderador.
+pub async fn open_ticket_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Json(req): Json<OpenTicketRequest>,
+) -> Result<(StatusCode, Json<MaintenanceTicket>), (StatusCode, String)> {
+    let opened_by = parse_user_id(&auth.user_id)?;
+    if req.blocks_room && !is_staff(&auth.role) {
+        return Err((StatusCode::FORBIDDEN, "Solo un moderador puede bloquear un room".to_string()));
+    }
+    if req.asset_id.is_none() && req.room_id.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Indica el activo o el room afectado".to_string()));
+    }
+    if req.title.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "El título es obligatorio".to_string()));
+    }
+    let priority = normalize_in(req.priority.as_deref().unwrap_or("MEDIUM"), PRIORITIES, "Prioridad")?;
+
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+
+    // Si solo viene el activo, el ticket hereda su room
+    let room_id = match (req.room_id, req.asset_id) {
+        (Some(room_id), _) => Some(room_id),
+        (None, Some(asset_id)) => sqlx::query_scalar::<_, Option<i32>>("SELECT room_id FROM studio_assets WHERE id = $1")
+            .bind(asset_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .ok_or((StatusCode::NOT_FOUND, "Activo no encontrado".to_string()))?,
+        (None, None) => None,
+    };
+
+    let ticket = sqlx::query_as::<_, MaintenanceTicket>(&format!(
+        r#"
+        INSERT INTO maintenance_tickets (asset_id, room_id, title, description, priority, blocks_room, opened_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {TICKET_COLUMNS}
+        "#
+    ))
+    .bind(req.asset_id)
+    .bind(room_id)
+    .bind(req.title.trim())
+    .bind(&req.description)
+    .bind(&priority)
+    .bind(req.blocks_room)
+    .bind(opened_by)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    if let Some(asset_id) = ticket.asset_id {
+        sqlx::query("UPDATE studio_assets SET status = 'MAINTENANCE' WHERE id = $1 AND status = 'ACTIVE'")
+            .bind(asset_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    }
+    if let (true, Some(room_id)) = (ticket.blocks_room, ticket.room_id) {
+        sqlx::query("UPDATE rooms SET status = 'MAINTENANCE' WHERE id = $1 AND status = 'ACTIVE'")
+            .bind(room_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    }
+
+    tx.commit().await.map_err(db_error)?;
+    tracing::warn!("🔧 Ticket de mantenimiento {} abierto: {}", ticket.id, ticket.title);
+    Ok((StatusCode::CREATED, Json(ticket)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TicketListQuery {
+    /// Por defecto, tickets no resueltos
+    pub status: Option<String>,
+    pub room_id: Option<i32>,
+}
+
+/// GET /api/admin/studio/maintenance
+pub async fn list_tickets_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Query(query): Query<TicketListQuery>,
+) -> Result<Json<Vec<MaintenanceTicket>>, (StatusCode, String)> {
+    let statuses: Vec<String> = match query.status {
+        Some(s) => vec![s.to_uppercase()],
+        None => vec!["OPEN".to_string(), "IN_PROGRESS".to_string()],
+    };
+    let rows = sqlx::query_as::<_, MaintenanceTicket>(&format!(
+        r#"
+        SELECT {TICKET_COLUMNS}
+        FROM maintenance_tickets
+        WHERE status = ANY($1) AND ($2::INT IS NULL OR room_id = $2)
+        ORDER BY CASE priority WHEN 'HIGH' THEN 0 WHEN 'MEDIUM' THEN 1 ELSE 2 END, created_at
+        "#
+    ))
+    .bind(&statuses)
+    .bind(query.room_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(rows))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTicketRequest {
+    /// OPEN | IN_PROGRESS | RESOLVED
+    pub status: Option<String>,
+    pub assigned_to: Option<Uuid>,
+    pub resolution: Option<String>,
+}
+
+/// PATCH /api/admin/studio/maintenance/:id
+/// Al resolver, el activo/room vuelve a ACTIVE si no quedan otros tickets abiertos.
+pub async fn update_ticket_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Path(ticket_id): Path<Uuid>,
+    Json(req): Json<UpdateTicketRequest>,
+) -> Result<Json<MaintenanceTicket>, (StatusCode, String)> {
+    let status = req
+        .status
+        .as_deref()
+        .map(|s| normalize_in(s, &["OPEN", "IN_PROGRESS", "RESOLVED"], "Estado"))
+        .transpose()?;
+
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+
+    let ticket = sqlx::query_as::<_, MaintenanceTicket>(&format!(
+        r#"
+        UPDATE maintenance_tickets
+        SET status = COALESCE($2, status),
+            assigned_to = COALESCE($3, assigned_to),
+            resolution = COALESCE($4, resolution),
+            resolved_at = CASE WHEN $2 = 'RESOLVED' THEN NOW() WHEN $2 IS NULL THEN resolved_at ELSE NULL END
+        WHERE id = $1
+        RETURNING {TICKET_COLUMNS}
+        "#
+    ))
+    .bind(ticket_id)
+    .bind(&status)
+    .bind(req.assigned_to)
+    .bind(&req.resolution)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Ticket no encontrado".to_string()))?;
+
+    if ticket.status == "RESOLVED" {
+        if let Some(asset_id) = ticket.asset_id {
+            sqlx::query(
+                r#"
+                UPDATE studio_assets SET status = 'ACTIVE'
+                WHERE id = $1 AND status = 'MAINTENANCE'
+                  AND NOT EXISTS (SELECT 1 FROM maintenance_tickets WHERE asset_id = $1 AND status <> 'RESOLVED')
+                "#,
+            )
+            .bind(asset_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        }
+        if let (true, Some(room_id)) = (ticket.blocks_room, ticket.room_id) {
+            sqlx::query(
+                r#"
+                UPDATE rooms SET status = 'ACTIVE'
+                WHERE id = $1 AND status = 'MAINTENANCE'
+                  AND NOT EXISTS (
+                      SELECT 1 FROM maintenance_tickets
+                      WHERE room_id = $1 AND blocks_room = TRUE AND status <> 'RESOLVED'
+                  )
+                "#,
+            )
+            .bind(room_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        }
+    }
+
+    tx.commit().await.map_err(db_error)?;
+    Ok(Json(ticket))
+}
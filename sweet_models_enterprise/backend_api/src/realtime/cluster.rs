@@ -0,0 +1,128 @@
+use futures_util::StreamExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use super::hub::{RealtimeEvent, RealtimeHub, Topic};
+
+/// Subject donde todas las réplicas intercambian los eventos del hub
+pub const HUB_SUBJECT: &str = "realtime.hub.events";
+
+/// Sobre común de todo lo que viaja entre nodos; `node_id` evita procesar el propio eco
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub node_id: String,
+    pub payload: T,
+}
+
+/// Identidad de esta réplica dentro del cluster
+#[derive(Clone)]
+pub struct ClusterNode {
+    pub id: String,
+    pub nats: async_nats::Client,
+}
+
+impl ClusterNode {
+    /// Usa `NODE_ID` si está definido (útil en logs); si no, un id aleatorio por proceso
+    pub fn new(nats: async_nats::Client) -> Self {
+        let id = std::env::var("NODE_ID")
+            .ok()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        Self::with_id(id, nats)
+    }
+
+    pub fn with_id(id: impl Into<String>, nats: async_nats::Client) -> Self {
+        ClusterNode { id: id.into(), nats }
+    }
+
+    pub fn encode<T: Serialize>(&self, payload: &T) -> Vec<u8> {
+        serde_json::to_vec(&Envelope { node_id: self.id.clone(), payload }).unwrap_or_default()
+    }
+
+    /// `Ok(None)` si el mensaje lo publicó este mismo nodo
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<Option<T>, serde_json::Error> {
+        open_envelope(&self.id, bytes)
+    }
+}
+
+fn open_envelope<T: DeserializeOwned>(own_id: &str, bytes: &[u8]) -> Result<Option<T>, serde_json::Error> {
+    let envelope: Envelope<T> = serde_json::from_slice(bytes)?;
+    if envelope.node_id == own_id {
+        return Ok(None);
+    }
+    Ok(Some(envelope.payload))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HubFrame {
+    topics: Vec<Topic>,
+    event: RealtimeEvent,
+}
+
+/// Conecta el hub local con los demás nodos.
+/// Lo publicado localmente sale a NATS; lo que llega de otros nodos se entrega solo
+/// a los suscriptores locales (`deliver_remote`), así un evento nunca rebota.
+pub async fn bridge_hub(hub: Arc<RealtimeHub>, node: ClusterNode) -> Result<JoinHandle<()>, async_nats::SubscribeError> {
+    let mut inbound = node.nats.subscribe(HUB_SUBJECT).await?;
+    let (outbound_tx, mut outbound) = mpsc::unbounded_channel::<(Vec<Topic>, RealtimeEvent)>();
+    if !hub.attach_cluster(outbound_tx) {
+        tracing::warn!("Realtime hub already bridged, ignoring second bridge");
+    }
+    tracing::info!("🛰️ Realtime hub bridged to NATS as node {}", node.id);
+
+    Ok(tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                Some((topics, event)) = outbound.recv() => {
+                    let bytes = node.encode(&HubFrame { topics, event });
+                    if let Err(e) = node.nats.publish(HUB_SUBJECT, bytes.into()).await {
+                        tracing::warn!("Failed to publish realtime event to cluster: {}", e);
+                    }
+                }
+                message = inbound.next() => {
+                    let Some(message) = message else {
+                        tracing::warn!("Realtime cluster subscription closed");
+                        break;
+                    };
+                    match node.decode::<HubFrame>(&message.payload) {
+                        Ok(Some(frame)) => {
+                            hub.deliver_remote(&frame.topics, frame.event);
+                        }
+                        Ok(None) => {}
+                        Err(e) => tracing::warn!("Invalid realtime cluster frame: {}", e),
+                    }
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn own_frames_are_dropped_and_foreign_ones_decoded() {
+        let bytes = serde_json::to_vec(&Envelope { node_id: "a".to_string(), payload: "hola".to_string() }).unwrap();
+        assert_eq!(open_envelope::<String>("a", &bytes).unwrap(), None);
+        assert_eq!(open_envelope::<String>("b", &bytes).unwrap().as_deref(), Some("hola"));
+        assert!(open_envelope::<String>("b", b"not json").is_err());
+    }
+
+    #[test]
+    fn hub_frames_keep_their_topics() {
+        let frame = HubFrame {
+            topics: vec![Topic::Room("3".to_string()), Topic::User("abc".to_string())],
+            event: RealtimeEvent {
+                event_type: "ROOM_UPDATE".to_string(),
+                room_id: "3".to_string(),
+                data: serde_json::json!({}),
+                timestamp: 1,
+            },
+        };
+        let bytes = serde_json::to_vec(&Envelope { node_id: "a".to_string(), payload: &frame }).unwrap();
+        let decoded: HubFrame = open_envelope("b", &bytes).unwrap().unwrap();
+        assert_eq!(decoded.topics, frame.topics);
+    }
+}
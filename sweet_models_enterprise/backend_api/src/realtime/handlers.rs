@@ -1,12 +1,120 @@
 use axum::{
-    extract::{ws::{WebSocket, WebSocketUpgrade}, State},
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, State},
     response::IntoResponse,
     http::StatusCode,
 };
-use futures_util::{SinkExt, StreamExt};
+use chrono::Datelike;
+use futures_util::{stream::SplitSink, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
-use crate::state::AppState;
+use tokio::{sync::{broadcast::error::RecvError, mpsc}, task::JoinHandle};
+
+use super::hub::{RealtimeEvent, RealtimeHub, Topic};
 use crate::middleware::auth::AuthenticatedUser;
+use crate::operations::is_staff;
+use crate::state::AppState;
+
+/// Tópicos simultáneos por conexión
+const MAX_SUBSCRIPTIONS: usize = 50;
+/// Mensajes pendientes de escribir al socket antes de frenar a los suscriptores
+const OUTBOX_CAPACITY: usize = 256;
+
+/// Comandos que envía el cliente por el WebSocket
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientCommand {
+    Subscribe { topic: String },
+    Unsubscribe { topic: String },
+    Ping,
+}
+
+/// Mensajes del servidor. `EVENT` mantiene los campos del evento en la raíz
+/// (event_type, room_id, data, timestamp) para los dashboards existentes.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ServerMessage {
+    Event {
+        topic: Topic,
+        #[serde(flatten)]
+        event: RealtimeEvent,
+    },
+    Subscribed { topic: Topic, snapshot: Vec<RealtimeEvent> },
+    Unsubscribed { topic: Topic },
+    /// El suscriptor se rezagó: se perdieron `missed` eventos y se reenvía el estado actual
+    Resync { topic: Topic, missed: u64, snapshot: Vec<RealtimeEvent> },
+    Error { topic: Option<String>, error: String },
+    Pong,
+}
+
+fn is_admin(role: &str) -> bool {
+    matches!(role.to_uppercase().as_str(), "ADMIN" | "SUPER_ADMIN")
+}
+
+/// Autorización por tópico: cada quien ve lo suyo; staff ve cualquier room; admin todo.
+/// Una modelo solo puede escuchar el room al que está asignada esta semana.
+async fn authorize(state: &AppState, user: &AuthenticatedUser, topic: &Topic) -> Result<(), String> {
+    if is_admin(&user.role) {
+        return Ok(());
+    }
+    let allowed = match topic {
+        Topic::Admin => false,
+        Topic::User(id) => id.eq_ignore_ascii_case(&user.user_id),
+        Topic::Role(role) => role.eq_ignore_ascii_case(&user.role),
+        Topic::Room(_) if is_staff(&user.role) => true,
+        Topic::Room(room) => {
+            let Ok(user_id) = uuid::Uuid::parse_str(&user.user_id) else {
+                return Err("Invalid user id in token".to_string());
+            };
+            let today = chrono::Utc::now().date_naive();
+            let week_id = format!("{}-W{:02}", today.iso_week().year(), today.iso_week().week());
+            sqlx::query_scalar::<_, bool>(
+                r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM user_shifts us
+                    JOIN rooms r ON r.id = us.assigned_room
+                    WHERE us.user_id = $1 AND us.week_id = $2
+                      AND (r.id::TEXT = $3 OR LOWER(r.name) = LOWER($3))
+                )
+                "#,
+            )
+            .bind(user_id)
+            .bind(&week_id)
+            .bind(room)
+            .fetch_one(&state.db)
+            .await
+            .map_err(|e| {
+                tracing::error!("DB error authorizing realtime topic: {}", e);
+                "Authorization check failed".to_string()
+            })?
+        }
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err("Not allowed to subscribe to this topic".to_string())
+    }
+}
+
+/// Reenvía un tópico al buzón de la conexión; si se rezaga manda RESYNC en vez de perder en silencio
+fn spawn_forwarder(hub: Arc<RealtimeHub>, topic: Topic, outbox: mpsc::Sender<ServerMessage>) -> JoinHandle<()> {
+    let mut rx = hub.subscribe(&topic);
+    tokio::spawn(async move {
+        loop {
+            let message = match rx.recv().await {
+                Ok(event) => ServerMessage::Event { topic: topic.clone(), event },
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("Realtime subscriber lagged {} events on {}", missed, topic);
+                    ServerMessage::Resync { topic: topic.clone(), missed, snapshot: hub.snapshot(&topic) }
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if outbox.send(message).await.is_err() {
+                break;
+            }
+        }
+    })
+}
 
 /// Manejador para WebSocket en GET /ws/dashboard
 /// Requiere autenticación (cualquier usuario).
@@ -15,48 +123,148 @@ pub async fn ws_dashboard_handler(
     State(state): State<Arc<AppState>>,
     auth: AuthenticatedUser, // Requiere token JWT válido
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let hub = state.realtime_hub.clone();
-    Ok(ws.on_upgrade(move |socket| handle_socket(socket, hub, auth.user_id)))
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, auth)))
+}
+
+/// Suscripciones de una conexión
+struct Session {
+    state: Arc<AppState>,
+    user: AuthenticatedUser,
+    outbox: mpsc::Sender<ServerMessage>,
+    subscriptions: HashMap<Topic, JoinHandle<()>>,
+}
+
+impl Session {
+    async fn subscribe(&mut self, raw: &str) -> ServerMessage {
+        let Some(topic) = Topic::parse(raw) else {
+            return ServerMessage::Error { topic: Some(raw.to_string()), error: "Invalid topic".to_string() };
+        };
+        if self.subscriptions.contains_key(&topic) {
+            return ServerMessage::Subscribed { snapshot: self.state.realtime_hub.snapshot(&topic), topic };
+        }
+        if self.subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            return ServerMessage::Error { topic: Some(topic.to_string()), error: "Too many subscriptions".to_string() };
+        }
+        if let Err(error) = authorize(&self.state, &self.user, &topic).await {
+            tracing::warn!("User {} denied realtime topic {}", self.user.user_id, topic);
+            return ServerMessage::Error { topic: Some(topic.to_string()), error };
+        }
+
+        let hub = self.state.realtime_hub.clone();
+        let handle = spawn_forwarder(hub.clone(), topic.clone(), self.outbox.clone());
+        self.subscriptions.insert(topic.clone(), handle);
+        ServerMessage::Subscribed { snapshot: hub.snapshot(&topic), topic }
+    }
+
+    fn unsubscribe(&mut self, raw: &str) -> ServerMessage {
+        let Some(topic) = Topic::parse(raw) else {
+            return ServerMessage::Error { topic: Some(raw.to_string()), error: "Invalid topic".to_string() };
+        };
+        if let Some(handle) = self.subscriptions.remove(&topic) {
+            handle.abort();
+        }
+        ServerMessage::Unsubscribed { topic }
+    }
+
+    async fn handle_command(&mut self, text: &str) -> ServerMessage {
+        match serde_json::from_str::<ClientCommand>(text) {
+            Ok(ClientCommand::Subscribe { topic }) => self.subscribe(&topic).await,
+            Ok(ClientCommand::Unsubscribe { topic }) => self.unsubscribe(&topic),
+            Ok(ClientCommand::Ping) => ServerMessage::Pong,
+            Err(e) => ServerMessage::Error { topic: None, error: format!("Invalid command: {}", e) },
+        }
+    }
 }
 
-/// Maneja la conexión WebSocket activa
-async fn handle_socket(
-    socket: WebSocket,
-    hub: Arc<crate::realtime::RealtimeHub>,
-    user_id: String,
-) {
+impl Drop for Session {
+    fn drop(&mut self) {
+        for (_, handle) in self.subscriptions.drain() {
+            handle.abort();
+        }
+    }
+}
+
+async fn send_message(sender: &mut SplitSink<WebSocket, Message>, message: &ServerMessage) -> bool {
+    let text = serde_json::to_string(message).unwrap_or_default();
+    sender.send(Message::Text(text)).await.is_ok()
+}
+
+/// Maneja la conexión WebSocket activa.
+/// Las respuestas a comandos se escriben directo al socket; el buzón solo lleva eventos
+/// de los tópicos, así un buzón lleno nunca bloquea el procesamiento de comandos.
+async fn handle_socket(socket: WebSocket, state: Arc<AppState>, user: AuthenticatedUser) {
     let (mut sender, mut receiver) = socket.split();
-    
-    // Suscribirse al canal de broadcast
-    let mut rx = hub.subscribe();
-    
-    // Tarea 1: Recibir mensajes del cliente (opcional ping/pong)
-    let user_id_clone = user_id.clone();
-    tokio::spawn(async move {
-        while let Some(Ok(msg)) = receiver.next().await {
-            match msg {
-                axum::extract::ws::Message::Text(text) => {
-                    tracing::debug!("User {} sent: {}", user_id_clone, text);
-                    // Opcional: procesar comandos del cliente
+    let (outbox, mut inbox) = mpsc::channel::<ServerMessage>(OUTBOX_CAPACITY);
+    let user_id = user.user_id.clone();
+
+    // Suscripciones por defecto: su usuario, su rol y (si es admin) todo
+    let mut defaults = vec![format!("user:{}", user.user_id), format!("role:{}", user.role)];
+    if is_admin(&user.role) {
+        defaults.push("admin".to_string());
+    }
+    let mut session = Session { state, user, outbox, subscriptions: HashMap::new() };
+    for topic in defaults {
+        let reply = session.subscribe(&topic).await;
+        if !send_message(&mut sender, &reply).await {
+            return;
+        }
+    }
+
+    tracing::info!("User {} connected to WebSocket dashboard", user_id);
+    loop {
+        tokio::select! {
+            incoming = receiver.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    let reply = session.handle_command(&text).await;
+                    if !send_message(&mut sender, &reply).await {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => {
+                    tracing::info!("User {} disconnected", user_id);
+                    break;
                 }
-                axum::extract::ws::Message::Close(_) => {
-                    tracing::info!("User {} disconnected", user_id_clone);
+                Some(Ok(_)) => {}
+            },
+            Some(message) = inbox.recv() => {
+                if !send_message(&mut sender, &message).await {
+                    tracing::info!("User {} socket closed", user_id);
                     break;
                 }
-                _ => {}
             }
         }
-    });
+    }
+}
 
-    // Tarea 2: Enviar eventos del canal al cliente
-    tracing::info!("User {} connected to WebSocket dashboard", user_id);
-    while let Ok(event) = rx.recv().await {
-        let msg = axum::extract::ws::Message::Text(
-            serde_json::to_string(&event).unwrap_or_default()
-        );
-        if sender.send(msg).await.is_err() {
-            tracing::info!("User {} socket closed", user_id);
-            break;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_commands_parse() {
+        assert!(matches!(
+            serde_json::from_str::<ClientCommand>(r#"{"action":"subscribe","topic":"room:3"}"#),
+            Ok(ClientCommand::Subscribe { topic }) if topic == "room:3"
+        ));
+        assert!(matches!(serde_json::from_str::<ClientCommand>(r#"{"action":"ping"}"#), Ok(ClientCommand::Ping)));
+        assert!(serde_json::from_str::<ClientCommand>(r#"{"action":"explode"}"#).is_err());
+    }
+
+    #[test]
+    fn events_keep_their_fields_at_the_root() {
+        let message = ServerMessage::Event {
+            topic: Topic::Room("3".to_string()),
+            event: RealtimeEvent {
+                event_type: "ROOM_UPDATE".to_string(),
+                room_id: "3".to_string(),
+                data: serde_json::json!({"new_total": 10}),
+                timestamp: 1,
+            },
+        };
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["type"], "EVENT");
+        assert_eq!(json["topic"], "room:3");
+        assert_eq!(json["event_type"], "ROOM_UPDATE");
+        assert_eq!(json["data"]["new_total"], 10);
     }
 }
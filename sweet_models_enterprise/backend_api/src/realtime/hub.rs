@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast;
-use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Eventos recientes que se guardan por tópico para resincronizar suscriptores
+const SNAPSHOT_LIMIT: usize = 256;
 
 /// Evento que se difunde a través del canal realtime
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,28 +16,181 @@ pub struct RealtimeEvent {
     pub timestamp: i64,
 }
 
-/// Hub central de difusión (Broadcast Channel)
-/// Todos los clientes WebSocket se suscriben a este canal.
+/// Tópico de suscripción: `room:<id>`, `user:<uuid>`, `role:<ROLE>` o `admin`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Topic {
+    /// Todo lo que pasa por el hub (solo administradores)
+    Admin,
+    Room(String),
+    User(String),
+    Role(String),
+}
+
+impl Topic {
+    pub fn parse(raw: &str) -> Option<Topic> {
+        let raw = raw.trim();
+        if raw.eq_ignore_ascii_case("admin") {
+            return Some(Topic::Admin);
+        }
+        let (kind, id) = raw.split_once(':')?;
+        let id = id.trim();
+        if id.is_empty() {
+            return None;
+        }
+        match kind.to_lowercase().as_str() {
+            "room" => Some(Topic::Room(id.to_string())),
+            "user" => Some(Topic::User(id.to_lowercase())),
+            "role" => Some(Topic::Role(id.to_uppercase())),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topic::Admin => write!(f, "admin"),
+            Topic::Room(id) => write!(f, "room:{}", id),
+            Topic::User(id) => write!(f, "user:{}", id),
+            Topic::Role(role) => write!(f, "role:{}", role),
+        }
+    }
+}
+
+impl TryFrom<String> for Topic {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Topic::parse(&value).ok_or_else(|| format!("Invalid topic '{}'", value))
+    }
+}
+
+impl From<Topic> for String {
+    fn from(topic: Topic) -> Self {
+        topic.to_string()
+    }
+}
+
+/// Hub central de difusión por tópicos.
+/// Cada tópico tiene su propio canal broadcast, así un room con mucho tráfico no hace
+/// perder eventos a quien solo escucha sus notificaciones. Además se guarda el último
+/// evento de cada tipo por tópico para mandar un snapshot al suscribirse o al rezagarse.
+///
+/// Con varias réplicas, `realtime::cluster::bridge_hub` engancha un enlace de salida:
+/// lo publicado aquí se reenvía a NATS y lo que llega de otros nodos entra por
+/// `deliver_remote`, que nunca vuelve a salir del nodo.
 pub struct RealtimeHub {
-    /// Transmisor broadcast: cualquier component puede enviar eventos
-    pub tx: broadcast::Sender<RealtimeEvent>,
+    capacity: usize,
+    channels: Mutex<HashMap<Topic, broadcast::Sender<RealtimeEvent>>>,
+    snapshots: Mutex<HashMap<Topic, BTreeMap<String, RealtimeEvent>>>,
+    cluster: OnceLock<mpsc::UnboundedSender<(Vec<Topic>, RealtimeEvent)>>,
 }
 
 impl RealtimeHub {
-    /// Crea un nuevo Hub con capacidad inicial
+    /// Crea un nuevo Hub; `capacity` es el buffer de cada tópico
     pub fn new(capacity: usize) -> Self {
-        let (tx, _) = broadcast::channel(capacity);
-        RealtimeHub { tx }
+        RealtimeHub {
+            capacity,
+            channels: Mutex::new(HashMap::new()),
+            snapshots: Mutex::new(HashMap::new()),
+            cluster: OnceLock::new(),
+        }
+    }
+
+    /// Engancha el enlace hacia el cluster. Solo se puede hacer una vez por hub.
+    pub fn attach_cluster(&self, outbound: mpsc::UnboundedSender<(Vec<Topic>, RealtimeEvent)>) -> bool {
+        self.cluster.set(outbound).is_ok()
+    }
+
+    /// Obtiene un receptor para un tópico (lo crea si no existe)
+    pub fn subscribe(&self, topic: &Topic) -> broadcast::Receiver<RealtimeEvent> {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        channels
+            .entry(topic.clone())
+            .or_insert_with(|| broadcast::channel(self.capacity).0)
+            .subscribe()
+    }
+
+    /// Publica un evento en su room y en el canal de administradores
+    /// (usado por endpoints de telemetría). Devuelve cuántos receptores lo recibieron.
+    pub fn publish(&self, event: RealtimeEvent) -> usize {
+        let topics: Vec<Topic> = if event.room_id.is_empty() {
+            Vec::new()
+        } else {
+            vec![Topic::Room(event.room_id.clone())]
+        };
+        self.publish_to(&topics, event)
     }
 
-    /// Obtiene un receptor para suscribirse a eventos
-    pub fn subscribe(&self) -> broadcast::Receiver<RealtimeEvent> {
-        self.tx.subscribe()
+    /// Publica en tópicos explícitos; el canal `admin` siempre recibe copia.
+    /// Si hay cluster, el evento también se reenvía a los demás nodos.
+    pub fn publish_to(&self, topics: &[Topic], event: RealtimeEvent) -> usize {
+        if let Some(outbound) = self.cluster.get() {
+            if outbound.send((topics.to_vec(), event.clone())).is_err() {
+                tracing::warn!("Realtime cluster link closed, event {} stays local", event.event_type);
+            }
+        }
+        self.deliver_local(topics, event)
     }
 
-    /// Publica un evento al canal (usado por endpoints de telemetría)
-    pub fn publish(&self, event: RealtimeEvent) -> Result<usize, broadcast::error::SendError<RealtimeEvent>> {
-        self.tx.send(event)
+    /// Entrega un evento que llegó de otro nodo: solo a los suscriptores locales
+    pub fn deliver_remote(&self, topics: &[Topic], event: RealtimeEvent) -> usize {
+        self.deliver_local(topics, event)
+    }
+
+    fn deliver_local(&self, topics: &[Topic], event: RealtimeEvent) -> usize {
+        let mut targets: Vec<Topic> = topics.to_vec();
+        targets.push(Topic::Admin);
+        targets.sort();
+        targets.dedup();
+
+        self.remember(&targets, &event);
+
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        let mut delivered = 0;
+        for topic in &targets {
+            let Some(tx) = channels.get(topic) else {
+                continue;
+            };
+            match tx.send(event.clone()) {
+                Ok(receivers) => delivered += receivers,
+                // Nadie escucha: se libera el canal
+                Err(_) => {
+                    channels.remove(topic);
+                }
+            }
+        }
+        delivered
+    }
+
+    /// Último evento de cada tipo (y room) publicado en el tópico, en orden cronológico
+    pub fn snapshot(&self, topic: &Topic) -> Vec<RealtimeEvent> {
+        let snapshots = self.snapshots.lock().unwrap_or_else(|e| e.into_inner());
+        let mut events: Vec<RealtimeEvent> = snapshots
+            .get(topic)
+            .map(|latest| latest.values().cloned().collect())
+            .unwrap_or_default();
+        events.sort_by_key(|e| e.timestamp);
+        events
+    }
+
+    fn remember(&self, topics: &[Topic], event: &RealtimeEvent) {
+        let key = format!("{}:{}", event.event_type, event.room_id);
+        let mut snapshots = self.snapshots.lock().unwrap_or_else(|e| e.into_inner());
+        for topic in topics {
+            let latest = snapshots.entry(topic.clone()).or_default();
+            if latest.len() >= SNAPSHOT_LIMIT && !latest.contains_key(&key) {
+                let oldest = latest
+                    .iter()
+                    .min_by_key(|(_, e)| e.timestamp)
+                    .map(|(k, _)| k.clone());
+                if let Some(oldest) = oldest {
+                    latest.remove(&oldest);
+                }
+            }
+            latest.insert(key.clone(), event.clone());
+        }
     }
 
     /// Envía un evento ROOM_UPDATE (caso de uso principal)
@@ -41,7 +199,7 @@ impl RealtimeHub {
         room_id: String,
         new_total: f64,
         members: Vec<serde_json::Value>,
-    ) -> Result<usize, broadcast::error::SendError<RealtimeEvent>> {
+    ) -> usize {
         let event = RealtimeEvent {
             event_type: "ROOM_UPDATE".to_string(),
             room_id,
@@ -57,5 +215,83 @@ impl RealtimeHub {
 
 /// Crea una instancia global del Hub
 pub fn create_global_hub() -> Arc<RealtimeHub> {
-    Arc::new(RealtimeHub::new(128)) // Capacidad de 128 eventos en buffer
+    Arc::new(RealtimeHub::new(128)) // Capacidad de 128 eventos en buffer por tópico
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+    fn event(room_id: &str, event_type: &str, timestamp: i64) -> RealtimeEvent {
+        RealtimeEvent {
+            event_type: event_type.to_string(),
+            room_id: room_id.to_string(),
+            data: serde_json::json!({}),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn topics_round_trip_through_strings() {
+        assert_eq!(Topic::parse("room:3"), Some(Topic::Room("3".to_string())));
+        assert_eq!(Topic::parse("ROLE:moderator"), Some(Topic::Role("MODERATOR".to_string())));
+        assert_eq!(Topic::parse("admin"), Some(Topic::Admin));
+        assert_eq!(Topic::parse("room:"), None);
+        assert_eq!(Topic::parse("planet:1"), None);
+        let topic = Topic::User("abc".to_string());
+        assert_eq!(Topic::parse(&topic.to_string()), Some(topic));
+    }
+
+    #[tokio::test]
+    async fn room_events_only_reach_that_room_and_admin() {
+        let hub = RealtimeHub::new(8);
+        let mut room_a = hub.subscribe(&Topic::Room("a".to_string()));
+        let mut admin = hub.subscribe(&Topic::Admin);
+
+        hub.publish(event("b", "TELEMETRY_UPDATE", 1));
+        hub.publish(event("a", "TELEMETRY_UPDATE", 2));
+
+        assert_eq!(room_a.recv().await.unwrap().room_id, "a");
+        assert!(matches!(room_a.try_recv(), Err(TryRecvError::Empty)));
+        assert_eq!(admin.recv().await.unwrap().room_id, "b");
+        assert_eq!(admin.recv().await.unwrap().room_id, "a");
+    }
+
+    #[tokio::test]
+    async fn lagging_subscriber_can_resync_from_snapshot() {
+        let hub = RealtimeHub::new(2);
+        let topic = Topic::Room("a".to_string());
+        let mut rx = hub.subscribe(&topic);
+
+        for ts in 0..5 {
+            hub.publish(event("a", "TELEMETRY_UPDATE", ts));
+        }
+        hub.publish(event("a", "ROOM_UPDATE", 5));
+
+        assert!(matches!(rx.recv().await, Err(RecvError::Lagged(_))));
+        let snapshot = hub.snapshot(&topic);
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].timestamp, 4);
+        assert_eq!(snapshot[1].event_type, "ROOM_UPDATE");
+    }
+
+    #[tokio::test]
+    async fn only_local_publishes_are_forwarded_to_the_cluster() {
+        let hub = RealtimeHub::new(8);
+        let (tx, mut outbound) = mpsc::unbounded_channel();
+        assert!(hub.attach_cluster(tx.clone()));
+        assert!(!hub.attach_cluster(tx));
+        let mut room = hub.subscribe(&Topic::Room("a".to_string()));
+
+        hub.publish(event("a", "TELEMETRY_UPDATE", 1));
+        hub.deliver_remote(&[Topic::Room("a".to_string())], event("a", "TELEMETRY_UPDATE", 2));
+
+        let (topics, forwarded) = outbound.recv().await.unwrap();
+        assert_eq!(topics, vec![Topic::Room("a".to_string())]);
+        assert_eq!(forwarded.timestamp, 1);
+        assert!(outbound.try_recv().is_err());
+        assert_eq!(room.recv().await.unwrap().timestamp, 1);
+        assert_eq!(room.recv().await.unwrap().timestamp, 2);
+    }
 }
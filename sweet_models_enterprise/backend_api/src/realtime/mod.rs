@@ -1,5 +1,7 @@
 pub mod hub;
 pub mod handlers;
+pub mod cluster;
 
-pub use hub::RealtimeHub;
+pub use hub::{RealtimeEvent, RealtimeHub, Topic};
 pub use handlers::ws_dashboard_handler;
+pub use cluster::{bridge_hub, ClusterNode};
@@ -0,0 +1,805 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::{presence, ChatState};
+use crate::middleware::auth::AuthenticatedUser;
+use crate::operations::parse_user_id;
+use crate::state::AppState;
+
+type HmacSha1 = Hmac<Sha1>;
+type ApiError = (StatusCode, String);
+
+/// Segundos que suena una llamada antes de darse por perdida
+pub const RINGING_TIMEOUT_SECS: i64 = 45;
+/// Duración máxima de una llamada contestada; pasado esto el sweeper la corta
+pub const MAX_CALL_DURATION_SECS: i64 = 4 * 3600;
+/// Vida por defecto de las credenciales TURN
+const DEFAULT_TURN_TTL_SECS: i64 = 3600;
+const DEFAULT_STUN_URLS: &str = "stun:stun.l.google.com:19302";
+const DEFAULT_LOG_PAGE: i64 = 50;
+const MAX_LOG_PAGE: i64 = 200;
+
+const CALL_COLUMNS: &str =
+    "id, caller_id, callee_id, media, state, end_reason, ended_by, created_at, answered_at, ended_at";
+
+// ============================================================================
+// MÁQUINA DE ESTADOS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CallState {
+    Ringing,
+    Accepted,
+    Declined,
+    Missed,
+    Ended,
+}
+
+impl CallState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallState::Ringing => "RINGING",
+            CallState::Accepted => "ACCEPTED",
+            CallState::Declined => "DECLINED",
+            CallState::Missed => "MISSED",
+            CallState::Ended => "ENDED",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<CallState> {
+        match raw {
+            "RINGING" => Some(CallState::Ringing),
+            "ACCEPTED" => Some(CallState::Accepted),
+            "DECLINED" => Some(CallState::Declined),
+            "MISSED" => Some(CallState::Missed),
+            "ENDED" => Some(CallState::Ended),
+            _ => None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self, CallState::Ringing | CallState::Accepted)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallAction {
+    Accept,
+    Decline,
+    Hangup,
+    Timeout,
+    /// La parte perdió todos sus sockets (o dejó de mandar heartbeat)
+    Disconnect,
+    /// La llamada superó `MAX_CALL_DURATION_SECS`
+    Expire,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallRole {
+    Caller,
+    Callee,
+}
+
+/// Nuevo estado y motivo de cierre (si la llamada termina)
+pub fn transition(
+    state: CallState,
+    action: CallAction,
+    role: CallRole,
+) -> Result<(CallState, Option<&'static str>), &'static str> {
+    use CallAction::*;
+    use CallRole::*;
+    match (state, action, role) {
+        (CallState::Ringing, Accept, Callee) => Ok((CallState::Accepted, None)),
+        (CallState::Ringing, Decline, Callee) | (CallState::Ringing, Hangup, Callee) => {
+            Ok((CallState::Declined, Some("DECLINED")))
+        }
+        (CallState::Ringing, Hangup, Caller) => Ok((CallState::Missed, Some("CANCELLED"))),
+        (CallState::Ringing, Timeout, _) => Ok((CallState::Missed, Some("TIMEOUT"))),
+        (CallState::Ringing, Disconnect | Expire, _) => Err("Call not answered yet"),
+        (CallState::Ringing, _, Caller) => Err("Only the callee can answer a call"),
+        (CallState::Accepted, Hangup, _) => Ok((CallState::Ended, Some("HANGUP"))),
+        (CallState::Accepted, Disconnect, _) => Ok((CallState::Ended, Some("DISCONNECT"))),
+        (CallState::Accepted, Expire, _) => Ok((CallState::Ended, Some("MAX_DURATION"))),
+        (CallState::Accepted, _, _) => Err("Call already answered"),
+        _ => Err("Call already finished"),
+    }
+}
+
+/// Cómo cerrar una llamada en curso que quedó colgada: por duración máxima, o por la parte
+/// sin presencia viva (`online = None` si Redis no respondió; solo aplica la duración).
+/// Las recién contestadas tienen un TTL de presencia de gracia.
+fn stale_accepted_action(
+    call: &CallSession,
+    now: DateTime<Utc>,
+    online: Option<&BTreeMap<Uuid, Vec<String>>>,
+) -> Option<(CallAction, Option<Uuid>)> {
+    let answered_at = call.answered_at.unwrap_or(call.created_at);
+    if now - answered_at > chrono::Duration::seconds(MAX_CALL_DURATION_SECS) {
+        return Some((CallAction::Expire, None));
+    }
+    if now - answered_at <= chrono::Duration::seconds(presence::PRESENCE_TTL_SECS) {
+        return None;
+    }
+    let online = online?;
+    [call.caller_id, call.callee_id]
+        .into_iter()
+        .find(|party| !online.contains_key(party))
+        .map(|party| (CallAction::Disconnect, Some(party)))
+}
+
+/// Sonando por más del tiempo límite (p. ej. si el nodo que lo vigilaba se cayó)
+fn ringing_expired(created_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    now - created_at > chrono::Duration::seconds(RINGING_TIMEOUT_SECS)
+}
+
+// ============================================================================
+// TIPOS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CallSession {
+    pub id: Uuid,
+    pub caller_id: Uuid,
+    pub callee_id: Uuid,
+    pub media: String,
+    pub state: String,
+    pub end_reason: Option<String>,
+    pub ended_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub answered_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl CallSession {
+    fn role_of(&self, user_id: Uuid) -> Option<CallRole> {
+        if user_id == self.caller_id {
+            Some(CallRole::Caller)
+        } else if user_id == self.callee_id {
+            Some(CallRole::Callee)
+        } else {
+            None
+        }
+    }
+
+    fn current_state(&self) -> CallState {
+        CallState::parse(&self.state).unwrap_or(CallState::Ended)
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct CallLogEntry {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub call: CallSession,
+    /// OUTGOING o INCOMING desde el punto de vista de quien consulta
+    pub direction: String,
+    pub peer_id: Uuid,
+    pub duration_secs: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct IceServer {
+    pub urls: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CallResponse {
+    pub call: CallSession,
+    pub ice_servers: Vec<IceServer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartCallRequest {
+    pub callee_id: Uuid,
+    #[serde(default)]
+    pub video: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallLogQuery {
+    /// Llamadas anteriores a esta fecha (paginación hacia atrás)
+    pub before: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+/// Eventos de llamada que llegan por el socket de chat (a todos los dispositivos)
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CallEvent {
+    CallIncoming { call: CallSession, ice_servers: Vec<IceServer> },
+    CallState { call: CallSession },
+}
+
+// ============================================================================
+// TURN
+// ============================================================================
+
+/// Credenciales efímeras estilo coturn (`use-auth-secret`):
+/// usuario = `<expira_unix>:<user_id>`, clave = base64(HMAC-SHA1(secreto, usuario))
+pub fn turn_credentials(secret: &str, user_id: Uuid, now: i64, ttl_secs: i64) -> (String, String) {
+    let username = format!("{}:{}", now + ttl_secs, user_id);
+    let mut mac = HmacSha1::new_from_slice(secret.as_bytes()).expect("HMAC acepta llaves de cualquier tamaño");
+    mac.update(username.as_bytes());
+    let credential = base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes());
+    (username, credential)
+}
+
+fn split_urls(raw: &str) -> Vec<String> {
+    raw.split(',').map(str::trim).filter(|u| !u.is_empty()).map(str::to_string).collect()
+}
+
+/// STUN siempre; TURN solo si `TURN_URLS` y `TURN_SECRET` están configurados
+pub fn ice_servers_for(user_id: Uuid) -> Vec<IceServer> {
+    let stun = split_urls(&std::env::var("STUN_URLS").unwrap_or_else(|_| DEFAULT_STUN_URLS.to_string()));
+    let mut servers = Vec::new();
+    if !stun.is_empty() {
+        servers.push(IceServer { urls: stun, username: None, credential: None });
+    }
+
+    let turn_urls = split_urls(&std::env::var("TURN_URLS").unwrap_or_default());
+    let secret = std::env::var("TURN_SECRET").unwrap_or_default();
+    if turn_urls.is_empty() || secret.is_empty() {
+        return servers;
+    }
+    let ttl = std::env::var("TURN_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|ttl| *ttl > 0)
+        .unwrap_or(DEFAULT_TURN_TTL_SECS);
+    let (username, credential) = turn_credentials(&secret, user_id, Utc::now().timestamp(), ttl);
+    servers.push(IceServer { urls: turn_urls, username: Some(username), credential: Some(credential) });
+    servers
+}
+
+// ============================================================================
+// SERVICIO
+// ============================================================================
+
+fn db_error(context: &str, e: sqlx::Error) -> ApiError {
+    tracing::error!("DB error {}: {}", context, e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+async fn push_event(chat: &ChatState, user_id: Uuid, event: &CallEvent) -> bool {
+    let payload = serde_json::to_string(event).unwrap_or_default();
+    chat.peers.send_to(user_id, payload).await
+}
+
+async fn display_name(chat: &ChatState, user_id: Uuid) -> String {
+    sqlx::query_scalar::<_, String>("SELECT COALESCE(display_name, username, email) FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&chat.db)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "Alguien".to_string())
+}
+
+/// Aplica una acción sobre la llamada con la fila bloqueada y avisa a ambas partes.
+/// `actor = None` es el sistema (timeout de timbre).
+async fn apply_action(chat: &ChatState, call_id: Uuid, actor: Option<Uuid>, action: CallAction) -> Result<CallSession, ApiError> {
+    let mut tx = chat.db.begin().await.map_err(|e| db_error("starting call transaction", e))?;
+    let call = sqlx::query_as::<_, CallSession>(&format!("SELECT {} FROM call_sessions WHERE id = $1 FOR UPDATE", CALL_COLUMNS))
+        .bind(call_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| db_error("loading call", e))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Call not found".to_string()))?;
+
+    let role = match actor {
+        Some(user_id) => call
+            .role_of(user_id)
+            .ok_or_else(|| (StatusCode::NOT_FOUND, "Call not found".to_string()))?,
+        None => CallRole::Caller,
+    };
+    // Una llamada que sonó de más ya es perdida, aunque nadie la haya marcado todavía
+    let action = if call.current_state() == CallState::Ringing && ringing_expired(call.created_at, Utc::now()) {
+        CallAction::Timeout
+    } else {
+        action
+    };
+    let (next, end_reason) =
+        transition(call.current_state(), action, role).map_err(|e| (StatusCode::CONFLICT, e.to_string()))?;
+
+    let updated = sqlx::query_as::<_, CallSession>(&format!(
+        r#"
+        UPDATE call_sessions SET
+            state = $2,
+            end_reason = $3,
+            ended_by = $4,
+            answered_at = CASE WHEN $2 = 'ACCEPTED' THEN NOW() ELSE answered_at END,
+            ended_at = CASE WHEN $2 IN ('DECLINED', 'MISSED', 'ENDED') THEN NOW() ELSE ended_at END
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        CALL_COLUMNS
+    ))
+    .bind(call_id)
+    .bind(next.as_str())
+    .bind(end_reason)
+    .bind(if end_reason.is_some() { actor } else { None })
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| db_error("updating call", e))?;
+    tx.commit().await.map_err(|e| db_error("committing call", e))?;
+
+    tracing::info!("📞 Call {} {} -> {}", call_id, call.state, updated.state);
+    let event = CallEvent::CallState { call: updated.clone() };
+    push_event(chat, updated.caller_id, &event).await;
+    push_event(chat, updated.callee_id, &event).await;
+
+    if next == CallState::Missed {
+        notify_missed(chat, &updated).await;
+    }
+    if action == CallAction::Timeout && actor.is_some() {
+        return Err((StatusCode::CONFLICT, "Call is no longer ringing".to_string()));
+    }
+    Ok(updated)
+}
+
+async fn notify_missed(chat: &ChatState, call: &CallSession) {
+    let Some(notifier) = chat.notifier.clone() else {
+        return;
+    };
+    let caller_name = display_name(chat, call.caller_id).await;
+    let (call_id, caller_id, callee_id) = (call.id, call.caller_id, call.callee_id);
+    tokio::spawn(async move {
+        if let Err(e) = notifier.notify_missed_call(call_id, caller_id, caller_name, callee_id).await {
+            tracing::warn!("Missed call push for {} failed: {}", call_id, e);
+        }
+    });
+}
+
+/// Vigila el timbre: si nadie contestó a tiempo la llamada pasa a MISSED
+fn spawn_ringing_timeout(chat: Arc<ChatState>, call_id: Uuid) {
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(RINGING_TIMEOUT_SECS as u64)).await;
+        match apply_action(&chat, call_id, None, CallAction::Timeout).await {
+            Ok(call) => tracing::info!("📵 Call {} missed after {}s ringing", call.id, RINGING_TIMEOUT_SECS),
+            // Ya la contestaron, rechazaron o colgaron
+            Err((StatusCode::CONFLICT, _)) => {}
+            Err((_, e)) => tracing::warn!("Ringing timeout for call {} failed: {}", call_id, e),
+        }
+    });
+}
+
+/// Pasa a MISSED los timbres vencidos que nadie cerró (p. ej. el nodo que tenía el timer murió).
+/// Con `user_ids` vacío barre todas las llamadas; si no, solo las de esos usuarios.
+async fn expire_stale_ringing(chat: &ChatState, user_ids: &[Uuid]) -> Result<usize, ApiError> {
+    let ids = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM call_sessions
+        WHERE state = 'RINGING'
+          AND created_at < NOW() - make_interval(secs => $1)
+          AND (cardinality($2::uuid[]) = 0 OR caller_id = ANY($2) OR callee_id = ANY($2))
+        "#,
+    )
+    .bind(RINGING_TIMEOUT_SECS as f64)
+    .bind(user_ids)
+    .fetch_all(&chat.db)
+    .await
+    .map_err(|e| db_error("loading stale ringing calls", e))?;
+
+    let mut expired = 0;
+    for id in ids {
+        match apply_action(chat, id, None, CallAction::Timeout).await {
+            Ok(_) => expired += 1,
+            // Otro nodo o el timer local la cerró primero
+            Err((StatusCode::CONFLICT, _)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(expired)
+}
+
+/// Cierra las llamadas en curso del usuario cuando ya no le queda ningún socket vivo
+pub async fn end_calls_on_disconnect(chat: &ChatState, user_id: Uuid) {
+    let ids = match sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM call_sessions WHERE state = 'ACCEPTED' AND (caller_id = $1 OR callee_id = $1)",
+    )
+    .bind(user_id)
+    .fetch_all(&chat.db)
+    .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::warn!("Loading active calls of disconnected user {} failed: {}", user_id, e);
+            return;
+        }
+    };
+    for id in ids {
+        match apply_action(chat, id, Some(user_id), CallAction::Disconnect).await {
+            Ok(_) => tracing::info!("📴 Call {} ended: {} disconnected", id, user_id),
+            Err((StatusCode::CONFLICT, _)) => {}
+            Err((_, e)) => tracing::warn!("Ending call {} on disconnect failed: {}", id, e),
+        }
+    }
+}
+
+/// Cierra las llamadas contestadas que quedaron colgadas (cliente caído sin colgar,
+/// nodo muerto antes de ver el cierre del socket)
+async fn expire_stale_accepted(state: &AppState) -> Result<usize, ApiError> {
+    let calls = sqlx::query_as::<_, CallSession>(&format!(
+        "SELECT {} FROM call_sessions WHERE state = 'ACCEPTED'",
+        CALL_COLUMNS
+    ))
+    .fetch_all(&state.chat.db)
+    .await
+    .map_err(|e| db_error("loading accepted calls", e))?;
+    if calls.is_empty() {
+        return Ok(0);
+    }
+    let online = presence::online_users(&state.redis)
+        .await
+        .map_err(|e| tracing::warn!("Presence unavailable for call sweep, checking duration only: {}", e))
+        .ok();
+
+    let now = Utc::now();
+    let mut ended = 0;
+    for call in calls {
+        let Some((action, actor)) = stale_accepted_action(&call, now, online.as_ref()) else {
+            continue;
+        };
+        match apply_action(&state.chat, call.id, actor, action).await {
+            Ok(_) => ended += 1,
+            Err((StatusCode::CONFLICT, _)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(ended)
+}
+
+/// Worker de respaldo de las llamadas: los timers de timbre viven en memoria del nodo y el
+/// cierre por desconexión depende de que el nodo vea caer el socket, así que si el nodo cae
+/// las llamadas quedan abiertas. Se barren cada tercio del timeout de timbre.
+pub async fn run_call_sweeper(state: Arc<AppState>, mut shutdown: tokio::sync::broadcast::Receiver<()>) {
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs((RINGING_TIMEOUT_SECS / 3) as u64));
+    tracing::info!("📵 Call sweeper iniciado (intervalo {}s)", RINGING_TIMEOUT_SECS / 3);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                match expire_stale_ringing(&state.chat, &[]).await {
+                    Ok(0) => {}
+                    Ok(n) => tracing::info!("📵 {} stale ringing calls marked as missed", n),
+                    Err((_, e)) => tracing::warn!("Ringing sweep failed: {}", e),
+                }
+                match expire_stale_accepted(&state).await {
+                    Ok(0) => {}
+                    Ok(n) => tracing::info!("📴 {} stale calls ended", n),
+                    Err((_, e)) => tracing::warn!("Accepted call sweep failed: {}", e),
+                }
+            }
+            _ = shutdown.recv() => {
+                tracing::info!("Call sweeper apagado");
+                break;
+            }
+        }
+    }
+}
+
+/// Llamada activa del usuario (sonando o en curso)
+async fn active_call(chat: &ChatState, user_id: Uuid) -> Result<Option<CallSession>, ApiError> {
+    sqlx::query_as::<_, CallSession>(&format!(
+        r#"
+        SELECT {} FROM call_sessions
+        WHERE (caller_id = $1 OR callee_id = $1)
+          AND (state = 'ACCEPTED' OR (state = 'RINGING' AND created_at > NOW() - make_interval(secs => $2)))
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        CALL_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(RINGING_TIMEOUT_SECS as f64)
+    .fetch_optional(&chat.db)
+    .await
+    .map_err(|e| db_error("loading active call", e))
+}
+
+/// Valida una señal WebRTC antes de reenviarla: ambos deben ser las partes de una
+/// llamada activa. Un `hangup` además cierra la llamada.
+pub async fn authorize_signal(chat: &ChatState, call_id: Uuid, from: Uuid, target: Uuid, kind: &str) -> Result<(), ApiError> {
+    let call = sqlx::query_as::<_, CallSession>(&format!("SELECT {} FROM call_sessions WHERE id = $1", CALL_COLUMNS))
+        .bind(call_id)
+        .fetch_optional(&chat.db)
+        .await
+        .map_err(|e| db_error("loading call for signal", e))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Call not found".to_string()))?;
+
+    let parties_match = (call.caller_id == from && call.callee_id == target) || (call.callee_id == from && call.caller_id == target);
+    if !parties_match {
+        return Err((StatusCode::FORBIDDEN, "Signal does not belong to this call".to_string()));
+    }
+    if kind == "hangup" {
+        apply_action(chat, call_id, Some(from), CallAction::Hangup).await?;
+        return Ok(());
+    }
+    if !call.current_state().is_active() {
+        return Err((StatusCode::CONFLICT, "Call already finished".to_string()));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// ENDPOINTS
+// ============================================================================
+
+/// POST /api/chat/calls
+/// Crea la llamada en RINGING, avisa al destinatario (push si no tiene socket) y arranca el timbre
+pub async fn start_call_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Json(req): Json<StartCallRequest>,
+) -> Result<(StatusCode, Json<CallResponse>), ApiError> {
+    let caller = parse_user_id(&auth.user_id)?;
+    if req.callee_id == caller {
+        return Err((StatusCode::BAD_REQUEST, "You cannot call yourself".to_string()));
+    }
+    let chat = state.chat.clone();
+    // Un timbre vencido sin cerrar ocuparía el índice único de llamadas activas
+    expire_stale_ringing(&chat, &[caller, req.callee_id]).await?;
+    if active_call(&chat, caller).await?.is_some() {
+        return Err((StatusCode::CONFLICT, "You already have an active call".to_string()));
+    }
+    if active_call(&chat, req.callee_id).await?.is_some() {
+        return Err((StatusCode::CONFLICT, "User is busy".to_string()));
+    }
+
+    let call = sqlx::query_as::<_, CallSession>(&format!(
+        "INSERT INTO call_sessions (caller_id, callee_id, media) VALUES ($1, $2, $3) RETURNING {}",
+        CALL_COLUMNS
+    ))
+    .bind(caller)
+    .bind(req.callee_id)
+    .bind(if req.video { "VIDEO" } else { "AUDIO" })
+    .fetch_one(&chat.db)
+    .await
+    .map_err(|e| {
+        let sqlx::Error::Database(db) = &e else {
+            return db_error("creating call", e);
+        };
+        match (db.code().as_deref(), db.constraint()) {
+            (Some("23503"), _) => (StatusCode::NOT_FOUND, "User not found".to_string()),
+            // Otra llamada ganó la carrera contra la comprobación de arriba
+            (Some("23505"), Some("uq_call_sessions_active_caller")) => {
+                (StatusCode::CONFLICT, "You already have an active call".to_string())
+            }
+            (Some("23505"), _) => (StatusCode::CONFLICT, "User is busy".to_string()),
+            _ => db_error("creating call", e),
+        }
+    })?;
+
+    let incoming = CallEvent::CallIncoming { call: call.clone(), ice_servers: ice_servers_for(req.callee_id) };
+    let ringing_live = push_event(&chat, req.callee_id, &incoming).await;
+    if !ringing_live {
+        if let Some(notifier) = chat.notifier.clone() {
+            let caller_name = display_name(&chat, caller).await;
+            let callee_id = req.callee_id;
+            tokio::spawn(async move {
+                if let Err(e) = notifier.notify_incoming_call(caller, caller_name, callee_id).await {
+                    tracing::warn!("Incoming call push failed: {}", e);
+                }
+            });
+        }
+    }
+    spawn_ringing_timeout(chat, call.id);
+
+    tracing::info!("📞 Call {} from {} to {} (live: {})", call.id, caller, req.callee_id, ringing_live);
+    Ok((StatusCode::CREATED, Json(CallResponse { call, ice_servers: ice_servers_for(caller) })))
+}
+
+/// POST /api/chat/calls/:id/accept
+pub async fn accept_call_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(call_id): Path<Uuid>,
+) -> Result<Json<CallResponse>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let call = apply_action(&state.chat, call_id, Some(user_id), CallAction::Accept).await?;
+    Ok(Json(CallResponse { call, ice_servers: ice_servers_for(user_id) }))
+}
+
+/// POST /api/chat/calls/:id/decline
+pub async fn decline_call_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(call_id): Path<Uuid>,
+) -> Result<Json<CallSession>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    Ok(Json(apply_action(&state.chat, call_id, Some(user_id), CallAction::Decline).await?))
+}
+
+/// POST /api/chat/calls/:id/end
+/// Colgar: si aún sonaba queda como perdida (quien llama) o rechazada (quien recibe)
+pub async fn end_call_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(call_id): Path<Uuid>,
+) -> Result<Json<CallSession>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    Ok(Json(apply_action(&state.chat, call_id, Some(user_id), CallAction::Hangup).await?))
+}
+
+/// GET /api/chat/calls/active
+/// Para que la app, al abrirse desde el push, recupere la llamada que está sonando
+pub async fn active_call_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<Option<CallResponse>>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let call = active_call(&state.chat, user_id).await?;
+    Ok(Json(call.map(|call| CallResponse { call, ice_servers: ice_servers_for(user_id) })))
+}
+
+/// GET /api/chat/calls/turn
+/// Servidores ICE con credenciales TURN de vida corta
+pub async fn turn_credentials_handler(auth: AuthenticatedUser) -> Result<Json<Vec<IceServer>>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    Ok(Json(ice_servers_for(user_id)))
+}
+
+/// GET /api/chat/calls?before=&limit=
+/// Registro de llamadas del usuario, de la más reciente a la más vieja
+pub async fn call_log_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Query(query): Query<CallLogQuery>,
+) -> Result<Json<Vec<CallLogEntry>>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let limit = query.limit.unwrap_or(DEFAULT_LOG_PAGE).clamp(1, MAX_LOG_PAGE);
+
+    let entries = sqlx::query_as::<_, CallLogEntry>(&format!(
+        r#"
+        SELECT {},
+               CASE WHEN caller_id = $1 THEN 'OUTGOING' ELSE 'INCOMING' END AS direction,
+               CASE WHEN caller_id = $1 THEN callee_id ELSE caller_id END AS peer_id,
+               EXTRACT(EPOCH FROM (ended_at - answered_at))::BIGINT AS duration_secs
+        FROM call_sessions
+        WHERE (caller_id = $1 OR callee_id = $1)
+          AND ($2::TIMESTAMPTZ IS NULL OR created_at < $2)
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+        CALL_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(query.before)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("loading call log", e))?;
+
+    Ok(Json(entries))
+}
+
+/// GET /api/chat/calls/:id
+pub async fn get_call_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(call_id): Path<Uuid>,
+) -> Result<Json<CallSession>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let call = sqlx::query_as::<_, CallSession>(&format!(
+        "SELECT {} FROM call_sessions WHERE id = $1 AND (caller_id = $2 OR callee_id = $2)",
+        CALL_COLUMNS
+    ))
+    .bind(call_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| db_error("loading call", e))?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Call not found".to_string()))?;
+    Ok(Json(call))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ringing_calls_resolve_by_who_acts() {
+        assert_eq!(transition(CallState::Ringing, CallAction::Accept, CallRole::Callee), Ok((CallState::Accepted, None)));
+        assert_eq!(
+            transition(CallState::Ringing, CallAction::Decline, CallRole::Callee),
+            Ok((CallState::Declined, Some("DECLINED")))
+        );
+        assert_eq!(
+            transition(CallState::Ringing, CallAction::Hangup, CallRole::Caller),
+            Ok((CallState::Missed, Some("CANCELLED")))
+        );
+        assert_eq!(
+            transition(CallState::Ringing, CallAction::Timeout, CallRole::Caller),
+            Ok((CallState::Missed, Some("TIMEOUT")))
+        );
+        assert!(transition(CallState::Ringing, CallAction::Accept, CallRole::Caller).is_err());
+    }
+
+    #[test]
+    fn answered_and_finished_calls() {
+        assert_eq!(
+            transition(CallState::Accepted, CallAction::Hangup, CallRole::Caller),
+            Ok((CallState::Ended, Some("HANGUP")))
+        );
+        assert!(transition(CallState::Accepted, CallAction::Timeout, CallRole::Caller).is_err());
+        for state in [CallState::Declined, CallState::Missed, CallState::Ended] {
+            assert!(transition(state, CallAction::Hangup, CallRole::Callee).is_err());
+            assert_eq!(CallState::parse(state.as_str()), Some(state));
+        }
+    }
+
+    #[test]
+    fn ringing_expires_after_timeout() {
+        let now = Utc::now();
+        assert!(!ringing_expired(now - chrono::Duration::seconds(RINGING_TIMEOUT_SECS), now));
+        assert!(ringing_expired(now - chrono::Duration::seconds(RINGING_TIMEOUT_SECS + 1), now));
+    }
+
+    #[test]
+    fn hung_calls_end_on_disconnect_or_max_duration() {
+        assert_eq!(
+            transition(CallState::Accepted, CallAction::Disconnect, CallRole::Callee),
+            Ok((CallState::Ended, Some("DISCONNECT")))
+        );
+        assert!(transition(CallState::Ringing, CallAction::Disconnect, CallRole::Callee).is_err());
+
+        let now = Utc::now();
+        let (caller, callee) = (Uuid::new_v4(), Uuid::new_v4());
+        let call = |answered_secs_ago: i64| CallSession {
+            id: Uuid::new_v4(),
+            caller_id: caller,
+            callee_id: callee,
+            media: "AUDIO".to_string(),
+            state: "ACCEPTED".to_string(),
+            end_reason: None,
+            ended_by: None,
+            created_at: now - chrono::Duration::seconds(answered_secs_ago + 5),
+            answered_at: Some(now - chrono::Duration::seconds(answered_secs_ago)),
+            ended_at: None,
+        };
+        let both: BTreeMap<Uuid, Vec<String>> = [(caller, vec![]), (callee, vec![])].into_iter().collect();
+        let only_caller: BTreeMap<Uuid, Vec<String>> = [(caller, vec![])].into_iter().collect();
+
+        assert_eq!(stale_accepted_action(&call(600), now, Some(&both)), None);
+        assert_eq!(
+            stale_accepted_action(&call(600), now, Some(&only_caller)),
+            Some((CallAction::Disconnect, Some(callee)))
+        );
+        // Recién contestada: todavía no se exige presencia
+        assert_eq!(stale_accepted_action(&call(10), now, Some(&only_caller)), None);
+        // Sin Redis solo se corta por duración
+        assert_eq!(stale_accepted_action(&call(600), now, None), None);
+        assert_eq!(
+            stale_accepted_action(&call(MAX_CALL_DURATION_SECS + 1), now, None),
+            Some((CallAction::Expire, None))
+        );
+    }
+
+    #[test]
+    fn turn_credentials_follow_the_coturn_scheme() {
+        let user = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
+        let (username, credential) = turn_credentials("secret", user, 1_700_000_000, 3600);
+        assert_eq!(username, format!("1700003600:{}", user));
+        let mut mac = HmacSha1::new_from_slice(b"secret").unwrap();
+        mac.update(username.as_bytes());
+        let raw = base64::engine::general_purpose::STANDARD.decode(&credential).unwrap();
+        assert!(mac.verify_slice(&raw).is_ok());
+        assert_ne!(turn_credentials("other", user, 1_700_000_000, 3600).1, credential);
+    }
+}
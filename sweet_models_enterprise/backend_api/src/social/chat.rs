@@ -0,0 +1,960 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::chat_notifications::ChatMessage;
+use super::classifier::ContentKind;
+use super::moderation::{self, ModerationStatus};
+use super::ChatState;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::operations::parse_user_id;
+use crate::state::AppState;
+
+/// Largo máximo de un mensaje (igual que el CHECK de chat_messages)
+pub const MAX_MESSAGE_LEN: usize = 4000;
+/// Minutos en los que el autor puede editar su mensaje
+pub const EDIT_WINDOW_MINUTES: i64 = 15;
+/// Minutos en los que el autor puede borrar su mensaje para todos
+pub const DELETE_WINDOW_MINUTES: i64 = 60;
+const MAX_GROUP_PARTICIPANTS: usize = 100;
+const MAX_TITLE_LEN: usize = 120;
+const MAX_CLIENT_ID_LEN: usize = 64;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+/// Caracteres del mensaje que viajan en el push offline
+const PUSH_PREVIEW_CHARS: usize = 120;
+
+type ApiError = (StatusCode, String);
+
+const MESSAGE_COLUMNS: &str =
+    "id, conversation_id, seq, sender_id, content, client_id, created_at, edited_at, deleted_at, moderation_status";
+
+// ============================================================================
+// TIPOS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConversationKind {
+    Direct,
+    Group,
+}
+
+impl ConversationKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ConversationKind::Direct => "DIRECT",
+            ConversationKind::Group => "GROUP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptStatus {
+    Delivered,
+    Read,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StoredMessage {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub seq: i64,
+    pub sender_id: Uuid,
+    /// `None` si fue borrado
+    pub content: Option<String>,
+    pub client_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// VISIBLE, PENDING (retenido para revisión: solo lo ve el autor) o REMOVED
+    pub moderation_status: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ConversationSummary {
+    pub id: Uuid,
+    pub kind: String,
+    pub title: Option<String>,
+    pub last_seq: i64,
+    pub last_message_at: Option<DateTime<Utc>>,
+    pub read_seq: i64,
+    pub unread: i64,
+    pub participants: Vec<Uuid>,
+}
+
+/// Marcas de agua de un participante: todo mensaje con `seq` menor o igual está entregado/leído
+#[derive(Debug, Serialize, FromRow)]
+pub struct ParticipantReceipt {
+    pub user_id: Uuid,
+    pub role: String,
+    pub delivered_seq: i64,
+    pub read_seq: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConversationDetail {
+    #[serde(flatten)]
+    pub conversation: ConversationSummary,
+    pub receipts: Vec<ParticipantReceipt>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateConversationRequest {
+    pub kind: ConversationKind,
+    pub participant_ids: Vec<Uuid>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Devuelve mensajes con `seq` menor a este (paginación hacia atrás)
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryPage {
+    pub messages: Vec<StoredMessage>,
+    /// Cursor para la siguiente página; `None` cuando ya no hay más
+    pub next_before: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SendMessageRequest {
+    pub content: String,
+    pub client_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReceiptRequest {
+    pub seq: i64,
+    pub status: ReceiptStatus,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditMessageRequest {
+    pub content: String,
+}
+
+/// Comandos de chat que llegan por el WebSocket (la señalización WebRTC va aparte)
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatCommand {
+    Message {
+        conversation_id: Uuid,
+        content: String,
+        client_id: Option<String>,
+    },
+    Typing {
+        conversation_id: Uuid,
+    },
+    Receipt {
+        conversation_id: Uuid,
+        seq: i64,
+        status: ReceiptStatus,
+    },
+    Edit {
+        message_id: Uuid,
+        content: String,
+    },
+    Delete {
+        message_id: Uuid,
+    },
+}
+
+/// Eventos que el servidor empuja a los participantes conectados
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatEvent {
+    Message { message: StoredMessage },
+    MessageEdited { message: StoredMessage },
+    MessageDeleted { message: StoredMessage },
+    /// El mensaje quedó retenido por moderación; el cliente debe ocultarlo
+    MessageHidden { conversation_id: Uuid, message_id: Uuid },
+    Receipt { conversation_id: Uuid, user_id: Uuid, status: ReceiptStatus, seq: i64 },
+    Typing { conversation_id: Uuid, user_id: Uuid },
+}
+
+struct ConversationAccess {
+    kind: String,
+    participants: Vec<Uuid>,
+}
+
+// ============================================================================
+// REGLAS
+// ============================================================================
+
+/// Clave única de la conversación directa entre dos usuarios (independiente del orden)
+pub fn direct_key(a: Uuid, b: Uuid) -> String {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    format!("{}:{}", low, high)
+}
+
+fn within_window(created_at: DateTime<Utc>, now: DateTime<Utc>, minutes: i64) -> bool {
+    now - created_at <= Duration::minutes(minutes)
+}
+
+fn validate_content(content: &str) -> Result<String, ApiError> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "content is required".to_string()));
+    }
+    if content.chars().count() > MAX_MESSAGE_LEN {
+        return Err((StatusCode::BAD_REQUEST, format!("content must be <= {} characters", MAX_MESSAGE_LEN)));
+    }
+    Ok(content.to_string())
+}
+
+fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+fn preview(content: &str) -> String {
+    if content.chars().count() <= PUSH_PREVIEW_CHARS {
+        return content.to_string();
+    }
+    let cut: String = content.chars().take(PUSH_PREVIEW_CHARS).collect();
+    format!("{}…", cut)
+}
+
+/// Participantes sin el autor y sin repetir; la conversación directa exige exactamente uno
+fn resolve_participants(creator: Uuid, req: &CreateConversationRequest) -> Result<Vec<Uuid>, ApiError> {
+    let mut others: Vec<Uuid> = req.participant_ids.iter().copied().filter(|id| *id != creator).collect();
+    others.sort();
+    others.dedup();
+    match req.kind {
+        ConversationKind::Direct if others.len() != 1 => Err((
+            StatusCode::BAD_REQUEST,
+            "A direct conversation needs exactly one other participant".to_string(),
+        )),
+        ConversationKind::Group if others.is_empty() || others.len() >= MAX_GROUP_PARTICIPANTS => Err((
+            StatusCode::BAD_REQUEST,
+            format!("A group needs between 1 and {} other participants", MAX_GROUP_PARTICIPANTS - 1),
+        )),
+        _ => Ok(others),
+    }
+}
+
+fn is_db_code(e: &sqlx::Error, code: &str) -> bool {
+    matches!(e, sqlx::Error::Database(db) if db.code().as_deref() == Some(code))
+}
+
+fn db_error(context: &str, e: sqlx::Error) -> ApiError {
+    tracing::error!("DB error {}: {}", context, e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+// ============================================================================
+// SERVICIO (compartido por REST y WebSocket)
+// ============================================================================
+
+/// Participantes de la conversación si `user_id` es uno de ellos.
+/// 404 también cuando no es miembro, para no revelar que la conversación existe.
+async fn require_participant(db: &PgPool, conversation_id: Uuid, user_id: Uuid) -> Result<ConversationAccess, ApiError> {
+    let rows = sqlx::query_as::<_, (String, Uuid)>(
+        r#"
+        SELECT c.kind, p.user_id
+        FROM chat_conversations c
+        JOIN chat_participants p ON p.conversation_id = c.id
+        WHERE c.id = $1
+        "#,
+    )
+    .bind(conversation_id)
+    .fetch_all(db)
+    .await
+    .map_err(|e| db_error("loading conversation", e))?;
+
+    if !rows.iter().any(|(_, participant)| *participant == user_id) {
+        return Err((StatusCode::NOT_FOUND, "Conversation not found".to_string()));
+    }
+    let kind = rows[0].0.clone();
+    Ok(ConversationAccess { kind, participants: rows.into_iter().map(|(_, id)| id).collect() })
+}
+
+/// Empuja un evento a todos los dispositivos conectados (en cualquier nodo).
+/// Devuelve los usuarios que no tenían socket.
+async fn push_event(chat: &ChatState, recipients: &[Uuid], event: &ChatEvent) -> Vec<Uuid> {
+    let payload = serde_json::to_string(event).unwrap_or_default();
+    let mut offline = Vec::new();
+    for recipient in recipients {
+        if !chat.peers.send_to(*recipient, payload.clone()).await {
+            offline.push(*recipient);
+        }
+    }
+    offline
+}
+
+async fn load_message(db: &PgPool, message_id: Uuid) -> Result<StoredMessage, ApiError> {
+    sqlx::query_as::<_, StoredMessage>(&format!("SELECT {} FROM chat_messages WHERE id = $1", MESSAGE_COLUMNS))
+        .bind(message_id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| db_error("loading chat message", e))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Message not found".to_string()))
+}
+
+/// Guarda el mensaje con la siguiente secuencia de la conversación y lo reparte.
+/// Un reintento con el mismo `client_id` devuelve el mensaje original.
+pub async fn send_message(
+    chat: &ChatState,
+    sender: Uuid,
+    conversation_id: Uuid,
+    content: &str,
+    client_id: Option<String>,
+) -> Result<StoredMessage, ApiError> {
+    let content = validate_content(content)?;
+    let client_id = client_id.map(|id| id.trim().to_string()).filter(|id| !id.is_empty());
+    if client_id.as_ref().is_some_and(|id| id.len() > MAX_CLIENT_ID_LEN) {
+        return Err((StatusCode::BAD_REQUEST, format!("client_id must be <= {} characters", MAX_CLIENT_ID_LEN)));
+    }
+    let access = require_participant(&chat.db, conversation_id, sender).await?;
+    moderation::ensure_not_muted(&chat.db, sender).await?;
+
+    if let Some(client_id) = &client_id {
+        let existing = sqlx::query_as::<_, StoredMessage>(&format!(
+            "SELECT {} FROM chat_messages WHERE sender_id = $1 AND client_id = $2",
+            MESSAGE_COLUMNS
+        ))
+        .bind(sender)
+        .bind(client_id)
+        .fetch_optional(&chat.db)
+        .await
+        .map_err(|e| db_error("checking client_id", e))?;
+        if let Some(existing) = existing {
+            return Ok(existing);
+        }
+    }
+
+    let verdict = moderation::screen(ContentKind::ChatMessage, &content).await;
+    let status = moderation::status_for(&verdict);
+
+    let mut tx = chat.db.begin().await.map_err(|e| db_error("starting chat transaction", e))?;
+    let (seq,) = sqlx::query_as::<_, (i64,)>(
+        "UPDATE chat_conversations SET last_seq = last_seq + 1, last_message_at = NOW() WHERE id = $1 RETURNING last_seq",
+    )
+    .bind(conversation_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| db_error("bumping conversation seq", e))?;
+
+    let message = sqlx::query_as::<_, StoredMessage>(&format!(
+        r#"
+        INSERT INTO chat_messages (conversation_id, seq, sender_id, content, client_id, moderation_status)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING {}
+        "#,
+        MESSAGE_COLUMNS
+    ))
+    .bind(conversation_id)
+    .bind(seq)
+    .bind(sender)
+    .bind(&content)
+    .bind(&client_id)
+    .bind(status.as_str())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        if is_db_code(&e, "23505") {
+            (StatusCode::CONFLICT, "Duplicate client_id".to_string())
+        } else {
+            db_error("inserting chat message", e)
+        }
+    })?;
+
+    // El autor ya tiene entregado y leído su propio mensaje
+    sqlx::query("UPDATE chat_participants SET delivered_seq = $3, read_seq = $3 WHERE conversation_id = $1 AND user_id = $2")
+        .bind(conversation_id)
+        .bind(sender)
+        .bind(seq)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| db_error("updating sender receipts", e))?;
+    let held = if status == ModerationStatus::Pending {
+        Some(moderation::hold_for_review(&mut tx, ContentKind::ChatMessage, message.id, sender, &verdict).await?)
+    } else {
+        None
+    };
+    tx.commit().await.map_err(|e| db_error("committing chat message", e))?;
+
+    if let Some(case_id) = held {
+        // Retenido: solo el autor lo ve hasta que un moderador lo apruebe
+        push_event(chat, &[sender], &ChatEvent::Message { message: message.clone() }).await;
+        moderation::announce_held(&chat.hub, ContentKind::ChatMessage, message.id, case_id, &verdict);
+        return Ok(message);
+    }
+    deliver(chat, &access, sender, &message).await;
+    Ok(message)
+}
+
+/// Reparte un mensaje nuevo: los conectados quedan como entregados y a los demás
+/// les llega un push por `ChatNotificationManager`
+async fn deliver(chat: &ChatState, access: &ConversationAccess, sender: Uuid, message: &StoredMessage) {
+    let offline = push_event(chat, &access.participants, &ChatEvent::Message { message: message.clone() }).await;
+    // Presencia real según la entrega por el registro de peers (en cualquier réplica)
+    let connected: HashMap<Uuid, bool> = access
+        .participants
+        .iter()
+        .filter(|id| **id != sender)
+        .map(|id| (*id, !offline.contains(id)))
+        .collect();
+    let online: Vec<Uuid> = connected.iter().filter(|(_, online)| **online).map(|(id, _)| *id).collect();
+
+    if !online.is_empty() {
+        let marked = sqlx::query(
+            "UPDATE chat_participants SET delivered_seq = GREATEST(delivered_seq, $2) WHERE conversation_id = $1 AND user_id = ANY($3)",
+        )
+        .bind(message.conversation_id)
+        .bind(message.seq)
+        .bind(&online)
+        .execute(&chat.db)
+        .await;
+        match marked {
+            Ok(_) => {
+                for user_id in online {
+                    let receipt = ChatEvent::Receipt {
+                        conversation_id: message.conversation_id,
+                        user_id,
+                        status: ReceiptStatus::Delivered,
+                        seq: message.seq,
+                    };
+                    push_event(chat, &[sender], &receipt).await;
+                }
+            }
+            Err(e) => tracing::error!("DB error marking chat delivery: {}", e),
+        }
+    }
+
+    let Some(notifier) = chat.notifier.clone() else {
+        return;
+    };
+    if connected.values().all(|online| *online) {
+        return;
+    }
+
+    let sender_name = sqlx::query_scalar::<_, String>(
+        "SELECT COALESCE(display_name, username, email) FROM users WHERE id = $1",
+    )
+    .bind(sender)
+    .fetch_optional(&chat.db)
+    .await
+    .ok()
+    .flatten()
+    .unwrap_or_else(|| "Alguien".to_string());
+    let content = preview(message.content.as_deref().unwrap_or_default());
+    let conversation_id = message.conversation_id;
+    let timestamp = message.created_at;
+    let is_group = access.kind == ConversationKind::Group.as_str();
+
+    tokio::spawn(async move {
+        let recipients: Vec<Uuid> = connected.keys().copied().collect();
+        if is_group {
+            if let Err(e) = notifier
+                .notify_group_message(conversation_id, sender, sender_name, content, recipients, &connected)
+                .await
+            {
+                tracing::warn!("Group chat push failed: {}", e);
+            }
+            return;
+        }
+        for to_user_id in recipients {
+            let push = ChatMessage {
+                from_user_id: sender,
+                to_user_id,
+                from_user_name: sender_name.clone(),
+                content: content.clone(),
+                timestamp,
+            };
+            if let Err(e) = notifier.notify_if_offline(push, &connected).await {
+                tracing::warn!("Chat push to {} failed: {}", to_user_id, e);
+            }
+        }
+    });
+}
+
+/// Avanza las marcas de agua del usuario (nunca retroceden ni pasan del último mensaje)
+pub async fn apply_receipt(
+    chat: &ChatState,
+    user_id: Uuid,
+    conversation_id: Uuid,
+    seq: i64,
+    status: ReceiptStatus,
+) -> Result<ParticipantReceipt, ApiError> {
+    let access = require_participant(&chat.db, conversation_id, user_id).await?;
+    let receipt = sqlx::query_as::<_, ParticipantReceipt>(
+        r#"
+        UPDATE chat_participants p SET
+            delivered_seq = GREATEST(p.delivered_seq, LEAST($3, c.last_seq)),
+            read_seq = CASE WHEN $4 THEN GREATEST(p.read_seq, LEAST($3, c.last_seq)) ELSE p.read_seq END
+        FROM chat_conversations c
+        WHERE c.id = p.conversation_id AND p.conversation_id = $1 AND p.user_id = $2
+        RETURNING p.user_id, p.role, p.delivered_seq, p.read_seq
+        "#,
+    )
+    .bind(conversation_id)
+    .bind(user_id)
+    .bind(seq.max(0))
+    .bind(status == ReceiptStatus::Read)
+    .fetch_one(&chat.db)
+    .await
+    .map_err(|e| db_error("applying chat receipt", e))?;
+
+    let seq = match status {
+        ReceiptStatus::Delivered => receipt.delivered_seq,
+        ReceiptStatus::Read => receipt.read_seq,
+    };
+    let others: Vec<Uuid> = access.participants.into_iter().filter(|id| *id != user_id).collect();
+    push_event(chat, &others, &ChatEvent::Receipt { conversation_id, user_id, status, seq }).await;
+    Ok(receipt)
+}
+
+/// Indicador de escritura: solo se reenvía, no se guarda
+pub async fn typing(chat: &ChatState, user_id: Uuid, conversation_id: Uuid) -> Result<(), ApiError> {
+    let access = require_participant(&chat.db, conversation_id, user_id).await?;
+    let others: Vec<Uuid> = access.participants.into_iter().filter(|id| *id != user_id).collect();
+    push_event(chat, &others, &ChatEvent::Typing { conversation_id, user_id }).await;
+    Ok(())
+}
+
+/// Edita un mensaje propio dentro de la ventana de edición
+pub async fn edit_message(chat: &ChatState, user_id: Uuid, message_id: Uuid, content: &str) -> Result<StoredMessage, ApiError> {
+    let content = validate_content(content)?;
+    let message = load_message(&chat.db, message_id).await?;
+    if message.sender_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "Only the author can edit a message".to_string()));
+    }
+    if message.deleted_at.is_some() {
+        return Err((StatusCode::CONFLICT, "Message was deleted".to_string()));
+    }
+    if !within_window(message.created_at, Utc::now(), EDIT_WINDOW_MINUTES) {
+        return Err((StatusCode::FORBIDDEN, format!("Messages can only be edited within {} minutes", EDIT_WINDOW_MINUTES)));
+    }
+    let access = require_participant(&chat.db, message.conversation_id, user_id).await?;
+    moderation::ensure_not_muted(&chat.db, user_id).await?;
+    let verdict = moderation::screen(ContentKind::ChatMessage, &content).await;
+
+    // Una edición marcada retiene el mensaje; una limpia no libera uno que ya estaba retenido
+    let mut tx = chat.db.begin().await.map_err(|e| db_error("starting chat edit", e))?;
+    let edited = sqlx::query_as::<_, StoredMessage>(&format!(
+        r#"
+        UPDATE chat_messages
+        SET content = $2, edited_at = NOW(),
+            moderation_status = CASE WHEN $3 THEN 'PENDING' ELSE moderation_status END
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING {}
+        "#,
+        MESSAGE_COLUMNS
+    ))
+    .bind(message_id)
+    .bind(&content)
+    .bind(verdict.flagged)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| db_error("editing chat message", e))?
+    .ok_or_else(|| (StatusCode::CONFLICT, "Message was deleted".to_string()))?;
+    let held = if verdict.flagged {
+        Some(moderation::hold_for_review(&mut tx, ContentKind::ChatMessage, message_id, user_id, &verdict).await?)
+    } else {
+        None
+    };
+    tx.commit().await.map_err(|e| db_error("committing chat edit", e))?;
+
+    if edited.moderation_status != ModerationStatus::Pending.as_str() {
+        push_event(chat, &access.participants, &ChatEvent::MessageEdited { message: edited.clone() }).await;
+        return Ok(edited);
+    }
+    push_event(chat, &[user_id], &ChatEvent::MessageEdited { message: edited.clone() }).await;
+    if message.moderation_status == ModerationStatus::Visible.as_str() {
+        let others: Vec<Uuid> = access.participants.into_iter().filter(|id| *id != user_id).collect();
+        let hidden = ChatEvent::MessageHidden { conversation_id: edited.conversation_id, message_id };
+        push_event(chat, &others, &hidden).await;
+    }
+    if let Some(case_id) = held {
+        moderation::announce_held(&chat.hub, ContentKind::ChatMessage, message_id, case_id, &verdict);
+    }
+    Ok(edited)
+}
+
+/// Decisión de moderación ya guardada sobre un mensaje, pendiente de avisar
+pub(crate) struct ModeratedMessage {
+    previous_status: String,
+    status: ModerationStatus,
+    message: StoredMessage,
+}
+
+/// Aplica una decisión de moderación sobre un mensaje dentro de la transacción del llamador
+pub(crate) async fn apply_moderation(
+    conn: &mut PgConnection,
+    message_id: Uuid,
+    status: ModerationStatus,
+) -> Result<ModeratedMessage, ApiError> {
+    let previous_status =
+        sqlx::query_scalar::<_, String>("SELECT moderation_status FROM chat_messages WHERE id = $1 FOR UPDATE")
+            .bind(message_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| db_error("loading chat message", e))?
+            .ok_or_else(|| (StatusCode::NOT_FOUND, "Message not found".to_string()))?;
+    let message = sqlx::query_as::<_, StoredMessage>(&format!(
+        r#"
+        UPDATE chat_messages
+        SET moderation_status = $2,
+            content = CASE WHEN $2 = 'REMOVED' THEN NULL ELSE content END,
+            deleted_at = CASE WHEN $2 = 'REMOVED' THEN COALESCE(deleted_at, NOW()) ELSE deleted_at END
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        MESSAGE_COLUMNS
+    ))
+    .bind(message_id)
+    .bind(status.as_str())
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| db_error("applying moderation to chat message", e))?;
+    Ok(ModeratedMessage { previous_status, status, message })
+}
+
+/// Avisa a los participantes de una decisión de moderación ya confirmada
+pub(crate) async fn announce_moderation(chat: &ChatState, moderated: ModeratedMessage) {
+    let ModeratedMessage { previous_status, status, message } = moderated;
+    let access = match require_participant(&chat.db, message.conversation_id, message.sender_id).await {
+        Ok(access) => access,
+        Err((_, e)) => {
+            tracing::warn!("No se pudo avisar la moderación del mensaje {}: {}", message.id, e);
+            return;
+        }
+    };
+
+    match status {
+        ModerationStatus::Removed => {
+            push_event(chat, &access.participants, &ChatEvent::MessageDeleted { message }).await;
+        }
+        ModerationStatus::Pending => {
+            let others: Vec<Uuid> = access.participants.into_iter().filter(|id| *id != message.sender_id).collect();
+            let hidden = ChatEvent::MessageHidden { conversation_id: message.conversation_id, message_id: message.id };
+            push_event(chat, &others, &hidden).await;
+        }
+        // Aprobado: quien no lo tenía lo recibe como mensaje nuevo (los clientes deduplican por id)
+        ModerationStatus::Visible if previous_status != ModerationStatus::Visible.as_str() => {
+            deliver(chat, &access, message.sender_id, &message).await;
+        }
+        ModerationStatus::Visible => {}
+    }
+}
+
+/// Borra un mensaje propio para todos; queda la lápida (sin contenido) en el historial
+pub async fn delete_message(chat: &ChatState, user_id: Uuid, message_id: Uuid) -> Result<StoredMessage, ApiError> {
+    let message = load_message(&chat.db, message_id).await?;
+    if message.sender_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "Only the author can delete a message".to_string()));
+    }
+    if message.deleted_at.is_some() {
+        return Ok(message);
+    }
+    if !within_window(message.created_at, Utc::now(), DELETE_WINDOW_MINUTES) {
+        return Err((StatusCode::FORBIDDEN, format!("Messages can only be deleted within {} minutes", DELETE_WINDOW_MINUTES)));
+    }
+    let access = require_participant(&chat.db, message.conversation_id, user_id).await?;
+
+    let deleted = sqlx::query_as::<_, StoredMessage>(&format!(
+        "UPDATE chat_messages SET content = NULL, deleted_at = NOW() WHERE id = $1 RETURNING {}",
+        MESSAGE_COLUMNS
+    ))
+    .bind(message_id)
+    .fetch_one(&chat.db)
+    .await
+    .map_err(|e| db_error("deleting chat message", e))?;
+
+    push_event(chat, &access.participants, &ChatEvent::MessageDeleted { message: deleted.clone() }).await;
+    Ok(deleted)
+}
+
+/// Ejecuta un comando recibido por el socket de chat
+pub async fn handle_command(chat: &ChatState, user_id: Uuid, command: ChatCommand) -> Result<(), ApiError> {
+    match command {
+        ChatCommand::Message { conversation_id, content, client_id } => {
+            send_message(chat, user_id, conversation_id, &content, client_id).await.map(|_| ())
+        }
+        ChatCommand::Typing { conversation_id } => typing(chat, user_id, conversation_id).await,
+        ChatCommand::Receipt { conversation_id, seq, status } => {
+            apply_receipt(chat, user_id, conversation_id, seq, status).await.map(|_| ())
+        }
+        ChatCommand::Edit { message_id, content } => edit_message(chat, user_id, message_id, &content).await.map(|_| ()),
+        ChatCommand::Delete { message_id } => delete_message(chat, user_id, message_id).await.map(|_| ()),
+    }
+}
+
+async fn load_summaries(db: &PgPool, user_id: Uuid, conversation_id: Option<Uuid>) -> Result<Vec<ConversationSummary>, ApiError> {
+    sqlx::query_as::<_, ConversationSummary>(
+        r#"
+        SELECT c.id, c.kind, c.title, c.last_seq, c.last_message_at, me.read_seq,
+               (SELECT COUNT(*) FROM chat_messages m
+                 WHERE m.conversation_id = c.id AND m.seq > me.read_seq
+                   AND m.sender_id <> me.user_id AND m.deleted_at IS NULL
+                   AND m.moderation_status = 'VISIBLE') AS unread,
+               ARRAY(SELECT p.user_id FROM chat_participants p
+                      WHERE p.conversation_id = c.id ORDER BY p.joined_at, p.user_id) AS participants
+        FROM chat_conversations c
+        JOIN chat_participants me ON me.conversation_id = c.id AND me.user_id = $1
+        WHERE ($2::UUID IS NULL OR c.id = $2)
+        ORDER BY c.last_message_at DESC NULLS LAST, c.created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(conversation_id)
+    .fetch_all(db)
+    .await
+    .map_err(|e| db_error("listing conversations", e))
+}
+
+// ============================================================================
+// ENDPOINTS
+// ============================================================================
+
+/// GET /api/social/chat/conversations
+pub async fn list_conversations_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<Vec<ConversationSummary>>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    Ok(Json(load_summaries(&state.db, user_id, None).await?))
+}
+
+/// POST /api/social/chat/conversations
+/// Una conversación directa repetida devuelve la existente (200 en vez de 201)
+pub async fn create_conversation_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Json(req): Json<CreateConversationRequest>,
+) -> Result<(StatusCode, Json<ConversationSummary>), ApiError> {
+    let creator = parse_user_id(&auth.user_id)?;
+    let others = resolve_participants(creator, &req)?;
+    let title = req.title.as_deref().map(str::trim).filter(|t| !t.is_empty()).map(str::to_string);
+    if req.kind == ConversationKind::Group && title.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "A group needs a title".to_string()));
+    }
+    if title.as_ref().is_some_and(|t| t.chars().count() > MAX_TITLE_LEN) {
+        return Err((StatusCode::BAD_REQUEST, format!("title must be <= {} characters", MAX_TITLE_LEN)));
+    }
+    let key = match req.kind {
+        ConversationKind::Direct => Some(direct_key(creator, others[0])),
+        ConversationKind::Group => None,
+    };
+
+    let mut tx = state.db.begin().await.map_err(|e| db_error("starting conversation transaction", e))?;
+    let (conversation_id, created) = sqlx::query_as::<_, (Uuid, bool)>(
+        r#"
+        INSERT INTO chat_conversations (kind, title, direct_key, created_by)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (direct_key) DO UPDATE SET updated_at = NOW()
+        RETURNING id, (xmax = 0) AS created
+        "#,
+    )
+    .bind(req.kind.as_str())
+    .bind(if req.kind == ConversationKind::Group { title.as_deref() } else { None })
+    .bind(&key)
+    .bind(creator)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| db_error("creating conversation", e))?;
+
+    if created {
+        let mut members = vec![(creator, "OWNER")];
+        members.extend(others.iter().map(|id| (*id, "MEMBER")));
+        for (user_id, role) in members {
+            sqlx::query("INSERT INTO chat_participants (conversation_id, user_id, role) VALUES ($1, $2, $3)")
+                .bind(conversation_id)
+                .bind(user_id)
+                .bind(role)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    if is_db_code(&e, "23503") {
+                        (StatusCode::BAD_REQUEST, format!("Unknown participant {}", user_id))
+                    } else {
+                        db_error("adding conversation participant", e)
+                    }
+                })?;
+        }
+    }
+    tx.commit().await.map_err(|e| db_error("committing conversation", e))?;
+
+    let summary = load_summaries(&state.db, creator, Some(conversation_id))
+        .await?
+        .pop()
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Conversation not found".to_string()))?;
+    if created {
+        tracing::info!("💬 Conversation {} ({}) created by {}", conversation_id, req.kind.as_str(), creator);
+    }
+    let status = if created { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, Json(summary)))
+}
+
+/// GET /api/social/chat/conversations/:id
+/// Incluye las marcas de entrega/lectura de cada participante
+pub async fn get_conversation_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(conversation_id): Path<Uuid>,
+) -> Result<Json<ConversationDetail>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let conversation = load_summaries(&state.db, user_id, Some(conversation_id))
+        .await?
+        .pop()
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Conversation not found".to_string()))?;
+    let receipts = sqlx::query_as::<_, ParticipantReceipt>(
+        "SELECT user_id, role, delivered_seq, read_seq FROM chat_participants WHERE conversation_id = $1 ORDER BY joined_at, user_id",
+    )
+    .bind(conversation_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("loading receipts", e))?;
+    Ok(Json(ConversationDetail { conversation, receipts }))
+}
+
+/// GET /api/social/chat/conversations/:id/messages?before=&limit=
+/// Del más nuevo al más viejo; `next_before` es el cursor de la página siguiente
+pub async fn history_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(conversation_id): Path<Uuid>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryPage>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    require_participant(&state.db, conversation_id, user_id).await?;
+    let limit = page_size(query.limit);
+
+    let messages = sqlx::query_as::<_, StoredMessage>(&format!(
+        r#"
+        SELECT {} FROM chat_messages
+        WHERE conversation_id = $1 AND ($2::BIGINT IS NULL OR seq < $2)
+          AND (moderation_status <> 'PENDING' OR sender_id = $4)
+        ORDER BY seq DESC
+        LIMIT $3
+        "#,
+        MESSAGE_COLUMNS
+    ))
+    .bind(conversation_id)
+    .bind(query.before)
+    .bind(limit)
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("loading chat history", e))?;
+
+    let next_before = if messages.len() as i64 == limit { messages.last().map(|m| m.seq) } else { None };
+    Ok(Json(HistoryPage { messages, next_before }))
+}
+
+/// POST /api/social/chat/conversations/:id/messages
+pub async fn send_message_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(conversation_id): Path<Uuid>,
+    Json(req): Json<SendMessageRequest>,
+) -> Result<(StatusCode, Json<StoredMessage>), ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let message = send_message(&state.chat, user_id, conversation_id, &req.content, req.client_id).await?;
+    Ok((StatusCode::CREATED, Json(message)))
+}
+
+/// POST /api/social/chat/conversations/:id/receipts
+pub async fn receipt_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(conversation_id): Path<Uuid>,
+    Json(req): Json<ReceiptRequest>,
+) -> Result<Json<ParticipantReceipt>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    Ok(Json(apply_receipt(&state.chat, user_id, conversation_id, req.seq, req.status).await?))
+}
+
+/// PATCH /api/social/chat/messages/:id
+pub async fn edit_message_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(message_id): Path<Uuid>,
+    Json(req): Json<EditMessageRequest>,
+) -> Result<Json<StoredMessage>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    Ok(Json(edit_message(&state.chat, user_id, message_id, &req.content).await?))
+}
+
+/// DELETE /api/social/chat/messages/:id
+pub async fn delete_message_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(message_id): Path<Uuid>,
+) -> Result<Json<StoredMessage>, ApiError> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    Ok(Json(delete_message(&state.chat, user_id, message_id).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn direct_key_ignores_order() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(direct_key(a, b), direct_key(b, a));
+        assert_ne!(direct_key(a, b), direct_key(a, Uuid::new_v4()));
+    }
+
+    #[test]
+    fn edit_and_delete_windows() {
+        let now = Utc::now();
+        assert!(within_window(now - Duration::minutes(EDIT_WINDOW_MINUTES), now, EDIT_WINDOW_MINUTES));
+        assert!(!within_window(now - Duration::minutes(EDIT_WINDOW_MINUTES + 1), now, EDIT_WINDOW_MINUTES));
+        assert!(within_window(now - Duration::minutes(30), now, DELETE_WINDOW_MINUTES));
+    }
+
+    #[test]
+    fn participants_are_validated_per_kind() {
+        let me = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let direct = |ids: Vec<Uuid>| CreateConversationRequest { kind: ConversationKind::Direct, participant_ids: ids, title: None };
+
+        assert_eq!(resolve_participants(me, &direct(vec![me, other, other])).unwrap(), vec![other]);
+        assert!(resolve_participants(me, &direct(vec![me])).is_err());
+        assert!(resolve_participants(me, &direct(vec![other, Uuid::new_v4()])).is_err());
+
+        let group = CreateConversationRequest {
+            kind: ConversationKind::Group,
+            participant_ids: (0..MAX_GROUP_PARTICIPANTS).map(|_| Uuid::new_v4()).collect(),
+            title: Some("Turno noche".to_string()),
+        };
+        assert!(resolve_participants(me, &group).is_err());
+    }
+
+    #[test]
+    fn content_and_page_limits() {
+        assert_eq!(validate_content("  hola ").unwrap(), "hola");
+        assert!(validate_content("   ").is_err());
+        assert!(validate_content(&"a".repeat(MAX_MESSAGE_LEN + 1)).is_err());
+        assert_eq!(page_size(None), DEFAULT_PAGE_SIZE);
+        assert_eq!(page_size(Some(0)), 1);
+        assert_eq!(page_size(Some(10_000)), MAX_PAGE_SIZE);
+        assert!(preview(&"x".repeat(500)).ends_with('…'));
+    }
+
+    #[test]
+    fn socket_commands_and_events_use_snake_case_tags() {
+        let id = Uuid::new_v4();
+        let command: ChatCommand =
+            serde_json::from_str(&format!(r#"{{"type":"receipt","conversation_id":"{}","seq":4,"status":"read"}}"#, id)).unwrap();
+        assert!(matches!(command, ChatCommand::Receipt { seq: 4, status: ReceiptStatus::Read, .. }));
+
+        let event = serde_json::to_value(ChatEvent::Typing { conversation_id: id, user_id: id }).unwrap();
+        assert_eq!(event["type"], "typing");
+    }
+}
@@ -152,6 +152,36 @@ impl ChatNotificationManager {
         Ok(())
     }
 
+    /// Enviar notificación de llamada perdida (nadie contestó o se canceló mientras sonaba)
+    pub async fn notify_missed_call(
+        &self,
+        call_id: Uuid,
+        from_user_id: Uuid,
+        from_user_name: String,
+        to_user_id: Uuid,
+    ) -> Result<(), String> {
+        let title = "Llamada perdida".to_string();
+        let body = format!("{} te llamó", from_user_name);
+
+        let mut data = HashMap::new();
+        data.insert("call_id".to_string(), call_id.to_string());
+        data.insert("from_user_id".to_string(), from_user_id.to_string());
+        data.insert("call_type".to_string(), "missed".to_string());
+        data.insert("action".to_string(), "open_call_log".to_string());
+
+        self.notification_service
+            .send_alert(
+                to_user_id,
+                title,
+                body,
+                Some(data),
+                "missed_call",
+            )
+            .await?;
+
+        Ok(())
+    }
+
     /// Enviar notificación de reacción a mensaje
     pub async fn notify_message_reaction(
         &self,
@@ -0,0 +1,392 @@
+/// Clasificación automática del contenido que publican los usuarios (posts, comentarios y chat).
+///
+/// `ContentClassifier` es el punto de extensión: por defecto se usan reglas locales
+/// (`RuleClassifier`: expresiones regulares + lista de palabras), ampliables con
+/// `MODERATION_RULES` (JSON) o `MODERATION_RULES_FILE` con el formato de `RuleConfig`, y
+/// `MODERATION_KEYWORDS` (palabras separadas por coma). Un clasificador externo se
+/// instala con `install_classifier` antes de que llegue el primer contenido.
+use std::sync::{Arc, OnceLock};
+
+use axum::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Puntaje desde el que el contenido se oculta hasta que lo revise un moderador
+pub const DEFAULT_FLAG_THRESHOLD: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ContentKind {
+    Post,
+    Comment,
+    ChatMessage,
+}
+
+impl ContentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentKind::Post => "POST",
+            ContentKind::Comment => "COMMENT",
+            ContentKind::ChatMessage => "CHAT_MESSAGE",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "POST" => Some(ContentKind::Post),
+            "COMMENT" => Some(ContentKind::Comment),
+            "CHAT_MESSAGE" => Some(ContentKind::ChatMessage),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Verdict {
+    pub flagged: bool,
+    /// Categorías que dispararon (p. ej. "harassment", "personal_data")
+    pub categories: Vec<String>,
+    /// 0.0 = limpio, 1.0 = seguro que infringe
+    pub score: f32,
+}
+
+impl Verdict {
+    pub fn clean() -> Self {
+        Self { flagged: false, categories: Vec::new(), score: 0.0 }
+    }
+}
+
+#[async_trait]
+pub trait ContentClassifier: Send + Sync {
+    /// Se guarda en cada caso para saber qué clasificador lo abrió
+    fn name(&self) -> &str;
+
+    async fn classify(&self, kind: ContentKind, text: &str) -> Verdict;
+}
+
+/// Regla declarada por configuración
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleConfig {
+    pub category: String,
+    /// Regex sobre el texto original (números, correos, enlaces)
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Palabras o frases; se comparan sin mayúsculas, tildes ni "leet" (p0t4 = pota)
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// Tipos de contenido a los que aplica; vacío = todos
+    #[serde(default)]
+    pub applies_to: Vec<ContentKind>,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+struct CompiledRule {
+    category: String,
+    patterns: Option<Regex>,
+    keywords: Option<Regex>,
+    weight: f32,
+    applies_to: Vec<ContentKind>,
+}
+
+impl CompiledRule {
+    fn compile(config: &RuleConfig) -> Result<Self, regex::Error> {
+        let patterns = if config.patterns.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&config.patterns.join("|"))?)
+        };
+        let words: Vec<String> = config
+            .keywords
+            .iter()
+            .map(|k| normalize(k))
+            .filter(|k| !k.is_empty())
+            .map(|k| regex::escape(&k))
+            .collect();
+        let keywords = if words.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&format!(r"\b(?:{})\b", words.join("|")))?)
+        };
+        Ok(Self {
+            category: config.category.clone(),
+            patterns,
+            keywords,
+            weight: config.weight.clamp(0.0, 1.0),
+            applies_to: config.applies_to.clone(),
+        })
+    }
+
+    fn matches(&self, kind: ContentKind, original: &str, normalized: &str) -> bool {
+        if !self.applies_to.is_empty() && !self.applies_to.contains(&kind) {
+            return false;
+        }
+        self.patterns.as_ref().is_some_and(|re| re.is_match(original))
+            || self.keywords.as_ref().is_some_and(|re| re.is_match(normalized))
+    }
+}
+
+/// Minúsculas, sin tildes, la puntuación como separador y las sustituciones típicas para
+/// esquivar filtros deshechas. Los símbolos "leet" solo cuentan como letras dentro de una
+/// palabra: en "puta!" el signo final es puntuación, en "p!ta" es una i.
+fn normalize(text: &str) -> String {
+    let lowered: String = text.to_lowercase().chars().map(strip_accent).collect();
+    lowered
+        .split(|c: char| !(c.is_alphanumeric() || is_leet_symbol(c)))
+        .map(|token| token.trim_matches(is_leet_symbol))
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            // Un número suelto ("300", "2025") no es una palabra disfrazada
+            if token.chars().any(char::is_alphabetic) {
+                token.chars().map(unleet).collect()
+            } else {
+                token.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn strip_accent(c: char) -> char {
+    match c {
+        'á' | 'à' | 'ä' | 'â' => 'a',
+        'é' | 'è' | 'ë' | 'ê' => 'e',
+        'í' | 'ì' | 'ï' | 'î' => 'i',
+        'ó' | 'ò' | 'ö' | 'ô' => 'o',
+        'ú' | 'ù' | 'ü' | 'û' => 'u',
+        other => other,
+    }
+}
+
+fn is_leet_symbol(c: char) -> bool {
+    matches!(c, '@' | '$' | '!')
+}
+
+fn unleet(c: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '3' => 'e',
+        '1' | '!' => 'i',
+        '0' => 'o',
+        '5' | '$' => 's',
+        '7' => 't',
+        other => other,
+    }
+}
+
+/// Reglas incluidas: amenazas, acoso, odio, datos personales en publicaciones y spam de enlaces
+pub fn default_rules() -> Vec<RuleConfig> {
+    let rule = |category: &str, patterns: &[&str], keywords: &[&str], weight: f32, applies_to: &[ContentKind]| RuleConfig {
+        category: category.to_string(),
+        patterns: patterns.iter().map(|s| s.to_string()).collect(),
+        keywords: keywords.iter().map(|s| s.to_string()).collect(),
+        weight,
+        applies_to: applies_to.to_vec(),
+    };
+    vec![
+        rule(
+            "threat",
+            &[],
+            &["te voy a matar", "te mato", "matate", "suicidate", "i will kill you", "kill yourself", "kys"],
+            1.0,
+            &[],
+        ),
+        rule(
+            "harassment",
+            &[],
+            &["puta", "perra", "zorra", "gonorrea", "malparida", "hijueputa", "slut", "whore", "bitch"],
+            1.0,
+            &[],
+        ),
+        rule("hate", &[], &["sudaca", "maricon", "faggot", "retard", "retrasada", "retrasado"], 1.0, &[]),
+        // En el chat privado compartir un teléfono es normal; en el feed lo ve todo el estudio
+        rule(
+            "personal_data",
+            &[
+                r"\b(?:\d[ -]?){13,16}\b",
+                r"(?:\+?\d{1,3}[ .-]?)?\(?\d{3}\)?[ .-]?\d{3}[ .-]?\d{4}\b",
+                r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b",
+            ],
+            &[],
+            1.0,
+            &[ContentKind::Post, ContentKind::Comment],
+        ),
+        rule("spam", &[r"(?i)\b(?:bit\.ly|tinyurl\.com|t\.me|wa\.me|cutt\.ly)/\S+"], &[], 0.5, &[]),
+    ]
+}
+
+/// Clasificador por defecto: suma el peso de cada categoría que coincide
+pub struct RuleClassifier {
+    rules: Vec<CompiledRule>,
+    threshold: f32,
+}
+
+impl RuleClassifier {
+    /// Compila las reglas; una regla inválida se descarta (y se registra) sin tumbar las demás
+    pub fn new(configs: &[RuleConfig], threshold: f32) -> Self {
+        let rules = configs
+            .iter()
+            .filter_map(|config| match CompiledRule::compile(config) {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    tracing::error!("Regla de moderación '{}' inválida, se ignora: {}", config.category, e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules, threshold }
+    }
+
+    /// Incluidas + `MODERATION_RULES`/`MODERATION_RULES_FILE` + `MODERATION_KEYWORDS`
+    pub fn from_env() -> Self {
+        let mut configs = default_rules();
+        let raw = std::env::var("MODERATION_RULES").ok().or_else(|| {
+            std::env::var("MODERATION_RULES_FILE")
+                .ok()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+        });
+        if let Some(raw) = raw {
+            match serde_json::from_str::<Vec<RuleConfig>>(&raw) {
+                Ok(extra) => {
+                    tracing::info!("🛡️ {} reglas de moderación agregadas por configuración", extra.len());
+                    configs.extend(extra);
+                }
+                Err(e) => tracing::error!("MODERATION_RULES inválido, se usan solo las incluidas: {}", e),
+            }
+        }
+        if let Ok(raw) = std::env::var("MODERATION_KEYWORDS") {
+            let keywords: Vec<String> = raw.split(',').map(|k| k.trim().to_string()).filter(|k| !k.is_empty()).collect();
+            if !keywords.is_empty() {
+                configs.push(RuleConfig {
+                    category: "blocklist".to_string(),
+                    patterns: Vec::new(),
+                    keywords,
+                    weight: 1.0,
+                    applies_to: Vec::new(),
+                });
+            }
+        }
+        Self::new(&configs, DEFAULT_FLAG_THRESHOLD)
+    }
+
+    pub fn evaluate(&self, kind: ContentKind, text: &str) -> Verdict {
+        let normalized = normalize(text);
+        let mut categories: Vec<String> = Vec::new();
+        let mut score = 0.0_f32;
+        for rule in &self.rules {
+            if rule.matches(kind, text, &normalized) && !categories.contains(&rule.category) {
+                categories.push(rule.category.clone());
+                score += rule.weight;
+            }
+        }
+        let score = score.min(1.0);
+        Verdict { flagged: score >= self.threshold && !categories.is_empty(), categories, score }
+    }
+}
+
+#[async_trait]
+impl ContentClassifier for RuleClassifier {
+    fn name(&self) -> &str {
+        "rules"
+    }
+
+    async fn classify(&self, kind: ContentKind, text: &str) -> Verdict {
+        self.evaluate(kind, text)
+    }
+}
+
+static CLASSIFIER: OnceLock<Arc<dyn ContentClassifier>> = OnceLock::new();
+
+/// Reemplaza el clasificador por defecto; solo funciona antes del primer uso
+pub fn install_classifier(classifier: Arc<dyn ContentClassifier>) -> bool {
+    CLASSIFIER.set(classifier).is_ok()
+}
+
+/// Clasificador global (reglas del entorno si nadie instaló otro)
+pub fn classifier() -> Arc<dyn ContentClassifier> {
+    CLASSIFIER.get_or_init(|| Arc::new(RuleClassifier::from_env())).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> RuleClassifier {
+        RuleClassifier::new(&default_rules(), DEFAULT_FLAG_THRESHOLD)
+    }
+
+    #[test]
+    fn clean_text_passes() {
+        let verdict = defaults().evaluate(ContentKind::Post, "Hoy cerramos la sala 3 con récord de tokens 🎉");
+        assert_eq!(verdict, Verdict::clean());
+    }
+
+    #[test]
+    fn keywords_survive_accents_case_and_leet() {
+        let classifier = defaults();
+        for text in ["eres una PUTA", "Maricón", "3res una p3rr4", "te voy a matar mañana"] {
+            assert!(classifier.evaluate(ContentKind::ChatMessage, text).flagged, "{}", text);
+        }
+        // Palabra completa: "computadora" no es "puta"
+        assert!(!classifier.evaluate(ContentKind::ChatMessage, "la computadora se colgó").flagged);
+    }
+
+    #[test]
+    fn punctuation_is_a_word_boundary() {
+        let classifier = defaults();
+        for text in ["puta!", "¡¡PUTA!!", "eres una puta.", "hola,puta", "b!tch", "5lut"] {
+            assert!(classifier.evaluate(ContentKind::ChatMessage, text).flagged, "{}", text);
+        }
+        assert_eq!(normalize("¡Hola, p3rr4!"), "hola perra");
+        assert_eq!(normalize("sala 3 a las 10"), "sala 3 a las 10");
+    }
+
+    #[test]
+    fn personal_data_only_applies_to_public_content() {
+        let classifier = defaults();
+        let text = "escríbanme al 300 555 1234";
+        let verdict = classifier.evaluate(ContentKind::Post, text);
+        assert!(verdict.flagged);
+        assert_eq!(verdict.categories, vec!["personal_data".to_string()]);
+        assert!(!classifier.evaluate(ContentKind::ChatMessage, text).flagged);
+    }
+
+    #[test]
+    fn low_weight_rules_need_company_to_flag() {
+        let classifier = defaults();
+        let spam = classifier.evaluate(ContentKind::Post, "mira esto bit.ly/abc123");
+        assert_eq!(spam.categories, vec!["spam".to_string()]);
+        assert!(spam.flagged, "0.5 llega justo al umbral por defecto");
+
+        let strict = RuleClassifier::new(&default_rules(), 0.8);
+        assert!(!strict.evaluate(ContentKind::Post, "mira esto bit.ly/abc123").flagged);
+        assert!(strict.evaluate(ContentKind::Post, "bit.ly/abc123 zorra").flagged);
+    }
+
+    #[test]
+    fn invalid_rules_are_skipped() {
+        let mut configs = default_rules();
+        configs.push(RuleConfig {
+            category: "broken".to_string(),
+            patterns: vec!["(".to_string()],
+            keywords: Vec::new(),
+            weight: 1.0,
+            applies_to: Vec::new(),
+        });
+        let classifier = RuleClassifier::new(&configs, DEFAULT_FLAG_THRESHOLD);
+        assert_eq!(classifier.rules.len(), default_rules().len());
+    }
+
+    #[test]
+    fn rule_config_parses_with_defaults() {
+        let configs: Vec<RuleConfig> =
+            serde_json::from_str(r#"[{"category": "competencia", "keywords": ["otro estudio"]}]"#).unwrap();
+        assert_eq!(configs[0].weight, 1.0);
+        let classifier = RuleClassifier::new(&configs, DEFAULT_FLAG_THRESHOLD);
+        assert!(classifier.evaluate(ContentKind::Comment, "me voy a OTRO ESTUDIO").flagged);
+    }
+}
@@ -1,25 +1,105 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
-use chrono::{DateTime, Utc};
+use base64::Engine;
+use chrono::{DateTime, Datelike, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, PgExecutor, PgPool};
 use uuid::Uuid;
 
+use super::classifier::ContentKind;
+use super::moderation::{self, ModerationStatus};
+use crate::middleware::auth::AuthenticatedUser;
+use crate::operations::{is_staff, parse_user_id};
 use crate::state::AppState;
 
+/// Largo máximo de un post (igual que el CHECK de posts)
+pub const MAX_POST_LEN: usize = 5000;
+/// Largo máximo de un comentario (igual que el CHECK de comments)
+pub const MAX_COMMENT_LEN: usize = 1000;
+/// Profundidad máxima de un hilo: 0 = comentario al post, 1..=3 = respuestas
+pub const MAX_COMMENT_DEPTH: i16 = 3;
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+type ApiError = (StatusCode, String);
+
+/// Columnas del post vistas por `$1` (el lector), para `liked_by_me`
+const POST_COLUMNS: &str = r#"
+    p.id, p.user_id, p.content, p.media_url, p.visibility, p.room_id,
+    p.likes_count, p.comments_count, p.moderation_status, p.created_at,
+    COALESCE(u.display_name, u.username, u.email, 'Unknown') AS user_name,
+    NULL::TEXT AS user_avatar,
+    EXISTS (SELECT 1 FROM likes l WHERE l.post_id = p.id AND l.user_id = $1) AS liked_by_me
+    FROM posts p
+    LEFT JOIN users u ON u.id = p.user_id
+"#;
+
+/// Qué posts puede ver el lector (`$1` usuario, `$2` es staff, `$3` semana ISO actual).
+/// Staff ve todo, el autor ve lo suyo, ROOM solo quien tiene la sala asignada esta semana.
+/// Lo retenido por moderación solo lo ve su autor (los moderadores lo revisan en la cola).
+const VISIBLE_TO_VIEWER: &str = r#"
+    p.deleted_at IS NULL
+    AND (p.moderation_status = 'VISIBLE' OR p.user_id = $1)
+    AND (
+        $2 OR p.user_id = $1 OR p.visibility = 'STUDIO'
+        OR (p.visibility = 'ROOM' AND EXISTS (
+            SELECT 1 FROM user_shifts us
+            WHERE us.user_id = $1 AND us.week_id = $3 AND us.assigned_room = p.room_id
+        ))
+    )
+"#;
+
+const COMMENT_COLUMNS: &str = r#"
+    c.id, c.post_id, c.parent_id, c.depth, c.user_id,
+    CASE WHEN c.deleted_at IS NULL THEN c.content END AS content,
+    c.moderation_status, c.created_at, c.deleted_at,
+    COALESCE(u.display_name, u.username, u.email, 'Unknown') AS user_name,
+    (SELECT COUNT(*) FROM comments r
+      WHERE r.parent_id = c.id AND r.deleted_at IS NULL AND r.moderation_status = 'VISIBLE') AS replies_count
+    FROM comments c
+    LEFT JOIN users u ON u.id = c.user_id
+"#;
+
 // ============================================================================
 // TIPOS
 // ============================================================================
 
+/// Alcance de un post
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Visibility {
+    /// Todo el estudio
+    #[default]
+    Studio,
+    /// Quienes trabajan en la sala (`room_id`) esta semana
+    Room,
+    /// Solo moderadores y administración
+    Moderators,
+}
+
+impl Visibility {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Studio => "STUDIO",
+            Visibility::Room => "ROOM",
+            Visibility::Moderators => "MODERATORS",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreatePostRequest {
     pub content: String,
     pub media_url: Option<String>,
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// Obligatorio con visibilidad ROOM
+    pub room_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -28,17 +108,211 @@ pub struct PostResponse {
     pub user_id: Uuid,
     pub content: String,
     pub media_url: Option<String>,
+    pub visibility: String,
+    pub room_id: Option<i32>,
     pub likes_count: i32,
+    pub comments_count: i32,
+    /// VISIBLE, o PENDING mientras moderación lo revisa (solo lo ve el autor)
+    pub moderation_status: String,
     pub created_at: DateTime<Utc>,
     // Usuario que hizo el post
     pub user_name: String,
     pub user_avatar: Option<String>,
+    pub liked_by_me: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FeedResponse {
     pub posts: Vec<PostResponse>,
     pub total: i32,
+    /// Pasar como `cursor` para la página siguiente; `None` si no hay más
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeedQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    /// Solo posts de una sala
+    pub room_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCommentRequest {
+    pub content: String,
+    /// Comentario al que responde; `None` para comentar el post
+    pub parent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct CommentResponse {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub depth: i16,
+    pub user_id: Uuid,
+    /// `None` si fue borrado (queda en el hilo para no romper las respuestas)
+    pub content: Option<String>,
+    pub moderation_status: String,
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub user_name: String,
+    pub replies_count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentsQuery {
+    /// Respuestas de este comentario; sin él, los comentarios de primer nivel
+    pub parent_id: Option<Uuid>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentPage {
+    pub comments: Vec<CommentResponse>,
+    pub next_cursor: Option<String>,
+}
+
+/// Quién está leyendo, con lo necesario para `VISIBLE_TO_VIEWER`
+struct Viewer {
+    user_id: Uuid,
+    staff: bool,
+    week_id: String,
+}
+
+impl Viewer {
+    fn from_auth(auth: &AuthenticatedUser) -> Result<Self, ApiError> {
+        let today = Utc::now().date_naive();
+        Ok(Self {
+            user_id: parse_user_id(&auth.user_id)?,
+            staff: is_staff(&auth.role),
+            week_id: format!("{}-W{:02}", today.iso_week().year(), today.iso_week().week()),
+        })
+    }
+}
+
+// ============================================================================
+// REGLAS
+// ============================================================================
+
+/// Cursor opaco `(created_at, id)`: estable aunque entren posts nuevos entre páginas
+pub fn encode_cursor(created_at: DateTime<Utc>, id: Uuid) -> String {
+    let raw = format!("{}_{}", created_at.timestamp_micros(), id);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw)
+}
+
+pub fn decode_cursor(cursor: &str) -> Option<(DateTime<Utc>, Uuid)> {
+    let raw = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    let raw = String::from_utf8(raw).ok()?;
+    let (micros, id) = raw.split_once('_')?;
+    let created_at = Utc.timestamp_micros(micros.parse().ok()?).single()?;
+    Some((created_at, Uuid::parse_str(id).ok()?))
+}
+
+fn parse_cursor(cursor: Option<&str>) -> Result<(Option<DateTime<Utc>>, Option<Uuid>), ApiError> {
+    match cursor {
+        None => Ok((None, None)),
+        Some(raw) => decode_cursor(raw)
+            .map(|(at, id)| (Some(at), Some(id)))
+            .ok_or((StatusCode::BAD_REQUEST, "Invalid cursor".to_string())),
+    }
+}
+
+fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Valida el alcance pedido al publicar. `in_room` = el autor tiene esa sala asignada esta semana.
+fn validate_scope(visibility: Visibility, room_id: Option<i32>, staff: bool, in_room: bool) -> Result<(), ApiError> {
+    match (visibility, room_id) {
+        (Visibility::Room, None) => Err((StatusCode::BAD_REQUEST, "room_id is required for ROOM posts".to_string())),
+        (Visibility::Room, Some(_)) if !staff && !in_room => {
+            Err((StatusCode::FORBIDDEN, "You are not assigned to this room".to_string()))
+        }
+        (Visibility::Room, Some(_)) => Ok(()),
+        (_, Some(_)) => Err((StatusCode::BAD_REQUEST, "room_id is only valid for ROOM posts".to_string())),
+        (Visibility::Moderators, None) if !staff => {
+            Err((StatusCode::FORBIDDEN, "Only moderators can post to moderators".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// El autor o un moderador pueden borrar
+fn can_delete(author_id: Uuid, viewer_id: Uuid, staff: bool) -> bool {
+    staff || author_id == viewer_id
+}
+
+fn validate_content(content: &str, max_len: usize) -> Result<String, ApiError> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "content is required".to_string()));
+    }
+    if content.chars().count() > max_len {
+        return Err((StatusCode::BAD_REQUEST, format!("content must be <= {} characters", max_len)));
+    }
+    Ok(content.to_string())
+}
+
+fn db_error(context: &str, e: sqlx::Error) -> ApiError {
+    tracing::error!("DB error {}: {}", context, e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed {}", context))
+}
+
+/// Post visible para el lector; 404 si no existe, está borrado o no le corresponde verlo
+async fn load_visible_post(db: &PgPool, viewer: &Viewer, post_id: Uuid) -> Result<PostResponse, ApiError> {
+    sqlx::query_as::<_, PostResponse>(&format!(
+        "SELECT {} WHERE p.id = $4 AND {}",
+        POST_COLUMNS, VISIBLE_TO_VIEWER
+    ))
+    .bind(viewer.user_id)
+    .bind(viewer.staff)
+    .bind(&viewer.week_id)
+    .bind(post_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| db_error("loading post", e))?
+    .ok_or((StatusCode::NOT_FOUND, "Post not found".to_string()))
+}
+
+/// Post que el usuario autenticado puede ver (para reportes y moderación)
+pub(crate) async fn visible_post(db: &PgPool, auth: &AuthenticatedUser, post_id: Uuid) -> Result<PostResponse, ApiError> {
+    let viewer = Viewer::from_auth(auth)?;
+    load_visible_post(db, &viewer, post_id).await
+}
+
+async fn refresh_likes_count(db: &PgPool, post_id: Uuid) -> Result<i32, ApiError> {
+    sqlx::query_scalar::<_, i32>(
+        r#"
+        UPDATE posts
+        SET likes_count = (SELECT COUNT(*) FROM likes WHERE post_id = $1)
+        WHERE id = $1
+        RETURNING likes_count
+        "#,
+    )
+    .bind(post_id)
+    .fetch_one(db)
+    .await
+    .map_err(|e| db_error("updating likes count", e))
+}
+
+pub(crate) async fn refresh_comments_count<'e, E: PgExecutor<'e>>(db: E, post_id: Uuid) -> Result<i32, ApiError> {
+    sqlx::query_scalar::<_, i32>(
+        r#"
+        UPDATE posts
+        SET comments_count = (
+            SELECT COUNT(*) FROM comments
+            WHERE post_id = $1 AND deleted_at IS NULL AND moderation_status = 'VISIBLE'
+        )
+        WHERE id = $1
+        RETURNING comments_count
+        "#,
+    )
+    .bind(post_id)
+    .fetch_one(db)
+    .await
+    .map_err(|e| db_error("updating comments count", e))
 }
 
 // ============================================================================
@@ -46,104 +320,156 @@ pub struct FeedResponse {
 // ============================================================================
 
 /// POST /api/social/posts
-/// Crear un nuevo post
+/// Crear un nuevo post a nombre del usuario autenticado
 pub async fn create_post(
     State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
     Json(req): Json<CreatePostRequest>,
-) -> Result<(StatusCode, Json<PostResponse>), (StatusCode, String)> {
-    let user_id = Uuid::new_v4(); // En producción, obtener del JWT
+) -> Result<(StatusCode, Json<PostResponse>), ApiError> {
+    let viewer = Viewer::from_auth(&auth)?;
+    let content = validate_content(&req.content, MAX_POST_LEN)?;
+    moderation::ensure_not_muted(&state.db, viewer.user_id).await?;
 
-    if req.content.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "content is required".to_string()));
-    }
-
-    if req.content.len() > 5000 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "content must be <= 5000 characters".to_string(),
-        ));
-    }
+    let in_room = match req.room_id {
+        Some(room_id) if req.visibility == Visibility::Room && !viewer.staff => sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM user_shifts WHERE user_id = $1 AND week_id = $2 AND assigned_room = $3)",
+        )
+        .bind(viewer.user_id)
+        .bind(&viewer.week_id)
+        .bind(room_id)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| db_error("checking room assignment", e))?,
+        _ => false,
+    };
+    validate_scope(req.visibility, req.room_id, viewer.staff, in_room)?;
+    let verdict = moderation::screen(ContentKind::Post, &content).await;
+    let status = moderation::status_for(&verdict);
 
-    let post = sqlx::query_as::<_, PostResponse>(
+    let mut tx = state.db.begin().await.map_err(|e| db_error("starting post transaction", e))?;
+    let post_id = sqlx::query_scalar::<_, Uuid>(
         r#"
-        INSERT INTO posts (user_id, content, media_url, likes_count, created_at)
-        VALUES ($1, $2, $3, 0, NOW())
-        RETURNING 
-            posts.id, 
-            posts.user_id, 
-            posts.content, 
-            posts.media_url,
-            posts.likes_count,
-            posts.created_at,
-            COALESCE(users.name, 'Unknown') as user_name,
-            users.avatar as user_avatar
-        FROM posts
-        LEFT JOIN users ON posts.user_id = users.id
-        WHERE posts.user_id = $1
-        ORDER BY posts.created_at DESC
-        LIMIT 1
+        INSERT INTO posts (user_id, content, media_url, visibility, room_id, moderation_status)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
         "#,
     )
-    .bind(user_id)
-    .bind(&req.content)
+    .bind(viewer.user_id)
+    .bind(&content)
     .bind(&req.media_url)
-    .fetch_one(&state.db)
+    .bind(req.visibility.as_str())
+    .bind(req.room_id)
+    .bind(status.as_str())
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
-        tracing::error!("DB error creating post: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to create post".to_string(),
-        )
+        if matches!(&e, sqlx::Error::Database(db) if db.code().as_deref() == Some("23503")) {
+            return (StatusCode::NOT_FOUND, "Room not found".to_string());
+        }
+        db_error("creating post", e)
     })?;
 
+    let held = if status == ModerationStatus::Pending {
+        Some(moderation::hold_for_review(&mut tx, ContentKind::Post, post_id, viewer.user_id, &verdict).await?)
+    } else {
+        None
+    };
+    tx.commit().await.map_err(|e| db_error("committing post", e))?;
+
+    tracing::info!("📝 Post {} created by {} ({})", post_id, viewer.user_id, req.visibility.as_str());
+    if let Some(case_id) = held {
+        moderation::announce_held(&state.realtime_hub, ContentKind::Post, post_id, case_id, &verdict);
+    }
+    let post = load_visible_post(&state.db, &viewer, post_id).await?;
     Ok((StatusCode::CREATED, Json(post)))
 }
 
-/// GET /api/social/feed
-/// Obtener feed de los últimos 50 posts
+/// GET /api/social/feed?cursor=...&limit=20&room_id=3
+/// Posts visibles para el usuario, del más nuevo al más viejo
 pub async fn get_feed(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<FeedResponse>, (StatusCode, String)> {
-    let posts = sqlx::query_as::<_, PostResponse>(
+    auth: AuthenticatedUser,
+    Query(query): Query<FeedQuery>,
+) -> Result<Json<FeedResponse>, ApiError> {
+    let viewer = Viewer::from_auth(&auth)?;
+    let (cursor_at, cursor_id) = parse_cursor(query.cursor.as_deref())?;
+    let limit = page_size(query.limit);
+
+    let posts = sqlx::query_as::<_, PostResponse>(&format!(
         r#"
-        SELECT 
-            posts.id,
-            posts.user_id,
-            posts.content,
-            posts.media_url,
-            posts.likes_count,
-            posts.created_at,
-            COALESCE(users.name, 'Unknown') as user_name,
-            users.avatar as user_avatar
-        FROM posts
-        LEFT JOIN users ON posts.user_id = users.id
-        ORDER BY posts.created_at DESC
-        LIMIT 50
+        SELECT {}
+        WHERE {}
+          AND ($4::TIMESTAMPTZ IS NULL OR (p.created_at, p.id) < ($4, $5))
+          AND ($6::INT IS NULL OR p.room_id = $6)
+        ORDER BY p.created_at DESC, p.id DESC
+        LIMIT $7
         "#,
-    )
+        POST_COLUMNS, VISIBLE_TO_VIEWER
+    ))
+    .bind(viewer.user_id)
+    .bind(viewer.staff)
+    .bind(&viewer.week_id)
+    .bind(cursor_at)
+    .bind(cursor_id)
+    .bind(query.room_id)
+    .bind(limit)
     .fetch_all(&state.db)
     .await
-    .map_err(|e| {
-        tracing::error!("DB error fetching feed: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to fetch feed".to_string(),
-        )
-    })?;
+    .map_err(|e| db_error("fetching feed", e))?;
 
+    let next_cursor = if posts.len() as i64 == limit {
+        posts.last().map(|p| encode_cursor(p.created_at, p.id))
+    } else {
+        None
+    };
     let total = posts.len() as i32;
 
-    Ok(Json(FeedResponse { posts, total }))
+    Ok(Json(FeedResponse { posts, total, next_cursor }))
+}
+
+/// GET /api/social/posts/:id
+pub async fn get_post(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(post_id): Path<Uuid>,
+) -> Result<Json<PostResponse>, ApiError> {
+    let viewer = Viewer::from_auth(&auth)?;
+    Ok(Json(load_visible_post(&state.db, &viewer, post_id).await?))
+}
+
+/// DELETE /api/social/posts/:id
+/// Borrado lógico por el autor o un moderador
+pub async fn delete_post(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(post_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let viewer = Viewer::from_auth(&auth)?;
+    let post = load_visible_post(&state.db, &viewer, post_id).await?;
+    if !can_delete(post.user_id, viewer.user_id, viewer.staff) {
+        return Err((StatusCode::FORBIDDEN, "Only the author or a moderator can delete this post".to_string()));
+    }
+
+    sqlx::query("UPDATE posts SET deleted_at = NOW(), deleted_by = $2, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
+        .bind(post_id)
+        .bind(viewer.user_id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| db_error("deleting post", e))?;
+
+    tracing::info!("🗑️ Post {} deleted by {}", post_id, viewer.user_id);
+    Ok(StatusCode::NO_CONTENT)
 }
 
 /// POST /api/social/posts/{id}/like
-/// Incrementar likes en un post
+/// Dar like a un post visible
 pub async fn like_post(
     State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
     Path(post_id): Path<Uuid>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let user_id = Uuid::new_v4(); // En producción, obtener del JWT
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let viewer = Viewer::from_auth(&auth)?;
+    load_visible_post(&state.db, &viewer, post_id).await?;
 
     // Intentar insertar el like (constraint unique evita duplicados)
     let result = sqlx::query(
@@ -154,45 +480,261 @@ pub async fn like_post(
         "#,
     )
     .bind(post_id)
-    .bind(user_id)
+    .bind(viewer.user_id)
     .execute(&state.db)
     .await
-    .map_err(|e| {
-        tracing::error!("DB error adding like: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to like post".to_string(),
-        )
-    })?;
+    .map_err(|e| db_error("liking post", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::CONFLICT, "User already liked this post".to_string()));
+    }
+
+    let likes_count = refresh_likes_count(&state.db, post_id).await?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "message": "Post liked successfully",
+        "likes_count": likes_count
+    })))
+}
+
+/// DELETE /api/social/posts/{id}/like
+/// Quitar el like propio
+pub async fn unlike_post(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(post_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let viewer = Viewer::from_auth(&auth)?;
+    load_visible_post(&state.db, &viewer, post_id).await?;
+
+    let result = sqlx::query("DELETE FROM likes WHERE post_id = $1 AND user_id = $2")
+        .bind(post_id)
+        .bind(viewer.user_id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| db_error("unliking post", e))?;
 
     if result.rows_affected() == 0 {
-        return Err((
-            StatusCode::CONFLICT,
-            "User already liked this post".to_string(),
-        ));
+        return Err((StatusCode::NOT_FOUND, "User has not liked this post".to_string()));
     }
 
-    // Actualizar contador
-    sqlx::query(
+    let likes_count = refresh_likes_count(&state.db, post_id).await?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "message": "Like removed",
+        "likes_count": likes_count
+    })))
+}
+
+/// GET /api/social/posts/:id/comments?parent_id=...&cursor=...&limit=20
+/// Un nivel del hilo, del más viejo al más nuevo; `replies_count` indica si hay que abrir respuestas
+pub async fn list_comments(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(post_id): Path<Uuid>,
+    Query(query): Query<CommentsQuery>,
+) -> Result<Json<CommentPage>, ApiError> {
+    let viewer = Viewer::from_auth(&auth)?;
+    load_visible_post(&state.db, &viewer, post_id).await?;
+    let (cursor_at, cursor_id) = parse_cursor(query.cursor.as_deref())?;
+    let limit = page_size(query.limit);
+
+    let comments = sqlx::query_as::<_, CommentResponse>(&format!(
         r#"
-        UPDATE posts
-        SET likes_count = (SELECT COUNT(*) FROM likes WHERE post_id = $1)
-        WHERE id = $1
+        SELECT {}
+        WHERE c.post_id = $1
+          AND c.parent_id IS NOT DISTINCT FROM $2
+          AND ($3::TIMESTAMPTZ IS NULL OR (c.created_at, c.id) > ($3, $4))
+          AND (c.moderation_status <> 'PENDING' OR c.user_id = $6)
+        ORDER BY c.created_at ASC, c.id ASC
+        LIMIT $5
+        "#,
+        COMMENT_COLUMNS
+    ))
+    .bind(post_id)
+    .bind(query.parent_id)
+    .bind(cursor_at)
+    .bind(cursor_id)
+    .bind(limit)
+    .bind(viewer.user_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("loading comments", e))?;
+
+    let next_cursor = if comments.len() as i64 == limit {
+        comments.last().map(|c| encode_cursor(c.created_at, c.id))
+    } else {
+        None
+    };
+    Ok(Json(CommentPage { comments, next_cursor }))
+}
+
+/// POST /api/social/posts/:id/comments
+/// Comentar el post o responder a un comentario (`parent_id`)
+pub async fn create_comment(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(post_id): Path<Uuid>,
+    Json(req): Json<CreateCommentRequest>,
+) -> Result<(StatusCode, Json<CommentResponse>), ApiError> {
+    let viewer = Viewer::from_auth(&auth)?;
+    load_visible_post(&state.db, &viewer, post_id).await?;
+    let content = validate_content(&req.content, MAX_COMMENT_LEN)?;
+    moderation::ensure_not_muted(&state.db, viewer.user_id).await?;
+
+    let depth = match req.parent_id {
+        None => 0,
+        Some(parent_id) => {
+            let parent_depth = sqlx::query_scalar::<_, i16>(
+                r#"
+                SELECT depth FROM comments
+                WHERE id = $1 AND post_id = $2 AND deleted_at IS NULL
+                  AND (moderation_status = 'VISIBLE' OR user_id = $3)
+                "#,
+            )
+            .bind(parent_id)
+            .bind(post_id)
+            .bind(viewer.user_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| db_error("loading parent comment", e))?
+            .ok_or((StatusCode::NOT_FOUND, "Parent comment not found".to_string()))?;
+            if parent_depth >= MAX_COMMENT_DEPTH {
+                return Err((StatusCode::BAD_REQUEST, "Thread is too deep to reply".to_string()));
+            }
+            parent_depth + 1
+        }
+    };
+
+    let verdict = moderation::screen(ContentKind::Comment, &content).await;
+    let status = moderation::status_for(&verdict);
+
+    let mut tx = state.db.begin().await.map_err(|e| db_error("starting comment transaction", e))?;
+    let comment_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO comments (post_id, user_id, content, parent_id, depth, moderation_status)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
         "#,
     )
     .bind(post_id)
-    .execute(&state.db)
+    .bind(viewer.user_id)
+    .bind(&content)
+    .bind(req.parent_id)
+    .bind(depth)
+    .bind(status.as_str())
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| {
-        tracing::error!("DB error updating likes count: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to update likes count".to_string(),
-        )
-    })?;
+    .map_err(|e| db_error("creating comment", e))?;
+    refresh_comments_count(&mut *tx, post_id).await?;
+    let held = if status == ModerationStatus::Pending {
+        Some(moderation::hold_for_review(&mut tx, ContentKind::Comment, comment_id, viewer.user_id, &verdict).await?)
+    } else {
+        None
+    };
+    tx.commit().await.map_err(|e| db_error("committing comment", e))?;
+    if let Some(case_id) = held {
+        moderation::announce_held(&state.realtime_hub, ContentKind::Comment, comment_id, case_id, &verdict);
+    }
 
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "message": "Post liked successfully"
-    })))
+    let comment = sqlx::query_as::<_, CommentResponse>(&format!("SELECT {} WHERE c.id = $1", COMMENT_COLUMNS))
+        .bind(comment_id)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| db_error("loading comment", e))?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+/// DELETE /api/social/comments/:id
+/// Borrado lógico por el autor o un moderador; las respuestas quedan colgando de la lápida
+pub async fn delete_comment(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(comment_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let viewer = Viewer::from_auth(&auth)?;
+    let (post_id, author_id) = sqlx::query_as::<_, (Uuid, Uuid)>(
+        "SELECT post_id, user_id FROM comments WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(comment_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| db_error("loading comment", e))?
+    .ok_or((StatusCode::NOT_FOUND, "Comment not found".to_string()))?;
+    load_visible_post(&state.db, &viewer, post_id).await?;
+    if !can_delete(author_id, viewer.user_id, viewer.staff) {
+        return Err((StatusCode::FORBIDDEN, "Only the author or a moderator can delete this comment".to_string()));
+    }
+
+    sqlx::query("UPDATE comments SET deleted_at = NOW(), deleted_by = $2 WHERE id = $1 AND deleted_at IS NULL")
+        .bind(comment_id)
+        .bind(viewer.user_id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| db_error("deleting comment", e))?;
+    refresh_comments_count(&state.db, post_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_roundtrip_keeps_microseconds() {
+        let at = Utc.timestamp_micros(1_765_432_100_123_456).unwrap();
+        let id = Uuid::new_v4();
+        assert_eq!(decode_cursor(&encode_cursor(at, id)), Some((at, id)));
+    }
+
+    #[test]
+    fn garbage_cursors_are_rejected() {
+        assert_eq!(decode_cursor("not-a-cursor"), None);
+        assert_eq!(decode_cursor(&base64::engine::general_purpose::URL_SAFE_NO_PAD.encode("12_nope")), None);
+        assert!(parse_cursor(Some("???")).is_err());
+        assert_eq!(parse_cursor(None).unwrap(), (None, None));
+    }
+
+    #[test]
+    fn room_posts_need_a_room_the_author_works_in() {
+        assert_eq!(validate_scope(Visibility::Room, None, true, false).unwrap_err().0, StatusCode::BAD_REQUEST);
+        assert_eq!(validate_scope(Visibility::Room, Some(3), false, false).unwrap_err().0, StatusCode::FORBIDDEN);
+        assert!(validate_scope(Visibility::Room, Some(3), false, true).is_ok());
+        assert!(validate_scope(Visibility::Room, Some(3), true, false).is_ok());
+        assert_eq!(validate_scope(Visibility::Studio, Some(3), false, true).unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn only_staff_can_post_to_moderators() {
+        assert_eq!(validate_scope(Visibility::Moderators, None, false, false).unwrap_err().0, StatusCode::FORBIDDEN);
+        assert!(validate_scope(Visibility::Moderators, None, true, false).is_ok());
+        assert!(validate_scope(Visibility::Studio, None, false, false).is_ok());
+    }
+
+    #[test]
+    fn author_or_moderator_can_delete() {
+        let author = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        assert!(can_delete(author, author, false));
+        assert!(can_delete(author, other, true));
+        assert!(!can_delete(author, other, false));
+    }
+
+    #[test]
+    fn content_is_trimmed_and_bounded() {
+        assert_eq!(validate_content("  hola  ", MAX_COMMENT_LEN).unwrap(), "hola");
+        assert!(validate_content("   ", MAX_COMMENT_LEN).is_err());
+        assert!(validate_content(&"ñ".repeat(MAX_COMMENT_LEN), MAX_COMMENT_LEN).is_ok());
+        assert!(validate_content(&"ñ".repeat(MAX_COMMENT_LEN + 1), MAX_COMMENT_LEN).is_err());
+    }
+
+    #[test]
+    fn visibility_defaults_to_studio() {
+        let req: CreatePostRequest = serde_json::from_str(r#"{"content": "hola", "media_url": null}"#).unwrap();
+        assert_eq!(req.visibility, Visibility::Studio);
+        let req: CreatePostRequest =
+            serde_json::from_str(r#"{"content": "hola", "visibility": "ROOM", "room_id": 2}"#).unwrap();
+        assert_eq!((req.visibility, req.room_id), (Visibility::Room, Some(2)));
+    }
 }
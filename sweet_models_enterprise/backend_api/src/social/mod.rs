@@ -1,144 +1,227 @@
 pub mod feed;
+pub mod calls;
+pub mod chat;
 pub mod chat_notifications;
+pub mod classifier;
+pub mod moderation;
+pub mod peers;
+pub mod presence;
 
-use std::{collections::HashMap, sync::Arc, time::SystemTime};
+use std::{sync::Arc, time::Duration};
 
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
         Query, State,
     },
+    http::StatusCode,
     response::IntoResponse,
-    routing::{get, post},
-    Router,
+    routing::{delete, get, patch, post},
+    Json, Router,
 };
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tokio::sync::{broadcast, mpsc, RwLock};
+use sqlx::PgPool;
+use tokio::sync::{broadcast, mpsc};
 use uuid::Uuid;
 
+use crate::middleware::auth::{AuthenticatedUser, WebSocketUser, WS_BEARER_PROTOCOL};
+use crate::operations::parse_user_id;
+use crate::realtime::{ClusterNode, RealtimeHub};
 use crate::state::AppState;
+use chat_notifications::ChatNotificationManager;
+pub use peers::PeerRegistry;
 
 #[derive(Clone)]
 pub struct ChatState {
+    pub db: PgPool,
     pub tx: broadcast::Sender<String>,
-    pub peers: Arc<RwLock<HashMap<Uuid, mpsc::UnboundedSender<Message>>>>,
-    pub presence: Arc<RwLock<HashMap<Uuid, SystemTime>>>,
+    pub peers: Arc<PeerRegistry>,
+    /// Réplica actual; `None` en despliegues de un solo nodo
+    pub cluster: Option<ClusterNode>,
+    /// Push para quien no tiene socket abierto; `None` si FCM no está configurado
+    pub notifier: Option<Arc<ChatNotificationManager>>,
+    /// Avisos en vivo a moderadores (casos nuevos) y a usuarios sancionados
+    pub hub: Arc<RealtimeHub>,
 }
 
-pub fn social_routes_chat() -> Router<Arc<ChatState>> {
+impl ChatState {
+    /// Chat broadcast a todos los conectados, en este y en los demás nodos
+    pub async fn broadcast(&self, text: String) {
+        let _ = self.tx.send(text.clone());
+        if let Some(node) = &self.cluster {
+            if let Err(e) = node.nats.publish(peers::CHAT_BROADCAST_SUBJECT, node.encode(&text).into()).await {
+                tracing::warn!("Failed to publish chat broadcast to cluster: {}", e);
+            }
+        }
+    }
+}
+
+pub fn social_routes_chat() -> Router<Arc<AppState>> {
     Router::new()
         .route("/ws", get(websocket_handler))
-        .route("/presence", axum::routing::post(register_presence))
-        .route("/presence", axum::routing::get(list_presence))
+        .route("/presence", post(register_presence).get(list_presence))
+        .route("/calls", get(calls::call_log_handler).post(calls::start_call_handler))
+        .route("/calls/active", get(calls::active_call_handler))
+        .route("/calls/turn", get(calls::turn_credentials_handler))
+        .route("/calls/:id", get(calls::get_call_handler))
+        .route("/calls/:id/accept", post(calls::accept_call_handler))
+        .route("/calls/:id/decline", post(calls::decline_call_handler))
+        .route("/calls/:id/end", post(calls::end_call_handler))
 }
 
 pub fn social_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/posts", post(feed::create_post))
         .route("/feed", get(feed::get_feed))
-        .route("/posts/:id/like", post(feed::like_post))
+        .route("/posts/:id", get(feed::get_post).delete(feed::delete_post))
+        .route("/posts/:id/like", post(feed::like_post).delete(feed::unlike_post))
+        .route("/posts/:id/comments", get(feed::list_comments).post(feed::create_comment))
+        .route("/comments/:id", delete(feed::delete_comment))
+        .route("/reports", post(moderation::report_handler))
+        .route("/moderation/queue", get(moderation::queue_handler))
+        .route("/moderation/cases/:id", get(moderation::get_case_handler))
+        .route("/moderation/cases/:id/resolve", post(moderation::resolve_case_handler))
+        .route("/moderation/mutes", get(moderation::list_mutes_handler).post(moderation::mute_user_handler))
+        .route("/moderation/mutes/:user_id", delete(moderation::unmute_user_handler))
+        .route("/moderation/audit", get(moderation::audit_log_handler))
+        .route("/chat/conversations", get(chat::list_conversations_handler).post(chat::create_conversation_handler))
+        .route("/chat/conversations/:id", get(chat::get_conversation_handler))
+        .route("/chat/conversations/:id/messages", get(chat::history_handler).post(chat::send_message_handler))
+        .route("/chat/conversations/:id/receipts", post(chat::receipt_handler))
+        .route("/chat/messages/:id", patch(chat::edit_message_handler).delete(chat::delete_message_handler))
 }
 
 #[derive(Debug, Deserialize)]
 pub struct WsParams {
-    pub user_id: Uuid,
+    /// Id estable del dispositivo (para presencia); si falta se usa uno por conexión
+    pub device_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PresencePayload {
-    pub user_id: Uuid,
+    pub device_id: Option<String>,
 }
 
-/// Mensaje de señalización WebRTC
+/// Mensaje de señalización WebRTC; solo se reenvía dentro de una llamada activa (`call_id`)
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SignalMessage {
     #[serde(rename = "type")]
     pub msg_type: String,
     pub target_id: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call_id: Option<Uuid>,
     pub payload: Value,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub from_id: Option<Uuid>,
 }
 
+/// GET /api/chat/ws
+/// El usuario sale del JWT (header `Authorization` o `Sec-WebSocket-Protocol: bearer, <jwt>`)
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     Query(params): Query<WsParams>,
-    State(state): State<Arc<ChatState>>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(params.user_id, socket, state))
+    State(state): State<Arc<AppState>>,
+    auth: WebSocketUser,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user.user_id)?;
+    let device_id = presence::normalize_device_id(params.device_id.as_deref());
+    let ws = if auth.via_protocol { ws.protocols([WS_BEARER_PROTOCOL]) } else { ws };
+    Ok(ws.on_upgrade(move |socket| handle_socket(user_id, device_id, socket, state)))
 }
 
-/// Registra presencia del usuario (para prevalidar conexiones WS)
+/// POST /api/chat/presence
+/// Heartbeat REST para clientes sin socket abierto; expira tras `PRESENCE_TTL_SECS`
 pub async fn register_presence(
-    State(state): State<Arc<ChatState>>,
-    axum::Json(payload): axum::Json<PresencePayload>,
-) -> impl IntoResponse {
-    state
-        .presence
-        .write()
-        .await
-        .insert(payload.user_id, SystemTime::now());
-
-    axum::Json(serde_json::json!({
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Json(payload): Json<PresencePayload>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let device_id = presence::normalize_device_id(payload.device_id.as_deref());
+    let expires_at = presence::heartbeat(&state.redis, user_id, &device_id).await.map_err(|e| {
+        tracing::error!("Redis error registering presence: {}", e);
+        (StatusCode::SERVICE_UNAVAILABLE, "Presence unavailable".to_string())
+    })?;
+
+    Ok(Json(serde_json::json!({
         "status": "ok",
-        "user_id": payload.user_id,
-    }))
+        "user_id": user_id,
+        "device_id": device_id,
+        "expires_at": expires_at,
+    })))
 }
 
-/// Lista de usuarios con presencia registrada (online)
+/// GET /api/chat/presence
+/// Usuarios con algún dispositivo vivo en cualquier réplica
 pub async fn list_presence(
-    State(state): State<Arc<ChatState>>,
-) -> impl IntoResponse {
-    let users: Vec<_> = state
-        .presence
-        .read()
-        .await
-        .keys()
-        .cloned()
-        .collect();
-
-    axum::Json(serde_json::json!({
+    State(state): State<Arc<AppState>>,
+    _auth: AuthenticatedUser,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let online = presence::online_users(&state.redis).await.map_err(|e| {
+        tracing::error!("Redis error listing presence: {}", e);
+        (StatusCode::SERVICE_UNAVAILABLE, "Presence unavailable".to_string())
+    })?;
+    let users: Vec<Uuid> = online.keys().copied().collect();
+
+    Ok(Json(serde_json::json!({
         "status": "ok",
         "online": users,
-    }))
+        "devices": online,
+    })))
 }
 
-async fn handle_socket(user_id: Uuid, stream: WebSocket, state: Arc<ChatState>) {
+async fn handle_socket(user_id: Uuid, device_id: String, stream: WebSocket, app: Arc<AppState>) {
+    let state = app.chat.clone();
     let (mut ws_sender, mut ws_receiver) = stream.split();
     let mut rx = state.tx.subscribe();
-    let tx = state.tx.clone();
     let state_for_recv = state.clone();
 
-    // Canal dedicado para envíos directos a este usuario
+    // Canal dedicado para envíos directos a este dispositivo (también desde otros nodos)
     let (direct_tx, mut direct_rx) = mpsc::unbounded_channel::<Message>();
-    {
-        let mut peers = state.peers.write().await;
-        peers.insert(user_id, direct_tx);
+    let connection_id = state.peers.register(user_id, direct_tx.clone()).await;
+    let reply_tx = direct_tx;
+    if let Err(e) = presence::heartbeat(&app.redis, user_id, &device_id).await {
+        tracing::warn!("Presence heartbeat failed for {}: {}", user_id, e);
     }
 
+    let redis = app.redis.clone();
+    let heartbeat_device = device_id.clone();
     let mut send_task = tokio::spawn(async move {
+        let mut heartbeat = tokio::time::interval(Duration::from_secs(presence::HEARTBEAT_INTERVAL_SECS));
+        heartbeat.tick().await;
         loop {
             tokio::select! {
-                // Mensajes broadcast existentes (chat)
+                // Avisos broadcast para todos los conectados
                 Ok(msg) = rx.recv() => {
                     if ws_sender.send(Message::Text(msg)).await.is_err() {
                         break;
                     }
                 }
-                // Mensajes directos de señalización
+                // Mensajes directos: chat persistente y señalización
                 Some(msg) = direct_rx.recv() => {
                     if ws_sender.send(msg).await.is_err() {
                         break;
                     }
                 }
+                // Ping al cliente y renovación de la presencia mientras el socket viva
+                _ = heartbeat.tick() => {
+                    if ws_sender.send(Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
+                    if let Err(e) = presence::heartbeat(&redis, user_id, &heartbeat_device).await {
+                        tracing::warn!("Presence heartbeat failed for {}: {}", user_id, e);
+                    }
+                }
             }
         }
     });
 
     let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(msg)) = ws_receiver.next().await {
+        // Sin ningún frame (ni pong) durante el TTL el cliente se da por caído
+        let idle = Duration::from_secs(presence::PRESENCE_TTL_SECS as u64);
+        while let Ok(Some(Ok(msg))) = tokio::time::timeout(idle, ws_receiver.next()).await {
             match msg {
                 Message::Text(text) => {
                     // Intentar parsear como señalización WebRTC
@@ -154,28 +237,44 @@ async fn handle_socket(user_id: Uuid, stream: WebSocket, state: Arc<ChatState>)
                         );
 
                         if is_signal {
-                            // Buscar target conectado y reenviar inmediatamente
-                            if let Some(target_tx) = state_for_recv.peers.read().await.get(&signal.target_id).cloned() {
-                                if let Ok(payload) = serde_json::to_string(&signal) {
-                                    let _ = target_tx.send(Message::Text(payload));
-                                }
-                            } else {
+                            // La señal tiene que pertenecer a una llamada activa entre ambos
+                            let check = match signal.call_id {
+                                Some(call_id) => calls::authorize_signal(&state_for_recv, call_id, user_id, signal.target_id, kind)
+                                    .await
+                                    .map_err(|(_, e)| e),
+                                None => Err("call_id is required".to_string()),
+                            };
+                            if let Err(message) = check {
+                                let err = serde_json::json!({"type": "error", "message": message, "target_id": signal.target_id});
+                                let _ = reply_tx.send(Message::Text(err.to_string()));
+                                continue;
+                            }
+
+                            // Reenviar al target, esté en este nodo o en otra réplica
+                            let payload = serde_json::to_string(&signal).unwrap_or_default();
+                            if !state_for_recv.peers.send_to(signal.target_id, payload).await {
                                 let err = format!(
                                     "{{\"type\":\"error\",\"message\":\"Usuario no disponible\",\"target_id\":\"{}\"}}",
                                     signal.target_id
                                 );
-                                if let Some(self_tx) = state_for_recv.peers.read().await.get(&user_id).cloned() {
-                                    let _ = self_tx.send(Message::Text(err));
-                                }
+                                let _ = reply_tx.send(Message::Text(err));
                             }
                             continue;
                         }
-
-                        // Si no es señalización, tratar como mensaje normal broadcast
                     }
 
-                    println!("Mensaje recibido: {text}");
-                    let _ = tx.send(text);
+                    // Chat persistente: mensajes, recibos, escritura, edición y borrado
+                    let error = match serde_json::from_str::<chat::ChatCommand>(&text) {
+                        Ok(command) => chat::handle_command(&state_for_recv, user_id, command)
+                            .await
+                            .err()
+                            .map(|(_, message)| message),
+                        Err(e) => Some(format!("Invalid chat command: {}", e)),
+                    };
+                    if let Some(message) = error {
+                        let err = serde_json::json!({"type": "error", "message": message});
+                        let _ = reply_tx.send(Message::Text(err.to_string()));
+                    }
                 }
                 Message::Close(_) => break,
                 _ => {}
@@ -188,9 +287,18 @@ async fn handle_socket(user_id: Uuid, stream: WebSocket, state: Arc<ChatState>)
         _ = (&mut recv_task) => send_task.abort(),
     }
 
-    // Limpieza de la tabla de peers
-    {
-        state.peers.write().await.remove(&user_id);
-        state.presence.write().await.remove(&user_id);
+    // Limpieza: este dispositivo deja de recibir y de figurar como conectado
+    state.peers.unregister(user_id, connection_id).await;
+    if let Err(e) = presence::leave(&app.redis, user_id, &device_id).await {
+        tracing::warn!("Presence cleanup failed for {}: {}", user_id, e);
+    }
+    // Con el último dispositivo (en cualquier réplica) se cortan sus llamadas en curso;
+    // si Redis no responde, el sweeper de llamadas las cierra después
+    if state.peers.local_devices(user_id).await == 0 {
+        match presence::online_users(&app.redis).await {
+            Ok(online) if !online.contains_key(&user_id) => calls::end_calls_on_disconnect(&state, user_id).await,
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Presence check after disconnect failed for {}: {}", user_id, e),
+        }
     }
 }
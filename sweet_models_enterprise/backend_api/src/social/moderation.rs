@@ -0,0 +1,999 @@
+/// Moderación de contenido: el clasificador retiene lo marcado hasta que un moderador
+/// lo revise, los usuarios reportan, y cada decisión (automática o manual) queda auditada
+/// en `moderation_decisions`.
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use super::classifier::{classifier, ContentKind, Verdict};
+use super::{chat, feed};
+use crate::middleware::auth::{AuthenticatedUser, ModeratorOnly};
+use crate::operations::parse_user_id;
+use crate::realtime::{RealtimeEvent, RealtimeHub, Topic};
+use crate::state::AppState;
+
+/// Reportes de usuarios distintos que ocultan el contenido sin esperar al moderador
+pub const REPORT_HIDE_THRESHOLD: i32 = 3;
+pub const DEFAULT_MUTE_HOURS: i64 = 24;
+pub const MAX_MUTE_HOURS: i64 = 24 * 30;
+const MAX_NOTE_LEN: usize = 1000;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+type ApiError = (StatusCode, String);
+
+const CASE_COLUMNS: &str = "mc.id, mc.target_type, mc.target_id, mc.author_id, mc.source, mc.categories, mc.classifier, \
+     mc.score, mc.report_count, mc.hidden, mc.status, mc.resolution, mc.resolution_note, mc.resolved_by, mc.resolved_at, \
+     mc.created_at, mc.updated_at";
+
+// ============================================================================
+// TIPOS
+// ============================================================================
+
+/// Estado de moderación de un post, comentario o mensaje
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ModerationStatus {
+    Visible,
+    /// Retenido para revisión: solo lo ve su autor
+    Pending,
+    Removed,
+}
+
+impl ModerationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationStatus::Visible => "VISIBLE",
+            ModerationStatus::Pending => "PENDING",
+            ModerationStatus::Removed => "REMOVED",
+        }
+    }
+}
+
+/// Decisión del moderador sobre un caso. WARN y MUTE además retiran el contenido.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ModerationAction {
+    Approve,
+    Remove,
+    /// Retira el contenido y avisa al autor
+    Warn,
+    /// Retira el contenido y silencia al autor por `mute_hours`
+    Mute,
+}
+
+impl ModerationAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ModerationAction::Approve => "APPROVE",
+            ModerationAction::Remove => "REMOVE",
+            ModerationAction::Warn => "WARN",
+            ModerationAction::Mute => "MUTE",
+        }
+    }
+
+    /// Estado final del contenido
+    fn content_status(&self) -> ModerationStatus {
+        match self {
+            ModerationAction::Approve => ModerationStatus::Visible,
+            _ => ModerationStatus::Removed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    Hate,
+    Sexual,
+    Violence,
+    PersonalData,
+    Other,
+}
+
+impl ReportReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::Spam => "SPAM",
+            ReportReason::Harassment => "HARASSMENT",
+            ReportReason::Hate => "HATE",
+            ReportReason::Sexual => "SEXUAL",
+            ReportReason::Violence => "VIOLENCE",
+            ReportReason::PersonalData => "PERSONAL_DATA",
+            ReportReason::Other => "OTHER",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ModerationCase {
+    pub id: Uuid,
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub author_id: Uuid,
+    pub source: String,
+    pub categories: Vec<String>,
+    pub classifier: Option<String>,
+    pub score: Option<f32>,
+    pub report_count: i32,
+    pub hidden: bool,
+    pub status: String,
+    pub resolution: Option<String>,
+    pub resolution_note: Option<String>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Caso en la cola con el contenido a revisar
+#[derive(Debug, Serialize, FromRow)]
+pub struct QueueItem {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub case: ModerationCase,
+    pub content: Option<String>,
+    pub author_name: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ModerationReport {
+    pub id: Uuid,
+    pub reporter_id: Uuid,
+    pub reason: String,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ModerationDecision {
+    pub id: Uuid,
+    pub case_id: Option<Uuid>,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub subject_user_id: Option<Uuid>,
+    /// `None` = decisión automática
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub note: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CaseDetail {
+    #[serde(flatten)]
+    pub item: QueueItem,
+    pub reports: Vec<ModerationReport>,
+    pub decisions: Vec<ModerationDecision>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct MuteEntry {
+    pub user_id: Uuid,
+    pub muted_until: DateTime<Utc>,
+    pub reason: Option<String>,
+    pub muted_by: Option<Uuid>,
+    pub case_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportRequest {
+    pub target_type: ContentKind,
+    pub target_id: Uuid,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportResponse {
+    pub case_id: Uuid,
+    pub report_id: Uuid,
+    /// El contenido quedó oculto hasta la revisión
+    pub hidden: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueueQuery {
+    /// OPEN (por defecto) o RESOLVED
+    pub status: Option<String>,
+    pub target_type: Option<ContentKind>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveCaseRequest {
+    pub action: ModerationAction,
+    pub note: Option<String>,
+    /// Solo con MUTE; por defecto `DEFAULT_MUTE_HOURS`
+    pub mute_hours: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MuteRequest {
+    pub user_id: Uuid,
+    pub hours: Option<i64>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub user_id: Option<Uuid>,
+    pub case_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// Fila de auditoría por escribir
+struct Decision<'a> {
+    case_id: Option<Uuid>,
+    target: Option<(ContentKind, Uuid)>,
+    subject_user_id: Option<Uuid>,
+    actor_id: Option<Uuid>,
+    action: &'a str,
+    note: Option<&'a str>,
+    details: serde_json::Value,
+}
+
+impl Decision<'_> {
+    async fn record<'e, E: PgExecutor<'e>>(self, executor: E) -> Result<(), ApiError> {
+        sqlx::query(
+            r#"
+            INSERT INTO moderation_decisions (case_id, target_type, target_id, subject_user_id, actor_id, action, note, details)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(self.case_id)
+        .bind(self.target.map(|(kind, _)| kind.as_str()))
+        .bind(self.target.map(|(_, id)| id))
+        .bind(self.subject_user_id)
+        .bind(self.actor_id)
+        .bind(self.action)
+        .bind(self.note)
+        .bind(self.details)
+        .execute(executor)
+        .await
+        .map_err(|e| db_error("recording moderation decision", e))?;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// REGLAS
+// ============================================================================
+
+pub fn status_for(verdict: &Verdict) -> ModerationStatus {
+    if verdict.flagged {
+        ModerationStatus::Pending
+    } else {
+        ModerationStatus::Visible
+    }
+}
+
+/// Con cuántos reportes se oculta el contenido (si no estaba oculto ya)
+fn should_hide_on_report(report_count: i32, already_hidden: bool) -> bool {
+    !already_hidden && report_count >= REPORT_HIDE_THRESHOLD
+}
+
+fn mute_duration(hours: Option<i64>) -> Result<Duration, ApiError> {
+    let hours = hours.unwrap_or(DEFAULT_MUTE_HOURS);
+    if !(1..=MAX_MUTE_HOURS).contains(&hours) {
+        return Err((StatusCode::BAD_REQUEST, format!("mute_hours must be between 1 and {}", MAX_MUTE_HOURS)));
+    }
+    Ok(Duration::hours(hours))
+}
+
+fn clean_note(note: Option<&str>) -> Result<Option<String>, ApiError> {
+    let note = note.map(str::trim).filter(|n| !n.is_empty());
+    if note.is_some_and(|n| n.chars().count() > MAX_NOTE_LEN) {
+        return Err((StatusCode::BAD_REQUEST, format!("note must be <= {} characters", MAX_NOTE_LEN)));
+    }
+    Ok(note.map(str::to_string))
+}
+
+fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+fn db_error(context: &str, e: sqlx::Error) -> ApiError {
+    tracing::error!("DB error {}: {}", context, e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed {}", context))
+}
+
+fn is_db_code(e: &sqlx::Error, code: &str) -> bool {
+    matches!(e, sqlx::Error::Database(db) if db.code().as_deref() == Some(code))
+}
+
+// ============================================================================
+// SERVICIO (usado por feed y chat)
+// ============================================================================
+
+/// Pasa el texto por el clasificador instalado
+pub async fn screen(kind: ContentKind, text: &str) -> Verdict {
+    classifier().classify(kind, text).await
+}
+
+/// 403 si el usuario está silenciado
+pub async fn ensure_not_muted(db: &PgPool, user_id: Uuid) -> Result<(), ApiError> {
+    let muted_until = sqlx::query_scalar::<_, DateTime<Utc>>(
+        "SELECT muted_until FROM moderation_mutes WHERE user_id = $1 AND muted_until > NOW()",
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| db_error("checking mute", e))?;
+    match muted_until {
+        Some(until) => Err((StatusCode::FORBIDDEN, format!("You are muted until {}", until.to_rfc3339()))),
+        None => Ok(()),
+    }
+}
+
+fn notify(hub: &RealtimeHub, topics: &[Topic], event_type: &str, data: serde_json::Value) {
+    hub.publish_to(
+        topics,
+        RealtimeEvent {
+            event_type: event_type.to_string(),
+            room_id: String::new(),
+            data,
+            timestamp: Utc::now().timestamp(),
+        },
+    );
+}
+
+fn moderator_topics() -> Vec<Topic> {
+    ["MODERATOR", "ADMIN", "SUPER_ADMIN"].iter().map(|role| Topic::Role(role.to_string())).collect()
+}
+
+/// Abre (o actualiza) el caso de un contenido que el clasificador retuvo y lo audita.
+/// Va en la misma transacción que guarda el contenido: no queda nada retenido sin caso.
+/// Después del commit, `announce_held` avisa a los moderadores.
+pub async fn hold_for_review(
+    conn: &mut PgConnection,
+    kind: ContentKind,
+    target_id: Uuid,
+    author_id: Uuid,
+    verdict: &Verdict,
+) -> Result<Uuid, ApiError> {
+    let name = classifier().name().to_string();
+    let case_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO moderation_cases (target_type, target_id, author_id, source, categories, classifier, score, hidden)
+        VALUES ($1, $2, $3, 'CLASSIFIER', $4, $5, $6, TRUE)
+        ON CONFLICT (target_type, target_id) WHERE status = 'OPEN' DO UPDATE SET
+            categories = EXCLUDED.categories, classifier = EXCLUDED.classifier,
+            score = EXCLUDED.score, hidden = TRUE
+        RETURNING id
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(target_id)
+    .bind(author_id)
+    .bind(&verdict.categories)
+    .bind(&name)
+    .bind(verdict.score)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| db_error("opening moderation case", e))?;
+
+    Decision {
+        case_id: Some(case_id),
+        target: Some((kind, target_id)),
+        subject_user_id: Some(author_id),
+        actor_id: None,
+        action: "AUTO_HIDE",
+        note: None,
+        details: serde_json::json!({ "classifier": name, "categories": verdict.categories, "score": verdict.score }),
+    }
+    .record(&mut *conn)
+    .await?;
+    Ok(case_id)
+}
+
+/// Aviso a moderadores de un caso abierto por `hold_for_review`, una vez confirmado
+pub fn announce_held(hub: &RealtimeHub, kind: ContentKind, target_id: Uuid, case_id: Uuid, verdict: &Verdict) {
+    tracing::info!("🛡️ {} {} retenido por moderación ({:?})", kind.as_str(), target_id, verdict.categories);
+    notify(
+        hub,
+        &moderator_topics(),
+        "MODERATION_CASE",
+        serde_json::json!({ "case_id": case_id, "target_type": kind, "target_id": target_id, "source": "CLASSIFIER" }),
+    );
+}
+
+/// Cambia el estado de moderación del contenido (y lo que dependa de él) dentro de la
+/// transacción del llamador. Si es un mensaje de chat, devuelve el cambio para que
+/// `chat::announce_moderation` avise a los participantes después del commit.
+async fn set_content_status(
+    conn: &mut PgConnection,
+    kind: ContentKind,
+    target_id: Uuid,
+    status: ModerationStatus,
+    actor_id: Option<Uuid>,
+) -> Result<Option<chat::ModeratedMessage>, ApiError> {
+    let table = match kind {
+        ContentKind::ChatMessage => return chat::apply_moderation(conn, target_id, status).await.map(Some),
+        ContentKind::Post => "posts",
+        ContentKind::Comment => "comments",
+    };
+    let post_id = sqlx::query_scalar::<_, Uuid>(&format!(
+        r#"
+        UPDATE {table} SET
+            moderation_status = $2,
+            deleted_at = CASE WHEN $2 = 'REMOVED' THEN COALESCE(deleted_at, NOW()) ELSE deleted_at END,
+            deleted_by = CASE WHEN $2 = 'REMOVED' AND deleted_at IS NULL THEN $3 ELSE deleted_by END
+        WHERE id = $1
+        RETURNING {post}
+        "#,
+        table = table,
+        post = if kind == ContentKind::Post { "id" } else { "post_id" },
+    ))
+    .bind(target_id)
+    .bind(status.as_str())
+    .bind(actor_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| db_error("updating moderation status", e))?
+    .ok_or((StatusCode::NOT_FOUND, "Content not found".to_string()))?;
+
+    if kind == ContentKind::Comment {
+        feed::refresh_comments_count(&mut *conn, post_id).await?;
+    }
+    Ok(None)
+}
+
+/// Autor del contenido reportado, si el que reporta puede verlo
+async fn reportable_author(
+    state: &AppState,
+    auth: &AuthenticatedUser,
+    reporter: Uuid,
+    kind: ContentKind,
+    target_id: Uuid,
+) -> Result<Uuid, ApiError> {
+    let not_found = || (StatusCode::NOT_FOUND, "Content not found".to_string());
+    match kind {
+        ContentKind::Post => Ok(feed::visible_post(&state.db, auth, target_id).await?.user_id),
+        ContentKind::Comment => {
+            let (post_id, author_id) = sqlx::query_as::<_, (Uuid, Uuid)>(
+                "SELECT post_id, user_id FROM comments WHERE id = $1 AND deleted_at IS NULL",
+            )
+            .bind(target_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| db_error("loading reported comment", e))?
+            .ok_or_else(not_found)?;
+            feed::visible_post(&state.db, auth, post_id).await?;
+            Ok(author_id)
+        }
+        ContentKind::ChatMessage => sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT m.sender_id FROM chat_messages m
+            JOIN chat_participants p ON p.conversation_id = m.conversation_id AND p.user_id = $2
+            WHERE m.id = $1 AND m.deleted_at IS NULL
+            "#,
+        )
+        .bind(target_id)
+        .bind(reporter)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| db_error("loading reported message", e))?
+        .ok_or_else(not_found),
+    }
+}
+
+async fn load_item(db: &PgPool, case_id: Uuid) -> Result<QueueItem, ApiError> {
+    sqlx::query_as::<_, QueueItem>(&format!("{} WHERE mc.id = $1", queue_select()))
+        .bind(case_id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| db_error("loading moderation case", e))?
+        .ok_or((StatusCode::NOT_FOUND, "Case not found".to_string()))
+}
+
+/// Caso + contenido actual + nombre del autor
+fn queue_select() -> String {
+    format!(
+        r#"
+        SELECT {},
+            CASE mc.target_type
+                WHEN 'POST' THEN (SELECT content FROM posts WHERE id = mc.target_id)
+                WHEN 'COMMENT' THEN (SELECT content FROM comments WHERE id = mc.target_id)
+                ELSE (SELECT content FROM chat_messages WHERE id = mc.target_id)
+            END AS content,
+            COALESCE(u.display_name, u.username, u.email, 'Unknown') AS author_name
+        FROM moderation_cases mc
+        LEFT JOIN users u ON u.id = mc.author_id
+        "#,
+        CASE_COLUMNS
+    )
+}
+
+// ============================================================================
+// ENDPOINTS
+// ============================================================================
+
+/// POST /api/social/reports
+/// Reportar un post, comentario o mensaje; a los `REPORT_HIDE_THRESHOLD` reportes se oculta
+pub async fn report_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Json(req): Json<ReportRequest>,
+) -> Result<(StatusCode, Json<ReportResponse>), ApiError> {
+    let reporter = parse_user_id(&auth.user_id)?;
+    let details = clean_note(req.details.as_deref())?;
+    let author_id = reportable_author(&state, &auth, reporter, req.target_type, req.target_id).await?;
+    if author_id == reporter {
+        return Err((StatusCode::BAD_REQUEST, "You cannot report your own content".to_string()));
+    }
+
+    let mut tx = state.db.begin().await.map_err(|e| db_error("starting report transaction", e))?;
+    let (case_id, created) = sqlx::query_as::<_, (Uuid, bool)>(
+        r#"
+        INSERT INTO moderation_cases (target_type, target_id, author_id, source, categories)
+        VALUES ($1, $2, $3, 'REPORT', ARRAY[$4])
+        ON CONFLICT (target_type, target_id) WHERE status = 'OPEN' DO UPDATE SET
+            categories = CASE WHEN $4 = ANY(moderation_cases.categories) THEN moderation_cases.categories
+                              ELSE moderation_cases.categories || $4 END
+        RETURNING id, (xmax = 0) AS created
+        "#,
+    )
+    .bind(req.target_type.as_str())
+    .bind(req.target_id)
+    .bind(author_id)
+    .bind(req.reason.as_str().to_lowercase())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| db_error("opening report case", e))?;
+
+    let report_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO moderation_reports (case_id, target_type, target_id, reporter_id, reason, details)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+    )
+    .bind(case_id)
+    .bind(req.target_type.as_str())
+    .bind(req.target_id)
+    .bind(reporter)
+    .bind(req.reason.as_str())
+    .bind(&details)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        if is_db_code(&e, "23505") {
+            (StatusCode::CONFLICT, "You already reported this content".to_string())
+        } else {
+            db_error("saving report", e)
+        }
+    })?;
+
+    let (report_count, hidden) = sqlx::query_as::<_, (i32, bool)>(
+        "UPDATE moderation_cases SET report_count = report_count + 1 WHERE id = $1 RETURNING report_count, hidden",
+    )
+    .bind(case_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| db_error("counting reports", e))?;
+
+    let hide = should_hide_on_report(report_count, hidden);
+    if hide {
+        sqlx::query("UPDATE moderation_cases SET hidden = TRUE WHERE id = $1")
+            .bind(case_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| db_error("hiding reported content", e))?;
+        Decision {
+            case_id: Some(case_id),
+            target: Some((req.target_type, req.target_id)),
+            subject_user_id: Some(author_id),
+            actor_id: None,
+            action: "REPORT_HIDE",
+            note: None,
+            details: serde_json::json!({ "report_count": report_count }),
+        }
+        .record(&mut *tx)
+        .await?;
+    }
+    let moderated = if hide {
+        set_content_status(&mut tx, req.target_type, req.target_id, ModerationStatus::Pending, None).await?
+    } else {
+        None
+    };
+    tx.commit().await.map_err(|e| db_error("committing report", e))?;
+
+    if let Some(moderated) = moderated {
+        chat::announce_moderation(&state.chat, moderated).await;
+    }
+    if hide {
+        tracing::info!("🛡️ {} {} oculto tras {} reportes", req.target_type.as_str(), req.target_id, report_count);
+    }
+    if created || hide {
+        notify(
+            &state.realtime_hub,
+            &moderator_topics(),
+            "MODERATION_CASE",
+            serde_json::json!({
+                "case_id": case_id,
+                "target_type": req.target_type,
+                "target_id": req.target_id,
+                "source": "REPORT",
+                "hidden": hidden || hide,
+            }),
+        );
+    }
+
+    Ok((StatusCode::CREATED, Json(ReportResponse { case_id, report_id, hidden: hidden || hide })))
+}
+
+/// GET /api/social/moderation/queue?status=OPEN&target_type=POST&limit=50
+/// Primero lo que está oculto esperando revisión, después por antigüedad
+pub async fn queue_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Query(query): Query<QueueQuery>,
+) -> Result<Json<Vec<QueueItem>>, ApiError> {
+    let status = query.status.as_deref().unwrap_or("OPEN").to_uppercase();
+    if !matches!(status.as_str(), "OPEN" | "RESOLVED") {
+        return Err((StatusCode::BAD_REQUEST, "status must be OPEN or RESOLVED".to_string()));
+    }
+    let items = sqlx::query_as::<_, QueueItem>(&format!(
+        r#"
+        {}
+        WHERE mc.status = $1 AND ($2::TEXT IS NULL OR mc.target_type = $2)
+        ORDER BY
+            CASE WHEN mc.status = 'OPEN' THEN mc.hidden END DESC,
+            CASE WHEN mc.status = 'OPEN' THEN mc.created_at END ASC,
+            mc.resolved_at DESC
+        LIMIT $3
+        "#,
+        queue_select()
+    ))
+    .bind(&status)
+    .bind(query.target_type.map(|k| k.as_str()))
+    .bind(page_size(query.limit))
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("loading moderation queue", e))?;
+    Ok(Json(items))
+}
+
+/// GET /api/social/moderation/cases/:id
+/// Caso con sus reportes y el historial de decisiones
+pub async fn get_case_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Path(case_id): Path<Uuid>,
+) -> Result<Json<CaseDetail>, ApiError> {
+    let item = load_item(&state.db, case_id).await?;
+    let reports = sqlx::query_as::<_, ModerationReport>(
+        "SELECT id, reporter_id, reason, details, created_at FROM moderation_reports WHERE case_id = $1 ORDER BY created_at",
+    )
+    .bind(case_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("loading reports", e))?;
+    let decisions = sqlx::query_as::<_, ModerationDecision>(
+        r#"
+        SELECT id, case_id, target_type, target_id, subject_user_id, actor_id, action, note, details, created_at
+        FROM moderation_decisions WHERE case_id = $1 ORDER BY created_at
+        "#,
+    )
+    .bind(case_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("loading decisions", e))?;
+    Ok(Json(CaseDetail { item, reports, decisions }))
+}
+
+/// POST /api/social/moderation/cases/:id/resolve
+/// Aprobar (vuelve a ser visible), retirar, advertir o silenciar al autor
+pub async fn resolve_case_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Path(case_id): Path<Uuid>,
+    Json(req): Json<ResolveCaseRequest>,
+) -> Result<Json<QueueItem>, ApiError> {
+    let moderator_id = parse_user_id(&moderator.user_id)?;
+    let note = clean_note(req.note.as_deref())?;
+    let mute = match req.action {
+        ModerationAction::Mute => Some(mute_duration(req.mute_hours)?),
+        _ => None,
+    };
+
+    let mut tx = state.db.begin().await.map_err(|e| db_error("starting resolve transaction", e))?;
+    let case = sqlx::query_as::<_, ModerationCase>(&format!(
+        "SELECT {} FROM moderation_cases mc WHERE mc.id = $1 FOR UPDATE",
+        CASE_COLUMNS
+    ))
+    .bind(case_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| db_error("loading moderation case", e))?
+    .ok_or((StatusCode::NOT_FOUND, "Case not found".to_string()))?;
+    if case.status != "OPEN" {
+        return Err((StatusCode::CONFLICT, "Case is already resolved".to_string()));
+    }
+    let kind = ContentKind::parse(&case.target_type)
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Unknown target type".to_string()))?;
+
+    sqlx::query(
+        r#"
+        UPDATE moderation_cases
+        SET status = 'RESOLVED', resolution = $2, resolution_note = $3, resolved_by = $4, resolved_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(case_id)
+    .bind(req.action.as_str())
+    .bind(&note)
+    .bind(moderator_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| db_error("resolving moderation case", e))?;
+
+    let muted_until = mute.map(|duration| Utc::now() + duration);
+    if let Some(until) = muted_until {
+        sqlx::query(
+            r#"
+            INSERT INTO moderation_mutes (user_id, muted_until, reason, muted_by, case_id)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id) DO UPDATE SET
+                muted_until = GREATEST(moderation_mutes.muted_until, EXCLUDED.muted_until),
+                reason = EXCLUDED.reason, muted_by = EXCLUDED.muted_by, case_id = EXCLUDED.case_id
+            "#,
+        )
+        .bind(case.author_id)
+        .bind(until)
+        .bind(&note)
+        .bind(moderator_id)
+        .bind(case_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| db_error("muting user", e))?;
+    }
+
+    Decision {
+        case_id: Some(case_id),
+        target: Some((kind, case.target_id)),
+        subject_user_id: Some(case.author_id),
+        actor_id: Some(moderator_id),
+        action: req.action.as_str(),
+        note: note.as_deref(),
+        details: serde_json::json!({
+            "categories": case.categories,
+            "report_count": case.report_count,
+            "muted_until": muted_until,
+        }),
+    }
+    .record(&mut *tx)
+    .await?;
+    let status = req.action.content_status();
+    let moderated = set_content_status(&mut tx, kind, case.target_id, status, Some(moderator_id)).await?;
+    tx.commit().await.map_err(|e| db_error("committing resolution", e))?;
+
+    if let Some(moderated) = moderated {
+        chat::announce_moderation(&state.chat, moderated).await;
+    }
+
+    let user_topic = [Topic::User(case.author_id.to_string())];
+    match req.action {
+        ModerationAction::Warn => notify(
+            &state.realtime_hub,
+            &user_topic,
+            "MODERATION_WARNING",
+            serde_json::json!({ "case_id": case_id, "target_type": kind, "note": note }),
+        ),
+        ModerationAction::Mute => notify(
+            &state.realtime_hub,
+            &user_topic,
+            "MODERATION_MUTE",
+            serde_json::json!({ "case_id": case_id, "muted_until": muted_until, "note": note }),
+        ),
+        _ => {}
+    }
+    tracing::info!("🛡️ Caso {} resuelto con {} por {}", case_id, req.action.as_str(), moderator_id);
+
+    Ok(Json(load_item(&state.db, case_id).await?))
+}
+
+/// GET /api/social/moderation/mutes
+/// Usuarios silenciados ahora mismo
+pub async fn list_mutes_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+) -> Result<Json<Vec<MuteEntry>>, ApiError> {
+    let mutes = sqlx::query_as::<_, MuteEntry>(
+        r#"
+        SELECT user_id, muted_until, reason, muted_by, case_id, created_at
+        FROM moderation_mutes WHERE muted_until > NOW()
+        ORDER BY muted_until
+        "#,
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("listing mutes", e))?;
+    Ok(Json(mutes))
+}
+
+/// POST /api/social/moderation/mutes
+/// Silenciar a un usuario sin pasar por un caso
+pub async fn mute_user_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Json(req): Json<MuteRequest>,
+) -> Result<(StatusCode, Json<MuteEntry>), ApiError> {
+    let moderator_id = parse_user_id(&moderator.user_id)?;
+    if req.user_id == moderator_id {
+        return Err((StatusCode::BAD_REQUEST, "You cannot mute yourself".to_string()));
+    }
+    let until = Utc::now() + mute_duration(req.hours)?;
+    let reason = clean_note(req.reason.as_deref())?;
+
+    let mut tx = state.db.begin().await.map_err(|e| db_error("starting mute transaction", e))?;
+    let entry = sqlx::query_as::<_, MuteEntry>(
+        r#"
+        INSERT INTO moderation_mutes (user_id, muted_until, reason, muted_by)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id) DO UPDATE SET
+            muted_until = EXCLUDED.muted_until, reason = EXCLUDED.reason,
+            muted_by = EXCLUDED.muted_by, case_id = NULL, created_at = NOW()
+        RETURNING user_id, muted_until, reason, muted_by, case_id, created_at
+        "#,
+    )
+    .bind(req.user_id)
+    .bind(until)
+    .bind(&reason)
+    .bind(moderator_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        if is_db_code(&e, "23503") {
+            (StatusCode::NOT_FOUND, "User not found".to_string())
+        } else {
+            db_error("muting user", e)
+        }
+    })?;
+    Decision {
+        case_id: None,
+        target: None,
+        subject_user_id: Some(req.user_id),
+        actor_id: Some(moderator_id),
+        action: "MUTE",
+        note: reason.as_deref(),
+        details: serde_json::json!({ "muted_until": until }),
+    }
+    .record(&mut *tx)
+    .await?;
+    tx.commit().await.map_err(|e| db_error("committing mute", e))?;
+
+    notify(
+        &state.realtime_hub,
+        &[Topic::User(req.user_id.to_string())],
+        "MODERATION_MUTE",
+        serde_json::json!({ "muted_until": until, "note": reason }),
+    );
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+/// DELETE /api/social/moderation/mutes/:user_id
+pub async fn unmute_user_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let moderator_id = parse_user_id(&moderator.user_id)?;
+    let mut tx = state.db.begin().await.map_err(|e| db_error("starting unmute transaction", e))?;
+    let removed = sqlx::query("DELETE FROM moderation_mutes WHERE user_id = $1 AND muted_until > NOW()")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| db_error("unmuting user", e))?;
+    if removed.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "User is not muted".to_string()));
+    }
+    Decision {
+        case_id: None,
+        target: None,
+        subject_user_id: Some(user_id),
+        actor_id: Some(moderator_id),
+        action: "UNMUTE",
+        note: None,
+        details: serde_json::json!({}),
+    }
+    .record(&mut *tx)
+    .await?;
+    tx.commit().await.map_err(|e| db_error("committing unmute", e))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/social/moderation/audit?user_id=&case_id=&limit=
+/// Decisiones de moderación, de la más reciente a la más vieja
+pub async fn audit_log_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<ModerationDecision>>, ApiError> {
+    let decisions = sqlx::query_as::<_, ModerationDecision>(
+        r#"
+        SELECT id, case_id, target_type, target_id, subject_user_id, actor_id, action, note, details, created_at
+        FROM moderation_decisions
+        WHERE ($1::UUID IS NULL OR subject_user_id = $1 OR actor_id = $1)
+          AND ($2::UUID IS NULL OR case_id = $2)
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(query.user_id)
+    .bind(query.case_id)
+    .bind(page_size(query.limit))
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("loading moderation audit", e))?;
+    Ok(Json(decisions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flagged_content_is_held() {
+        let flagged = Verdict { flagged: true, categories: vec!["hate".to_string()], score: 1.0 };
+        assert_eq!(status_for(&flagged), ModerationStatus::Pending);
+        assert_eq!(status_for(&Verdict::clean()), ModerationStatus::Visible);
+    }
+
+    #[test]
+    fn only_approve_keeps_the_content() {
+        assert_eq!(ModerationAction::Approve.content_status(), ModerationStatus::Visible);
+        for action in [ModerationAction::Remove, ModerationAction::Warn, ModerationAction::Mute] {
+            assert_eq!(action.content_status(), ModerationStatus::Removed);
+        }
+    }
+
+    #[test]
+    fn reports_hide_once_at_threshold() {
+        assert!(!should_hide_on_report(REPORT_HIDE_THRESHOLD - 1, false));
+        assert!(should_hide_on_report(REPORT_HIDE_THRESHOLD, false));
+        assert!(!should_hide_on_report(REPORT_HIDE_THRESHOLD + 5, true));
+    }
+
+    #[test]
+    fn mute_duration_is_bounded() {
+        assert_eq!(mute_duration(None).unwrap(), Duration::hours(DEFAULT_MUTE_HOURS));
+        assert!(mute_duration(Some(0)).is_err());
+        assert!(mute_duration(Some(MAX_MUTE_HOURS + 1)).is_err());
+        assert_eq!(mute_duration(Some(2)).unwrap(), Duration::hours(2));
+    }
+
+    #[test]
+    fn notes_are_trimmed_and_bounded() {
+        assert_eq!(clean_note(Some("  spam  ")).unwrap(), Some("spam".to_string()));
+        assert_eq!(clean_note(Some("   ")).unwrap(), None);
+        assert!(clean_note(Some(&"x".repeat(MAX_NOTE_LEN + 1))).is_err());
+    }
+
+    #[test]
+    fn requests_use_screaming_case() {
+        let report: ReportRequest = serde_json::from_str(&format!(
+            r#"{{"target_type": "CHAT_MESSAGE", "target_id": "{}", "reason": "PERSONAL_DATA"}}"#,
+            Uuid::new_v4()
+        ))
+        .unwrap();
+        assert_eq!(report.target_type, ContentKind::ChatMessage);
+        assert_eq!(report.reason.as_str(), "PERSONAL_DATA");
+
+        let resolve: ResolveCaseRequest = serde_json::from_str(r#"{"action": "MUTE", "mute_hours": 6}"#).unwrap();
+        assert_eq!(resolve.action, ModerationAction::Mute);
+    }
+}
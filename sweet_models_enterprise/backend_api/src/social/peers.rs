@@ -0,0 +1,229 @@
+use std::{collections::HashMap, sync::Arc, sync::Mutex, time::Duration};
+
+use async_nats::RequestErrorKind;
+use axum::extract::ws::Message;
+use futures_util::StreamExt;
+use tokio::{
+    sync::{broadcast, mpsc, RwLock},
+    task::JoinHandle,
+};
+use uuid::Uuid;
+
+use crate::realtime::ClusterNode;
+
+/// Subject donde todas las réplicas reparten el chat broadcast
+pub const CHAT_BROADCAST_SUBJECT: &str = "chat.broadcast";
+/// Tiempo máximo para que otro nodo confirme la entrega directa
+const REMOTE_DELIVERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Subject directo de un usuario: solo lo escuchan los nodos donde tiene sockets abiertos
+pub fn peer_subject(user_id: Uuid) -> String {
+    format!("chat.peer.{}", user_id)
+}
+
+/// Sockets abiertos de un usuario en este nodo, por id de conexión
+type Devices = HashMap<Uuid, mpsc::UnboundedSender<Message>>;
+type LocalPeers = Arc<RwLock<HashMap<Uuid, Devices>>>;
+
+/// Registro de peers conectados, consciente del cluster y con varios dispositivos por usuario.
+/// Mientras un usuario tenga sockets en este nodo, el nodo escucha `chat.peer.<user_id>`;
+/// enviar a un usuario es entregar en sus sockets locales y publicar para los demás nodos.
+/// Si no hay sockets locales se hace un request: sin respuesta, no está conectado en ninguna réplica.
+pub struct PeerRegistry {
+    local: LocalPeers,
+    routes: Mutex<HashMap<Uuid, JoinHandle<()>>>,
+    cluster: Option<ClusterNode>,
+}
+
+impl PeerRegistry {
+    /// `cluster = None` deja el registro en memoria (un solo nodo, tests)
+    pub fn new(cluster: Option<ClusterNode>) -> Self {
+        PeerRegistry {
+            local: Arc::new(RwLock::new(HashMap::new())),
+            routes: Mutex::new(HashMap::new()),
+            cluster,
+        }
+    }
+
+    /// Registra un socket del usuario y devuelve su id de conexión.
+    /// Cada dispositivo recibe su propia copia; ninguna conexión reemplaza a otra.
+    pub async fn register(&self, user_id: Uuid, tx: mpsc::UnboundedSender<Message>) -> Uuid {
+        let connection_id = Uuid::new_v4();
+        let first_device = {
+            let mut local = self.local.write().await;
+            let devices = local.entry(user_id).or_default();
+            devices.insert(connection_id, tx);
+            devices.len() == 1
+        };
+
+        if let (true, Some(node)) = (first_device, &self.cluster) {
+            match node.nats.subscribe(peer_subject(user_id)).await {
+                Ok(subscriber) => {
+                    let route = spawn_route(node.clone(), subscriber, self.local.clone(), user_id);
+                    let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Some(previous) = routes.insert(user_id, route) {
+                        previous.abort();
+                    }
+                }
+                Err(e) => tracing::warn!("User {} reachable only on this node: {}", user_id, e),
+            }
+        }
+        connection_id
+    }
+
+    /// Quita un socket; con el último dispositivo el nodo deja de escuchar al usuario
+    pub async fn unregister(&self, user_id: Uuid, connection_id: Uuid) {
+        let mut local = self.local.write().await;
+        let Some(devices) = local.get_mut(&user_id) else {
+            return;
+        };
+        devices.remove(&connection_id);
+        if !devices.is_empty() {
+            return;
+        }
+        local.remove(&user_id);
+        let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(route) = routes.remove(&user_id) {
+            route.abort();
+        }
+    }
+
+    /// Conectado en este nodo
+    pub async fn is_local(&self, user_id: Uuid) -> bool {
+        self.local.read().await.contains_key(&user_id)
+    }
+
+    /// Sockets del usuario abiertos en este nodo
+    pub async fn local_devices(&self, user_id: Uuid) -> usize {
+        self.local.read().await.get(&user_id).map_or(0, |devices| devices.len())
+    }
+
+    /// Entrega directa a todos los dispositivos de un usuario, en este y en los demás nodos.
+    /// Devuelve `false` si no está conectado en ninguna réplica.
+    pub async fn send_to(&self, user_id: Uuid, text: String) -> bool {
+        let delivered_here = deliver_local(&self.local, user_id, &text).await;
+        let Some(node) = &self.cluster else {
+            return delivered_here;
+        };
+        let payload = node.encode(&text);
+
+        if delivered_here {
+            // Ya está entregado; los otros nodos solo necesitan la copia para sus dispositivos
+            if let Err(e) = node.nats.publish(peer_subject(user_id), payload.into()).await {
+                tracing::warn!("Failed to fan out message for {} to cluster: {}", user_id, e);
+            }
+            return true;
+        }
+
+        let request = async_nats::Request::new()
+            .payload(payload.into())
+            .timeout(Some(REMOTE_DELIVERY_TIMEOUT));
+        match node.nats.send_request(peer_subject(user_id), request).await {
+            Ok(_) => true,
+            Err(e) if e.kind() == RequestErrorKind::NoResponders => false,
+            Err(e) => {
+                tracing::warn!("Remote delivery to {} failed: {}", user_id, e);
+                false
+            }
+        }
+    }
+}
+
+impl Drop for PeerRegistry {
+    fn drop(&mut self) {
+        let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        for (_, route) in routes.drain() {
+            route.abort();
+        }
+    }
+}
+
+/// Copia el mensaje en cada socket local del usuario; `true` si al menos uno lo recibió
+async fn deliver_local(local: &LocalPeers, user_id: Uuid, text: &str) -> bool {
+    let local = local.read().await;
+    let Some(devices) = local.get(&user_id) else {
+        return false;
+    };
+    let mut delivered = false;
+    for tx in devices.values() {
+        delivered |= tx.send(Message::Text(text.to_string())).is_ok();
+    }
+    delivered
+}
+
+/// Lleva a los sockets locales lo que otros nodos mandan a este usuario y confirma la entrega
+fn spawn_route(node: ClusterNode, mut subscriber: async_nats::Subscriber, local: LocalPeers, user_id: Uuid) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Some(message) = subscriber.next().await {
+            let text = match node.decode::<String>(&message.payload) {
+                Ok(Some(text)) => text,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!("Invalid peer frame on {}: {}", message.subject, e);
+                    continue;
+                }
+            };
+            if !deliver_local(&local, user_id, &text).await {
+                continue;
+            }
+            if let Some(reply) = message.reply {
+                if let Err(e) = node.nats.publish(reply, "ok".into()).await {
+                    tracing::warn!("Failed to ack remote delivery: {}", e);
+                }
+            }
+        }
+    })
+}
+
+/// Reparte en este nodo el chat broadcast publicado por las demás réplicas
+pub async fn bridge_chat(tx: broadcast::Sender<String>, node: ClusterNode) -> Result<JoinHandle<()>, async_nats::SubscribeError> {
+    let mut inbound = node.nats.subscribe(CHAT_BROADCAST_SUBJECT).await?;
+    Ok(tokio::spawn(async move {
+        while let Some(message) = inbound.next().await {
+            match node.decode::<String>(&message.payload) {
+                Ok(Some(text)) => {
+                    let _ = tx.send(text);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Invalid chat cluster frame: {}", e),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn local_delivery_without_cluster() {
+        let registry = PeerRegistry::new(None);
+        let user = Uuid::new_v4();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        registry.register(user, tx).await;
+
+        assert!(registry.send_to(user, "hola".to_string()).await);
+        assert!(matches!(rx.recv().await, Some(Message::Text(text)) if text == "hola"));
+        assert!(!registry.send_to(Uuid::new_v4(), "nadie".to_string()).await);
+    }
+
+    #[tokio::test]
+    async fn every_device_gets_a_copy_until_the_last_one_leaves() {
+        let registry = PeerRegistry::new(None);
+        let user = Uuid::new_v4();
+        let (phone_tx, mut phone_rx) = mpsc::unbounded_channel();
+        let (laptop_tx, mut laptop_rx) = mpsc::unbounded_channel();
+        let phone = registry.register(user, phone_tx).await;
+        let laptop = registry.register(user, laptop_tx).await;
+        assert_eq!(registry.local_devices(user).await, 2);
+
+        assert!(registry.send_to(user, "hola".to_string()).await);
+        assert!(matches!(phone_rx.recv().await, Some(Message::Text(text)) if text == "hola"));
+        assert!(matches!(laptop_rx.recv().await, Some(Message::Text(text)) if text == "hola"));
+
+        registry.unregister(user, phone).await;
+        assert!(registry.is_local(user).await);
+        registry.unregister(user, laptop).await;
+        assert!(!registry.is_local(user).await);
+    }
+}
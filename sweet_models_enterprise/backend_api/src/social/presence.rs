@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use deadpool_redis::redis;
+use uuid::Uuid;
+
+/// Sorted set con un miembro `<user_id>:<device_id>` por dispositivo y score = expiración (unix)
+const PRESENCE_KEY: &str = "chat:presence";
+/// Sin heartbeat durante este tiempo el dispositivo se considera desconectado
+pub const PRESENCE_TTL_SECS: i64 = 60;
+/// Cada cuánto el socket renueva la presencia (bastante antes del TTL)
+pub const HEARTBEAT_INTERVAL_SECS: u64 = 20;
+const MAX_DEVICE_ID_LEN: usize = 64;
+
+/// Dispositivo elegido por el cliente; si no manda uno válido se genera uno por conexión
+pub fn normalize_device_id(raw: Option<&str>) -> String {
+    raw.map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_DEVICE_ID_LEN)
+        .filter(|id| id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+fn member(user_id: Uuid, device_id: &str) -> String {
+    format!("{}:{}", user_id, device_id)
+}
+
+/// Agrupa los miembros vivos por usuario
+fn group_devices(members: Vec<String>) -> BTreeMap<Uuid, Vec<String>> {
+    let mut online: BTreeMap<Uuid, Vec<String>> = BTreeMap::new();
+    for raw in members {
+        let Some((user, device)) = raw.split_once(':') else {
+            continue;
+        };
+        if let Ok(user_id) = Uuid::parse_str(user) {
+            online.entry(user_id).or_default().push(device.to_string());
+        }
+    }
+    online
+}
+
+/// Marca (o renueva) un dispositivo como conectado
+pub async fn heartbeat(redis: &deadpool_redis::Pool, user_id: Uuid, device_id: &str) -> Result<i64, String> {
+    let mut conn = redis.get().await.map_err(|e| e.to_string())?;
+    let expires_at = Utc::now().timestamp() + PRESENCE_TTL_SECS;
+    redis::cmd("ZADD")
+        .arg(PRESENCE_KEY)
+        .arg(expires_at)
+        .arg(member(user_id, device_id))
+        .query_async::<_, ()>(&mut conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(expires_at)
+}
+
+/// Quita un dispositivo al cerrar su socket
+pub async fn leave(redis: &deadpool_redis::Pool, user_id: Uuid, device_id: &str) -> Result<(), String> {
+    let mut conn = redis.get().await.map_err(|e| e.to_string())?;
+    redis::cmd("ZREM")
+        .arg(PRESENCE_KEY)
+        .arg(member(user_id, device_id))
+        .query_async::<_, ()>(&mut conn)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Usuarios con al menos un dispositivo vivo (en cualquier réplica), con sus dispositivos
+pub async fn online_users(redis: &deadpool_redis::Pool) -> Result<BTreeMap<Uuid, Vec<String>>, String> {
+    let mut conn = redis.get().await.map_err(|e| e.to_string())?;
+    let now = Utc::now().timestamp();
+    // Los expirados se limpian acá: un nodo caído no deja usuarios "online" para siempre
+    redis::cmd("ZREMRANGEBYSCORE")
+        .arg(PRESENCE_KEY)
+        .arg("-inf")
+        .arg(now)
+        .query_async::<_, ()>(&mut conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    let members: Vec<String> = redis::cmd("ZRANGEBYSCORE")
+        .arg(PRESENCE_KEY)
+        .arg(format!("({}", now))
+        .arg("+inf")
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(group_devices(members))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_ids_are_sanitized() {
+        assert_eq!(normalize_device_id(Some(" phone-1 ")), "phone-1");
+        assert!(Uuid::parse_str(&normalize_device_id(Some("bad id!"))).is_ok());
+        assert!(Uuid::parse_str(&normalize_device_id(None)).is_ok());
+        assert!(Uuid::parse_str(&normalize_device_id(Some(&"x".repeat(65)))).is_ok());
+    }
+
+    #[test]
+    fn members_are_grouped_by_user() {
+        let user = Uuid::new_v4();
+        let online = group_devices(vec![member(user, "phone"), member(user, "laptop"), "garbage".to_string()]);
+        assert_eq!(online.len(), 1);
+        assert_eq!(online[&user], vec!["phone".to_string(), "laptop".to_string()]);
+    }
+}
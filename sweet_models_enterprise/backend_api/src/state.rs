@@ -1,6 +1,8 @@
 use sqlx::PgPool;
+use crate::notifications::Notifier;
 use crate::storage::StorageService;
 use crate::realtime::RealtimeHub;
+use crate::social::ChatState;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -10,4 +12,7 @@ pub struct AppState {
     pub nats: async_nats::Client,
     pub storage: StorageService,
     pub realtime_hub: Arc<RealtimeHub>,
+    pub chat: Arc<ChatState>,
+    /// Plantillas + preferencias + bandeja + push
+    pub notifier: Arc<Notifier>,
 }
@@ -0,0 +1,521 @@
+/// Pipeline de subida de archivos: tipo real por magic bytes, límites de tamaño y
+/// dimensiones, imágenes re-codificadas sin metadatos (EXIF/GPS/XMP) con la orientación
+/// ya aplicada, y miniaturas en varios tamaños.
+///
+/// En MP4/MOV las cajas de metadatos (udta/meta/uuid, donde va la ubicación `©xyz`) se
+/// vacían en el mismo lugar sin mover el resto del archivo. WebM y documentos se validan
+/// y se guardan tal cual.
+use std::{io::Cursor, sync::OnceLock};
+
+use axum::http::StatusCode;
+use image::{imageops::FilterType, io::Limits, DynamicImage, ImageFormat, ImageOutputFormat};
+use serde::Serialize;
+use thiserror::Error;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Bytes necesarios para identificar el tipo (`ftyp` + marca en MP4/MOV)
+pub const SNIFF_LEN: usize = 12;
+
+/// Cajas ISO BMFF que pueden llevar ubicación, XMP u otros datos del dispositivo
+const VIDEO_METADATA_BOXES: [&[u8; 4]; 3] = [b"udta", b"meta", b"uuid"];
+/// Contenedores donde se buscan esas cajas
+const VIDEO_CONTAINER_BOXES: [&[u8; 4]; 2] = [b"moov", b"trak"];
+
+/// Miniaturas: nombre y lado mayor en px. Solo se generan las menores que el original.
+pub const THUMBNAIL_SIZES: &[(&str, u32)] = &[("thumb", 150), ("small", 480), ("medium", 1080)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MediaKind {
+    Image,
+    Video,
+    Document,
+}
+
+impl MediaKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaKind::Image => "IMAGE",
+            MediaKind::Video => "VIDEO",
+            MediaKind::Document => "DOCUMENT",
+        }
+    }
+}
+
+/// Tipo detectado por el contenido, no por el nombre ni el header del cliente
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detected {
+    pub kind: MediaKind,
+    pub content_type: &'static str,
+    pub extension: &'static str,
+}
+
+#[derive(Debug, Error)]
+pub enum MediaError {
+    #[error("unsupported file type")]
+    Unsupported,
+    #[error("{kind} files must be <= {max} bytes")]
+    TooLarge { kind: &'static str, max: usize },
+    #[error("image is {width}x{height}, max is {max} px per side and {max_pixels} px total")]
+    TooManyPixels { width: u32, height: u32, max: u32, max_pixels: u64 },
+    #[error("image could not be decoded: {0}")]
+    Decode(String),
+    #[error("image could not be encoded: {0}")]
+    Encode(String),
+    #[error("video container could not be parsed: {0}")]
+    InvalidVideo(String),
+}
+
+impl MediaError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            MediaError::Unsupported => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            MediaError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            MediaError::TooManyPixels { .. } | MediaError::Decode(_) | MediaError::InvalidVideo(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            MediaError::Encode(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<MediaError> for (StatusCode, String) {
+    fn from(e: MediaError) -> Self {
+        (e.status(), e.to_string())
+    }
+}
+
+/// Límites configurables: `MEDIA_MAX_IMAGE_BYTES`, `MEDIA_MAX_VIDEO_BYTES`,
+/// `MEDIA_MAX_DOCUMENT_BYTES`, `MEDIA_MAX_DIMENSION` y `MEDIA_MAX_PIXELS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaLimits {
+    pub max_image_bytes: usize,
+    pub max_video_bytes: usize,
+    pub max_document_bytes: usize,
+    /// Lado mayor permitido
+    pub max_dimension: u32,
+    /// Ancho x alto permitido (corta bombas de descompresión antes de decodificar)
+    pub max_pixels: u64,
+}
+
+impl Default for MediaLimits {
+    fn default() -> Self {
+        Self {
+            max_image_bytes: 15 * 1024 * 1024,
+            max_video_bytes: 200 * 1024 * 1024,
+            max_document_bytes: 20 * 1024 * 1024,
+            max_dimension: 8_000,
+            max_pixels: 40_000_000,
+        }
+    }
+}
+
+impl MediaLimits {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name).ok().and_then(|v| v.trim().parse().ok()).unwrap_or(default)
+        }
+        let defaults = Self::default();
+        Self {
+            max_image_bytes: var("MEDIA_MAX_IMAGE_BYTES", defaults.max_image_bytes),
+            max_video_bytes: var("MEDIA_MAX_VIDEO_BYTES", defaults.max_video_bytes),
+            max_document_bytes: var("MEDIA_MAX_DOCUMENT_BYTES", defaults.max_document_bytes),
+            max_dimension: var("MEDIA_MAX_DIMENSION", defaults.max_dimension),
+            max_pixels: var("MEDIA_MAX_PIXELS", defaults.max_pixels),
+        }
+    }
+
+    pub fn max_bytes(&self, kind: MediaKind) -> usize {
+        match kind {
+            MediaKind::Image => self.max_image_bytes,
+            MediaKind::Video => self.max_video_bytes,
+            MediaKind::Document => self.max_document_bytes,
+        }
+    }
+
+    /// Tope del cuerpo HTTP de la subida (el mayor de los límites + margen del multipart)
+    pub fn max_request_bytes(&self) -> usize {
+        self.max_image_bytes.max(self.max_video_bytes).max(self.max_document_bytes) + 64 * 1024
+    }
+
+    pub fn check_size(&self, detected: &Detected, len: usize) -> Result<(), MediaError> {
+        let max = self.max_bytes(detected.kind);
+        if len > max {
+            return Err(MediaError::TooLarge { kind: detected.kind.as_str(), max });
+        }
+        Ok(())
+    }
+
+    fn check_dimensions(&self, width: u32, height: u32) -> Result<(), MediaError> {
+        if width.max(height) > self.max_dimension || u64::from(width) * u64::from(height) > self.max_pixels {
+            return Err(MediaError::TooManyPixels { width, height, max: self.max_dimension, max_pixels: self.max_pixels });
+        }
+        Ok(())
+    }
+}
+
+/// Límites globales, leídos una vez del entorno
+pub fn limits() -> &'static MediaLimits {
+    static LIMITS: OnceLock<MediaLimits> = OnceLock::new();
+    LIMITS.get_or_init(MediaLimits::from_env)
+}
+
+/// Identifica el archivo por sus primeros bytes; `None` si no es un tipo aceptado
+pub fn sniff(bytes: &[u8]) -> Option<Detected> {
+    let detected = |kind, content_type, extension| Some(Detected { kind, content_type, extension });
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => detected(MediaKind::Image, "image/jpeg", "jpg"),
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => detected(MediaKind::Image, "image/png", "png"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => detected(MediaKind::Image, "image/webp", "webp"),
+        [b'%', b'P', b'D', b'F', b'-', ..] => detected(MediaKind::Document, "application/pdf", "pdf"),
+        [0x1A, 0x45, 0xDF, 0xA3, ..] => detected(MediaKind::Video, "video/webm", "webm"),
+        [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..] if brand.len() >= 4 => match &brand[..4] {
+            b"qt  " => detected(MediaKind::Video, "video/quicktime", "mov"),
+            b"isom" | b"iso2" | b"mp41" | b"mp42" | b"avc1" | b"M4V " | b"dash" => {
+                detected(MediaKind::Video, "video/mp4", "mp4")
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Una versión lista para subir (original limpio o miniatura)
+#[derive(Debug, Clone)]
+pub struct EncodedImage {
+    pub name: &'static str,
+    pub bytes: Vec<u8>,
+    pub content_type: &'static str,
+    pub extension: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessedImage {
+    pub original: EncodedImage,
+    pub variants: Vec<EncodedImage>,
+}
+
+/// Valor de la etiqueta Orientation (0x0112) del EXIF de un JPEG
+pub fn exif_orientation(jpeg: &[u8]) -> Option<u16> {
+    let mut pos = 2; // después de SOI
+    while pos + 4 <= jpeg.len() {
+        if jpeg[pos] != 0xFF {
+            return None;
+        }
+        let marker = jpeg[pos + 1];
+        // SOS: empieza la imagen comprimida, ya no hay más metadatos
+        if marker == 0xDA {
+            return None;
+        }
+        let len = u16::from_be_bytes([jpeg[pos + 2], jpeg[pos + 3]]) as usize;
+        let segment = jpeg.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            return tiff_orientation(&segment[6..]);
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let little = match tiff.get(..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| -> Option<u16> {
+        let b = tiff.get(at..at + 2)?;
+        Some(if little { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) })
+    };
+    let u32_at = |at: usize| -> Option<u32> {
+        let b = tiff.get(at..at + 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Some(if little { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    };
+    if u16_at(2)? != 42 {
+        return None;
+    }
+    let ifd = u32_at(4)? as usize;
+    let entries = u16_at(ifd)? as usize;
+    (0..entries)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(entry) == Some(0x0112))
+        .and_then(|entry| u16_at(entry + 8))
+}
+
+/// Gira/espeja los píxeles según la orientación EXIF (1-8) para no depender del metadato
+fn apply_orientation(img: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+fn encode(name: &'static str, img: &DynamicImage, keep_alpha: bool) -> Result<EncodedImage, MediaError> {
+    let mut bytes = Vec::new();
+    let (content_type, extension) = if keep_alpha {
+        img.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+            .map_err(|e| MediaError::Encode(e.to_string()))?;
+        ("image/png", "png")
+    } else {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+            .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Jpeg(JPEG_QUALITY))
+            .map_err(|e| MediaError::Encode(e.to_string()))?;
+        ("image/jpeg", "jpg")
+    };
+    Ok(EncodedImage { name, bytes, content_type, extension, width: img.width(), height: img.height() })
+}
+
+/// Decodifica, aplica la orientación, re-codifica sin metadatos y genera las miniaturas.
+/// Es trabajo de CPU: llamarlo desde `spawn_blocking`.
+pub fn process_image(bytes: &[u8], detected: &Detected, limits: &MediaLimits) -> Result<ProcessedImage, MediaError> {
+    let format = match detected.content_type {
+        "image/jpeg" => ImageFormat::Jpeg,
+        "image/png" => ImageFormat::Png,
+        "image/webp" => ImageFormat::WebP,
+        _ => return Err(MediaError::Unsupported),
+    };
+
+    // Dimensiones desde el header, antes de reservar memoria para los píxeles
+    let (width, height) = image::io::Reader::with_format(Cursor::new(bytes), format)
+        .into_dimensions()
+        .map_err(|e| MediaError::Decode(e.to_string()))?;
+    limits.check_dimensions(width, height)?;
+
+    let mut reader = image::io::Reader::with_format(Cursor::new(bytes), format);
+    let mut decode_limits = Limits::default();
+    decode_limits.max_image_width = Some(limits.max_dimension);
+    decode_limits.max_image_height = Some(limits.max_dimension);
+    reader.limits(decode_limits);
+    let img = reader.decode().map_err(|e| MediaError::Decode(e.to_string()))?;
+
+    let orientation = if format == ImageFormat::Jpeg { exif_orientation(bytes).unwrap_or(1) } else { 1 };
+    let img = apply_orientation(img, orientation);
+    // PNG/WebP con transparencia siguen en PNG; el resto va a JPEG
+    let keep_alpha = format != ImageFormat::Jpeg && img.color().has_alpha();
+
+    let original = encode("original", &img, keep_alpha)?;
+    let longest = img.width().max(img.height());
+    let variants = THUMBNAIL_SIZES
+        .iter()
+        .filter(|(_, size)| *size < longest)
+        .map(|(name, size)| encode(name, &img.resize(*size, *size, FilterType::Lanczos3), keep_alpha))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ProcessedImage { original, variants })
+}
+
+/// Vacía las cajas de metadatos de un MP4/MOV: se renombran a `free` y su contenido se
+/// pone en cero, así los offsets de `stco`/`co64` siguen siendo válidos. Devuelve `false`
+/// para formatos que no se limpian (WebM).
+pub fn strip_video_metadata(bytes: &mut [u8], detected: &Detected) -> Result<bool, MediaError> {
+    match detected.content_type {
+        "video/mp4" | "video/quicktime" => {
+            let stripped = neutralize_boxes(bytes)?;
+            tracing::debug!("{} cajas de metadatos de video vaciadas", stripped);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn neutralize_boxes(buf: &mut [u8]) -> Result<usize, MediaError> {
+    let malformed = |pos: usize| MediaError::InvalidVideo(format!("malformed box at offset {}", pos));
+    let mut pos = 0;
+    let mut stripped = 0;
+    while pos < buf.len() {
+        let head = buf.get(pos..pos + 8).ok_or_else(|| malformed(pos))?;
+        let (header, size) = match u32::from_be_bytes([head[0], head[1], head[2], head[3]]) {
+            // Tamaño 0: la caja llega hasta el final del archivo
+            0 => (8, buf.len() - pos),
+            1 => {
+                let large = buf.get(pos + 8..pos + 16).ok_or_else(|| malformed(pos))?;
+                let large = u64::from_be_bytes(large.try_into().map_err(|_| malformed(pos))?);
+                (16, usize::try_from(large).map_err(|_| malformed(pos))?)
+            }
+            n => (8, n as usize),
+        };
+        if size < header || size > buf.len() - pos {
+            return Err(malformed(pos));
+        }
+        let kind = [buf[pos + 4], buf[pos + 5], buf[pos + 6], buf[pos + 7]];
+        if VIDEO_METADATA_BOXES.contains(&&kind) {
+            buf[pos + 4..pos + 8].copy_from_slice(b"free");
+            buf[pos + header..pos + size].fill(0);
+            stripped += 1;
+        } else if VIDEO_CONTAINER_BOXES.contains(&&kind) {
+            stripped += neutralize_boxes(&mut buf[pos + header..pos + size])?;
+        }
+        pos += size;
+    }
+    Ok(stripped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, Rgb([200, 10, 10])));
+        let mut out = Vec::new();
+        img.write_to(&mut Cursor::new(&mut out), ImageOutputFormat::Png).unwrap();
+        out
+    }
+
+    /// JPEG con un segmento APP1 EXIF (orientación + un tag de GPS falso) justo después del SOI
+    fn jpeg_with_exif(width: u32, height: u32, orientation: u16) -> Vec<u8> {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, Rgb([10, 200, 10])));
+        let mut plain = Vec::new();
+        img.write_to(&mut Cursor::new(&mut plain), ImageOutputFormat::Jpeg(90)).unwrap();
+
+        let mut tiff = b"MM\x00\x2A\x00\x00\x00\x08".to_vec();
+        tiff.extend_from_slice(&2u16.to_be_bytes());
+        tiff.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0x00, 0x00]);
+        tiff.extend_from_slice(&[0x88, 0x25, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]);
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        let mut app1 = b"Exif\x00\x00".to_vec();
+        app1.extend_from_slice(&tiff);
+
+        let mut out = plain[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&((app1.len() + 2) as u16).to_be_bytes());
+        out.extend_from_slice(&app1);
+        out.extend_from_slice(&plain[2..]);
+        out
+    }
+
+    #[test]
+    fn sniffing_ignores_names_and_checks_magic_bytes() {
+        assert_eq!(sniff(&png(2, 2)).unwrap().content_type, "image/png");
+        assert_eq!(sniff(&jpeg_with_exif(2, 2, 1)).unwrap().extension, "jpg");
+        assert_eq!(sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 ").unwrap().kind, MediaKind::Image);
+        assert_eq!(sniff(b"%PDF-1.7\n").unwrap().kind, MediaKind::Document);
+        assert_eq!(sniff(b"\x00\x00\x00\x18ftypmp42").unwrap().content_type, "video/mp4");
+        assert_eq!(sniff(b"\x00\x00\x00\x14ftypqt  ").unwrap().extension, "mov");
+        assert_eq!(sniff(b"\x00\x00\x00\x18ftypheic"), None);
+        assert_eq!(sniff(b"<html><script>"), None);
+        assert_eq!(sniff(b"MZ\x90\x00"), None);
+    }
+
+    #[test]
+    fn size_limits_depend_on_kind() {
+        let limits = MediaLimits { max_image_bytes: 10, ..MediaLimits::default() };
+        let image = sniff(&png(1, 1)).unwrap();
+        assert!(limits.check_size(&image, 10).is_ok());
+        let err = limits.check_size(&image, 11).unwrap_err();
+        assert_eq!(err.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(limits.max_request_bytes() > limits.max_video_bytes);
+    }
+
+    #[test]
+    fn oversized_dimensions_are_rejected_before_decoding() {
+        let limits = MediaLimits { max_dimension: 100, ..MediaLimits::default() };
+        let bytes = png(101, 10);
+        let err = process_image(&bytes, &sniff(&bytes).unwrap(), &limits).unwrap_err();
+        assert!(matches!(err, MediaError::TooManyPixels { width: 101, .. }));
+
+        let limits = MediaLimits { max_pixels: 50, ..MediaLimits::default() };
+        assert!(process_image(&bytes, &sniff(&bytes).unwrap(), &limits).is_err());
+    }
+
+    #[test]
+    fn exif_orientation_is_read_in_both_byte_orders() {
+        assert_eq!(exif_orientation(&jpeg_with_exif(4, 2, 6)), Some(6));
+        assert_eq!(tiff_orientation(b"II\x2A\x00\x08\x00\x00\x00\x01\x00\x12\x01\x03\x00\x01\x00\x00\x00\x03\x00\x00\x00"), Some(3));
+        assert_eq!(tiff_orientation(b"XX\x00\x2A"), None);
+        assert_eq!(exif_orientation(&png(2, 2)), None);
+    }
+
+    #[test]
+    fn jpeg_metadata_is_stripped_and_orientation_applied() {
+        let bytes = jpeg_with_exif(40, 20, 6);
+        assert!(bytes.windows(4).any(|w| w == b"Exif"));
+
+        let processed = process_image(&bytes, &sniff(&bytes).unwrap(), &MediaLimits::default()).unwrap();
+        assert!(!processed.original.bytes.windows(4).any(|w| w == b"Exif"));
+        assert_eq!(exif_orientation(&processed.original.bytes), None);
+        // Orientación 6 = girar 90°: el ancho y el alto se intercambian
+        assert_eq!((processed.original.width, processed.original.height), (20, 40));
+        assert_eq!(processed.original.content_type, "image/jpeg");
+    }
+
+    #[test]
+    fn thumbnails_only_for_smaller_sizes_and_keep_aspect() {
+        let bytes = png(600, 300);
+        let processed = process_image(&bytes, &sniff(&bytes).unwrap(), &MediaLimits::default()).unwrap();
+        let names: Vec<&str> = processed.variants.iter().map(|v| v.name).collect();
+        assert_eq!(names, vec!["thumb", "small"]);
+        assert_eq!((processed.variants[0].width, processed.variants[0].height), (150, 75));
+        assert_eq!((processed.variants[1].width, processed.variants[1].height), (480, 240));
+        // PNG sin transparencia se normaliza a JPEG
+        assert_eq!(processed.original.content_type, "image/jpeg");
+    }
+
+    #[test]
+    fn transparent_png_stays_png() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(200, 200, Rgba([0, 0, 0, 0])));
+        let mut bytes = Vec::new();
+        img.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png).unwrap();
+        let processed = process_image(&bytes, &sniff(&bytes).unwrap(), &MediaLimits::default()).unwrap();
+        assert_eq!(processed.original.content_type, "image/png");
+        assert_eq!(processed.variants[0].extension, "png");
+    }
+
+    #[test]
+    fn garbage_with_valid_magic_fails_to_decode() {
+        let mut bytes = png(2, 2);
+        bytes.truncate(20);
+        let err = process_image(&bytes, &sniff(&bytes).unwrap(), &MediaLimits::default()).unwrap_err();
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn mp4_location_metadata_is_blanked_in_place() {
+        let location = mp4_box(b"\xA9xyz", b"+04.6097-074.0817/");
+        let trak = mp4_box(b"trak", &[mp4_box(b"tkhd", &[1; 12]), mp4_box(b"meta", b"com.apple.location")].concat());
+        let moov = mp4_box(b"moov", &[mp4_box(b"mvhd", &[2; 12]), mp4_box(b"udta", &location), trak].concat());
+        let mut bytes = [mp4_box(b"ftyp", b"isom\0\0\0\0"), moov, mp4_box(b"mdat", &[7; 32])].concat();
+        let len = bytes.len();
+
+        let detected = sniff(&bytes).unwrap();
+        assert!(strip_video_metadata(&mut bytes, &detected).unwrap());
+        assert_eq!(bytes.len(), len);
+        assert!(!bytes.windows(8).any(|w| w == b"+04.6097"));
+        assert!(!bytes.windows(8).any(|w| w == b"location"));
+        assert!(bytes.windows(4).any(|w| w == b"tkhd"));
+        assert!(bytes.ends_with(&[7; 32]));
+        assert_eq!(sniff(&bytes), Some(detected));
+    }
+
+    #[test]
+    fn truncated_mp4_is_rejected_and_webm_is_left_alone() {
+        let mut bytes = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        bytes.extend_from_slice(&[0, 0, 0x10, 0, b'm', b'o', b'o', b'v']);
+        let detected = sniff(&bytes).unwrap();
+        let err = strip_video_metadata(&mut bytes, &detected).unwrap_err();
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let mut webm = b"\x1A\x45\xDF\xA3\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        let detected = sniff(&webm).unwrap();
+        assert!(!strip_video_metadata(&mut webm, &detected).unwrap());
+    }
+}
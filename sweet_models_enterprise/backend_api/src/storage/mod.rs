@@ -1,19 +1,79 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use axum::{body::Bytes, extract::Multipart, http::StatusCode, Json};
-use mime_guess::mime;
+use axum::{
+    body::Bytes,
+    extract::{multipart::Field, Multipart, Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
 use s3::{creds::Credentials, error::S3Error, Bucket, Region};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::middleware::auth::AuthenticatedUser;
+use crate::operations::parse_user_id;
 use crate::state::AppState;
 
-const DEFAULT_BUCKET: &str = "sweet-media";
+pub mod media;
+pub mod private;
+
+use media::{Detected, MediaError, MediaKind};
+
+/// Buckets del estudio; solo `Public` se sirve por URL permanente
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BucketType {
+    Public,   // BUCKET_PUBLIC: fotos, miniaturas, medios del feed
+    Legal,    // BUCKET_LEGAL: firmas y contratos PDF
+    Kyc,      // BUCKET_KYC: documentos de identidad
+    Evidence, // BUCKET_EVIDENCE: soportes de ausencias, pruebas de moderación
+}
+
+impl BucketType {
+    pub const ALL: [BucketType; 4] = [BucketType::Public, BucketType::Legal, BucketType::Kyc, BucketType::Evidence];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BucketType::Public => "PUBLIC",
+            BucketType::Legal => "LEGAL",
+            BucketType::Kyc => "KYC",
+            BucketType::Evidence => "EVIDENCE",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_uppercase().as_str() {
+            "PUBLIC" => Some(BucketType::Public),
+            "LEGAL" => Some(BucketType::Legal),
+            "KYC" => Some(BucketType::Kyc),
+            "EVIDENCE" => Some(BucketType::Evidence),
+            _ => None,
+        }
+    }
+
+    /// Los privados solo se leen con URLs firmadas de corta duración
+    pub fn is_private(&self) -> bool {
+        !matches!(self, BucketType::Public)
+    }
+}
+
+/// Nombre real del bucket según el tipo usando variables de entorno
+pub fn bucket_name(bucket: BucketType) -> String {
+    let (var, default) = match bucket {
+        BucketType::Public => ("BUCKET_PUBLIC", "sweet-media"),
+        BucketType::Legal => ("BUCKET_LEGAL", "sweet-legal"),
+        BucketType::Kyc => ("BUCKET_KYC", "sweet-kyc"),
+        BucketType::Evidence => ("BUCKET_EVIDENCE", "sweet-evidence"),
+    };
+    std::env::var(var).unwrap_or_else(|_| default.to_string())
+}
 
 #[derive(Clone)]
 pub struct StorageService {
-    bucket: Bucket,
+    buckets: HashMap<BucketType, Bucket>,
     public_base: String,
 }
 
@@ -23,6 +83,8 @@ pub enum StorageError {
     Config(String),
     #[error(transparent)]
     S3(#[from] S3Error),
+    #[error("db error: {0}")]
+    Db(#[from] sqlx::Error),
 }
 
 impl StorageService {
@@ -33,8 +95,9 @@ impl StorageService {
         let secret_key = std::env::var("MINIO_ROOT_PASSWORD")
             .unwrap_or_else(|_| "password_super_seguro".to_string());
 
-        let public_base = std::env::var("MINIO_PUBLIC_BASE")
-            .unwrap_or_else(|_| format!("{}/{DEFAULT_BUCKET}", endpoint.trim_end_matches('/')));
+        let public_base = std::env::var("MINIO_PUBLIC_BASE").unwrap_or_else(|_| {
+            format!("{}/{}", endpoint.trim_end_matches('/'), bucket_name(BucketType::Public))
+        });
 
         let region = Region::Custom {
             region: "us-east-1".to_string(),
@@ -44,68 +107,360 @@ impl StorageService {
         let credentials = Credentials::new(Some(&access_key), Some(&secret_key), None, None, None)
             .map_err(|e| StorageError::Config(e.to_string()))?;
 
-        let mut bucket = Bucket::new(DEFAULT_BUCKET, region, credentials).map_err(StorageError::S3)?;
-        bucket.set_path_style(); // MinIO necesita path-style
+        let mut buckets = HashMap::new();
+        for kind in BucketType::ALL {
+            let mut bucket = Bucket::new(&bucket_name(kind), region.clone(), credentials.clone())
+                .map_err(StorageError::S3)?;
+            bucket.set_path_style(); // MinIO necesita path-style
+            buckets.insert(kind, bucket);
+        }
 
-        Ok(Self { bucket, public_base })
+        Ok(Self { buckets, public_base })
     }
 
-    pub async fn upload_file(&self, file_data: Bytes, extension: &str) -> Result<String, StorageError> {
-        let safe_ext = extension.trim_start_matches('.').to_ascii_lowercase();
-        let file_name = if safe_ext.is_empty() {
-            Uuid::new_v4().to_string()
-        } else {
-            format!("{}.{}", Uuid::new_v4(), safe_ext)
-        };
-
-        // Mejor esfuerzo de content-type
-        let content_type = mime_guess::from_ext(&safe_ext)
-            .first_or(mime::APPLICATION_OCTET_STREAM)
-            .essence_str()
-            .to_string();
+    fn bucket(&self, kind: BucketType) -> &Bucket {
+        // `from_env` crea todos los tipos, así que siempre está
+        &self.buckets[&kind]
+    }
 
-        self.bucket
-            .put_object_with_content_type(&file_name, &file_data, content_type.as_str())
+    /// Sube bytes a un bucket concreto; no devuelve URL porque los privados no tienen
+    pub async fn put_object_in(
+        &self,
+        kind: BucketType,
+        key: &str,
+        data: &[u8],
+        content_type: &str,
+    ) -> Result<(), StorageError> {
+        self.bucket(kind)
+            .put_object_with_content_type(key, data, content_type)
             .await
             .map_err(StorageError::S3)?;
+        Ok(())
+    }
 
-        let url = format!("{}/{}", self.public_base.trim_end_matches('/'), file_name);
-        Ok(url)
+    /// Sube bytes al bucket público; devuelve la URL permanente
+    pub async fn put_object(&self, key: &str, data: &[u8], content_type: &str) -> Result<String, StorageError> {
+        self.put_object_in(BucketType::Public, key, data, content_type).await?;
+        Ok(format!("{}/{}", self.public_base.trim_end_matches('/'), key))
     }
+
+    pub async fn delete_object_in(&self, kind: BucketType, key: &str) -> Result<(), StorageError> {
+        self.bucket(kind).delete_object(key).await.map_err(StorageError::S3)?;
+        Ok(())
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<(), StorageError> {
+        self.delete_object_in(BucketType::Public, key).await
+    }
+
+    /// URL GET firmada que caduca a los `expiry_secs`; con `download_name` fuerza descarga
+    pub async fn presign_get(
+        &self,
+        kind: BucketType,
+        key: &str,
+        expiry_secs: u32,
+        download_name: Option<&str>,
+    ) -> Result<String, StorageError> {
+        let queries = download_name.map(|name| {
+            let safe: String = name.chars().filter(|c| !c.is_control() && *c != '"').collect();
+            HashMap::from([(
+                "response-content-disposition".to_string(),
+                format!("attachment; filename=\"{}\"", safe),
+            )])
+        });
+        self.bucket(kind).presign_get(key, expiry_secs, queries).await.map_err(StorageError::S3)
+    }
+
+    /// Descarga un objeto; devuelve los bytes y su content-type
+    pub async fn get_object_in(&self, kind: BucketType, key: &str) -> Result<(Vec<u8>, String), StorageError> {
+        let response = self.bucket(kind).get_object(key).await.map_err(StorageError::S3)?;
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .cloned()
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        Ok((response.bytes().to_vec(), content_type))
+    }
+
+    /// Indica si la URL fue emitida por este almacenamiento (evidencias, adjuntos)
+    pub fn owns_url(&self, url: &str) -> bool {
+        self.public_key(url).is_some()
+    }
+
+    /// Clave en el bucket público de una URL emitida por `put_object`
+    pub fn public_key<'a>(&self, url: &'a str) -> Option<&'a str> {
+        url.strip_prefix(self.public_base.trim_end_matches('/'))
+            .and_then(|rest| rest.strip_prefix('/'))
+            .filter(|key| !key.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct MediaVariant {
+    pub name: String,
+    pub url: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub width: i32,
+    pub height: i32,
 }
 
-#[derive(Serialize)]
-pub struct UploadResponse {
+/// Archivo subido por el pipeline, con sus miniaturas
+#[derive(Debug, Serialize, FromRow)]
+pub struct MediaAsset {
+    pub id: Uuid,
+    pub owner_id: Option<Uuid>,
+    pub kind: String,
+    pub content_type: String,
     pub url: String,
+    pub size_bytes: i64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub metadata_stripped: bool,
+    pub original_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub variants: Vec<MediaVariant>,
 }
 
+const ASSET_COLUMNS: &str =
+    "id, owner_id, kind, content_type, url, size_bytes, width, height, metadata_stripped, original_name, created_at";
+
+/// Un objeto listo para subir al bucket
+struct Upload<'a> {
+    name: &'static str,
+    bytes: &'a [u8],
+    content_type: &'static str,
+    extension: &'static str,
+    dimensions: Option<(u32, u32)>,
+}
+
+impl<'a> From<&'a media::EncodedImage> for Upload<'a> {
+    fn from(image: &'a media::EncodedImage) -> Self {
+        Upload {
+            name: image.name,
+            bytes: &image.bytes,
+            content_type: image.content_type,
+            extension: image.extension,
+            dimensions: Some((image.width, image.height)),
+        }
+    }
+}
+
+/// Un objeto ya subido al bucket, pendiente de registrar
+struct StoredObject {
+    name: &'static str,
+    key: String,
+    url: String,
+    content_type: &'static str,
+    size_bytes: i64,
+    width: Option<i32>,
+    height: Option<i32>,
+}
+
+/// POST /api/storage/upload (multipart, campo `file`)
+/// Valida el tipo real, limpia metadatos de imágenes, genera miniaturas y registra todo
 pub async fn upload_handler(
-    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
     mut multipart: Multipart,
-) -> Result<Json<UploadResponse>, (StatusCode, String)> {
-    while let Some(field) = multipart.next_field().await.map_err(internal_error)? {
-        let field = field;
-        if field.name() == Some("file") {
-            let file_name = field.file_name().unwrap_or("upload").to_string();
-            let ext = std::path::Path::new(&file_name)
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("");
-
-            let data = field.bytes().await.map_err(internal_error)?;
-            let url = state
-                .storage
-                .upload_file(data, ext)
-                .await
-                .map_err(internal_error)?;
-
-            return Ok(Json(UploadResponse { url }));
+) -> Result<(StatusCode, Json<MediaAsset>), (StatusCode, String)> {
+    let owner_id = parse_user_id(&auth.user_id)?;
+
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| (e.status(), e.body_text()))? {
+        if field.name() != Some("file") {
+            continue;
         }
+        let original_name = field.file_name().map(|name| name.chars().take(255).collect::<String>());
+        let limits = *media::limits();
+        let (mut data, detected) = read_upload(&mut field, &limits).await?;
+        let video_stripped = detected.kind == MediaKind::Video && media::strip_video_metadata(&mut data, &detected)?;
+        let data = Bytes::from(data);
+
+        let processed = match detected.kind {
+            MediaKind::Image => {
+                let bytes = data.clone();
+                let processed = tokio::task::spawn_blocking(move || media::process_image(&bytes, &detected, &limits))
+                    .await
+                    .map_err(internal_error)??;
+                Some(processed)
+            }
+            MediaKind::Video | MediaKind::Document => None,
+        };
+
+        let asset_id = Uuid::new_v4();
+        // El original va primero; `record_asset` lo trata como el asset y el resto como variantes
+        let uploads: Vec<Upload> = match &processed {
+            Some(p) => std::iter::once(&p.original).chain(p.variants.iter()).map(Upload::from).collect(),
+            None => vec![Upload {
+                name: "original",
+                bytes: &data,
+                content_type: detected.content_type,
+                extension: detected.extension,
+                dimensions: None,
+            }],
+        };
+
+        let mut stored: Vec<StoredObject> = Vec::with_capacity(uploads.len());
+        for upload in uploads {
+            let key = format!("media/{}/{}.{}", asset_id, upload.name, upload.extension);
+            match state.storage.put_object(&key, upload.bytes, upload.content_type).await {
+                Ok(url) => stored.push(StoredObject {
+                    name: upload.name,
+                    key,
+                    url,
+                    content_type: upload.content_type,
+                    size_bytes: upload.bytes.len() as i64,
+                    width: upload.dimensions.map(|(w, _)| w as i32),
+                    height: upload.dimensions.map(|(_, h)| h as i32),
+                }),
+                Err(e) => {
+                    discard_objects(&state, &stored).await;
+                    return Err(internal_error(e));
+                }
+            }
+        }
+
+        let stripped = processed.is_some() || video_stripped;
+        return match record_asset(&state, asset_id, owner_id, detected.kind, original_name, stripped, &stored).await {
+            Ok(asset) => {
+                tracing::info!(
+                    "🖼️ Media {} subido por {} ({}, {} variantes)",
+                    asset.id,
+                    owner_id,
+                    asset.content_type,
+                    asset.variants.len()
+                );
+                Ok((StatusCode::CREATED, Json(asset)))
+            }
+            Err(e) => {
+                discard_objects(&state, &stored).await;
+                Err(e)
+            }
+        };
     }
 
     Err((StatusCode::BAD_REQUEST, "file field is required".to_string()))
 }
 
+/// Lee el campo por partes: el tipo sale de los primeros bytes y la lectura se corta en
+/// cuanto pasa el límite de ese tipo, sin esperar a tener todo el cuerpo en memoria
+pub(crate) async fn read_upload(
+    field: &mut Field<'_>,
+    limits: &media::MediaLimits,
+) -> Result<(Vec<u8>, Detected), (StatusCode, String)> {
+    let mut data = Vec::new();
+    let mut detected = None;
+    while let Some(chunk) = field.chunk().await.map_err(|e| (e.status(), e.body_text()))? {
+        data.extend_from_slice(&chunk);
+        if detected.is_none() && data.len() >= media::SNIFF_LEN {
+            detected = Some(media::sniff(&data).ok_or(MediaError::Unsupported)?);
+        }
+        if let Some(detected) = &detected {
+            limits.check_size(detected, data.len())?;
+        }
+    }
+    let detected = match detected {
+        Some(detected) => detected,
+        None => media::sniff(&data).ok_or(MediaError::Unsupported)?,
+    };
+    Ok((data, detected))
+}
+
+/// Borra del bucket lo que se subió de una carga que no terminó de registrarse
+async fn discard_objects(state: &AppState, objects: &[StoredObject]) {
+    for object in objects {
+        if let Err(e) = state.storage.delete_object(&object.key).await {
+            tracing::warn!("No se pudo borrar el objeto huérfano {}: {}", object.key, e);
+        }
+    }
+}
+
+async fn record_asset(
+    state: &AppState,
+    asset_id: Uuid,
+    owner_id: Uuid,
+    kind: MediaKind,
+    original_name: Option<String>,
+    metadata_stripped: bool,
+    objects: &[StoredObject],
+) -> Result<MediaAsset, (StatusCode, String)> {
+    let (original, variants) = objects
+        .split_first()
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Nothing stored".to_string()))?;
+
+    let mut tx = state.db.begin().await.map_err(internal_error)?;
+    let mut asset = sqlx::query_as::<_, MediaAsset>(&format!(
+        r#"
+        INSERT INTO media_assets
+            (id, owner_id, kind, content_type, object_key, url, size_bytes, width, height, metadata_stripped, original_name)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING {}
+        "#,
+        ASSET_COLUMNS
+    ))
+    .bind(asset_id)
+    .bind(owner_id)
+    .bind(kind.as_str())
+    .bind(original.content_type)
+    .bind(&original.key)
+    .bind(&original.url)
+    .bind(original.size_bytes)
+    .bind(original.width)
+    .bind(original.height)
+    .bind(metadata_stripped)
+    .bind(&original_name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+
+    for variant in variants {
+        let row = sqlx::query_as::<_, MediaVariant>(
+            r#"
+            INSERT INTO media_variants (asset_id, name, object_key, url, content_type, size_bytes, width, height)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING name, url, content_type, size_bytes, width, height
+            "#,
+        )
+        .bind(asset_id)
+        .bind(variant.name)
+        .bind(&variant.key)
+        .bind(&variant.url)
+        .bind(variant.content_type)
+        .bind(variant.size_bytes)
+        .bind(variant.width.unwrap_or_default())
+        .bind(variant.height.unwrap_or_default())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+        asset.variants.push(row);
+    }
+    tx.commit().await.map_err(internal_error)?;
+    Ok(asset)
+}
+
+/// GET /api/storage/media/:id
+pub async fn get_media_handler(
+    State(state): State<Arc<AppState>>,
+    _auth: AuthenticatedUser,
+    Path(asset_id): Path<Uuid>,
+) -> Result<Json<MediaAsset>, (StatusCode, String)> {
+    let mut asset = sqlx::query_as::<_, MediaAsset>(&format!("SELECT {} FROM media_assets WHERE id = $1", ASSET_COLUMNS))
+        .bind(asset_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Media not found".to_string()))?;
+    asset.variants = sqlx::query_as::<_, MediaVariant>(
+        "SELECT name, url, content_type, size_bytes, width, height FROM media_variants WHERE asset_id = $1 ORDER BY width",
+    )
+    .bind(asset_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(internal_error)?;
+    Ok(Json(asset))
+}
+
 fn internal_error<E: std::fmt::Display>(err: E) -> (StatusCode, String) {
     (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-}
\ No newline at end of file
+}
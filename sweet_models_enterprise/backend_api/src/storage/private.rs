@@ -0,0 +1,719 @@
+//! Objetos privados: contratos, firmas, KYC y evidencias.
+//! No tienen URL permanente; quien tenga acceso pide una URL firmada que caduca
+//! y cada emisión (concedida o no) queda en `storage_access_log`.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+
+use axum::{
+    extract::{ConnectInfo, Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use super::media::{self, MediaKind};
+use super::{BucketType, StorageError};
+use crate::middleware::auth::{AdminOnly, AuthenticatedUser};
+use crate::operations::{is_staff, parse_user_id};
+use crate::state::AppState;
+
+/// Duración mínima de una URL firmada (segundos)
+pub const MIN_PRESIGN_TTL_SECS: u32 = 30;
+
+const OBJECT_COLUMNS: &str =
+    "id, bucket, object_key, owner_id, purpose, content_type, size_bytes, original_name, uploaded_by, created_at";
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PrivateObject {
+    pub id: Uuid,
+    pub bucket: String,
+    /// La clave no sale de la API: solo se accede por URL firmada
+    #[serde(skip_serializing)]
+    pub object_key: String,
+    pub owner_id: Option<Uuid>,
+    pub purpose: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub original_name: Option<String>,
+    pub uploaded_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PrivateObject {
+    pub fn bucket_type(&self) -> Option<BucketType> {
+        BucketType::parse(&self.bucket)
+    }
+}
+
+/// Datos para guardar un objeto privado desde otros módulos (p. ej. contratos)
+pub struct NewPrivateObject<'a> {
+    pub bucket: BucketType,
+    pub owner_id: Option<Uuid>,
+    pub uploaded_by: Option<Uuid>,
+    pub purpose: &'a str,
+    pub data: &'a [u8],
+    pub content_type: &'a str,
+    pub extension: &'a str,
+    pub original_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresignPolicy {
+    pub default_ttl_secs: u32,
+    pub max_ttl_secs: u32,
+}
+
+impl Default for PresignPolicy {
+    fn default() -> Self {
+        Self { default_ttl_secs: 300, max_ttl_secs: 3600 }
+    }
+}
+
+impl PresignPolicy {
+    /// STORAGE_PRESIGN_TTL_SECS y STORAGE_PRESIGN_MAX_TTL_SECS
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str, default: u32| {
+            std::env::var(name).ok().and_then(|v| v.trim().parse().ok()).unwrap_or(default)
+        };
+        let max_ttl_secs = var("STORAGE_PRESIGN_MAX_TTL_SECS", defaults.max_ttl_secs).max(MIN_PRESIGN_TTL_SECS);
+        let default_ttl_secs =
+            var("STORAGE_PRESIGN_TTL_SECS", defaults.default_ttl_secs).clamp(MIN_PRESIGN_TTL_SECS, max_ttl_secs);
+        Self { default_ttl_secs, max_ttl_secs }
+    }
+
+    /// TTL efectivo para lo que pida el cliente, siempre dentro de los límites
+    pub fn ttl(&self, requested: Option<u32>) -> u32 {
+        requested.unwrap_or(self.default_ttl_secs).clamp(MIN_PRESIGN_TTL_SECS, self.max_ttl_secs)
+    }
+}
+
+pub fn presign_policy() -> &'static PresignPolicy {
+    static POLICY: OnceLock<PresignPolicy> = OnceLock::new();
+    POLICY.get_or_init(PresignPolicy::from_env)
+}
+
+/// El dueño siempre; KYC además solo administración, el resto también moderadores
+pub fn can_access(bucket: BucketType, owner_id: Option<Uuid>, user_id: Uuid, role: &str) -> bool {
+    if owner_id == Some(user_id) {
+        return true;
+    }
+    match bucket {
+        BucketType::Public => true,
+        BucketType::Kyc => matches!(role.to_uppercase().as_str(), "ADMIN" | "SUPER_ADMIN"),
+        BucketType::Legal | BucketType::Evidence => is_staff(role),
+    }
+}
+
+/// Normaliza el propósito a `[a-z0-9_]` para usarlo también como prefijo de la clave
+pub fn normalize_purpose(raw: &str) -> Option<String> {
+    let purpose: String = raw
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let purpose = purpose.trim_matches('_').to_string();
+    (!purpose.is_empty() && purpose.len() <= 40).then_some(purpose)
+}
+
+/// Sube el objeto al bucket privado y lo registra; si el registro falla se borra del bucket
+pub async fn store_private(
+    storage: &super::StorageService,
+    db: &PgPool,
+    new: NewPrivateObject<'_>,
+) -> Result<PrivateObject, StorageError> {
+    if !new.bucket.is_private() {
+        return Err(StorageError::Config("public objects are not registered as private".to_string()));
+    }
+    let id = Uuid::new_v4();
+    let owner = new.owner_id.map(|o| o.to_string()).unwrap_or_else(|| "studio".to_string());
+    let key = format!("{}/{}/{}.{}", new.purpose, owner, id, new.extension.trim_start_matches('.'));
+
+    storage.put_object_in(new.bucket, &key, new.data, new.content_type).await?;
+
+    let inserted = sqlx::query_as::<_, PrivateObject>(&format!(
+        r#"
+        INSERT INTO private_objects
+            (id, bucket, object_key, owner_id, purpose, content_type, size_bytes, original_name, uploaded_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING {}
+        "#,
+        OBJECT_COLUMNS
+    ))
+    .bind(id)
+    .bind(new.bucket.as_str())
+    .bind(&key)
+    .bind(new.owner_id)
+    .bind(new.purpose)
+    .bind(new.content_type)
+    .bind(new.data.len() as i64)
+    .bind(&new.original_name)
+    .bind(new.uploaded_by)
+    .fetch_one(db)
+    .await;
+
+    match inserted {
+        Ok(object) => {
+            let entry = AccessEntry {
+                object_id: object.id,
+                user_id: new.uploaded_by,
+                action: "UPLOAD",
+                granted: true,
+                ..Default::default()
+            };
+            log_access(db, entry).await;
+            Ok(object)
+        }
+        Err(e) => {
+            if let Err(cleanup) = storage.delete_object_in(new.bucket, &key).await {
+                tracing::warn!("No se pudo borrar el objeto privado huérfano {}: {}", key, cleanup);
+            }
+            Err(StorageError::Db(e))
+        }
+    }
+}
+
+/// Borra un objeto privado (bucket y registro); para deshacer subidas cuyo uso posterior falló
+pub async fn discard_private(storage: &super::StorageService, db: &PgPool, object: &PrivateObject) {
+    let Some(bucket) = object.bucket_type() else { return };
+    if let Err(e) = storage.delete_object_in(bucket, &object.object_key).await {
+        tracing::warn!("No se pudo borrar el objeto privado {}: {}", object.object_key, e);
+    }
+    if let Err(e) = sqlx::query("DELETE FROM private_objects WHERE id = $1").bind(object.id).execute(db).await {
+        tracing::warn!("No se pudo borrar el registro del objeto privado {}: {}", object.id, e);
+    }
+}
+
+/// Objeto privado de `owner_id` en `bucket`; para que otros módulos solo referencien lo que la persona subió
+pub async fn find_owned_object(
+    db: &PgPool,
+    id: Uuid,
+    bucket: BucketType,
+    owner_id: Uuid,
+) -> Result<Option<PrivateObject>, sqlx::Error> {
+    sqlx::query_as::<_, PrivateObject>(&format!(
+        "SELECT {} FROM private_objects WHERE id = $1 AND bucket = $2 AND owner_id = $3",
+        OBJECT_COLUMNS
+    ))
+    .bind(id)
+    .bind(bucket.as_str())
+    .bind(owner_id)
+    .fetch_optional(db)
+    .await
+}
+
+// ============================================================================
+// MIGRACIÓN DE OBJETOS PÚBLICOS ANTIGUOS
+// ============================================================================
+
+/// Columna con URL pública antigua y la columna que la reemplaza por un objeto privado
+struct LegacyColumn {
+    table: &'static str,
+    url_column: &'static str,
+    object_column: &'static str,
+    bucket: BucketType,
+    purpose: &'static str,
+}
+
+const LEGACY_COLUMNS: [LegacyColumn; 3] = [
+    LegacyColumn {
+        table: "legal_documents",
+        url_column: "signature_url",
+        object_column: "signature_object_id",
+        bucket: BucketType::Legal,
+        purpose: "signature",
+    },
+    LegacyColumn {
+        table: "legal_documents",
+        url_column: "pdf_url",
+        object_column: "pdf_object_id",
+        bucket: BucketType::Legal,
+        purpose: "contract_pdf",
+    },
+    LegacyColumn {
+        table: "leave_requests",
+        url_column: "document_url",
+        object_column: "document_object_id",
+        bucket: BucketType::Evidence,
+        purpose: "leave_support",
+    },
+];
+
+#[derive(Debug, Default, Serialize)]
+pub struct MigrationReport {
+    pub table: &'static str,
+    pub column: &'static str,
+    pub migrated: u32,
+    /// URLs que no son de nuestro bucket público: se dejan para revisión manual
+    pub skipped: u32,
+    pub failed: u32,
+}
+
+#[derive(FromRow)]
+struct LegacyRow {
+    id: Uuid,
+    user_id: Uuid,
+    url: String,
+}
+
+/// Copia cada objeto público antiguo al bucket privado, apunta la fila al objeto nuevo, borra la URL
+/// y elimina la copia pública. Idempotente: solo toca filas con URL y sin objeto.
+pub async fn migrate_legacy_public_objects(
+    storage: &super::StorageService,
+    db: &PgPool,
+) -> Result<Vec<MigrationReport>, StorageError> {
+    let mut reports = Vec::with_capacity(LEGACY_COLUMNS.len());
+    for column in &LEGACY_COLUMNS {
+        let mut report = MigrationReport { table: column.table, column: column.url_column, ..Default::default() };
+        let rows = sqlx::query_as::<_, LegacyRow>(&format!(
+            "SELECT id, user_id, {url} AS url FROM {table} WHERE {url} IS NOT NULL AND {object} IS NULL",
+            url = column.url_column,
+            table = column.table,
+            object = column.object_column,
+        ))
+        .fetch_all(db)
+        .await?;
+
+        for row in rows {
+            let Some(key) = storage.public_key(&row.url) else {
+                report.skipped += 1;
+                continue;
+            };
+            match migrate_one(storage, db, column, &row, key).await {
+                Ok(()) => report.migrated += 1,
+                Err(e) => {
+                    tracing::error!("No se pudo migrar {}.{} de {}: {}", column.table, column.url_column, row.id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+        tracing::info!(
+            "🔒 {}.{}: {} migrados, {} omitidos, {} fallidos",
+            column.table,
+            column.url_column,
+            report.migrated,
+            report.skipped,
+            report.failed
+        );
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+async fn migrate_one(
+    storage: &super::StorageService,
+    db: &PgPool,
+    column: &LegacyColumn,
+    row: &LegacyRow,
+    key: &str,
+) -> Result<(), StorageError> {
+    let (data, content_type) = storage.get_object_in(BucketType::Public, key).await?;
+    let extension = key.rsplit_once('.').map(|(_, ext)| ext).filter(|ext| ext.len() <= 5).unwrap_or("bin");
+    let object = store_private(
+        storage,
+        db,
+        NewPrivateObject {
+            bucket: column.bucket,
+            owner_id: Some(row.user_id),
+            uploaded_by: None,
+            purpose: column.purpose,
+            data: &data,
+            content_type: &content_type,
+            extension,
+            original_name: key.rsplit('/').next().map(str::to_string),
+        },
+    )
+    .await?;
+
+    let updated = sqlx::query(&format!(
+        "UPDATE {table} SET {object} = $2, {url} = NULL WHERE id = $1 AND {object} IS NULL",
+        table = column.table,
+        object = column.object_column,
+        url = column.url_column,
+    ))
+    .bind(row.id)
+    .bind(object.id)
+    .execute(db)
+    .await;
+    match updated {
+        Ok(result) if result.rows_affected() == 1 => {}
+        // Otra migración concurrente ganó, o la fila falló: no dejar la copia privada huérfana
+        Ok(_) => {
+            discard_private(storage, db, &object).await;
+            return Ok(());
+        }
+        Err(e) => {
+            discard_private(storage, db, &object).await;
+            return Err(e.into());
+        }
+    }
+
+    if let Err(e) = storage.delete_object_in(BucketType::Public, key).await {
+        tracing::warn!("Copia pública {} migrada pero no borrada: {}", key, e);
+    }
+    Ok(())
+}
+
+/// POST /api/admin/storage/migrate-legacy (admin)
+pub async fn migrate_legacy_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+) -> Result<Json<Vec<MigrationReport>>, (StatusCode, String)> {
+    migrate_legacy_public_objects(&state.storage, &state.db).await.map(Json).map_err(internal_error)
+}
+
+#[derive(Debug, Default)]
+struct AccessEntry {
+    object_id: Uuid,
+    user_id: Option<Uuid>,
+    action: &'static str,
+    granted: bool,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    url_expires_at: Option<DateTime<Utc>>,
+}
+
+impl AccessEntry {
+    fn from_request(object_id: Uuid, user_id: Uuid, peer: Option<SocketAddr>, headers: &HeaderMap) -> Self {
+        let header = |name: &str| headers.get(name).and_then(|h| h.to_str().ok());
+        Self {
+            object_id,
+            user_id: Some(user_id),
+            action: "PRESIGN",
+            ip_address: peer.map(|peer| client_ip(peer.ip(), header("x-forwarded-for"), trusted_proxies()).to_string()),
+            user_agent: header("user-agent").map(|ua| ua.chars().take(255).collect()),
+            ..Default::default()
+        }
+    }
+}
+
+/// TRUSTED_PROXIES: IPs de los balanceadores cuyo `X-Forwarded-For` se acepta
+fn trusted_proxies() -> &'static [IpAddr] {
+    static PROXIES: OnceLock<Vec<IpAddr>> = OnceLock::new();
+    PROXIES.get_or_init(|| {
+        std::env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|ip| ip.trim().parse().ok())
+            .collect()
+    })
+}
+
+/// IP del cliente para auditoría. `X-Forwarded-For` lo escribe quien quiera, así que solo se lee
+/// si la conexión viene de un proxy de confianza, y de derecha a izquierda saltando los proxies:
+/// la primera IP que no es nuestra es la que vio el último proxy.
+pub fn client_ip(peer: IpAddr, forwarded_for: Option<&str>, trusted: &[IpAddr]) -> IpAddr {
+    if !trusted.contains(&peer) {
+        return peer;
+    }
+    let mut client = peer;
+    for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) => {
+                client = ip;
+                if !trusted.contains(&ip) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    client
+}
+
+/// El registro de accesos es de mejor esfuerzo: un fallo no bloquea la operación
+async fn log_access(db: &PgPool, entry: AccessEntry) {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO storage_access_log (object_id, user_id, action, granted, ip_address, user_agent, url_expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(entry.object_id)
+    .bind(entry.user_id)
+    .bind(entry.action)
+    .bind(entry.granted)
+    .bind(&entry.ip_address)
+    .bind(&entry.user_agent)
+    .bind(entry.url_expires_at)
+    .execute(db)
+    .await;
+    if let Err(e) = result {
+        tracing::error!("No se pudo registrar el acceso al objeto {}: {}", entry.object_id, e);
+    }
+}
+
+async fn load_object(db: &PgPool, id: Uuid) -> Result<PrivateObject, (StatusCode, String)> {
+    sqlx::query_as::<_, PrivateObject>(&format!("SELECT {} FROM private_objects WHERE id = $1", OBJECT_COLUMNS))
+        .bind(id)
+        .fetch_optional(db)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Object not found".to_string()))
+}
+
+fn ensure_access(object: &PrivateObject, user_id: Uuid, role: &str) -> Result<BucketType, (StatusCode, String)> {
+    let bucket = object.bucket_type().ok_or_else(|| internal_error("unknown bucket"))?;
+    if can_access(bucket, object.owner_id, user_id, role) {
+        Ok(bucket)
+    } else {
+        Err((StatusCode::FORBIDDEN, "No tienes acceso a este documento".to_string()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadPrivateQuery {
+    pub bucket: String,
+    pub purpose: String,
+    /// Solo staff puede subir en nombre de otra persona
+    pub owner_id: Option<Uuid>,
+}
+
+/// POST /api/storage/private?bucket=kyc&purpose=id_front (multipart, campo `file`)
+pub async fn upload_private_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Query(query): Query<UploadPrivateQuery>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<PrivateObject>), (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let bucket = BucketType::parse(&query.bucket)
+        .filter(BucketType::is_private)
+        .ok_or((StatusCode::BAD_REQUEST, "bucket must be one of legal, kyc, evidence".to_string()))?;
+    let purpose =
+        normalize_purpose(&query.purpose).ok_or((StatusCode::BAD_REQUEST, "Invalid purpose".to_string()))?;
+    let owner_id = match query.owner_id {
+        Some(owner) if owner != user_id && !is_staff(&auth.role) => {
+            return Err((StatusCode::FORBIDDEN, "Solo el staff puede subir documentos de otra persona".to_string()));
+        }
+        Some(owner) => owner,
+        None => user_id,
+    };
+
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| (e.status(), e.body_text()))? {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let original_name = field.file_name().map(|name| name.chars().take(255).collect::<String>());
+        let limits = *media::limits();
+        let (mut data, detected) = super::read_upload(&mut field, &limits).await?;
+
+        // Las imágenes (p. ej. fotos de cédula) se guardan sin EXIF/GPS; sin miniaturas
+        let (bytes, content_type, extension) = match detected.kind {
+            MediaKind::Image => {
+                let processed = tokio::task::spawn_blocking(move || media::process_image(&data, &detected, &limits))
+                    .await
+                    .map_err(internal_error)??;
+                let original = processed.original;
+                (original.bytes, original.content_type, original.extension)
+            }
+            MediaKind::Video => {
+                media::strip_video_metadata(&mut data, &detected)?;
+                (data, detected.content_type, detected.extension)
+            }
+            MediaKind::Document => (data, detected.content_type, detected.extension),
+        };
+
+        let object = store_private(
+            &state.storage,
+            &state.db,
+            NewPrivateObject {
+                bucket,
+                owner_id: Some(owner_id),
+                uploaded_by: Some(user_id),
+                purpose: &purpose,
+                data: &bytes,
+                content_type,
+                extension,
+                original_name,
+            },
+        )
+        .await
+        .map_err(internal_error)?;
+
+        tracing::info!("🔒 Objeto privado {} ({}) subido por {}", object.id, object.bucket, user_id);
+        return Ok((StatusCode::CREATED, Json(object)));
+    }
+
+    Err((StatusCode::BAD_REQUEST, "file field is required".to_string()))
+}
+
+/// GET /api/storage/private/:id
+pub async fn get_private_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    Path(object_id): Path<Uuid>,
+) -> Result<Json<PrivateObject>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let object = load_object(&state.db, object_id).await?;
+    ensure_access(&object, user_id, &auth.role)?;
+    Ok(Json(object))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PresignQuery {
+    pub ttl: Option<u32>,
+    /// Fuerza `Content-Disposition: attachment` con el nombre original
+    #[serde(default)]
+    pub download: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PresignedUrl {
+    pub object_id: Uuid,
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// GET /api/storage/private/:id/url
+/// Emite una URL firmada de corta duración; se registra también si se deniega
+pub async fn presign_handler(
+    State(state): State<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Path(object_id): Path<Uuid>,
+    Query(query): Query<PresignQuery>,
+) -> Result<Json<PresignedUrl>, (StatusCode, String)> {
+    let user_id = parse_user_id(&auth.user_id)?;
+    let object = load_object(&state.db, object_id).await?;
+    let mut entry = AccessEntry::from_request(object.id, user_id, peer.map(|ConnectInfo(addr)| addr), &headers);
+
+    let bucket = match ensure_access(&object, user_id, &auth.role) {
+        Ok(bucket) => bucket,
+        Err(denied) => {
+            tracing::warn!("⛔ Acceso denegado a {} para {} ({})", object.id, user_id, auth.role);
+            log_access(&state.db, entry).await;
+            return Err(denied);
+        }
+    };
+
+    let ttl = presign_policy().ttl(query.ttl);
+    let download_name = query.download.then(|| object.original_name.clone()).flatten();
+    let url = state
+        .storage
+        .presign_get(bucket, &object.object_key, ttl, download_name.as_deref())
+        .await
+        .map_err(internal_error)?;
+    let expires_at = Utc::now() + Duration::seconds(i64::from(ttl));
+
+    entry.granted = true;
+    entry.url_expires_at = Some(expires_at);
+    log_access(&state.db, entry).await;
+
+    Ok(Json(PresignedUrl { object_id: object.id, url, expires_at }))
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AccessLogEntry {
+    pub id: i64,
+    pub object_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub action: String,
+    pub granted: bool,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub url_expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccessLogQuery {
+    pub limit: Option<i64>,
+}
+
+/// GET /api/storage/private/:id/access-log (admin)
+pub async fn access_log_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Path(object_id): Path<Uuid>,
+    Query(query): Query<AccessLogQuery>,
+) -> Result<Json<Vec<AccessLogEntry>>, (StatusCode, String)> {
+    let entries = sqlx::query_as::<_, AccessLogEntry>(
+        r#"
+        SELECT id, object_id, user_id, action, granted, ip_address, user_agent, url_expires_at, created_at
+        FROM storage_access_log
+        WHERE object_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(object_id)
+    .bind(query.limit.unwrap_or(50).clamp(1, 500))
+    .fetch_all(&state.db)
+    .await
+    .map_err(internal_error)?;
+    Ok(Json(entries))
+}
+
+fn internal_error<E: std::fmt::Display>(err: E) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owner_always_has_access() {
+        let owner = Uuid::new_v4();
+        for bucket in [BucketType::Legal, BucketType::Kyc, BucketType::Evidence] {
+            assert!(can_access(bucket, Some(owner), owner, "MODEL"));
+        }
+    }
+
+    #[test]
+    fn kyc_is_admin_only_for_non_owners() {
+        let owner = Some(Uuid::new_v4());
+        let other = Uuid::new_v4();
+        assert!(!can_access(BucketType::Kyc, owner, other, "MODERATOR"));
+        assert!(can_access(BucketType::Kyc, owner, other, "admin"));
+        assert!(can_access(BucketType::Legal, owner, other, "MODERATOR"));
+        assert!(!can_access(BucketType::Evidence, owner, other, "MODEL"));
+        assert!(!can_access(BucketType::Legal, None, other, "MONITOR"));
+    }
+
+    #[test]
+    fn ttl_is_clamped_to_policy() {
+        let policy = PresignPolicy { default_ttl_secs: 300, max_ttl_secs: 900 };
+        assert_eq!(policy.ttl(None), 300);
+        assert_eq!(policy.ttl(Some(5)), MIN_PRESIGN_TTL_SECS);
+        assert_eq!(policy.ttl(Some(86_400)), 900);
+        assert_eq!(policy.ttl(Some(600)), 600);
+    }
+
+    #[test]
+    fn purpose_is_normalized_for_keys() {
+        assert_eq!(normalize_purpose(" Contract PDF ").as_deref(), Some("contract_pdf"));
+        assert_eq!(normalize_purpose("../../etc").as_deref(), Some("etc"));
+        assert_eq!(normalize_purpose("///"), None);
+        assert_eq!(normalize_purpose(&"x".repeat(41)), None);
+    }
+
+    #[test]
+    fn forwarded_for_is_only_trusted_from_known_proxies() {
+        let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+        let client: IpAddr = "181.50.1.7".parse().unwrap();
+        let spoofed = Some("1.2.3.4, 181.50.1.7");
+
+        // Conexión directa: se ignora la cabecera
+        assert_eq!(client_ip(client, Some("1.2.3.4"), &[proxy]), client);
+        // Detrás del proxy: la IP que añadió el proxy, no la que inventó el cliente
+        assert_eq!(client_ip(proxy, spoofed, &[proxy]), client);
+        assert_eq!(client_ip(proxy, Some("garbage"), &[proxy]), proxy);
+        assert_eq!(client_ip(proxy, None, &[]), proxy);
+    }
+
+    #[test]
+    fn only_non_public_buckets_are_private() {
+        assert!(!BucketType::Public.is_private());
+        assert!(BucketType::ALL.iter().filter(|b| b.is_private()).count() == 3);
+        assert_eq!(BucketType::parse("kyc"), Some(BucketType::Kyc));
+        assert_eq!(BucketType::parse("backup"), None);
+    }
+}
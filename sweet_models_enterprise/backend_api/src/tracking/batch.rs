@@ -0,0 +1,341 @@
+/// Ingesta de telemetría por lotes.
+///
+/// Cuando la red del estudio se cae la extensión acumula updates con un número de
+/// secuencia creciente y los reenvía en lote (arreglo JSON o NDJSON). El servidor
+/// guarda por dispositivo la última secuencia procesada: todo `seq` menor o igual se
+/// trata como duplicado, así que reenviar un lote es idempotente. La respuesta
+/// devuelve `last_acked_seq` para que el cliente descarte su buffer hasta ahí.
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use super::{devices, ingest_update, platforms, publish_snapshot, TelemetryUpdate};
+use crate::state::AppState;
+
+/// Código de Postgres cuando `FOR UPDATE NOWAIT` no obtiene el lock
+const LOCK_NOT_AVAILABLE: &str = "55P03";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct BatchLimits {
+    pub max_items: usize,
+    pub max_bytes: usize,
+    pub batches_per_minute: i64,
+}
+
+impl BatchLimits {
+    pub fn from_env() -> Self {
+        let var = |key: &str, default: i64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            max_items: var("TELEMETRY_BATCH_MAX_ITEMS", 500) as usize,
+            max_bytes: var("TELEMETRY_BATCH_MAX_BYTES", 1024 * 1024) as usize,
+            batches_per_minute: var("TELEMETRY_BATCH_PER_MINUTE", 20),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchItem {
+    pub seq: i64,
+    #[serde(flatten)]
+    pub update: TelemetryUpdate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedItem {
+    pub seq: i64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchResponse {
+    pub accepted: usize,
+    pub duplicates: usize,
+    pub rejected: Vec<RejectedItem>,
+    pub last_acked_seq: i64,
+    /// false si el lote se cortó por un error; reenviar desde `last_acked_seq + 1`
+    pub complete: bool,
+    pub limits: BatchLimits,
+    pub processed_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AckResponse {
+    pub device_id: Uuid,
+    pub last_acked_seq: i64,
+    pub limits: BatchLimits,
+}
+
+/// Arreglo JSON o NDJSON (una update por línea)
+pub fn parse_batch(body: &[u8], ndjson: bool) -> Result<Vec<BatchItem>, String> {
+    let looks_like_array = body.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[');
+    if !ndjson || looks_like_array {
+        return serde_json::from_slice(body).map_err(|e| format!("Invalid batch payload: {}", e));
+    }
+
+    body.split(|b| *b == b'\n')
+        .enumerate()
+        .filter(|(_, line)| !line.iter().all(u8::is_ascii_whitespace))
+        .map(|(i, line)| serde_json::from_slice(line).map_err(|e| format!("Invalid NDJSON line {}: {}", i + 1, e)))
+        .collect()
+}
+
+/// Valida el orden del lote y devuelve los índices pendientes de procesar
+/// (los `seq <= last_acked` son duplicados de un reenvío).
+pub fn pending_items(last_acked: i64, seqs: &[i64]) -> Result<Vec<usize>, String> {
+    if let Some(bad) = seqs.iter().find(|s| **s <= 0) {
+        return Err(format!("seq must be positive (got {})", bad));
+    }
+    if let Some(pair) = seqs.windows(2).find(|w| w[1] <= w[0]) {
+        return Err(format!("seq must be strictly increasing ({} followed by {})", pair[0], pair[1]));
+    }
+    Ok(seqs
+        .iter()
+        .enumerate()
+        .filter(|(_, seq)| **seq > last_acked)
+        .map(|(i, _)| i)
+        .collect())
+}
+
+fn reject(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, message.into()).into_response()
+}
+
+fn too_many(message: &str, retry_after_secs: u64) -> Response {
+    let mut response = reject(StatusCode::TOO_MANY_REQUESTS, message);
+    if let Ok(value) = HeaderValue::from_str(&retry_after_secs.to_string()) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+/// Límite de lotes por minuto y dispositivo (ventana fija en Redis)
+async fn check_rate(state: &AppState, device_id: Uuid, limits: BatchLimits) -> Result<(), Response> {
+    let mut conn = state
+        .redis
+        .get()
+        .await
+        .map_err(|e| reject(StatusCode::SERVICE_UNAVAILABLE, format!("Redis error: {}", e)))?;
+    let minute = chrono::Utc::now().timestamp() / 60;
+    let key = format!("telemetry:batch_rate:{}:{}", device_id, minute);
+    let count: i64 = deadpool_redis::redis::cmd("INCR")
+        .arg(&key)
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| reject(StatusCode::SERVICE_UNAVAILABLE, format!("Redis error: {}", e)))?;
+    if count == 1 {
+        let _: Result<(), _> = deadpool_redis::redis::cmd("EXPIRE").arg(&key).arg(120).query_async(&mut conn).await;
+    }
+    if count > limits.batches_per_minute {
+        let retry_after = 60 - (chrono::Utc::now().timestamp() % 60) as u64;
+        return Err(too_many("Batch rate limit exceeded", retry_after));
+    }
+    Ok(())
+}
+
+/// POST /api/tracking/telemetry/batch
+/// Cuerpo: `[{"seq": 1, ...TelemetryUpdate}, ...]` o NDJSON (`Content-Type: application/x-ndjson`).
+/// Firmado igual que la ingesta unitaria.
+pub async fn telemetry_batch_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    uri: Uri,
+    body: Bytes,
+) -> Result<Json<BatchResponse>, Response> {
+    let limits = BatchLimits::from_env();
+    if body.len() > limits.max_bytes {
+        return Err(reject(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Batch exceeds {} bytes", limits.max_bytes),
+        ));
+    }
+
+    let device = devices::verify_signed_request(&state, &headers, "POST", uri.path(), &body)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    check_rate(&state, device.id, limits).await?;
+
+    let ndjson = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("ndjson"));
+    let items = parse_batch(&body, ndjson).map_err(|e| reject(StatusCode::BAD_REQUEST, e))?;
+
+    if items.len() > limits.max_items {
+        return Err(reject(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Batch exceeds {} items", limits.max_items),
+        ));
+    }
+
+    let db_error = |e: sqlx::Error| {
+        tracing::error!("DB error in telemetry batch: {}", e);
+        reject(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+
+    // Un lote a la vez por dispositivo. Las muestras se insertan en esta misma transacción:
+    // si el commit del ack falla, tampoco quedan muestras y el reintento no las duplica
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+    let last_acked = sqlx::query_scalar::<_, i64>(
+        "SELECT last_acked_seq FROM telemetry_devices WHERE id = $1 FOR UPDATE NOWAIT",
+    )
+    .bind(device.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        let locked = e
+            .as_database_error()
+            .and_then(|d| d.code())
+            .is_some_and(|code| code == LOCK_NOT_AVAILABLE);
+        if locked {
+            too_many("Another batch for this device is in progress", 1)
+        } else {
+            db_error(e)
+        }
+    })?;
+
+    let seqs: Vec<i64> = items.iter().map(|i| i.seq).collect();
+    let pending = pending_items(last_acked, &seqs).map_err(|e| reject(StatusCode::BAD_REQUEST, e))?;
+    let duplicates = items.len() - pending.len();
+
+    let mut acked = last_acked;
+    let mut accepted = 0;
+    let mut rejected = Vec::new();
+    let mut complete = true;
+    let mut latest: HashMap<String, (TelemetryUpdate, Vec<super::deltas::TokenDelta>)> = HashMap::new();
+
+    let registry = platforms::registry();
+    for idx in pending {
+        let item = &items[idx];
+        // Reintentar no arregla estos casos: se confirman como procesados y se informan
+        if item.update.room_id != device.room_id {
+            rejected.push(RejectedItem { seq: item.seq, reason: "Device not allowed to report this room".to_string() });
+            acked = item.seq;
+            continue;
+        }
+        let update = match registry.normalize(item.update.clone()) {
+            Ok(update) => update,
+            Err(reason) => {
+                rejected.push(RejectedItem { seq: item.seq, reason });
+                acked = item.seq;
+                continue;
+            }
+        };
+
+        match ingest_update(&mut tx, &update).await {
+            Ok(deltas) => {
+                accepted += 1;
+                acked = item.seq;
+                latest.insert(update.platform.clone(), (update, deltas));
+            }
+            Err(e) => {
+                tracing::error!("Telemetry batch item {} failed for device {}: {}", item.seq, device.id, e);
+                complete = false;
+                break;
+            }
+        }
+    }
+
+    sqlx::query("UPDATE telemetry_devices SET last_acked_seq = $2, last_batch_at = NOW() WHERE id = $1")
+        .bind(device.id)
+        .bind(acked)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    // Solo el estado más reciente por plataforma va a Redis/WebSocket
+    for (update, deltas) in latest.values() {
+        publish_snapshot(&state, update, deltas).await;
+    }
+
+    tracing::info!(
+        "📦 Lote de telemetría de {} ({}): {} aceptadas, {} duplicadas, {} rechazadas, ack {}",
+        device.name,
+        device.room_id,
+        accepted,
+        duplicates,
+        rejected.len(),
+        acked
+    );
+
+    Ok(Json(BatchResponse {
+        accepted,
+        duplicates,
+        rejected,
+        last_acked_seq: acked,
+        complete,
+        limits,
+        processed_at: chrono::Utc::now().timestamp(),
+    }))
+}
+
+/// GET /api/tracking/telemetry/batch
+/// Última secuencia confirmada (firmado con cuerpo vacío); la extensión la consulta al arrancar.
+pub async fn telemetry_ack_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Result<Json<AckResponse>, (StatusCode, String)> {
+    let device = devices::verify_signed_request(&state, &headers, "GET", uri.path(), &[]).await?;
+    let last_acked_seq = sqlx::query_scalar::<_, i64>("SELECT last_acked_seq FROM telemetry_devices WHERE id = $1")
+        .bind(device.id)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(AckResponse { device_id: device.id, last_acked_seq, limits: BatchLimits::from_env() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEM_1: &str = r#"{"seq":1,"room_id":"1","platform":"chaturbate","tokens_count":10,"tips_count":0,"viewers_count":3,"timestamp":1733900000}"#;
+    const ITEM_2: &str = r#"{"seq":2,"room_id":"1","platform":"chaturbate","tokens_count":25,"tips_count":1,"viewers_count":4,"timestamp":1733900005}"#;
+
+    #[test]
+    fn parses_json_array_and_ndjson() {
+        let array = format!("[{},{}]", ITEM_1, ITEM_2);
+        let items = parse_batch(array.as_bytes(), false).unwrap();
+        assert_eq!(items.iter().map(|i| i.seq).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(items[1].update.tokens_count, 25);
+
+        let ndjson = format!("{}\n\n{}\n", ITEM_1, ITEM_2);
+        let items = parse_batch(ndjson.as_bytes(), true).unwrap();
+        assert_eq!(items.len(), 2);
+
+        let err = parse_batch(format!("{}\nnot json", ITEM_1).as_bytes(), true).unwrap_err();
+        assert!(err.contains("line 2"));
+    }
+
+    #[test]
+    fn already_acked_sequences_are_skipped() {
+        assert_eq!(pending_items(0, &[1, 2, 3]).unwrap(), vec![0, 1, 2]);
+        assert_eq!(pending_items(2, &[1, 2, 3, 4]).unwrap(), vec![2, 3]);
+        assert!(pending_items(10, &[4, 5]).unwrap().is_empty());
+        // Huecos permitidos: el cliente pudo descartar updates
+        assert_eq!(pending_items(0, &[3, 7]).unwrap(), vec![0, 1]);
+    }
+
+    #[test]
+    fn unordered_or_non_positive_sequences_are_rejected() {
+        assert!(pending_items(0, &[1, 3, 2]).is_err());
+        assert!(pending_items(0, &[1, 1]).is_err());
+        assert!(pending_items(0, &[0, 1]).is_err());
+    }
+}
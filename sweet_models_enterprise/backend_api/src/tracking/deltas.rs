@@ -0,0 +1,404 @@
+/// Conversión de saldos de tokens en deltas ganados.
+///
+/// Los scrapers envían el saldo acumulado de la plataforma (`tokens_count`), que vuelve
+/// a cero cuando la transmisión se reinicia o la modelo cobra. Aquí se compara cada
+/// muestra con el último saldo visto por room/plataforma para obtener lo ganado,
+/// se abren sesiones nuevas en resets y silencios, se descartan mensajes repetidos o
+/// fuera de orden, y cada delta se atribuye a las modelos en turno en ese room: si hay
+/// varias se reparte entre ellas (una fila por modelo) y si no hay nadie queda sin dueño.
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{types::Uuid, Connection, FromRow, PgConnection, Postgres, Transaction};
+
+use crate::operations::{attendance::Shift, leave};
+
+/// Silencio máximo entre muestras antes de abrir una sesión nueva (el scraper envía cada 5 s)
+pub const SESSION_GAP_SECONDS: i64 = 300;
+
+/// Margen antes del inicio del turno en el que un check-in cuenta como presencia
+const EARLY_CHECK_IN_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SessionStart {
+    First,
+    Reset,
+    Gap,
+}
+
+impl SessionStart {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionStart::First => "FIRST",
+            SessionStart::Reset => "RESET",
+            SessionStart::Gap => "GAP",
+        }
+    }
+}
+
+/// Último saldo conocido de un room/plataforma
+#[derive(Debug, Clone, FromRow)]
+pub struct CounterState {
+    pub session_id: Uuid,
+    pub last_tokens: i64,
+    pub last_sampled_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaOutcome {
+    /// Muestra repetida o más vieja que la última procesada
+    Stale,
+    Counted {
+        delta: i64,
+        new_session: Option<SessionStart>,
+        is_reset: bool,
+        after_gap: bool,
+    },
+}
+
+/// Compara una muestra con el estado previo.
+///
+/// - Sin estado: la muestra es la línea base de la primera sesión (delta 0).
+/// - Saldo menor al anterior: el contador se reinició; lo ganado desde el reinicio es el saldo actual.
+/// - Silencio mayor a `SESSION_GAP_SECONDS`: sesión nueva; como el saldo es acumulado,
+///   lo ganado durante el silencio se conserva pero queda marcado `after_gap`.
+pub fn compute_delta(prev: Option<&CounterState>, tokens: i64, at: DateTime<Utc>) -> DeltaOutcome {
+    let Some(prev) = prev else {
+        return DeltaOutcome::Counted { delta: 0, new_session: Some(SessionStart::First), is_reset: false, after_gap: false };
+    };
+
+    if at <= prev.last_sampled_at {
+        return DeltaOutcome::Stale;
+    }
+
+    let after_gap = at - prev.last_sampled_at > Duration::seconds(SESSION_GAP_SECONDS);
+
+    if tokens < prev.last_tokens {
+        return DeltaOutcome::Counted { delta: tokens, new_session: Some(SessionStart::Reset), is_reset: true, after_gap };
+    }
+
+    DeltaOutcome::Counted {
+        delta: tokens - prev.last_tokens,
+        new_session: after_gap.then_some(SessionStart::Gap),
+        is_reset: false,
+        after_gap,
+    }
+}
+
+/// A quién pertenece un delta
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Attribution {
+    pub studio_room_id: Option<i32>,
+    pub shift_id: Option<i32>,
+    pub work_date: Option<NaiveDate>,
+    /// Modelos presentes en el room/turno, en orden estable
+    pub present: Vec<Uuid>,
+}
+
+/// Reparte el delta en partes iguales entre las modelos presentes; el residuo va token a
+/// token a las primeras. Si nadie marcó entrada, el delta queda sin dueño para revisión.
+pub fn split_delta(delta: i64, present: &[Uuid]) -> Vec<(Option<Uuid>, i64)> {
+    if present.is_empty() {
+        return vec![(None, delta)];
+    }
+    let count = present.len() as i64;
+    let (share, remainder) = (delta / count, delta % count);
+    present
+        .iter()
+        .enumerate()
+        .map(|(i, model_id)| (Some(*model_id), share + i64::from((i as i64) < remainder)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TokenDelta {
+    pub id: Uuid,
+    pub room_id: String,
+    pub platform: String,
+    pub session_id: Uuid,
+    pub sampled_at: DateTime<Utc>,
+    pub tokens_delta: i64,
+    pub tokens_balance: i64,
+    pub is_reset: bool,
+    pub after_gap: bool,
+    pub studio_room_id: Option<i32>,
+    pub shift_id: Option<i32>,
+    pub work_date: Option<NaiveDate>,
+    pub model_id: Option<Uuid>,
+    pub attribution_candidates: i32,
+}
+
+async fn attribute(
+    tx: &mut Transaction<'_, Postgres>,
+    room_id: &str,
+    at: DateTime<Utc>,
+) -> Result<Attribution, sqlx::Error> {
+    // El turno y su día siguen la grilla de turnos; el día de trabajo (y las licencias) es el día local del estudio
+    let (shift, shift_day) = Shift::at(at);
+    let work_date = leave::studio_date(at);
+    let mut attribution = Attribution {
+        shift_id: Some(shift.as_int()),
+        work_date: Some(work_date),
+        ..Default::default()
+    };
+
+    // El scraper reporta el room como texto: id numérico o nombre del registro
+    let studio_room_id = sqlx::query_scalar::<_, i32>(
+        "SELECT id FROM rooms WHERE id::TEXT = $1 OR LOWER(name) = LOWER($1) ORDER BY id LIMIT 1",
+    )
+    .bind(room_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(studio_room_id) = studio_room_id else {
+        return Ok(attribution);
+    };
+    attribution.studio_room_id = Some(studio_room_id);
+
+    let (window_start, _) = shift.window_utc(shift_day);
+    let week_id = format!("{}-W{:02}", shift_day.iso_week().year(), shift_day.iso_week().week());
+
+    let present = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT us.user_id
+        FROM user_shifts us
+        WHERE us.assigned_room = $1 AND us.assigned_shift = $2 AND us.week_id = $3
+          AND EXISTS (
+              SELECT 1 FROM attendance_logs al
+              WHERE al.user_id = us.user_id
+                AND al.check_in <= $4
+                AND al.check_in >= $5
+                AND (al.check_out IS NULL OR al.check_out >= $4)
+          )
+          AND NOT EXISTS (
+              SELECT 1 FROM leave_requests lr
+              WHERE lr.user_id = us.user_id AND lr.status = 'APPROVED' AND $6 BETWEEN lr.start_date AND lr.end_date
+          )
+        ORDER BY us.user_id
+        "#,
+    )
+    .bind(studio_room_id)
+    .bind(shift.as_int())
+    .bind(&week_id)
+    .bind(at)
+    .bind(window_start - Duration::minutes(EARLY_CHECK_IN_MINUTES))
+    .bind(work_date)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    attribution.present = present;
+    Ok(attribution)
+}
+
+/// Procesa una muestra ya persistida y devuelve las filas registradas: una por modelo
+/// presente (o una sin dueño), vacío si se descartó o no hubo tokens nuevos en la sesión.
+pub async fn record_sample(
+    conn: &mut PgConnection,
+    room_id: &str,
+    platform: &str,
+    tokens: i64,
+    sampled_at: DateTime<Utc>,
+) -> Result<Vec<TokenDelta>, sqlx::Error> {
+    let mut tx = conn.begin().await?;
+
+    // Serializa el procesamiento por room/plataforma (incluida la primera muestra, sin fila aún)
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1 || ':' || $2))")
+        .bind(room_id)
+        .bind(platform)
+        .execute(&mut *tx)
+        .await?;
+
+    let prev = sqlx::query_as::<_, CounterState>(
+        "SELECT session_id, last_tokens, last_sampled_at FROM telemetry_counter_state WHERE room_id = $1 AND platform = $2",
+    )
+    .bind(room_id)
+    .bind(platform)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let (delta, new_session, is_reset, after_gap) = match compute_delta(prev.as_ref(), tokens, sampled_at) {
+        DeltaOutcome::Stale => {
+            tracing::debug!("⏭️ Muestra fuera de orden descartada: {}/{} @ {}", room_id, platform, sampled_at);
+            tx.rollback().await?;
+            return Ok(Vec::new());
+        }
+        DeltaOutcome::Counted { delta, new_session, is_reset, after_gap } => (delta, new_session, is_reset, after_gap),
+    };
+
+    let session_id = match (new_session, prev.as_ref()) {
+        (None, Some(prev)) => prev.session_id,
+        (reason, _) => {
+            let reason = reason.unwrap_or(SessionStart::First);
+            if reason != SessionStart::First {
+                tracing::info!("🔄 Nueva sesión de telemetría ({}) para {}/{}", reason.as_str(), room_id, platform);
+            }
+            sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO telemetry_sessions (room_id, platform, started_at, last_sample_at, start_reason)
+                VALUES ($1, $2, $3, $3, $4)
+                RETURNING id
+                "#,
+            )
+            .bind(room_id)
+            .bind(platform)
+            .bind(sampled_at)
+            .bind(reason.as_str())
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO telemetry_counter_state (room_id, platform, session_id, last_tokens, last_sampled_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (room_id, platform) DO UPDATE
+        SET session_id = EXCLUDED.session_id,
+            last_tokens = EXCLUDED.last_tokens,
+            last_sampled_at = EXCLUDED.last_sampled_at,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(room_id)
+    .bind(platform)
+    .bind(session_id)
+    .bind(tokens)
+    .bind(sampled_at)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE telemetry_sessions SET last_sample_at = $2, tokens_earned = tokens_earned + $3 WHERE id = $1",
+    )
+    .bind(session_id)
+    .bind(sampled_at)
+    .bind(delta)
+    .execute(&mut *tx)
+    .await?;
+
+    // Solo se guardan filas con tokens o que abren sesión; las muestras planas no aportan nada
+    if delta == 0 && new_session.is_none() {
+        tx.commit().await?;
+        return Ok(Vec::new());
+    }
+
+    let attribution = attribute(&mut tx, room_id, sampled_at).await?;
+    let candidates = attribution.present.len() as i32;
+
+    let mut recorded = Vec::new();
+    for (model_id, share) in split_delta(delta, &attribution.present) {
+        let row = sqlx::query_as::<_, TokenDelta>(
+            r#"
+            INSERT INTO telemetry_token_deltas
+                (room_id, platform, session_id, sampled_at, tokens_delta, tokens_balance, is_reset, after_gap,
+                 studio_room_id, shift_id, work_date, model_id, attribution_candidates)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT DO NOTHING
+            RETURNING id, room_id, platform, session_id, sampled_at, tokens_delta, tokens_balance, is_reset,
+                      after_gap, studio_room_id, shift_id, work_date, model_id, attribution_candidates
+            "#,
+        )
+        .bind(room_id)
+        .bind(platform)
+        .bind(session_id)
+        .bind(sampled_at)
+        .bind(share)
+        .bind(tokens)
+        .bind(is_reset)
+        .bind(after_gap)
+        .bind(attribution.studio_room_id)
+        .bind(attribution.shift_id)
+        .bind(attribution.work_date)
+        .bind(model_id)
+        .bind(candidates)
+        .fetch_optional(&mut *tx)
+        .await?;
+        recorded.extend(row);
+    }
+
+    tx.commit().await?;
+    Ok(recorded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn state(tokens: i64, at: DateTime<Utc>) -> CounterState {
+        CounterState { session_id: Uuid::nil(), last_tokens: tokens, last_sampled_at: at }
+    }
+
+    fn t0() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 12, 11, 15, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn first_sample_is_a_zero_baseline() {
+        assert_eq!(
+            compute_delta(None, 1200, t0()),
+            DeltaOutcome::Counted { delta: 0, new_session: Some(SessionStart::First), is_reset: false, after_gap: false }
+        );
+    }
+
+    #[test]
+    fn increasing_balance_yields_difference() {
+        let prev = state(1200, t0());
+        assert_eq!(
+            compute_delta(Some(&prev), 1250, t0() + Duration::seconds(5)),
+            DeltaOutcome::Counted { delta: 50, new_session: None, is_reset: false, after_gap: false }
+        );
+    }
+
+    #[test]
+    fn duplicate_and_out_of_order_samples_are_stale() {
+        let prev = state(1200, t0());
+        assert_eq!(compute_delta(Some(&prev), 1200, t0()), DeltaOutcome::Stale);
+        assert_eq!(compute_delta(Some(&prev), 1100, t0() - Duration::seconds(5)), DeltaOutcome::Stale);
+    }
+
+    #[test]
+    fn lower_balance_is_a_reset() {
+        let prev = state(1200, t0());
+        assert_eq!(
+            compute_delta(Some(&prev), 30, t0() + Duration::seconds(5)),
+            DeltaOutcome::Counted { delta: 30, new_session: Some(SessionStart::Reset), is_reset: true, after_gap: false }
+        );
+    }
+
+    #[test]
+    fn long_silence_opens_a_new_session_keeping_earned_tokens() {
+        let prev = state(1200, t0());
+        let at = t0() + Duration::seconds(SESSION_GAP_SECONDS + 1);
+        assert_eq!(
+            compute_delta(Some(&prev), 1300, at),
+            DeltaOutcome::Counted { delta: 100, new_session: Some(SessionStart::Gap), is_reset: false, after_gap: true }
+        );
+        assert_eq!(
+            compute_delta(Some(&prev), 10, at),
+            DeltaOutcome::Counted { delta: 10, new_session: Some(SessionStart::Reset), is_reset: true, after_gap: true }
+        );
+    }
+
+    #[test]
+    fn shared_room_deltas_are_split_among_present_models() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        let c = Uuid::new_v4();
+        assert_eq!(split_delta(100, &[]), vec![(None, 100)]);
+        assert_eq!(split_delta(100, &[a]), vec![(Some(a), 100)]);
+        assert_eq!(split_delta(100, &[a, b]), vec![(Some(a), 50), (Some(b), 50)]);
+        // El residuo se reparte de a un token y el total se conserva
+        let shares = split_delta(100, &[a, b, c]);
+        assert_eq!(shares, vec![(Some(a), 34), (Some(b), 33), (Some(c), 33)]);
+        assert_eq!(shares.iter().map(|(_, t)| t).sum::<i64>(), 100);
+    }
+
+    #[test]
+    fn shift_at_maps_late_night_to_previous_day() {
+        let day = NaiveDate::from_ymd_opt(2025, 12, 11).unwrap();
+        assert_eq!(Shift::at(Utc.with_ymd_and_hms(2025, 12, 11, 1, 30, 0).unwrap()), (Shift::Shift4, day.pred_opt().unwrap()));
+        assert_eq!(Shift::at(Utc.with_ymd_and_hms(2025, 12, 11, 2, 0, 0).unwrap()), (Shift::Shift1, day));
+        assert_eq!(Shift::at(Utc.with_ymd_and_hms(2025, 12, 11, 21, 0, 0).unwrap()), (Shift::Shift4, day));
+    }
+}
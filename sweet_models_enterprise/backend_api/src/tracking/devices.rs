@@ -0,0 +1,412 @@
+/// Credenciales de dispositivos de telemetría y verificación de firmas.
+///
+/// Cada extensión registrada recibe `device_id`, `key_id` y un secreto. Las
+/// peticiones se firman con HMAC-SHA256 sobre
+/// `timestamp \n nonce \n METHOD \n path \n sha256(body)` y viajan en las
+/// cabeceras `X-Device-Id`, `X-Key-Id`, `X-Timestamp`, `X-Nonce`, `X-Signature`.
+/// Se rechazan timestamps fuera de ventana y nonces repetidos (Redis).
+///
+/// La base no guarda el secreto: solo una semilla aleatoria por llave. El secreto es
+/// `HMAC-SHA256(TELEMETRY_KEY_PEPPER, semilla)`, así que un volcado de la base no basta
+/// para firmar. Cambiar el pepper invalida todas las llaves (hay que rotarlas).
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::AdminOnly,
+    state::AppState,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Ventana aceptada entre el reloj del dispositivo y el servidor
+pub const SIGNATURE_MAX_SKEW_SECONDS: i64 = 300;
+/// Los nonces se recuerdan algo más que la ventana para cubrir ambos extremos
+const NONCE_TTL_SECONDS: i64 = SIGNATURE_MAX_SKEW_SECONDS * 2;
+const DEFAULT_ROTATION_GRACE_MINUTES: i64 = 10;
+
+pub const HEADER_DEVICE_ID: &str = "x-device-id";
+pub const HEADER_KEY_ID: &str = "x-key-id";
+pub const HEADER_TIMESTAMP: &str = "x-timestamp";
+pub const HEADER_NONCE: &str = "x-nonce";
+pub const HEADER_SIGNATURE: &str = "x-signature";
+
+/// Cadena canónica que firma el dispositivo
+pub fn signing_string(timestamp: i64, nonce: &str, method: &str, path: &str, body: &[u8]) -> String {
+    let body_hash = hex::encode(Sha256::digest(body));
+    format!("{}\n{}\n{}\n{}\n{}", timestamp, nonce, method.to_uppercase(), path, body_hash)
+}
+
+pub fn compute_signature(secret: &str, message: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC acepta llaves de cualquier tamaño");
+    mac.update(message.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Comparación en tiempo constante de la firma recibida (hex)
+pub fn verify_signature(secret: &str, message: &str, signature_hex: &str) -> bool {
+    let Ok(signature) = hex::decode(signature_hex.trim()) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC acepta llaves de cualquier tamaño");
+    mac.update(message.as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+pub fn timestamp_is_fresh(timestamp: i64, now: DateTime<Utc>) -> bool {
+    (now.timestamp() - timestamp).abs() <= SIGNATURE_MAX_SKEW_SECONDS
+}
+
+fn valid_nonce(nonce: &str) -> bool {
+    (16..=64).contains(&nonce.len()) && nonce.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn generate_seed() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Pepper del servidor para derivar los secretos (obligatorio, se valida al arrancar)
+fn key_pepper() -> Result<String, (StatusCode, String)> {
+    std::env::var("TELEMETRY_KEY_PEPPER").map_err(|_| {
+        tracing::error!("❌ TELEMETRY_KEY_PEPPER no configurado");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Telemetry key pepper not configured".to_string())
+    })
+}
+
+/// Secreto que recibe el dispositivo a partir de la semilla guardada
+pub fn derive_secret(pepper: &str, seed: &str) -> String {
+    compute_signature(pepper, seed)
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TelemetryDevice {
+    pub id: Uuid,
+    pub name: String,
+    pub room_id: String,
+    pub status: String,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+const DEVICE_COLUMNS: &str = "id, name, room_id, status, last_seen_at, revoked_at, created_at";
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, (StatusCode, String)> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .ok_or((StatusCode::UNAUTHORIZED, format!("Missing {} header", name)))
+}
+
+/// Verifica la firma de una petición de telemetría y devuelve el dispositivo emisor
+pub async fn verify_signed_request(
+    state: &AppState,
+    headers: &HeaderMap,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> Result<TelemetryDevice, (StatusCode, String)> {
+    let unauthorized = |msg: &str| (StatusCode::UNAUTHORIZED, msg.to_string());
+
+    let device_id = Uuid::parse_str(header(headers, HEADER_DEVICE_ID)?).map_err(|_| unauthorized("Invalid device id"))?;
+    let key_id = Uuid::parse_str(header(headers, HEADER_KEY_ID)?).map_err(|_| unauthorized("Invalid key id"))?;
+    let timestamp: i64 = header(headers, HEADER_TIMESTAMP)?
+        .parse()
+        .map_err(|_| unauthorized("Invalid timestamp"))?;
+    let nonce = header(headers, HEADER_NONCE)?;
+    let signature = header(headers, HEADER_SIGNATURE)?;
+
+    if !timestamp_is_fresh(timestamp, Utc::now()) {
+        return Err(unauthorized("Timestamp outside allowed window"));
+    }
+    if !valid_nonce(nonce) {
+        return Err(unauthorized("Invalid nonce"));
+    }
+
+    let key = sqlx::query_as::<_, (String, String, Option<DateTime<Utc>>)>(
+        r#"
+        SELECT k.secret_seed, k.status, k.expires_at
+        FROM telemetry_device_keys k
+        JOIN telemetry_devices d ON d.id = k.device_id
+        WHERE k.id = $1 AND k.device_id = $2 AND d.status = 'ACTIVE'
+        "#,
+    )
+    .bind(key_id)
+    .bind(device_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let Some((seed, status, expires_at)) = key else {
+        return Err(unauthorized("Unknown or revoked device"));
+    };
+    let usable = match status.as_str() {
+        "ACTIVE" => true,
+        "ROTATED" => expires_at.is_some_and(|exp| exp > Utc::now()),
+        _ => false,
+    };
+    if !usable {
+        return Err(unauthorized("Key revoked or expired"));
+    }
+
+    let message = signing_string(timestamp, nonce, method, path, body);
+    if !verify_signature(&derive_secret(&key_pepper()?, &seed), &message, signature) {
+        tracing::warn!("🚫 Firma de telemetría inválida para dispositivo {}", device_id);
+        return Err(unauthorized("Invalid signature"));
+    }
+
+    // Anti-replay: el nonce solo puede usarse una vez dentro de la ventana
+    let mut conn = state
+        .redis
+        .get()
+        .await
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, format!("Redis error: {}", e)))?;
+    let fresh: Option<String> = deadpool_redis::redis::cmd("SET")
+        .arg(format!("telemetry:nonce:{}:{}", device_id, nonce))
+        .arg(timestamp)
+        .arg("NX")
+        .arg("EX")
+        .arg(NONCE_TTL_SECONDS)
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, format!("Redis error: {}", e)))?;
+    if fresh.is_none() {
+        tracing::warn!("🚫 Replay de telemetría detectado (dispositivo {})", device_id);
+        return Err(unauthorized("Replayed request"));
+    }
+
+    let device = sqlx::query_as::<_, TelemetryDevice>(&format!(
+        "UPDATE telemetry_devices SET last_seen_at = NOW() WHERE id = $1 RETURNING {DEVICE_COLUMNS}"
+    ))
+    .bind(device_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(device)
+}
+
+// ============================================================================
+// ADMINISTRACIÓN DE DISPOSITIVOS
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct DeviceCredentials {
+    pub device: TelemetryDevice,
+    pub key_id: Uuid,
+    /// Se muestra una sola vez; configurarlo en la extensión
+    pub secret: String,
+}
+
+/// Crea la llave guardando solo la semilla; el secreto derivado se devuelve una vez
+async fn issue_key(
+    device_id: Uuid,
+    pepper: &str,
+    conn: &mut sqlx::PgConnection,
+) -> Result<(Uuid, String), sqlx::Error> {
+    let seed = generate_seed();
+    let key_id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO telemetry_device_keys (device_id, secret_seed) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(device_id)
+    .bind(&seed)
+    .fetch_one(conn)
+    .await?;
+    Ok((key_id, derive_secret(pepper, &seed)))
+}
+
+async fn fetch_device(device_id: Uuid, pool: &PgPool) -> Result<TelemetryDevice, (StatusCode, String)> {
+    sqlx::query_as::<_, TelemetryDevice>(&format!("SELECT {DEVICE_COLUMNS} FROM telemetry_devices WHERE id = $1"))
+        .bind(device_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Dispositivo no encontrado".to_string()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterDeviceRequest {
+    pub name: String,
+    pub room_id: String,
+}
+
+/// POST /api/admin/tracking/devices
+pub async fn register_device_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminOnly,
+    Json(req): Json<RegisterDeviceRequest>,
+) -> Result<(StatusCode, Json<DeviceCredentials>), (StatusCode, String)> {
+    if req.name.trim().is_empty() || req.room_id.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name y room_id son obligatorios".to_string()));
+    }
+    let admin_id = Uuid::parse_str(&admin.user_id).ok();
+    let internal = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let pepper = key_pepper()?;
+
+    let mut tx = state.db.begin().await.map_err(internal)?;
+    let device = sqlx::query_as::<_, TelemetryDevice>(&format!(
+        r#"
+        INSERT INTO telemetry_devices (name, room_id, registered_by)
+        VALUES ($1, $2, $3)
+        RETURNING {DEVICE_COLUMNS}
+        "#
+    ))
+    .bind(req.name.trim())
+    .bind(req.room_id.trim())
+    .bind(admin_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal)?;
+    let (key_id, secret) = issue_key(device.id, &pepper, &mut tx).await.map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+
+    tracing::info!("📡 Dispositivo de telemetría {} registrado para room {}", device.id, device.room_id);
+    Ok((StatusCode::CREATED, Json(DeviceCredentials { device, key_id, secret })))
+}
+
+/// GET /api/admin/tracking/devices
+pub async fn list_devices_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+) -> Result<Json<Vec<TelemetryDevice>>, (StatusCode, String)> {
+    let rows = sqlx::query_as::<_, TelemetryDevice>(&format!(
+        "SELECT {DEVICE_COLUMNS} FROM telemetry_devices ORDER BY room_id, created_at"
+    ))
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(rows))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateKeyRequest {
+    /// Minutos que la llave anterior sigue aceptándose (por defecto 10)
+    pub grace_minutes: Option<i64>,
+}
+
+/// POST /api/admin/tracking/devices/:id/rotate
+pub async fn rotate_key_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Path(device_id): Path<Uuid>,
+    body: Option<Json<RotateKeyRequest>>,
+) -> Result<Json<DeviceCredentials>, (StatusCode, String)> {
+    let req = body.map(|Json(r)| r).unwrap_or_default();
+    let grace = req.grace_minutes.unwrap_or(DEFAULT_ROTATION_GRACE_MINUTES).clamp(0, 24 * 60);
+    let device = fetch_device(device_id, &state.db).await?;
+    if device.status != "ACTIVE" {
+        return Err((StatusCode::CONFLICT, "El dispositivo está revocado".to_string()));
+    }
+
+    let internal = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let pepper = key_pepper()?;
+    let mut tx = state.db.begin().await.map_err(internal)?;
+    sqlx::query(
+        r#"
+        UPDATE telemetry_device_keys
+        SET status = 'ROTATED', expires_at = $2
+        WHERE device_id = $1 AND status = 'ACTIVE'
+        "#,
+    )
+    .bind(device_id)
+    .bind(Utc::now() + Duration::minutes(grace))
+    .execute(&mut *tx)
+    .await
+    .map_err(internal)?;
+    let (key_id, secret) = issue_key(device.id, &pepper, &mut tx).await.map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+
+    tracing::info!("🔑 Llave rotada para dispositivo {} (gracia {} min)", device_id, grace);
+    Ok(Json(DeviceCredentials { device, key_id, secret }))
+}
+
+/// POST /api/admin/tracking/devices/:id/revoke
+/// Revoca el dispositivo y todas sus llaves de inmediato
+pub async fn revoke_device_handler(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminOnly,
+    Path(device_id): Path<Uuid>,
+) -> Result<Json<TelemetryDevice>, (StatusCode, String)> {
+    let internal = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let mut tx = state.db.begin().await.map_err(internal)?;
+    let device = sqlx::query_as::<_, TelemetryDevice>(&format!(
+        r#"
+        UPDATE telemetry_devices
+        SET status = 'REVOKED', revoked_at = NOW()
+        WHERE id = $1
+        RETURNING {DEVICE_COLUMNS}
+        "#
+    ))
+    .bind(device_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(internal)?
+    .ok_or((StatusCode::NOT_FOUND, "Dispositivo no encontrado".to_string()))?;
+    sqlx::query(
+        "UPDATE telemetry_device_keys SET status = 'REVOKED', revoked_at = NOW() WHERE device_id = $1 AND status <> 'REVOKED'",
+    )
+    .bind(device_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+
+    tracing::warn!("⛔ Dispositivo de telemetría {} revocado", device_id);
+    Ok(Json(device))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_round_trip_and_tamper_detection() {
+        let secret = "s3cr3t";
+        let msg = signing_string(1_700_000_000, "abcdefghijklmnop", "post", "/api/tracking/telemetry", b"{}");
+        let sig = compute_signature(secret, &msg);
+        assert!(verify_signature(secret, &msg, &sig));
+
+        let tampered = signing_string(1_700_000_000, "abcdefghijklmnop", "POST", "/api/tracking/telemetry", b"{\"x\":1}");
+        assert!(!verify_signature(secret, &tampered, &sig));
+        assert!(!verify_signature("other", &msg, &sig));
+        assert!(!verify_signature(secret, &msg, "not-hex"));
+    }
+
+    #[test]
+    fn stale_timestamps_are_rejected() {
+        let now = Utc::now();
+        assert!(timestamp_is_fresh(now.timestamp() - 60, now));
+        assert!(!timestamp_is_fresh(now.timestamp() - SIGNATURE_MAX_SKEW_SECONDS - 1, now));
+        assert!(!timestamp_is_fresh(now.timestamp() + SIGNATURE_MAX_SKEW_SECONDS + 1, now));
+    }
+
+    #[test]
+    fn secrets_derive_from_seed_and_pepper() {
+        let seed = generate_seed();
+        assert_eq!(derive_secret("pepper", &seed), derive_secret("pepper", &seed));
+        assert_ne!(derive_secret("pepper", &seed), derive_secret("other", &seed));
+        assert_ne!(derive_secret("pepper", &seed), seed);
+        assert_eq!(derive_secret("pepper", &seed).len(), 64);
+    }
+
+    #[test]
+    fn nonce_format() {
+        assert!(valid_nonce("0123456789abcdef"));
+        assert!(!valid_nonce("short"));
+        assert!(!valid_nonce("0123456789abcdef;drop"));
+    }
+}
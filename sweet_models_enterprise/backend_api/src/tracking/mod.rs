@@ -1,14 +1,40 @@
 use axum::{
+    body::Bytes,
     extract::{State, Path},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode, Uri},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use deadpool_redis::redis::AsyncCommands;
+use sqlx::{Connection, PgConnection};
 use crate::state::AppState;
 use crate::realtime::hub::RealtimeEvent;
 
+pub mod batch;
+pub mod deltas;
+pub mod devices;
+pub mod platforms;
+pub mod reconcile;
+pub mod timeseries;
+
+pub use batch::{telemetry_ack_handler, telemetry_batch_handler};
+pub use devices::{
+    list_devices_handler,
+    register_device_handler,
+    revoke_device_handler,
+    rotate_key_handler,
+};
+pub use platforms::list_platforms_handler;
+pub use reconcile::{
+    confirm_draft_handler,
+    generate_drafts_handler,
+    get_draft_handler,
+    list_drafts_handler,
+    reject_draft_handler,
+};
+pub use timeseries::telemetry_series_handler;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelemetryUpdate {
     pub room_id: String,
@@ -28,10 +54,30 @@ pub struct TelemetryResponse {
 
 /// Recibe actualizaciones de telemetría desde la extensión Chrome
 /// POST /api/tracking/telemetry
+/// Requiere firma HMAC del dispositivo registrado (ver `devices`); el dispositivo
+/// solo puede reportar el room al que está vinculado.
 pub async fn telemetry_handler(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<TelemetryUpdate>,
+    headers: HeaderMap,
+    uri: Uri,
+    body: Bytes,
 ) -> Result<(StatusCode, Json<TelemetryResponse>), (StatusCode, String)> {
+    let device = devices::verify_signed_request(&state, &headers, "POST", uri.path(), &body).await?;
+
+    let payload: TelemetryUpdate = serde_json::from_slice(&body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid telemetry payload: {}", e)))?;
+    let payload = platforms::registry()
+        .normalize(payload)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    if payload.room_id != device.room_id {
+        tracing::warn!(
+            "🚫 Dispositivo {} intentó reportar room {} (vinculado a {})",
+            device.id, payload.room_id, device.room_id
+        );
+        return Err((StatusCode::FORBIDDEN, "Device not allowed to report this room".to_string()));
+    }
+
     tracing::debug!(
         "📊 Telemetría recibida - Room: {}, Plataforma: {}, Tokens: {}",
         payload.room_id,
@@ -39,16 +85,59 @@ pub async fn telemetry_handler(
         payload.tokens_count
     );
 
-    // Guardar en Redis para análisis
+    let db_error = |e: sqlx::Error| {
+        tracing::error!("DB error ingesting telemetry: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    let mut conn = state.db.acquire().await.map_err(db_error)?;
+    let deltas = ingest_update(&mut conn, &payload).await.map_err(db_error)?;
+    publish_snapshot(&state, &payload, &deltas).await;
+
+    let response = TelemetryResponse {
+        status: "success".to_string(),
+        message: format!("Telemetría procesada para room {}", payload.room_id),
+        processed_at: chrono::Utc::now().timestamp(),
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Persiste una muestra y la convierte en deltas atribuidos (compartido por la ingesta unitaria y por lotes)
+pub(crate) async fn ingest_update(
+    conn: &mut PgConnection,
+    payload: &TelemetryUpdate,
+) -> Result<Vec<deltas::TokenDelta>, sqlx::Error> {
+    // Savepoint: si falla una muestra del lote, las ya ingeridas en la transacción del llamador se conservan
+    let mut tx = conn.begin().await?;
+
+    // Historial durable (serie de tiempo particionada por día)
+    timeseries::persist_sample(&mut tx, payload).await?;
+
+    // Saldo acumulado -> tokens ganados, repartidos entre las modelos en turno
+    let sampled_at = timeseries::sample_time(payload.timestamp, chrono::Utc::now());
+    let deltas = deltas::record_sample(
+        &mut tx,
+        &payload.room_id,
+        &payload.platform,
+        i64::from(payload.tokens_count),
+        sampled_at,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(deltas)
+}
+
+/// Último snapshot en Redis (lectura rápida para God Mode) y evento WebSocket
+pub(crate) async fn publish_snapshot(state: &AppState, payload: &TelemetryUpdate, deltas: &[deltas::TokenDelta]) {
     let redis_key = format!("telemetry:{}:{}", payload.room_id, payload.platform);
-    
+
     if let Ok(mut conn) = state.redis.get().await {
-        let json_payload = serde_json::to_string(&payload).unwrap_or_default();
+        let json_payload = serde_json::to_string(payload).unwrap_or_default();
         let _ = conn.set::<&str, String, ()>(
             &redis_key,
             json_payload,
         ).await;
-        
+
         // Expirar en 1 hora
         let _ = conn.expire::<&str, ()>(&redis_key, 3600).await;
     }
@@ -63,19 +152,13 @@ pub async fn telemetry_handler(
             "tips": payload.tips_count,
             "viewers": payload.viewers_count,
             "timestamp": payload.timestamp,
+            "tokens_delta": (!deltas.is_empty()).then(|| deltas.iter().map(|d| d.tokens_delta).sum::<i64>()),
+            "model_ids": deltas.iter().filter_map(|d| d.model_id).collect::<Vec<_>>(),
         }),
         timestamp: chrono::Utc::now().timestamp(),
     };
 
     let _ = state.realtime_hub.publish(event);
-
-    let response = TelemetryResponse {
-        status: "success".to_string(),
-        message: format!("Telemetría procesada para room {}", payload.room_id),
-        processed_at: chrono::Utc::now().timestamp(),
-    };
-
-    Ok((StatusCode::OK, Json(response)))
 }
 
 /// Obtiene el último update de telemetría para una room
@@ -95,9 +178,35 @@ pub async fn get_telemetry_handler(
     if let Some(json_string) = data {
         let telemetry: TelemetryUpdate = serde_json::from_str(&json_string)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Parse error: {}", e)))?;
-        Ok(Json(telemetry))
-    } else {
-        Err((StatusCode::NOT_FOUND, "Telemetría no encontrada".to_string()))
+        return Ok(Json(telemetry));
+    }
+
+    // Cache expirado: última muestra persistida
+    let latest = sqlx::query_as::<_, (i64, i64, i32, chrono::DateTime<chrono::Utc>)>(
+        r#"
+        SELECT tokens_count, tips_count, viewers_count, sampled_at
+        FROM telemetry_samples
+        WHERE room_id = $1 AND platform = $2
+        ORDER BY sampled_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(&room_id)
+    .bind(&platform)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    match latest {
+        Some((tokens, tips, viewers, sampled_at)) => Ok(Json(TelemetryUpdate {
+            room_id,
+            platform,
+            tokens_count: tokens.clamp(0, u32::MAX as i64) as u32,
+            tips_count: tips.clamp(0, u32::MAX as i64) as u32,
+            viewers_count: viewers.max(0) as u32,
+            timestamp: sampled_at.timestamp(),
+        })),
+        None => Err((StatusCode::NOT_FOUND, "Telemetría no encontrada".to_string())),
     }
 }
 
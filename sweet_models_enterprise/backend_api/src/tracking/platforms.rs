@@ -0,0 +1,410 @@
+/// Plataformas de transmisión soportadas por la telemetría.
+///
+/// Cada plataforma se describe con un `PlatformAdapter`: valor del token en USD,
+/// calendario de pago, campos que reporta su scraper y reglas de normalización.
+/// Chaturbate, Stripchat y CamSoda vienen incluidas; otras se registran (o las
+/// incluidas se sobreescriben) con `PLATFORM_ADAPTERS` (JSON) o
+/// `PLATFORM_ADAPTERS_FILE` (ruta a un archivo JSON) con el formato de `PlatformConfig`.
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::TelemetryUpdate;
+use crate::finance::DEFAULT_TOKEN_USD_VALUE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PayoutSchedule {
+    Weekly,
+    BiWeekly,
+    /// Días 1 y 16 de cada mes
+    SemiMonthly,
+    Monthly,
+}
+
+/// Encabezados aceptados en el extracto oficial de ganancias (sin distinguir mayúsculas)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatementFormat {
+    pub username: Vec<String>,
+    pub tokens: Vec<String>,
+    /// Si el extracto no trae USD se calcula con el valor del token
+    #[serde(default)]
+    pub usd: Vec<String>,
+}
+
+impl StatementFormat {
+    pub fn new(username: &[&str], tokens: &[&str], usd: &[&str]) -> Self {
+        let owned = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        Self { username: owned(username), tokens: owned(tokens), usd: owned(usd) }
+    }
+}
+
+impl Default for StatementFormat {
+    fn default() -> Self {
+        Self::new(&["username", "model", "performer"], &["tokens", "total tokens"], &["usd", "amount", "earnings", "payout"])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TelemetryField {
+    Tokens,
+    Tips,
+    Viewers,
+}
+
+pub trait PlatformAdapter: Send + Sync {
+    /// Identificador canónico (el que se guarda en telemetría y production_logs)
+    fn id(&self) -> &str;
+
+    fn display_name(&self) -> &str;
+
+    /// Lo que la plataforma paga al estudio por token
+    fn token_usd_value(&self) -> f64;
+
+    fn payout_schedule(&self) -> PayoutSchedule;
+
+    /// Campos que el scraper de esta plataforma realmente lee
+    fn reported_fields(&self) -> &[TelemetryField];
+
+    /// Otros nombres con los que puede llegar (dominio, abreviaturas)
+    fn aliases(&self) -> &[String] {
+        &[]
+    }
+
+    /// Columnas del extracto de ganancias de la plataforma
+    fn statement_format(&self) -> StatementFormat {
+        StatementFormat::default()
+    }
+
+    fn tokens_to_usd(&self, tokens: f64) -> f64 {
+        tokens.max(0.0) * self.token_usd_value()
+    }
+
+    /// Deja la update en forma canónica: id de plataforma y en cero los campos que no reporta
+    fn normalize(&self, mut update: TelemetryUpdate) -> TelemetryUpdate {
+        let fields = self.reported_fields();
+        update.platform = self.id().to_string();
+        if !fields.contains(&TelemetryField::Tokens) {
+            update.tokens_count = 0;
+        }
+        if !fields.contains(&TelemetryField::Tips) {
+            update.tips_count = 0;
+        }
+        if !fields.contains(&TelemetryField::Viewers) {
+            update.viewers_count = 0;
+        }
+        update
+    }
+}
+
+/// Plataforma definida por configuración; también describe las incluidas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlatformConfig {
+    pub id: String,
+    pub display_name: String,
+    pub token_usd_value: f64,
+    pub payout_schedule: PayoutSchedule,
+    pub fields: Vec<TelemetryField>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub statement: Option<StatementFormat>,
+}
+
+impl PlatformAdapter for PlatformConfig {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    fn token_usd_value(&self) -> f64 {
+        self.token_usd_value
+    }
+
+    fn payout_schedule(&self) -> PayoutSchedule {
+        self.payout_schedule
+    }
+
+    fn reported_fields(&self) -> &[TelemetryField] {
+        &self.fields
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    fn statement_format(&self) -> StatementFormat {
+        self.statement.clone().unwrap_or_default()
+    }
+}
+
+pub struct Chaturbate;
+
+impl PlatformAdapter for Chaturbate {
+    fn id(&self) -> &str {
+        "chaturbate"
+    }
+
+    fn display_name(&self) -> &str {
+        "Chaturbate"
+    }
+
+    fn token_usd_value(&self) -> f64 {
+        0.05
+    }
+
+    fn payout_schedule(&self) -> PayoutSchedule {
+        PayoutSchedule::SemiMonthly
+    }
+
+    fn reported_fields(&self) -> &[TelemetryField] {
+        &[TelemetryField::Tokens]
+    }
+
+    fn statement_format(&self) -> StatementFormat {
+        StatementFormat::new(&["username", "broadcaster"], &["tokens", "tokens earned"], &["usd", "amount", "payout (usd)"])
+    }
+}
+
+pub struct Stripchat;
+
+impl PlatformAdapter for Stripchat {
+    fn id(&self) -> &str {
+        "stripchat"
+    }
+
+    fn display_name(&self) -> &str {
+        "Stripchat"
+    }
+
+    fn token_usd_value(&self) -> f64 {
+        0.05
+    }
+
+    fn payout_schedule(&self) -> PayoutSchedule {
+        PayoutSchedule::Weekly
+    }
+
+    fn reported_fields(&self) -> &[TelemetryField] {
+        &[TelemetryField::Tokens]
+    }
+
+    fn statement_format(&self) -> StatementFormat {
+        StatementFormat::new(&["model", "username", "model username"], &["tokens", "tk"], &["earnings", "usd", "amount"])
+    }
+}
+
+pub struct CamSoda;
+
+impl PlatformAdapter for CamSoda {
+    fn id(&self) -> &str {
+        "camsoda"
+    }
+
+    fn display_name(&self) -> &str {
+        "CamSoda"
+    }
+
+    fn token_usd_value(&self) -> f64 {
+        0.05
+    }
+
+    fn payout_schedule(&self) -> PayoutSchedule {
+        PayoutSchedule::Weekly
+    }
+
+    fn reported_fields(&self) -> &[TelemetryField] {
+        &[TelemetryField::Tokens]
+    }
+
+    fn statement_format(&self) -> StatementFormat {
+        StatementFormat::new(&["performer", "username"], &["tokens"], &["payout", "usd", "earnings"])
+    }
+}
+
+/// Clave de búsqueda: minúsculas, sin espacios, sin dominio ni "www."
+fn lookup_key(name: &str) -> String {
+    let lower = name.trim().to_lowercase();
+    let host = lower.trim_start_matches("https://").trim_start_matches("http://");
+    let host = host.split('/').next().unwrap_or(host);
+    let host = host.trim_start_matches("www.");
+    host.strip_suffix(".com").unwrap_or(host).replace([' ', '-', '_'], "")
+}
+
+#[derive(Clone, Default)]
+pub struct PlatformRegistry {
+    adapters: HashMap<String, Arc<dyn PlatformAdapter>>,
+    aliases: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlatformInfo {
+    pub id: String,
+    pub display_name: String,
+    pub token_usd_value: f64,
+    pub payout_schedule: PayoutSchedule,
+    pub fields: Vec<TelemetryField>,
+}
+
+impl PlatformRegistry {
+    /// Las tres plataformas con scraper en la extensión
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        registry.register(Arc::new(Chaturbate));
+        registry.register(Arc::new(Stripchat));
+        registry.register(Arc::new(CamSoda));
+        registry.add_alias("cb", "chaturbate");
+        registry.add_alias("sc", "stripchat");
+        registry.add_alias("stripchatgirls", "stripchat");
+        registry
+    }
+
+    /// Incluidas + las declaradas en configuración (una entrada con el mismo id reemplaza a la incluida)
+    pub fn from_env() -> Self {
+        let mut registry = Self::builtin();
+        let raw = std::env::var("PLATFORM_ADAPTERS").ok().or_else(|| {
+            std::env::var("PLATFORM_ADAPTERS_FILE")
+                .ok()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+        });
+        if let Some(raw) = raw {
+            match serde_json::from_str::<Vec<PlatformConfig>>(&raw) {
+                Ok(configs) => {
+                    for config in configs {
+                        tracing::info!("🧩 Plataforma registrada por configuración: {}", config.id);
+                        registry.register(Arc::new(config));
+                    }
+                }
+                Err(e) => tracing::error!("PLATFORM_ADAPTERS inválido, se usan solo las incluidas: {}", e),
+            }
+        }
+        registry
+    }
+
+    pub fn register(&mut self, adapter: Arc<dyn PlatformAdapter>) {
+        let id = adapter.id().to_string();
+        for alias in adapter.aliases() {
+            self.aliases.insert(lookup_key(alias), id.clone());
+        }
+        self.aliases.insert(lookup_key(&id), id.clone());
+        self.adapters.insert(id, adapter);
+    }
+
+    pub fn add_alias(&mut self, alias: &str, id: &str) {
+        self.aliases.insert(lookup_key(alias), id.to_string());
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<Arc<dyn PlatformAdapter>> {
+        self.aliases
+            .get(&lookup_key(name))
+            .and_then(|id| self.adapters.get(id))
+            .cloned()
+    }
+
+    /// Valor del token de la plataforma; desconocidas o sin plataforma (carga manual) usan el valor por defecto
+    pub fn token_usd_value(&self, name: Option<&str>) -> f64 {
+        name.and_then(|n| self.resolve(n))
+            .map_or(DEFAULT_TOKEN_USD_VALUE, |a| a.token_usd_value())
+    }
+
+    /// Normaliza una update entrante; las plataformas no registradas se rechazan
+    pub fn normalize(&self, update: TelemetryUpdate) -> Result<TelemetryUpdate, String> {
+        match self.resolve(&update.platform) {
+            Some(adapter) => Ok(adapter.normalize(update)),
+            None => Err(format!("Unknown platform '{}'", update.platform)),
+        }
+    }
+
+    pub fn list(&self) -> Vec<PlatformInfo> {
+        let mut out: Vec<PlatformInfo> = self
+            .adapters
+            .values()
+            .map(|a| PlatformInfo {
+                id: a.id().to_string(),
+                display_name: a.display_name().to_string(),
+                token_usd_value: a.token_usd_value(),
+                payout_schedule: a.payout_schedule(),
+                fields: a.reported_fields().to_vec(),
+            })
+            .collect();
+        out.sort_by(|a, b| a.id.cmp(&b.id));
+        out
+    }
+}
+
+/// Registro global, cargado una vez desde el entorno
+pub fn registry() -> &'static PlatformRegistry {
+    static REGISTRY: OnceLock<PlatformRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(PlatformRegistry::from_env)
+}
+
+/// GET /api/tracking/platforms
+pub async fn list_platforms_handler() -> axum::Json<Vec<PlatformInfo>> {
+    axum::Json(registry().list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(platform: &str) -> TelemetryUpdate {
+        TelemetryUpdate {
+            room_id: "1".to_string(),
+            platform: platform.to_string(),
+            tokens_count: 120,
+            tips_count: 4,
+            viewers_count: 30,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn resolves_aliases_and_domains() {
+        let registry = PlatformRegistry::builtin();
+        assert_eq!(registry.resolve("Chaturbate").unwrap().id(), "chaturbate");
+        assert_eq!(registry.resolve("https://www.stripchat.com/").unwrap().id(), "stripchat");
+        assert_eq!(registry.resolve("cb").unwrap().id(), "chaturbate");
+        assert!(registry.resolve("onlyfans").is_none());
+    }
+
+    #[test]
+    fn normalize_zeroes_unreported_fields_and_rejects_unknown() {
+        let registry = PlatformRegistry::builtin();
+        let normalized = registry.normalize(update("CamSoda.com")).unwrap();
+        assert_eq!(normalized.platform, "camsoda");
+        assert_eq!(normalized.tokens_count, 120);
+        assert_eq!(normalized.tips_count, 0);
+        assert_eq!(normalized.viewers_count, 0);
+        assert!(registry.normalize(update("myfreecams")).is_err());
+    }
+
+    #[test]
+    fn configured_platforms_register_and_override() {
+        let mut registry = PlatformRegistry::builtin();
+        let configs: Vec<PlatformConfig> = serde_json::from_str(
+            r#"[
+                {"id": "bongacams", "display_name": "BongaCams", "token_usd_value": 0.025,
+                 "payout_schedule": "BI_WEEKLY", "fields": ["tokens", "viewers"], "aliases": ["bonga"]},
+                {"id": "stripchat", "display_name": "Stripchat", "token_usd_value": 0.06,
+                 "payout_schedule": "WEEKLY", "fields": ["tokens"]}
+            ]"#,
+        )
+        .unwrap();
+        for config in configs {
+            registry.register(Arc::new(config));
+        }
+
+        assert_eq!(registry.resolve("bonga").unwrap().payout_schedule(), PayoutSchedule::BiWeekly);
+        assert_eq!(registry.token_usd_value(Some("bongacams")), 0.025);
+        assert_eq!(registry.token_usd_value(Some("stripchat")), 0.06);
+        assert_eq!(registry.token_usd_value(None), DEFAULT_TOKEN_USD_VALUE);
+        assert_eq!(registry.normalize(update("bonga")).unwrap().viewers_count, 30);
+    }
+}
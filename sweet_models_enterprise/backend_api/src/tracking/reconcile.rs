@@ -0,0 +1,763 @@
+/// Borradores de producción a partir de telemetría.
+///
+/// Los deltas atribuidos (`deltas`) se agregan por room/plataforma/día en borradores
+/// con una línea por modelo. Cada línea se compara contra lo digitado a mano en
+/// `production_logs` para esa modelo, plataforma y día (la carga manual sin plataforma
+/// no se compara ni se reemplaza) y se marca si la diferencia supera el umbral. Un moderador confirma el borrador y
+/// solo entonces se escriben los registros de producción, con auditoría completa.
+use std::{collections::BTreeMap, sync::Arc};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Uuid, FromRow, PgPool, Postgres, Transaction};
+
+use super::platforms;
+use crate::{
+    middleware::auth::ModeratorOnly,
+    operations::parse_user_id,
+    state::AppState,
+};
+
+pub const STATUS_DRAFT: &str = "DRAFT";
+pub const STATUS_CONFIRMED: &str = "CONFIRMED";
+pub const STATUS_REJECTED: &str = "REJECTED";
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReconcileConfig {
+    /// Diferencia porcentual tolerada entre telemetría y carga manual
+    pub discrepancy_threshold_pct: f64,
+}
+
+impl ReconcileConfig {
+    pub fn from_env() -> Self {
+        let threshold = std::env::var("PRODUCTION_DISCREPANCY_THRESHOLD_PCT")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|p| *p >= 0.0)
+            .unwrap_or(5.0);
+        Self { discrepancy_threshold_pct: threshold }
+    }
+}
+
+/// Diferencia de la telemetría respecto a lo manual (en %, con signo) y si supera el umbral.
+/// Sin carga manual no hay nada que comparar; manual en cero con telemetría sí se marca.
+pub fn discrepancy(telemetry: i64, manual: Option<f64>, threshold_pct: f64) -> (Option<f64>, bool) {
+    let Some(manual) = manual else {
+        return (None, false);
+    };
+    if manual <= 0.0 {
+        return (None, telemetry > 0);
+    }
+    let pct = (telemetry as f64 - manual) / manual * 100.0;
+    (Some(pct), pct.abs() > threshold_pct)
+}
+
+/// Fila agregada de deltas: room/plataforma/modelo en un día
+#[derive(Debug, Clone, FromRow)]
+pub struct DeltaTotal {
+    pub room_id: String,
+    pub platform: String,
+    pub studio_room_id: Option<i32>,
+    pub model_id: Option<Uuid>,
+    pub tokens: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DraftPlan {
+    pub room_id: String,
+    pub platform: String,
+    pub studio_room_id: Option<i32>,
+    pub telemetry_tokens: i64,
+    pub unattributed_tokens: i64,
+    pub lines: Vec<(Uuid, i64)>,
+}
+
+/// Agrupa los totales por room/plataforma; lo no atribuido queda en el borrador pero sin línea
+pub fn plan_drafts(totals: &[DeltaTotal]) -> Vec<DraftPlan> {
+    let mut plans: BTreeMap<(String, String), DraftPlan> = BTreeMap::new();
+    for t in totals {
+        let plan = plans
+            .entry((t.room_id.clone(), t.platform.clone()))
+            .or_insert_with(|| DraftPlan {
+                room_id: t.room_id.clone(),
+                platform: t.platform.clone(),
+                studio_room_id: None,
+                telemetry_tokens: 0,
+                unattributed_tokens: 0,
+                lines: Vec::new(),
+            });
+        plan.studio_room_id = plan.studio_room_id.or(t.studio_room_id);
+        plan.telemetry_tokens += t.tokens;
+        match t.model_id {
+            Some(model_id) => plan.lines.push((model_id, t.tokens)),
+            None => plan.unattributed_tokens += t.tokens,
+        }
+    }
+    plans.into_values().collect()
+}
+
+/// Total de telemetría por modelo y plataforma en el día (todas las rooms)
+pub fn model_day_totals(totals: &[DeltaTotal]) -> BTreeMap<(Uuid, String), i64> {
+    let mut out = BTreeMap::new();
+    for t in totals {
+        if let Some(model_id) = t.model_id {
+            *out.entry((model_id, t.platform.clone())).or_insert(0) += t.tokens;
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ProductionDraft {
+    pub id: Uuid,
+    pub room_id: String,
+    pub studio_room_id: Option<i32>,
+    pub platform: String,
+    pub work_date: NaiveDate,
+    pub telemetry_tokens: i64,
+    pub unattributed_tokens: i64,
+    pub flagged: bool,
+    pub status: String,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<chrono::DateTime<Utc>>,
+    pub review_notes: Option<String>,
+    pub generated_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DraftLine {
+    pub id: Uuid,
+    pub model_id: Uuid,
+    pub telemetry_tokens: i64,
+    pub model_day_telemetry: i64,
+    pub manual_tokens: Option<f64>,
+    pub discrepancy_pct: Option<f64>,
+    pub flagged: bool,
+    pub production_log_id: Option<Uuid>,
+}
+
+#[derive(FromRow)]
+struct DraftLineRow {
+    draft_id: Uuid,
+    #[sqlx(flatten)]
+    line: DraftLine,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DraftWithLines {
+    #[serde(flatten)]
+    pub draft: ProductionDraft,
+    pub lines: Vec<DraftLine>,
+}
+
+const DRAFT_COLUMNS: &str = "id, room_id, studio_room_id, platform, work_date, telemetry_tokens, unattributed_tokens, \
+    flagged, status, reviewed_by, reviewed_at, review_notes, generated_at";
+const LINE_COLUMNS: &str =
+    "id, model_id, telemetry_tokens, model_day_telemetry, manual_tokens, discrepancy_pct, flagged, production_log_id";
+
+/// Producción manual vigente por modelo y plataforma en el día
+async fn manual_totals(
+    pool: &PgPool,
+    models: &[Uuid],
+    date: NaiveDate,
+) -> Result<BTreeMap<(Uuid, String), f64>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (Uuid, String, f64)>(
+        r#"
+        SELECT model_id, platform, COALESCE(SUM(tokens_earned), 0)::DOUBLE PRECISION
+        FROM production_logs
+        WHERE model_id = ANY($1) AND production_date = $2 AND platform IS NOT NULL
+          AND source = 'MANUAL' AND superseded_by_draft IS NULL
+        GROUP BY model_id, platform
+        "#,
+    )
+    .bind(models)
+    .bind(date)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(model_id, platform, tokens)| ((model_id, platform), tokens)).collect())
+}
+
+/// Línea calculada antes de escribirla en `production_draft_lines`
+struct PlannedLine {
+    model_id: Uuid,
+    tokens: i64,
+    model_total: i64,
+    manual_tokens: Option<f64>,
+    discrepancy_pct: Option<f64>,
+    flagged: bool,
+}
+
+/// (Re)genera los borradores de un día. Los ya confirmados o rechazados no se tocan.
+pub async fn generate_drafts(pool: &PgPool, date: NaiveDate, config: ReconcileConfig) -> Result<usize, sqlx::Error> {
+    let totals = sqlx::query_as::<_, DeltaTotal>(
+        r#"
+        SELECT room_id, platform, MAX(studio_room_id) AS studio_room_id, model_id,
+               SUM(tokens_delta)::BIGINT AS tokens
+        FROM telemetry_token_deltas
+        WHERE work_date = $1
+        GROUP BY room_id, platform, model_id
+        "#,
+    )
+    .bind(date)
+    .fetch_all(pool)
+    .await?;
+
+    let day_totals = model_day_totals(&totals);
+    let mut models: Vec<Uuid> = day_totals.keys().map(|(model_id, _)| *model_id).collect();
+    models.dedup();
+    let manual = manual_totals(pool, &models, date).await?;
+
+    let mut written = 0;
+    for plan in plan_drafts(&totals) {
+        let lines: Vec<PlannedLine> = plan
+            .lines
+            .iter()
+            .map(|(model_id, tokens)| {
+                let key = (*model_id, plan.platform.clone());
+                let model_total = day_totals.get(&key).copied().unwrap_or(*tokens);
+                let manual_tokens = manual.get(&key).copied();
+                let (discrepancy_pct, flagged) =
+                    discrepancy(model_total, manual_tokens, config.discrepancy_threshold_pct);
+                PlannedLine {
+                    model_id: *model_id,
+                    tokens: *tokens,
+                    model_total,
+                    manual_tokens,
+                    discrepancy_pct,
+                    flagged,
+                }
+            })
+            .collect();
+        let flagged = lines.iter().any(|l| l.flagged);
+
+        let mut tx = pool.begin().await?;
+        let draft_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO production_drafts
+                (room_id, studio_room_id, platform, work_date, telemetry_tokens, unattributed_tokens, flagged)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (room_id, platform, work_date) DO UPDATE
+            SET studio_room_id = EXCLUDED.studio_room_id,
+                telemetry_tokens = EXCLUDED.telemetry_tokens,
+                unattributed_tokens = EXCLUDED.unattributed_tokens,
+                flagged = EXCLUDED.flagged,
+                generated_at = NOW()
+            WHERE production_drafts.status = 'DRAFT'
+            RETURNING id
+            "#,
+        )
+        .bind(&plan.room_id)
+        .bind(plan.studio_room_id)
+        .bind(&plan.platform)
+        .bind(date)
+        .bind(plan.telemetry_tokens)
+        .bind(plan.unattributed_tokens)
+        .bind(flagged)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(draft_id) = draft_id else {
+            tx.rollback().await?;
+            continue;
+        };
+
+        sqlx::query("DELETE FROM production_draft_lines WHERE draft_id = $1")
+            .bind(draft_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for line in lines {
+            sqlx::query(
+                r#"
+                INSERT INTO production_draft_lines
+                    (draft_id, model_id, telemetry_tokens, model_day_telemetry, manual_tokens, discrepancy_pct, flagged)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+            )
+            .bind(draft_id)
+            .bind(line.model_id)
+            .bind(line.tokens)
+            .bind(line.model_total)
+            .bind(line.manual_tokens)
+            .bind(line.discrepancy_pct)
+            .bind(line.flagged)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        if flagged {
+            tracing::warn!("⚠️ Borrador de producción {} ({}/{} {}) con discrepancias", draft_id, plan.room_id, plan.platform, date);
+        }
+        written += 1;
+    }
+
+    Ok(written)
+}
+
+/// Regenera hoy y ayer (el turno 4 cierra a las 02:00 del día siguiente)
+pub async fn run_reconcile(pool: &PgPool, config: ReconcileConfig) -> Result<(), sqlx::Error> {
+    let today = Utc::now().date_naive();
+    for date in [today - Duration::days(1), today] {
+        generate_drafts(pool, date, config).await?;
+    }
+    Ok(())
+}
+
+async fn audit(
+    tx: &mut Transaction<'_, Postgres>,
+    entity_type: &str,
+    entity_id: Uuid,
+    action: &str,
+    old_value: Option<serde_json::Value>,
+    new_value: serde_json::Value,
+    user_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO audit_trail (entity_type, entity_id, action, old_value, new_value, user_id) VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(action)
+    .bind(old_value)
+    .bind(new_value)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("DB error in production reconciliation: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+async fn load_draft(pool: &PgPool, id: Uuid) -> Result<DraftWithLines, (StatusCode, String)> {
+    let draft = sqlx::query_as::<_, ProductionDraft>(&format!("SELECT {DRAFT_COLUMNS} FROM production_drafts WHERE id = $1"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Borrador no encontrado".to_string()))?;
+    let lines = sqlx::query_as::<_, DraftLine>(&format!(
+        "SELECT {LINE_COLUMNS} FROM production_draft_lines WHERE draft_id = $1 ORDER BY telemetry_tokens DESC"
+    ))
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)?;
+    Ok(DraftWithLines { draft, lines })
+}
+
+// ============================================================================
+// HANDLERS
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct DraftsQuery {
+    pub date: Option<NaiveDate>,
+    pub status: Option<String>,
+    pub flagged: Option<bool>,
+}
+
+/// GET /api/admin/production/drafts?date=&status=&flagged=
+pub async fn list_drafts_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Query(query): Query<DraftsQuery>,
+) -> Result<Json<Vec<DraftWithLines>>, (StatusCode, String)> {
+    let drafts = sqlx::query_as::<_, ProductionDraft>(&format!(
+        r#"
+        SELECT {DRAFT_COLUMNS} FROM production_drafts
+        WHERE ($1::DATE IS NULL OR work_date = $1)
+          AND ($2::TEXT IS NULL OR status = $2)
+          AND ($3::BOOLEAN IS NULL OR flagged = $3)
+        ORDER BY work_date DESC, room_id, platform
+        LIMIT 200
+        "#
+    ))
+    .bind(query.date)
+    .bind(query.status.map(|s| s.to_uppercase()))
+    .bind(query.flagged)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    let ids: Vec<Uuid> = drafts.iter().map(|d| d.id).collect();
+    let lines = sqlx::query_as::<_, DraftLineRow>(&format!(
+        "SELECT draft_id, {LINE_COLUMNS} FROM production_draft_lines WHERE draft_id = ANY($1) ORDER BY telemetry_tokens DESC"
+    ))
+    .bind(&ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    let mut by_draft: BTreeMap<Uuid, Vec<DraftLine>> = BTreeMap::new();
+    for row in lines {
+        by_draft.entry(row.draft_id).or_default().push(row.line);
+    }
+
+    Ok(Json(
+        drafts
+            .into_iter()
+            .map(|draft| {
+                let lines = by_draft.remove(&draft.id).unwrap_or_default();
+                DraftWithLines { draft, lines }
+            })
+            .collect(),
+    ))
+}
+
+/// GET /api/admin/production/drafts/:id
+pub async fn get_draft_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DraftWithLines>, (StatusCode, String)> {
+    Ok(Json(load_draft(&state.db, id).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GenerateDraftsRequest {
+    pub date: NaiveDate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GenerateDraftsResponse {
+    pub date: NaiveDate,
+    pub drafts_written: usize,
+}
+
+/// POST /api/admin/production/drafts/generate
+/// Fuerza la regeneración de un día (el worker solo cubre hoy y ayer)
+pub async fn generate_drafts_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Json(payload): Json<GenerateDraftsRequest>,
+) -> Result<Json<GenerateDraftsResponse>, (StatusCode, String)> {
+    if payload.date > Utc::now().date_naive() {
+        return Err((StatusCode::BAD_REQUEST, "Date cannot be in the future".to_string()));
+    }
+    let drafts_written = generate_drafts(&state.db, payload.date, ReconcileConfig::from_env())
+        .await
+        .map_err(db_error)?;
+    Ok(Json(GenerateDraftsResponse { date: payload.date, drafts_written }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmDraftRequest {
+    pub notes: Option<String>,
+    /// Requerido si el borrador tiene discrepancias marcadas
+    #[serde(default)]
+    pub acknowledge_discrepancies: bool,
+    /// Marca como reemplazada la producción manual de esas modelos en esa plataforma ese día (evita doble conteo)
+    #[serde(default)]
+    pub supersede_manual: bool,
+}
+
+/// POST /api/admin/production/drafts/:id/confirm
+/// Escribe una fila en production_logs por modelo atribuida y audita cada cambio.
+pub async fn confirm_draft_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ConfirmDraftRequest>,
+) -> Result<Json<DraftWithLines>, (StatusCode, String)> {
+    let reviewer = parse_user_id(&moderator.user_id)?;
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+
+    let draft = sqlx::query_as::<_, ProductionDraft>(&format!(
+        "SELECT {DRAFT_COLUMNS} FROM production_drafts WHERE id = $1 FOR UPDATE"
+    ))
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Borrador no encontrado".to_string()))?;
+
+    if draft.status != STATUS_DRAFT {
+        return Err((StatusCode::CONFLICT, format!("El borrador ya está {}", draft.status)));
+    }
+    if draft.flagged && !payload.acknowledge_discrepancies {
+        return Err((
+            StatusCode::CONFLICT,
+            "El borrador tiene discrepancias con la carga manual; confirme con acknowledge_discrepancies".to_string(),
+        ));
+    }
+
+    let lines = sqlx::query_as::<_, DraftLine>(&format!(
+        "SELECT {LINE_COLUMNS} FROM production_draft_lines WHERE draft_id = $1"
+    ))
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    if lines.is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, "El borrador no tiene tokens atribuidos a ninguna modelo".to_string()));
+    }
+
+    // La carga manual se relee acá (bloqueada) y no de la foto del borrador: pudo entrar
+    // producción manual después de generarlo y confirmarla encima sería doble conteo
+    let model_ids: Vec<Uuid> = lines.iter().map(|l| l.model_id).collect();
+    let live_manual = sqlx::query_as::<_, (Uuid, Uuid, f64)>(
+        r#"
+        SELECT id, model_id, tokens_earned::DOUBLE PRECISION
+        FROM production_logs
+        WHERE model_id = ANY($1) AND production_date = $2 AND platform = $3
+          AND source = 'MANUAL' AND superseded_by_draft IS NULL
+        ORDER BY id
+        FOR UPDATE
+        "#,
+    )
+    .bind(&model_ids)
+    .bind(draft.work_date)
+    .bind(&draft.platform)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let mut manual_by_model: BTreeMap<Uuid, f64> = BTreeMap::new();
+    for (_, model_id, tokens) in &live_manual {
+        *manual_by_model.entry(*model_id).or_default() += tokens;
+    }
+    if !manual_by_model.is_empty() && !payload.supersede_manual {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "{} modelo(s) ya tienen producción manual en {} ese día; \
+                 confirme con supersede_manual para evitar doble conteo",
+                manual_by_model.len(),
+                draft.platform
+            ),
+        ));
+    }
+
+    if payload.supersede_manual && !live_manual.is_empty() {
+        let log_ids: Vec<Uuid> = live_manual.iter().map(|(log_id, _, _)| *log_id).collect();
+        sqlx::query("UPDATE production_logs SET superseded_by_draft = $1 WHERE id = ANY($2)")
+            .bind(id)
+            .bind(&log_ids)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        for (log_id, model_id, tokens) in &live_manual {
+            audit(
+                &mut tx,
+                "production",
+                *log_id,
+                "update",
+                Some(serde_json::json!({ "model_id": model_id, "tokens_earned": tokens, "superseded_by_draft": null })),
+                serde_json::json!({ "model_id": model_id, "tokens_earned": tokens, "superseded_by_draft": id }),
+                reviewer,
+            )
+            .await
+            .map_err(db_error)?;
+        }
+    }
+
+    let token_usd_value = platforms::registry().token_usd_value(Some(&draft.platform));
+    for line in &lines {
+        let tokens_usd = line.telemetry_tokens as f64 * token_usd_value;
+        let log_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO production_logs
+                (model_id, date, production_date, tokens_earned, tokens_usd, entered_by, source, platform, draft_id,
+                 studio_room_id)
+            VALUES ($1, $2, $2, $3, $4, $5, 'TELEMETRY', $6, $7, $8)
+            RETURNING id
+            "#,
+        )
+        .bind(line.model_id)
+        .bind(draft.work_date)
+        .bind(line.telemetry_tokens as f64)
+        .bind(tokens_usd)
+        .bind(reviewer)
+        .bind(&draft.platform)
+        .bind(id)
+        .bind(draft.studio_room_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        sqlx::query("UPDATE production_draft_lines SET production_log_id = $2 WHERE id = $1")
+            .bind(line.id)
+            .bind(log_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        audit(
+            &mut tx,
+            "production",
+            log_id,
+            "create",
+            None,
+            serde_json::json!({
+                "model_id": line.model_id,
+                "production_date": draft.work_date,
+                "tokens_earned": line.telemetry_tokens,
+                "tokens_usd": tokens_usd,
+                "platform": draft.platform,
+                "source": "TELEMETRY",
+                "draft_id": id,
+                "manual_tokens": manual_by_model.get(&line.model_id),
+                "discrepancy_pct": line.discrepancy_pct,
+            }),
+            reviewer,
+        )
+        .await
+        .map_err(db_error)?;
+    }
+
+    sqlx::query(
+        "UPDATE production_drafts SET status = $2, reviewed_by = $3, reviewed_at = NOW(), review_notes = $4 WHERE id = $1",
+    )
+    .bind(id)
+    .bind(STATUS_CONFIRMED)
+    .bind(reviewer)
+    .bind(&payload.notes)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    audit(
+        &mut tx,
+        "production_draft",
+        id,
+        "update",
+        Some(serde_json::json!({ "status": STATUS_DRAFT })),
+        serde_json::json!({
+            "status": STATUS_CONFIRMED,
+            "telemetry_tokens": draft.telemetry_tokens,
+            "unattributed_tokens": draft.unattributed_tokens,
+            "flagged": draft.flagged,
+            "supersede_manual": payload.supersede_manual,
+            "notes": payload.notes,
+        }),
+        reviewer,
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    tracing::info!(
+        "✅ Borrador {} confirmado por {}: {} tokens en {} registro(s)",
+        id,
+        moderator.email,
+        draft.telemetry_tokens - draft.unattributed_tokens,
+        lines.len()
+    );
+
+    Ok(Json(load_draft(&state.db, id).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RejectDraftRequest {
+    pub notes: String,
+}
+
+/// POST /api/admin/production/drafts/:id/reject
+pub async fn reject_draft_handler(
+    State(state): State<Arc<AppState>>,
+    moderator: ModeratorOnly,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<RejectDraftRequest>,
+) -> Result<Json<DraftWithLines>, (StatusCode, String)> {
+    let reviewer = parse_user_id(&moderator.user_id)?;
+    if payload.notes.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Indique el motivo del rechazo".to_string()));
+    }
+
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+    let updated = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE production_drafts
+        SET status = $2, reviewed_by = $3, reviewed_at = NOW(), review_notes = $4
+        WHERE id = $1 AND status = 'DRAFT'
+        RETURNING id
+        "#,
+    )
+    .bind(id)
+    .bind(STATUS_REJECTED)
+    .bind(reviewer)
+    .bind(&payload.notes)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    if updated.is_none() {
+        return Err((StatusCode::CONFLICT, "El borrador no existe o ya fue revisado".to_string()));
+    }
+
+    audit(
+        &mut tx,
+        "production_draft",
+        id,
+        "update",
+        Some(serde_json::json!({ "status": STATUS_DRAFT })),
+        serde_json::json!({ "status": STATUS_REJECTED, "notes": payload.notes }),
+        reviewer,
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+    Ok(Json(load_draft(&state.db, id).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total(room: &str, platform: &str, model_id: Option<Uuid>, tokens: i64) -> DeltaTotal {
+        DeltaTotal {
+            room_id: room.to_string(),
+            platform: platform.to_string(),
+            studio_room_id: Some(1),
+            model_id,
+            tokens,
+        }
+    }
+
+    #[test]
+    fn discrepancy_is_relative_to_manual_entry() {
+        assert_eq!(discrepancy(1000, None, 5.0), (None, false));
+        assert_eq!(discrepancy(1040, Some(1000.0), 5.0), (Some(4.0), false));
+        assert_eq!(discrepancy(900, Some(1000.0), 5.0), (Some(-10.0), true));
+        assert_eq!(discrepancy(50, Some(0.0), 5.0), (None, true));
+        assert_eq!(discrepancy(0, Some(0.0), 5.0), (None, false));
+    }
+
+    #[test]
+    fn plans_group_by_room_and_platform_keeping_unattributed_apart() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        let totals = vec![
+            total("1", "chaturbate", Some(a), 700),
+            total("1", "chaturbate", None, 100),
+            total("1", "stripchat", Some(a), 300),
+            total("2", "chaturbate", Some(b), 500),
+        ];
+
+        let plans = plan_drafts(&totals);
+        assert_eq!(plans.len(), 3);
+        let first = &plans[0];
+        assert_eq!((first.room_id.as_str(), first.platform.as_str()), ("1", "chaturbate"));
+        assert_eq!(first.telemetry_tokens, 800);
+        assert_eq!(first.unattributed_tokens, 100);
+        assert_eq!(first.lines, vec![(a, 700)]);
+
+        let day = model_day_totals(&totals);
+        assert_eq!(day.get(&(a, "chaturbate".to_string())), Some(&700));
+        assert_eq!(day.get(&(a, "stripchat".to_string())), Some(&300));
+        assert_eq!(day.get(&(b, "chaturbate".to_string())), Some(&500));
+    }
+}
@@ -0,0 +1,430 @@
+/// Serie de tiempo durable de telemetría.
+///
+/// Cada `TelemetryUpdate` se guarda en `telemetry_samples` (particionada por día).
+/// Un worker agrega rollups de 1 minuto y 1 hora, crea particiones futuras y
+/// aplica la retención. Las consultas por rango eligen la resolución adecuada.
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, DurationRound, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgPool};
+use tokio::time::interval;
+
+use super::TelemetryUpdate;
+use crate::{middleware::auth::ModeratorOnly, state::AppState};
+
+/// Muestras que llegan tarde se re-agregan dentro de esta ventana
+const LATE_LOOKBACK_MINUTES: i64 = 5;
+/// Tolerancia para relojes adelantados en la extensión
+const MAX_CLOCK_SKEW_SECONDS: i64 = 300;
+const MAX_QUERY_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TelemetryRetention {
+    pub raw_days: i64,
+    pub minute_days: i64,
+    pub hour_days: i64,
+}
+
+impl TelemetryRetention {
+    pub fn from_env() -> Self {
+        let days = |key: &str, default: i64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|d| *d > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            raw_days: days("TELEMETRY_RAW_RETENTION_DAYS", 7),
+            minute_days: days("TELEMETRY_1M_RETENTION_DAYS", 30),
+            hour_days: days("TELEMETRY_1H_RETENTION_DAYS", 365),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Raw,
+    #[serde(rename = "1m")]
+    Minute,
+    #[serde(rename = "1h")]
+    Hour,
+}
+
+impl Resolution {
+    /// Resolución por defecto según el tamaño del rango consultado
+    pub fn for_range(range: Duration) -> Self {
+        if range <= Duration::hours(2) {
+            Resolution::Raw
+        } else if range <= Duration::days(2) {
+            Resolution::Minute
+        } else {
+            Resolution::Hour
+        }
+    }
+
+    /// Rango máximo admitido para cada resolución
+    pub fn max_range(&self) -> Duration {
+        match self {
+            Resolution::Raw => Duration::days(1),
+            Resolution::Minute => Duration::days(14),
+            Resolution::Hour => Duration::days(MAX_QUERY_RANGE_DAYS),
+        }
+    }
+}
+
+/// Momento de la muestra: el timestamp del cliente (segundos) salvo que sea
+/// inválido o esté en el futuro más allá de la tolerancia.
+pub fn sample_time(client_ts: i64, now: DateTime<Utc>) -> DateTime<Utc> {
+    match Utc.timestamp_opt(client_ts, 0).single() {
+        Some(ts) if ts <= now + Duration::seconds(MAX_CLOCK_SKEW_SECONDS) && client_ts > 0 => ts,
+        _ => now,
+    }
+}
+
+/// Guarda una muestra cruda
+pub async fn persist_sample(conn: &mut PgConnection, update: &TelemetryUpdate) -> Result<(), sqlx::Error> {
+    let sampled_at = sample_time(update.timestamp, Utc::now());
+    sqlx::query(
+        r#"
+        INSERT INTO telemetry_samples (room_id, platform, sampled_at, tokens_count, tips_count, viewers_count)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(&update.room_id)
+    .bind(&update.platform)
+    .bind(sampled_at)
+    .bind(i64::from(update.tokens_count))
+    .bind(i64::from(update.tips_count))
+    .bind(update.viewers_count as i32)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+// ============================================================================
+// ROLLUPS Y RETENCIÓN
+// ============================================================================
+
+async fn rollup_minutes(pool: &PgPool, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO telemetry_rollups_1m
+            (room_id, platform, bucket, samples, tokens_min, tokens_last, tips_last, viewers_avg, viewers_max)
+        SELECT room_id, platform, date_trunc('minute', sampled_at) AS bucket,
+               COUNT(*)::INT,
+               MIN(tokens_count),
+               (array_agg(tokens_count ORDER BY sampled_at DESC))[1],
+               (array_agg(tips_count ORDER BY sampled_at DESC))[1],
+               AVG(viewers_count)::DOUBLE PRECISION,
+               MAX(viewers_count)
+        FROM telemetry_samples
+        WHERE sampled_at >= $1 AND sampled_at < $2
+        GROUP BY room_id, platform, bucket
+        ON CONFLICT (room_id, platform, bucket) DO UPDATE SET
+            samples = EXCLUDED.samples,
+            tokens_min = EXCLUDED.tokens_min,
+            tokens_last = EXCLUDED.tokens_last,
+            tips_last = EXCLUDED.tips_last,
+            viewers_avg = EXCLUDED.viewers_avg,
+            viewers_max = EXCLUDED.viewers_max
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+async fn rollup_hours(pool: &PgPool, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO telemetry_rollups_1h
+            (room_id, platform, bucket, samples, tokens_min, tokens_last, tips_last, viewers_avg, viewers_max)
+        SELECT room_id, platform, date_trunc('hour', bucket) AS hour_bucket,
+               SUM(samples)::INT,
+               MIN(tokens_min),
+               (array_agg(tokens_last ORDER BY bucket DESC))[1],
+               (array_agg(tips_last ORDER BY bucket DESC))[1],
+               (SUM(viewers_avg * samples) / NULLIF(SUM(samples), 0))::DOUBLE PRECISION,
+               MAX(viewers_max)
+        FROM telemetry_rollups_1m
+        WHERE bucket >= $1 AND bucket < $2
+        GROUP BY room_id, platform, hour_bucket
+        ON CONFLICT (room_id, platform, bucket) DO UPDATE SET
+            samples = EXCLUDED.samples,
+            tokens_min = EXCLUDED.tokens_min,
+            tokens_last = EXCLUDED.tokens_last,
+            tips_last = EXCLUDED.tips_last,
+            viewers_avg = EXCLUDED.viewers_avg,
+            viewers_max = EXCLUDED.viewers_max
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Agrega desde la última marca de agua (con margen para muestras tardías)
+/// hasta el minuto actual. Es idempotente: los buckets se recalculan completos.
+pub async fn run_rollups(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    let to = now.duration_trunc(Duration::minutes(1)).unwrap_or(now);
+
+    let watermark = sqlx::query_scalar::<_, DateTime<Utc>>(
+        "SELECT rolled_until FROM telemetry_rollup_state WHERE resolution = '1m'",
+    )
+    .fetch_optional(pool)
+    .await?
+    .unwrap_or(to - Duration::hours(1));
+
+    let from = watermark - Duration::minutes(LATE_LOOKBACK_MINUTES);
+    rollup_minutes(pool, from, to).await?;
+
+    // La hora en curso también se recalcula para que las consultas la vean
+    let hour_from = from.duration_trunc(Duration::hours(1)).unwrap_or(from);
+    rollup_hours(pool, hour_from, to).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO telemetry_rollup_state (resolution, rolled_until)
+        VALUES ('1m', $1)
+        ON CONFLICT (resolution) DO UPDATE SET rolled_until = EXCLUDED.rolled_until
+        "#,
+    )
+    .bind(to)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Crea particiones de hoy/mañana (días UTC) y aplica la retención de cada nivel.
+/// Un fallo con las particiones se registra y no frena la retención de los rollups.
+pub async fn run_maintenance(pool: &PgPool, retention: TelemetryRetention) -> Result<(), sqlx::Error> {
+    let today = Utc::now().date_naive();
+    for day in [today, today + Duration::days(1)] {
+        if let Err(e) = sqlx::query("SELECT ensure_telemetry_partition($1)").bind(day).execute(pool).await {
+            tracing::warn!("Telemetry partition for {} not created: {}", day, e);
+        }
+    }
+
+    let dropped = sqlx::query_scalar::<_, i32>("SELECT drop_telemetry_partitions_before($1)")
+        .bind(today - Duration::days(retention.raw_days))
+        .fetch_one(pool)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Telemetry raw retention failed: {}", e);
+            0
+        });
+
+    let now = Utc::now();
+    let minute_deleted = sqlx::query("DELETE FROM telemetry_rollups_1m WHERE bucket < $1")
+        .bind(now - Duration::days(retention.minute_days))
+        .execute(pool)
+        .await?
+        .rows_affected();
+    let hour_deleted = sqlx::query("DELETE FROM telemetry_rollups_1h WHERE bucket < $1")
+        .bind(now - Duration::days(retention.hour_days))
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+    if dropped > 0 || minute_deleted > 0 || hour_deleted > 0 {
+        tracing::info!(
+            "🧹 Retención telemetría: {} particiones, {} rollups 1m, {} rollups 1h eliminados",
+            dropped, minute_deleted, hour_deleted
+        );
+    }
+    Ok(())
+}
+
+/// Worker de rollups (cada minuto) y mantenimiento (cada hora)
+pub async fn run_worker(pool: PgPool, mut shutdown: tokio::sync::broadcast::Receiver<()>) {
+    let retention = TelemetryRetention::from_env();
+    let mut rollup_ticker = interval(std::time::Duration::from_secs(60));
+    let mut maintenance_ticker = interval(std::time::Duration::from_secs(3600));
+    let reconcile_config = super::reconcile::ReconcileConfig::from_env();
+    let mut reconcile_ticker = interval(std::time::Duration::from_secs(900));
+    tracing::info!("📈 Telemetry worker iniciado (rollups 60s, borradores 15m, retención {:?})", retention);
+
+    loop {
+        tokio::select! {
+            _ = rollup_ticker.tick() => {
+                if let Err(e) = run_rollups(&pool).await {
+                    tracing::warn!("Telemetry rollup error: {}", e);
+                }
+            }
+            _ = maintenance_ticker.tick() => {
+                if let Err(e) = run_maintenance(&pool, retention).await {
+                    tracing::warn!("Telemetry maintenance error: {}", e);
+                }
+            }
+            _ = reconcile_ticker.tick() => {
+                if let Err(e) = super::reconcile::run_reconcile(&pool, reconcile_config).await {
+                    tracing::warn!("Production draft reconcile error: {}", e);
+                }
+            }
+            _ = shutdown.recv() => {
+                tracing::info!("Telemetry worker apagado");
+                break;
+            }
+        }
+    }
+}
+
+// ============================================================================
+// CONSULTAS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SeriesPoint {
+    pub t: DateTime<Utc>,
+    pub tokens: i64,
+    pub tips: i64,
+    pub viewers: f64,
+    pub viewers_max: i32,
+    pub samples: i32,
+}
+
+pub async fn query_series(
+    pool: &PgPool,
+    room_id: &str,
+    platform: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    resolution: Resolution,
+) -> Result<Vec<SeriesPoint>, sqlx::Error> {
+    let sql = match resolution {
+        Resolution::Raw => {
+            r#"
+            SELECT sampled_at AS t, tokens_count AS tokens, tips_count AS tips,
+                   viewers_count::DOUBLE PRECISION AS viewers, viewers_count AS viewers_max, 1 AS samples
+            FROM telemetry_samples
+            WHERE room_id = $1 AND platform = $2 AND sampled_at >= $3 AND sampled_at < $4
+            ORDER BY sampled_at
+            "#
+        }
+        Resolution::Minute => {
+            r#"
+            SELECT bucket AS t, tokens_last AS tokens, tips_last AS tips,
+                   viewers_avg AS viewers, viewers_max, samples
+            FROM telemetry_rollups_1m
+            WHERE room_id = $1 AND platform = $2 AND bucket >= $3 AND bucket < $4
+            ORDER BY bucket
+            "#
+        }
+        Resolution::Hour => {
+            r#"
+            SELECT bucket AS t, tokens_last AS tokens, tips_last AS tips,
+                   viewers_avg AS viewers, viewers_max, samples
+            FROM telemetry_rollups_1h
+            WHERE room_id = $1 AND platform = $2 AND bucket >= $3 AND bucket < $4
+            ORDER BY bucket
+            "#
+        }
+    };
+
+    sqlx::query_as::<_, SeriesPoint>(sql)
+        .bind(room_id)
+        .bind(platform)
+        .bind(from)
+        .bind(to)
+        .fetch_all(pool)
+        .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeriesQuery {
+    pub from: DateTime<Utc>,
+    /// Por defecto, ahora
+    pub to: Option<DateTime<Utc>>,
+    /// raw | 1m | 1h (por defecto se elige según el rango)
+    pub resolution: Option<Resolution>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesResponse {
+    pub room_id: String,
+    pub platform: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub resolution: Resolution,
+    pub points: Vec<SeriesPoint>,
+}
+
+/// GET /api/tracking/telemetry/:room_id/:platform/series?from=...&to=...&resolution=1m
+/// Curvas de tokens, tips y viewers de un room/plataforma en un rango
+pub async fn telemetry_series_handler(
+    State(state): State<Arc<AppState>>,
+    _moderator: ModeratorOnly,
+    Path((room_id, platform)): Path<(String, String)>,
+    Query(query): Query<SeriesQuery>,
+) -> Result<Json<SeriesResponse>, (StatusCode, String)> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    if to <= query.from {
+        return Err((StatusCode::BAD_REQUEST, "`to` debe ser posterior a `from`".to_string()));
+    }
+    let range = to - query.from;
+    let resolution = query.resolution.unwrap_or_else(|| Resolution::for_range(range));
+    if range > resolution.max_range() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Rango demasiado amplio para resolución {:?}", resolution),
+        ));
+    }
+
+    let points = query_series(&state.db, &room_id, &platform, query.from, to, resolution)
+        .await
+        .map_err(|e| {
+            tracing::error!("DB error querying telemetry series: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    Ok(Json(SeriesResponse {
+        room_id,
+        platform,
+        from: query.from,
+        to,
+        resolution,
+        points,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_scales_with_range() {
+        assert_eq!(Resolution::for_range(Duration::minutes(30)), Resolution::Raw);
+        assert_eq!(Resolution::for_range(Duration::hours(12)), Resolution::Minute);
+        assert_eq!(Resolution::for_range(Duration::days(30)), Resolution::Hour);
+    }
+
+    #[test]
+    fn sample_time_rejects_future_and_invalid_timestamps() {
+        let now = Utc.with_ymd_and_hms(2025, 12, 11, 12, 0, 0).unwrap();
+        let past = now - Duration::minutes(3);
+        assert_eq!(sample_time(past.timestamp(), now), past);
+        assert_eq!(sample_time((now + Duration::hours(1)).timestamp(), now), now);
+        assert_eq!(sample_time(0, now), now);
+    }
+
+    #[test]
+    fn resolution_parses_query_values() {
+        let r: Resolution = serde_json::from_str("\"1m\"").unwrap();
+        assert_eq!(r, Resolution::Minute);
+        let r: Resolution = serde_json::from_str("\"raw\"").unwrap();
+        assert_eq!(r, Resolution::Raw);
+    }
+}
@@ -0,0 +1,100 @@
+// Fan-out entre réplicas vía NATS: dos hubs y dos registros de peers en el mismo proceso,
+// cada uno con su propia conexión y node id, como si fueran dos backends.
+// Requieren un NATS real, así que van con #[ignore]: `cargo test --test cluster_fanout -- --ignored`
+// (NATS_URL o nats://127.0.0.1:4222). Sin NATS alcanzable fallan en vez de pasar en silencio.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ws::Message;
+use backend_api::realtime::{bridge_hub, ClusterNode, RealtimeEvent, RealtimeHub, Topic};
+use backend_api::social::PeerRegistry;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+async fn connect() -> async_nats::Client {
+    let url = std::env::var("NATS_URL").unwrap_or_else(|_| "nats://127.0.0.1:4222".to_string());
+    tokio::time::timeout(Duration::from_secs(2), async_nats::connect(url.as_str()))
+        .await
+        .unwrap_or_else(|_| panic!("timed out connecting to NATS at {}", url))
+        .unwrap_or_else(|e| panic!("NATS not reachable at {}: {}", url, e))
+}
+
+async fn two_nodes() -> (ClusterNode, ClusterNode) {
+    let a = connect().await;
+    let b = connect().await;
+    (ClusterNode::with_id("node-a", a), ClusterNode::with_id("node-b", b))
+}
+
+fn event(room_id: &str) -> RealtimeEvent {
+    RealtimeEvent {
+        event_type: "ROOM_UPDATE".to_string(),
+        room_id: room_id.to_string(),
+        data: serde_json::json!({"new_total": 42}),
+        timestamp: chrono::Utc::now().timestamp(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a running NATS server"]
+async fn hub_events_reach_the_other_node_exactly_once() {
+    let (node_a, node_b) = two_nodes().await;
+    let hub_a = Arc::new(RealtimeHub::new(16));
+    let hub_b = Arc::new(RealtimeHub::new(16));
+    let bridge_a = bridge_hub(hub_a.clone(), node_a.clone()).await.unwrap();
+    let bridge_b = bridge_hub(hub_b.clone(), node_b.clone()).await.unwrap();
+    node_a.nats.flush().await.unwrap();
+    node_b.nats.flush().await.unwrap();
+
+    let room = format!("cluster-{}", Uuid::new_v4());
+    let mut remote_room = hub_b.subscribe(&Topic::Room(room.clone()));
+    let mut remote_admin = hub_b.subscribe(&Topic::Admin);
+    let mut local_admin = hub_a.subscribe(&Topic::Admin);
+
+    hub_a.publish(event(&room));
+
+    let received = tokio::time::timeout(Duration::from_secs(2), remote_room.recv()).await.unwrap().unwrap();
+    assert_eq!(received.room_id, room);
+    assert_eq!(received.data["new_total"], 42);
+    assert_eq!(
+        tokio::time::timeout(Duration::from_secs(2), remote_admin.recv()).await.unwrap().unwrap().room_id,
+        room
+    );
+    assert_eq!(hub_b.snapshot(&Topic::Room(room.clone())).len(), 1);
+
+    // El nodo que publica lo ve una sola vez: su eco en NATS se descarta
+    assert_eq!(local_admin.recv().await.unwrap().room_id, room);
+    assert!(tokio::time::timeout(Duration::from_millis(300), local_admin.recv()).await.is_err());
+    // Y el nodo remoto no lo rebota de vuelta
+    assert!(tokio::time::timeout(Duration::from_millis(300), remote_room.recv()).await.is_err());
+
+    bridge_a.abort();
+    bridge_b.abort();
+}
+
+#[tokio::test]
+#[ignore = "requires a running NATS server"]
+async fn signals_reach_peers_connected_to_another_node() {
+    let (node_a, node_b) = two_nodes().await;
+    let registry_a = PeerRegistry::new(Some(node_a.clone()));
+    let registry_b = PeerRegistry::new(Some(node_b.clone()));
+
+    let callee = Uuid::new_v4();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let connection = registry_b.register(callee, tx).await;
+    node_b.nats.flush().await.unwrap();
+
+    assert!(!registry_a.is_local(callee).await);
+    assert!(registry_a.send_to(callee, r#"{"type":"offer"}"#.to_string()).await);
+    let delivered = tokio::time::timeout(Duration::from_secs(2), rx.recv()).await.unwrap();
+    assert!(matches!(delivered, Some(Message::Text(text)) if text == r#"{"type":"offer"}"#));
+
+    // Nadie conectado en ninguna réplica
+    assert!(!registry_a.send_to(Uuid::new_v4(), "hola".to_string()).await);
+
+    // Al desconectarse deja de ser alcanzable desde el otro nodo
+    registry_b.unregister(callee, connection).await;
+    node_b.nats.flush().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!registry_a.send_to(callee, "tarde".to_string()).await);
+}
@@ -0,0 +1,63 @@
+// Restricciones de `leave_requests` contra un Postgres real con las migraciones aplicadas.
+// Van con #[ignore]: `cargo test --test leave_documents -- --ignored` (DATABASE_URL).
+// Todo corre dentro de una transacción que se descarta al final.
+
+use backend_api::operations::leave::{insert_leave, leave_days, CreateLeaveRequest, LeaveType};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn connect() -> PgPool {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must point to a migrated database");
+    PgPool::connect(&url)
+        .await
+        .unwrap_or_else(|e| panic!("Postgres not reachable at {}: {}", url, e))
+}
+
+#[tokio::test]
+#[ignore = "requires a migrated Postgres database"]
+async fn medical_leave_accepts_a_private_document_without_url() {
+    let pool = connect().await;
+    let mut tx = pool.begin().await.unwrap();
+
+    let user_id = sqlx::query_scalar::<_, Uuid>("INSERT INTO users (email, role) VALUES ($1, 'model') RETURNING id")
+        .bind(format!("leave-doc-{}@test.local", Uuid::new_v4()))
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+    let object_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO private_objects (bucket, object_key, owner_id, purpose, content_type, size_bytes, uploaded_by)
+        VALUES ('EVIDENCE', $1, $2, 'leave_support', 'application/pdf', 1024, $2)
+        RETURNING id
+        "#,
+    )
+    .bind(format!("evidence/{}.pdf", Uuid::new_v4()))
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap();
+
+    let start = NaiveDate::from_ymd_opt(2025, 12, 10).unwrap();
+    let end = NaiveDate::from_ymd_opt(2025, 12, 12).unwrap();
+    let req = CreateLeaveRequest {
+        leave_type: LeaveType::Medical,
+        start_date: start,
+        end_date: end,
+        reason: Some("Incapacidad".to_string()),
+        document_object_id: Some(object_id),
+    };
+    let leave = insert_leave(user_id, &req, leave_days(start, end), &mut *tx)
+        .await
+        .expect("MEDICAL leave with only document_object_id must satisfy chk_medical_document");
+    assert_eq!(leave.leave_type, "MEDICAL");
+    assert_eq!(leave.document_url, None);
+    assert_eq!(leave.document_object_id, Some(object_id));
+
+    // Sin ningún soporte la restricción sigue rechazándola
+    let missing = CreateLeaveRequest { document_object_id: None, start_date: end, ..req };
+    let err = insert_leave(user_id, &missing, 1, &mut *tx).await.unwrap_err();
+    assert!(matches!(&err, sqlx::Error::Database(db) if db.constraint() == Some("chk_medical_document")));
+
+    tx.rollback().await.unwrap();
+}